@@ -0,0 +1,105 @@
+// Calculator engine benchmarks
+//
+// Run with `cargo bench --bench engine`. Covers the paths a refactor of the
+// decimal backend or the parser would touch: typing numbers, chaining
+// operations, parsing and evaluating expressions, and formatting results.
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use hello_project::calculator::Calculator;
+use hello_project::expression::{self, Environment, ParseOptions};
+use hello_project::format::{self, NumberFormat};
+use hello_project::input::InputEvent;
+use hello_project::operation::Operation;
+
+const EXPRESSIONS: [(&str, &str); 3] = [
+    ("arithmetic", "1 + 2 * 3 - 4 / 5"),
+    ("nested", "((1.5 + 2.25) * (3 - 0.125)) / ((4 + 5) * (6 - 7.5))"),
+    ("functions", "sqrt(2) * sin(pi / 4) + ln(10) ^ 2 - abs(-3)"),
+];
+
+fn digit_entry(c: &mut Criterion) {
+    // 1234567890.1234567, one key at a time
+    let keys: Vec<InputEvent> = (1..=9)
+        .chain([0])
+        .map(InputEvent::Digit)
+        .chain([InputEvent::DecimalPoint])
+        .chain((1..=7).map(InputEvent::Digit))
+        .collect();
+    c.bench_function("digit entry", |b| {
+        b.iter(|| {
+            let mut calc = Calculator::new();
+            for &key in &keys {
+                calc.handle_event(key);
+            }
+            black_box(calc.get_display_text().len())
+        })
+    });
+}
+
+fn chained_operations(c: &mut Criterion) {
+    // 1 + 2 × 3 − 4 ÷ 5 + … for 100 operations, then =
+    let operations = [Operation::Add, Operation::Multiply, Operation::Subtract, Operation::Divide];
+    let mut group = c.benchmark_group("chained operations");
+    for decimal in [false, true] {
+        let name = if decimal { "decimal" } else { "float" };
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut calc = Calculator::new();
+                calc.set_decimal_mode(decimal);
+                for step in 0..100u8 {
+                    calc.input_digit(step % 9 + 1);
+                    calc.input_operation(operations[usize::from(step) % operations.len()]);
+                }
+                calc.input_digit(7);
+                calc.calculate();
+                black_box(calc.display_value())
+            })
+        });
+    }
+    group.finish();
+}
+
+fn expressions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("expression");
+    let env = Environment::new();
+    for (name, source) in EXPRESSIONS {
+        group.bench_with_input(BenchmarkId::new("parse", name), source, |b, source| {
+            b.iter(|| expression::parse_with(black_box(source), ParseOptions::default()))
+        });
+        let parsed = expression::parse_with(source, ParseOptions::default()).unwrap();
+        // An error would end evaluation early and flatter the timing
+        assert!(parsed.eval(&env).is_ok(), "{} fails to evaluate", source);
+        group.bench_with_input(BenchmarkId::new("eval", name), &parsed, |b, parsed| {
+            b.iter(|| parsed.eval(&env))
+        });
+        let calc = Calculator::new();
+        group.bench_with_input(BenchmarkId::new("parse and eval", name), source, |b, source| {
+            b.iter(|| calc.preview_expression(black_box(source), None))
+        });
+    }
+    group.finish();
+}
+
+fn formatting(c: &mut Criterion) {
+    let values = [0.0, 4700.0, -0.000_123_4, 1.0 / 3.0, 6.022e23, 299_792_458.0];
+    let mut group = c.benchmark_group("format");
+    for (name, number_format) in [
+        ("plain", NumberFormat::Plain),
+        ("si prefix", NumberFormat::SiPrefix),
+        ("engineering", NumberFormat::Engineering),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                values
+                    .iter()
+                    .map(|&value| format::format_number(black_box(value), number_format, "").len())
+                    .sum::<usize>()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, digit_entry, chained_operations, expressions, formatting);
+criterion_main!(benches);
@@ -0,0 +1,90 @@
+// Heap allocations per frame while drawing the keypad
+//
+// Run with `cargo bench --bench frame_allocations`. Draws the keypad headless
+// for a number of frames and prints how many allocations each frame made,
+// next to the same keys drawn as `egui::Button`s with `RichText` labels.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use hello_project::keypad::{Keypad, KeypadLayout};
+use hello_project::operation::OperationRegistry;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const WARMUP_FRAMES: usize = 10;
+const FRAMES: usize = 200;
+
+// Average allocations per frame once fonts and caches have settled
+fn measure(mut draw: impl FnMut(&mut egui::Ui)) -> f64 {
+    let ctx = egui::Context::default();
+    let mut frame = || {
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| draw(ui));
+        });
+    };
+    for _ in 0..WARMUP_FRAMES {
+        frame();
+    }
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..FRAMES {
+        frame();
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / FRAMES as f64
+}
+
+// The digit and operator grid as plain buttons, laid out anew every frame
+fn rich_text_grid(ui: &mut egui::Ui) {
+    const ROWS: [[&str; 4]; 4] = [
+        ["7", "8", "9", "÷"],
+        ["4", "5", "6", "×"],
+        ["1", "2", "3", "−"],
+        ["0", ".", "=", "+"],
+    ];
+    for row in ROWS {
+        ui.horizontal(|ui| {
+            for label in row {
+                ui.add_sized([65.0, 65.0], egui::Button::new(egui::RichText::new(label).size(24.0)));
+            }
+        });
+    }
+}
+
+fn main() {
+    // `cargo test` runs benches once in test mode; there's nothing to check
+    if std::env::args().any(|arg| arg == "--test") {
+        return;
+    }
+    let operations = OperationRegistry::basic();
+    let mut keypad = Keypad::new(&operations, KeypadLayout::STANDARD);
+    let empty = measure(|_| {});
+    let cached = measure(|ui| {
+        keypad.ui(ui, true);
+    });
+    let rich_text = measure(rich_text_grid);
+
+    println!("allocations per frame, over {} frames", FRAMES);
+    println!("  empty frame             {:>8.1}", empty);
+    println!("  keypad, cached labels   {:>8.1}", cached - empty);
+    println!("  4x4 grid of RichText    {:>8.1}", rich_text - empty);
+}
@@ -0,0 +1,152 @@
+// Expression parser fuzz target
+//
+// Run with `cargo fuzz run expression` from the repository root. Feeds raw
+// bytes and sequences of expression pieces to the parser, the evaluators
+// and the calculator, which must never panic and must report every failure
+// as a `CalculatorError` the display can show.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use hello_project::calculator::Calculator;
+use hello_project::error::CalculatorError;
+use hello_project::expression::{self, Environment, ParseOptions};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+enum Input {
+    Bytes(Vec<u8>),
+    Tokens(Vec<Piece>),
+    /// `operand operator operand operator …`, long enough to nest deeper
+    /// than the stack allows if nothing stopped it
+    Chain {
+        operand: Piece,
+        operator: Operator,
+        length: u16,
+    },
+}
+
+#[derive(Debug, Arbitrary)]
+enum Piece {
+    Integer(u32),
+    Float(f64),
+    Hex(u64),
+    Name(Name),
+    Operator(Operator),
+    LeftParen,
+    RightParen,
+    Comma,
+    Equals,
+    Space,
+    Char(char),
+}
+
+#[derive(Debug, Arbitrary)]
+enum Name {
+    X,
+    Pi,
+    Ans,
+    Sqrt,
+    Sin,
+    Min,
+    Unknown,
+}
+
+#[derive(Debug, Arbitrary)]
+enum Operator {
+    Plus,
+    Minus,
+    Times,
+    Divide,
+    Power,
+    Percent,
+    Factorial,
+    Superscript,
+}
+
+impl Operator {
+    fn symbol(&self) -> char {
+        match self {
+            Operator::Plus => '+',
+            Operator::Minus => '-',
+            Operator::Times => '*',
+            Operator::Divide => '/',
+            Operator::Power => '^',
+            Operator::Percent => '%',
+            Operator::Factorial => '!',
+            Operator::Superscript => '²',
+        }
+    }
+}
+
+impl Piece {
+    fn write(&self, text: &mut String) {
+        match self {
+            Piece::Integer(value) => text.push_str(&value.to_string()),
+            Piece::Float(value) => text.push_str(&value.to_string()),
+            Piece::Hex(value) => text.push_str(&format!("0x{:X}", value)),
+            Piece::Name(name) => text.push_str(match name {
+                Name::X => "x",
+                Name::Pi => "pi",
+                Name::Ans => "ans",
+                Name::Sqrt => "sqrt",
+                Name::Sin => "sin",
+                Name::Min => "min",
+                Name::Unknown => "foo",
+            }),
+            Piece::Operator(operator) => text.push(operator.symbol()),
+            Piece::LeftParen => text.push('('),
+            Piece::RightParen => text.push(')'),
+            Piece::Comma => text.push(','),
+            Piece::Equals => text.push('='),
+            Piece::Space => text.push(' '),
+            Piece::Char(c) => text.push(*c),
+        }
+    }
+}
+
+fn check(error: CalculatorError, input: &str) {
+    if let CalculatorError::Syntax { message, column } = &error {
+        assert!(!message.is_empty(), "{:?}", input);
+        assert!(*column >= 1 && *column <= input.chars().count() + 1, "{:?}: column {}", input, column);
+    }
+    assert!(error.to_string().starts_with("Error: "), "{:?}: {}", input, error);
+}
+
+fuzz_target!(|input: Input| {
+    let text = match input {
+        Input::Bytes(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Input::Tokens(pieces) => {
+            let mut text = String::new();
+            for piece in &pieces {
+                piece.write(&mut text);
+            }
+            text
+        }
+        Input::Chain { operand, operator, length } => {
+            let mut text = String::new();
+            operand.write(&mut text);
+            for _ in 0..length {
+                text.push(operator.symbol());
+                operand.write(&mut text);
+            }
+            text
+        }
+    };
+
+    let env = Environment::new();
+    let options = ParseOptions::default();
+    match expression::parse_with(&text, options) {
+        Ok(parsed) => {
+            let _ = parsed.eval(&env).map_err(|error| check(error, &text));
+            let _ = parsed.eval_integer(&env).map_err(|error| check(error, &text));
+        }
+        Err(error) => check(error, &text),
+    }
+    let _ = expression::parse_equation(&text, options).map_err(|error| check(error, &text));
+    let _ = Calculator::new().preview_expression(&text, None).map_err(|error| check(error, &text));
+
+    let normalized = expression::normalize(&text);
+    let _ = Calculator::new()
+        .preview_expression(&normalized, None)
+        .map_err(|error| check(error, &normalized));
+});
@@ -0,0 +1,205 @@
+// Screen Reader Announcements
+//
+// When a calculation finishes, the operation and its outcome are put in words,
+// e.g. "7 divided by 0 — error: division by zero", and published through an
+// AccessKit live region so screen readers speak them without the user having
+// to move focus to the display.
+use egui::accesskit::{Live, Role};
+
+use crate::error::CalculatorError;
+use crate::expression::{BinaryOp, Expr};
+
+/// What the live region currently says.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Announcement {
+    pub text: String,
+    /// Errors interrupt whatever is being read; results wait their turn
+    pub urgent: bool,
+}
+
+impl Announcement {
+    /// "`operation` equals `result`", or "`operation` — `error`".
+    pub fn new(operation: &str, outcome: &Result<f64, CalculatorError>) -> Self {
+        match outcome {
+            Ok(value) => Self {
+                text: format!("{} equals {}", operation, value),
+                urgent: false,
+            },
+            Err(err) => Self {
+                text: format!("{} — {}", operation, spoken_error(err)),
+                urgent: true,
+            },
+        }
+        .trimmed()
+    }
+
+    /// An error with no operation to go with it.
+    pub fn error(err: &CalculatorError) -> Self {
+        Self {
+            text: spoken_error(err),
+            urgent: true,
+        }
+    }
+
+    // No leading separator when the operation is unknown
+    fn trimmed(mut self) -> Self {
+        if let Some(rest) = self.text.strip_prefix(" — ") {
+            self.text = rest.to_string();
+        }
+        self
+    }
+
+    /// Writes the live region's node into this frame's accessibility tree.
+    /// Does nothing unless a screen reader is connected.
+    pub fn publish(&self, ctx: &egui::Context) {
+        ctx.accesskit_node_builder(egui::Id::new("announcement"), |builder| {
+            builder.set_role(Role::Status);
+            builder.set_live(if self.urgent { Live::Assertive } else { Live::Polite });
+            builder.set_name(self.text.as_str());
+        });
+    }
+}
+
+/// "error: division by zero" rather than the display's "Error: Division by zero".
+pub fn spoken_error(err: &CalculatorError) -> String {
+    let text = err.to_string();
+    let message = text.strip_prefix("Error: ").unwrap_or(&text);
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => format!("error: {}{}", first.to_lowercase(), chars.as_str()),
+        None => String::from("error"),
+    }
+}
+
+/// The expression read out: operators as words, and brackets named, since
+/// screen readers usually skip punctuation.
+pub fn spoken(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(value) => value.to_string(),
+        Expr::Variable(name) => name.clone(),
+        Expr::Negate(operand) => {
+            let bracket = operand.precedence() < BinaryOp::Power.precedence();
+            format!("minus {}", spoken_child(operand, bracket))
+        }
+        Expr::Binary { op, left, right } => {
+            let precedence = op.precedence();
+            let word = match op {
+                BinaryOp::Add => "plus",
+                BinaryOp::Subtract => "minus",
+                BinaryOp::Multiply => "times",
+                BinaryOp::Divide => "divided by",
+                BinaryOp::Power => "to the power of",
+                BinaryOp::ElementMultiply => "element-wise times",
+                BinaryOp::ElementDivide => "element-wise divided by",
+                BinaryOp::ElementPower => "element-wise to the power of",
+            };
+            // Power groups to the right, the others to the left
+            let (left_bracket, right_bracket) = match op {
+                BinaryOp::Power | BinaryOp::ElementPower => (left.precedence() <= precedence, right.precedence() < precedence),
+                _ => (left.precedence() < precedence, right.precedence() <= precedence),
+            };
+            format!(
+                "{} {} {}",
+                spoken_child(left, left_bracket),
+                word,
+                spoken_child(right, right_bracket)
+            )
+        }
+        Expr::Call { name, args } => {
+            let name = match name.to_ascii_lowercase().as_str() {
+                "sqrt" => String::from("square root"),
+                "abs" => String::from("absolute value"),
+                "ln" => String::from("natural log"),
+                _ => name.clone(),
+            };
+            match args.as_slice() {
+                [arg] => {
+                    let bracket = !matches!(arg, Expr::Number(_) | Expr::Variable(_));
+                    format!("{} of {}", name, spoken_child(arg, bracket))
+                }
+                _ => {
+                    let args: Vec<String> = args.iter().map(spoken).collect();
+                    format!("{} of open bracket {} close bracket", name, args.join(", "))
+                }
+            }
+        }
+        Expr::Matrix(rows) => {
+            let rows: Vec<String> = rows
+                .iter()
+                .map(|row| row.iter().map(spoken).collect::<Vec<_>>().join(", "))
+                .collect();
+            format!("matrix with rows {}", rows.join("; "))
+        }
+    }
+}
+
+fn spoken_child(expr: &Expr, bracket: bool) -> String {
+    if bracket {
+        format!("open bracket {} close bracket", spoken(expr))
+    } else {
+        spoken(expr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::{self, ParseOptions};
+    use proptest::prelude::*;
+
+    fn spoken_source(source: &str) -> String {
+        spoken(&expression::parse_with(source, ParseOptions::default()).unwrap())
+    }
+
+    #[test]
+    fn test_announcements() {
+        let division = Expr::binary(BinaryOp::Divide, Expr::Number(7.0), Expr::Number(0.0));
+        assert_eq!(
+            Announcement::new(&spoken(&division), &Err(CalculatorError::DivisionByZero)),
+            Announcement {
+                text: String::from("7 divided by 0 — error: division by zero"),
+                urgent: true,
+            }
+        );
+        assert_eq!(Announcement::new("12 plus 3", &Ok(15.0)).text, "12 plus 3 equals 15");
+        assert_eq!(
+            Announcement::new("", &Err(CalculatorError::UnknownVariable(String::from("Price")))).text,
+            "error: unknown variable 'Price'"
+        );
+        assert_eq!(Announcement::error(&CalculatorError::Overflow).text, "error: overflow");
+    }
+
+    #[test]
+    fn test_spoken_expressions() {
+        assert_eq!(spoken_source("1 + 2 * 3"), "1 plus 2 times 3");
+        assert_eq!(spoken_source("(1 + 2) * 3"), "open bracket 1 plus 2 close bracket times 3");
+        assert_eq!(spoken_source("5 - (3 - 1)"), "5 minus open bracket 3 minus 1 close bracket");
+        assert_eq!(spoken_source("-2^2"), "minus 2 to the power of 2");
+        assert_eq!(spoken_source("sqrt(2) + sin(pi / 4)"), "square root of 2 plus sin of open bracket pi divided by 4 close bracket");
+        assert_eq!(spoken_source("max(1, x)"), "max of open bracket 1, x close bracket");
+    }
+
+    // Feature: gui-calculator, Property 49: Every operator and bracket in an expression is spoken
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_every_operator_spoken(
+            numbers in prop::collection::vec(0u32..1000, 1..8),
+            ops in prop::collection::vec(prop::sample::select(vec!["+", "-", "*", "/"]), 7)
+        ) {
+            let mut source = numbers[0].to_string();
+            for (number, op) in numbers[1..].iter().zip(&ops) {
+                source.push_str(&format!(" {} {}", op, number));
+            }
+            let text = spoken_source(&source);
+            prop_assert!(!text.contains(|c: char| "+-*/()".contains(c)));
+            let words = text.matches(" plus ").count()
+                + text.matches(" minus ").count()
+                + text.matches(" times ").count()
+                + text.matches(" divided by ").count();
+            prop_assert_eq!(words, numbers.len() - 1);
+            prop_assert_eq!(text.matches("open bracket").count(), text.matches("close bracket").count());
+        }
+    }
+}
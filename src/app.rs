@@ -1,124 +1,4780 @@
 // UI Layer
-use crate::calculator::Calculator;
-use crate::operation::Operation;
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use crate::announce::{self, Announcement};
+use crate::batch::{self, BatchLine};
+use crate::business::{self, Known};
+use crate::cashflow;
+use crate::calculator::{Calculator, Pasted};
+use crate::cli::LaunchOptions;
+use crate::color;
+use crate::constants;
+use crate::convert::ConversionUsage;
+use crate::cooking::{self, Ingredient, Measure};
+use crate::datasize;
+use crate::diagnostics;
+use crate::distributions::Distribution;
+use crate::dms;
+use crate::error::CalculatorError;
+use crate::expression::{self, Environment, Functions, ParseOptions};
+use crate::format::{self, CopyFormat, DecimalPlaces, Interpretation, NumberFormat, RoundingSwitch};
+use crate::grid::{self, CellRef};
+use crate::highlight::{self, Class};
+use crate::history::{Favorites, History, HistoryEntry, HistoryFilter, Recall, RecentValues};
+use crate::ieee754::{self, FloatBits};
+use crate::input::{self, InputEvent};
+use crate::instance::{Listener, Message};
+use crate::journal::{self, Journal, Recovered};
+use crate::keypad::{Keypad, KeypadPreset};
+use crate::link;
+use crate::macros::{Macro, MacroRecorder};
+use crate::matrix::{Matrix, Value};
+use crate::montecarlo::{self, Definition, Histogram, Summary};
+use crate::number;
+use crate::operation::OperationRegistry;
+use crate::percent;
+use crate::polynomial;
+use crate::precision;
+use crate::print;
+use crate::programmer::{self, Base, IntOp, OverflowBehavior, Outcome, WordSize};
+use crate::ratio;
+use crate::report::{self, ReportFormat};
+use crate::settings::{Settings, Theme};
+use crate::stats::{self, Tail};
+use crate::symbols::{self, Symbol};
+use crate::task::Task;
+use crate::timestamp::{self, Precision};
+use crate::tip;
+use crate::total::RunningTotal;
+use crate::travel::{self, Economy, Speed};
+use crate::template::Template;
+use crate::tray::{Tray, TrayAction};
+use crate::typeset::{self, Layout};
+use crate::variables::{self, Dependencies, Variables, WatchList};
+use crate::window::CalculatorWindow;
+use crate::words::{self, English};
+use crate::worksheet::{self, WorksheetLine};
+#[cfg(feature = "digest")]
+use crate::digest;
+#[cfg(feature = "scripting")]
+use crate::scripting::{ScriptEngine, DEFAULT_SCRIPT_PATH};
+
+pub const TITLE: &str = "GUI Calculator";
+
+const MACROS_KEY: &str = "macros";
+const FAVORITES_KEY: &str = "favorites";
+const RECENT_KEY: &str = "recent";
+const HISTORY_KEY: &str = "history";
+const SETTINGS_KEY: &str = "settings";
+const WORKSHEET_KEY: &str = "worksheet";
+const GRID_KEY: &str = "grid";
+const VARIABLES_KEY: &str = "variables";
+const WATCHES_KEY: &str = "watches";
+const TOTAL_KEY: &str = "total";
+const INGREDIENTS_KEY: &str = "ingredients";
+const CONVERSIONS_KEY: &str = "conversions";
+// Where File > Export history writes, relative to the working directory
+const HISTORY_EXPORT_PATH: &str = "history.csv";
+// Where File > Print tape writes the PDF to print from
+const TAPE_PATH: &str = "tape.pdf";
+// Seconds of no typing before the expression preview is recomputed
+const PREVIEW_DELAY: f64 = 0.3;
+// Longest gap between two Escape presses that still counts as a double press
+const DOUBLE_ESCAPE_SECONDS: f64 = 0.4;
+// Display font sizes and the most characters each fits in the 280px display;
+// anything longer than the last step scrolls sideways
+const DISPLAY_FONT_STEPS: &[(usize, f32)] = &[(12, 36.0), (16, 28.0), (23, 20.0)];
+const DISPLAY_WIDTH: f32 = 280.0;
+// Height of buttons and fields in touch mode, about a fingertip
+const TOUCH_TARGET: f32 = 44.0;
+// Opens the quick conversions for the displayed number
+const CONVERT_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::U);
+// How many conversions the popup offers
+const QUICK_CONVERSIONS: usize = 6;
+// How many results the history sparkline plots
+const SPARKLINE_POINTS: usize = 30;
+// Samples along the polynomial plot
+const POLYNOMIAL_SAMPLES: usize = 120;
+// Most draws a Monte Carlo run may take
+const MAX_SIMULATION_SAMPLES: usize = 1_000_000;
+
+// Results of evaluating a dropped file
+struct BatchResults {
+    name: String,
+    path: Option<PathBuf>,
+    lines: Vec<BatchLine>,
+    // The evaluation while it's still running; `None` out of it means cancelled
+    running: Option<Task<Option<Vec<BatchLine>>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum PercentQuestion {
+    #[default]
+    PercentOf,
+    Change,
+    IncreaseBy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum Side {
+    #[default]
+    A,
+    B,
+}
+
+// Compare mode: the main calculator is A, this one is B
+#[derive(Default)]
+struct ComparePanel {
+    calculator: Calculator,
+    // Which one the keypad and keyboard type into
+    active: Side,
+}
+
+struct GridPanel {
+    // Row by row, as typed
+    cells: Vec<String>,
+    // Recomputed whenever a cell changes; `None` until the first frame
+    results: Option<Vec<Option<Result<f64, CalculatorError>>>>,
+    selected: usize,
+}
+
+#[derive(Default)]
+struct TemplatesPanel {
+    // The inputs and last result of each saved template, by position
+    values: Vec<Vec<f64>>,
+    results: Vec<Option<Result<f64, CalculatorError>>>,
+    // The template being written; the draft keeps its labels while the
+    // expression is edited
+    name: String,
+    source: String,
+    draft: Option<Result<Template, CalculatorError>>,
+}
+
+#[derive(Default)]
+struct PercentPanel {
+    question: PercentQuestion,
+    a: f64,
+    b: f64,
+}
+
+struct TipPanel {
+    bill: f64,
+    tip_percent: f64,
+    people: u32,
+    round_up: bool,
+}
+
+impl Default for TipPanel {
+    fn default() -> Self {
+        Self {
+            bill: 0.0,
+            tip_percent: 15.0,
+            people: 1,
+            round_up: false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct PricingPanel {
+    known: Known,
+    first: f64,
+    second: f64,
+}
+
+struct CashFlowPanel {
+    // Period 0 first
+    flows: Vec<f64>,
+    rate_percent: f64,
+}
+
+impl Default for CashFlowPanel {
+    fn default() -> Self {
+        Self {
+            flows: vec![-1000.0, 0.0],
+            rate_percent: 10.0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct PolynomialPanel {
+    // Highest power first, e.g. "1, -3, 2"
+    coefficients: String,
+    show_complex: bool,
+}
+
+struct SimulationPanel {
+    // One `name ~ distribution(...)` per line
+    definitions: String,
+    expression: String,
+    samples: usize,
+    // Bumped on each run so running again draws new samples
+    seed: u64,
+    result: Option<Result<Summary, String>>,
+    // The run while it's still going; `None` out of it means cancelled
+    running: Option<Task<Option<Result<Summary, String>>>>,
+}
+
+impl Default for SimulationPanel {
+    fn default() -> Self {
+        Self {
+            definitions: String::new(),
+            expression: String::new(),
+            samples: 10_000,
+            seed: 0,
+            result: None,
+            running: None,
+        }
+    }
+}
+
+// The script functions for work on another thread, which compiles its own
+// copy of them
+struct WorkerFunctions {
+    #[cfg(feature = "scripting")]
+    source: String,
+}
+
+impl WorkerFunctions {
+    fn with<T>(&self, work: impl FnOnce(Option<&dyn Functions>) -> T) -> T {
+        #[cfg(feature = "scripting")]
+        {
+            let mut scripts = ScriptEngine::new();
+            let scripts = scripts.load_source(&self.source).ok().map(|()| scripts);
+            work(scripts.as_ref().map(|scripts| scripts as &dyn Functions))
+        }
+        #[cfg(not(feature = "scripting"))]
+        work(None)
+    }
+}
+
+struct DistributionPanel {
+    distribution: Distribution,
+    x: f64,
+    // For the quantile
+    probability: f64,
+    show_plot: bool,
+}
+
+impl Default for DistributionPanel {
+    fn default() -> Self {
+        Self {
+            distribution: Distribution::DEFAULTS[0],
+            x: 1.96,
+            probability: 0.95,
+            show_plot: false,
+        }
+    }
+}
+
+struct HypothesisPanel {
+    // The population's standard deviation is known, so a z test
+    known_sd: bool,
+    sample_mean: f64,
+    null_mean: f64,
+    std_dev: f64,
+    n: u64,
+    tail: Tail,
+}
+
+impl Default for HypothesisPanel {
+    fn default() -> Self {
+        Self {
+            known_sd: false,
+            sample_mean: 0.0,
+            null_mean: 0.0,
+            std_dev: 1.0,
+            n: 30,
+            tail: Tail::TwoSided,
+        }
+    }
+}
+
+#[derive(Default)]
+struct DataSizePanel {
+    // An amount with its unit, e.g. "500 GB"
+    amount: String,
+    // Label everything as a rate, e.g. Mbit/s
+    per_second: bool,
+}
+
+#[derive(Default)]
+struct TravelPanel {
+    economy: String,
+    economy_unit: Economy,
+    // A speed, or a pace like 5:30
+    speed: String,
+    speed_unit: Speed,
+}
+
+#[derive(Default)]
+struct CookingPanel {
+    amount: String,
+    unit: Measure,
+    query: String,
+    // The picked ingredient's name, needed between volume and weight
+    ingredient: Option<String>,
+    new_name: String,
+    new_grams_per_cup: String,
+    error: Option<CalculatorError>,
+}
+
+#[derive(Default)]
+struct TimestampPanel {
+    // A date to turn back into a timestamp
+    date: String,
+    // Read `date` as local time rather than UTC
+    local: bool,
+}
+
+#[derive(Default)]
+struct ProgrammerPanel {
+    word_size: WordSize,
+    signed: bool,
+    base: Base,
+    a: String,
+    b: String,
+    // The last operation and what it gave
+    result: Option<(IntOp, Result<Outcome, CalculatorError>)>,
+    converter: ConverterPanel,
+    // A color as hex, RGB or HSL
+    color: String,
+}
+
+struct ConverterPanel {
+    input: String,
+    from_radix: u32,
+    to_radix: u32,
+    uppercase: bool,
+}
+
+impl Default for ConverterPanel {
+    fn default() -> Self {
+        Self {
+            input: String::new(),
+            from_radix: 10,
+            to_radix: 16,
+            uppercase: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Basic,
+    Expression,
+    Worksheet,
+    Grid,
+    Programmer,
+    Compare,
+}
+
+impl Mode {
+    const ALL: [Mode; 6] = [
+        Mode::Basic,
+        Mode::Expression,
+        Mode::Worksheet,
+        Mode::Grid,
+        Mode::Programmer,
+        Mode::Compare,
+    ];
+
+    /// The mode with this label, in any case. "scientific" is expression
+    /// mode, which is where the functions are.
+    pub fn from_name(name: &str) -> Option<Mode> {
+        if name.eq_ignore_ascii_case("scientific") {
+            return Some(Mode::Expression);
+        }
+        Self::ALL.into_iter().find(|mode| mode.label().eq_ignore_ascii_case(name))
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Mode::Basic => "Basic",
+            Mode::Expression => "Expression",
+            Mode::Worksheet => "Worksheet",
+            Mode::Grid => "Grid",
+            Mode::Programmer => "Programmer",
+            Mode::Compare => "Compare",
+        }
+    }
+}
 
 pub struct CalculatorApp {
     calculator: Calculator,
+    mode: Mode,
+    expression_input: String,
+    expression_error: Option<String>,
+    // Result of the expression as typed so far, and when to recompute it
+    expression_preview: Option<Result<f64, CalculatorError>>,
+    // Shown instead of the preview when the expression comes out as a matrix
+    matrix_result: Option<Matrix>,
+    preview_due: Option<f64>,
+    // The variables the preview was computed from
+    preview_dependencies: Dependencies,
+    // The expression typeset, while it parses
+    expression_layout: Option<Layout>,
+    new_variable: String,
+    // Expressions kept up to date as variables change
+    watches: WatchList,
+    new_watch: String,
+    // Base whole-number expression results are shown in, e.g. 0xFF
+    output_base: Base,
+    // Selected row of the completion list while it's open
+    autocomplete: Option<usize>,
+    // Up/Down through past expressions, like a shell
+    recall: Recall,
+    worksheet_text: String,
+    // Recomputed whenever the text changes; `None` until the first frame
+    worksheet_results: Option<Vec<WorksheetLine>>,
+    grid: GridPanel,
+    programmer: ProgrammerPanel,
+    compare: ComparePanel,
+    history_query: String,
+    constants_query: String,
+    confirm_clear_history: bool,
+    favorites: Favorites,
+    recent: RecentValues,
+    settings: Settings,
+    // Text to put on the clipboard at the end of the frame
+    clipboard_request: Option<String>,
+    // Message and the time it was shown
+    toast: Option<(String, f64)>,
+    // What screen readers were last told, kept in the accessibility tree
+    announcement: Announcement,
+    // When Escape was last pressed on its own, to spot a double press
+    last_escape: Option<f64>,
+    batch: Option<BatchResults>,
+    show_float_inspector: bool,
+    show_precision: bool,
+    show_full_precision: bool,
+    // Smart cleanup set aside to see the result as computed
+    show_raw_value: bool,
+    show_settings: bool,
+    show_about: bool,
+    show_convert: bool,
+    // Which quick conversions get picked, to offer those first
+    conversions: ConversionUsage,
+    // Every change to the calculator, appended as it happens
+    journal: Option<Journal>,
+    // A session that ended in a crash, until the user restores or drops it
+    recovered: Option<Recovered>,
+    tray: Option<Tray>,
+    // Hears from later launches while single-instance is on
+    instance: Option<Listener>,
+    // The keypad's unary keys and constants
+    operations: OperationRegistry,
+    keypad: Keypad,
+    // Opened with File → New Window, each with its own calculator
+    windows: Vec<CalculatorWindow>,
+    windows_opened: usize,
+    // Hidden to the tray rather than closed
+    window_hidden: bool,
+    // Quit was picked, so a close request is not to be turned into hiding
+    quitting: bool,
+    // Where an arrow key asked the overflowing display to scroll to
+    display_scroll: Option<f32>,
+    // How far the current drag across the display has gone, in touch mode
+    display_swipe: egui::Vec2,
+    // Shows the result as a time, angle or percentage instead of a plain number
+    interpretation: Option<Interpretation>,
+    show_words: bool,
+    // The adding-machine grand total, when the setting is on
+    running_total: RunningTotal,
+    // Lists what went into it, to leave results out
+    show_total_entries: bool,
+    percent: PercentPanel,
+    templates: TemplatesPanel,
+    tip: TipPanel,
+    pricing: PricingPanel,
+    cash_flows: CashFlowPanel,
+    // The four terms of `a : b = c : d` as typed, one of them left empty
+    ratio_terms: [String; 4],
+    polynomial: PolynomialPanel,
+    simulation: SimulationPanel,
+    distribution: DistributionPanel,
+    hypothesis: HypothesisPanel,
+    // Two angles in DMS or decimal degrees
+    angles: [String; 2],
+    timestamp: TimestampPanel,
+    data_size: DataSizePanel,
+    travel: TravelPanel,
+    cooking: CookingPanel,
+    // Ingredients the user added to the density table
+    ingredients: Vec<Ingredient>,
+    recorder: MacroRecorder,
+    macros: Vec<Macro>,
+    // Events captured by the last recording, waiting for a name
+    pending_macro: Option<Vec<InputEvent>>,
+    macro_name: String,
+    #[cfg(feature = "scripting")]
+    scripts: ScriptEngine,
+    #[cfg(feature = "scripting")]
+    script_path: String,
+    #[cfg(feature = "scripting")]
+    script_status: Option<String>,
+    #[cfg(feature = "digest")]
+    digest_input: String,
+    // What was hashed, and its digests; shown in a window while set
+    #[cfg(feature = "digest")]
+    digests: Option<(String, [(&'static str, String); 3])>,
 }
 
-impl CalculatorApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Self {
-            calculator: Calculator::new(),
-        }
+impl CalculatorApp {
+    pub fn new(cc: &eframe::CreationContext<'_>, launch: LaunchOptions) -> Self {
+        let macros = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, MACROS_KEY))
+            .unwrap_or_default();
+        let operations = OperationRegistry::basic();
+        let ingredients = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, INGREDIENTS_KEY))
+            .unwrap_or_default();
+        let favorites = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, FAVORITES_KEY))
+            .unwrap_or_default();
+        let recent = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, RECENT_KEY))
+            .unwrap_or_default();
+        let conversions = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, CONVERSIONS_KEY))
+            .unwrap_or_default();
+        let mut settings: Settings = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, SETTINGS_KEY))
+            .unwrap_or_default();
+        if let Some(theme) = launch.theme {
+            settings.theme = theme;
+        }
+        let mut history: History = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, HISTORY_KEY))
+            .unwrap_or_default();
+        history.set_limit(Some(settings.max_history));
+        let keypad = Keypad::new(&operations, settings.keypad_layout());
+        let variables: Variables = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, VARIABLES_KEY))
+            .unwrap_or_default();
+
+        let worksheet_text = cc
+            .storage
+            .and_then(|storage| storage.get_string(WORKSHEET_KEY))
+            .unwrap_or_default();
+        let mut cells: Vec<String> = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, GRID_KEY))
+            .unwrap_or_default();
+        cells.resize(grid::CELLS, String::new());
+
+        cc.egui_ctx.set_style(app_style(&settings));
+        if settings.frameless_window {
+            cc.egui_ctx.send_viewport_cmd(egui::ViewportCommand::Decorations(false));
+        }
+
+        let mut calculator = Calculator::new();
+        *calculator.history_mut() = history;
+        *calculator.variables_mut() = variables;
+        settings.configure(&mut calculator);
+
+        // The journal is only started over once the user has seen what it had
+        let recovered = journal::recover(journal_dir()).filter(|recovered| recovered.differs_from(&calculator));
+        let journal = recovered.is_none().then(|| Journal::start(journal_dir(), &calculator));
+
+        let mut app = Self {
+            calculator,
+            mode: Mode::Basic,
+            expression_input: String::new(),
+            expression_error: None,
+            preview_dependencies: Dependencies::default(),
+            expression_layout: None,
+            new_variable: String::new(),
+            watches: cc
+                .storage
+                .and_then(|storage| eframe::get_value(storage, WATCHES_KEY))
+                .unwrap_or_default(),
+            new_watch: String::new(),
+            expression_preview: None,
+            matrix_result: None,
+            output_base: Base::Dec,
+            preview_due: None,
+            autocomplete: None,
+            recall: Recall::default(),
+            worksheet_text,
+            worksheet_results: None,
+            grid: GridPanel {
+                cells,
+                results: None,
+                selected: 0,
+            },
+            programmer: ProgrammerPanel::default(),
+            compare: ComparePanel::default(),
+            history_query: String::new(),
+            constants_query: String::new(),
+            confirm_clear_history: false,
+            favorites,
+            recent,
+            settings,
+            clipboard_request: None,
+            toast: None,
+            announcement: Announcement::default(),
+            last_escape: None,
+            batch: None,
+            show_float_inspector: false,
+            show_precision: false,
+            show_full_precision: false,
+            show_raw_value: false,
+            show_settings: false,
+            show_about: false,
+            show_convert: false,
+            conversions,
+            journal,
+            recovered,
+            tray: Tray::new(&cc.egui_ctx),
+            instance: None,
+            operations,
+            keypad,
+            windows: Vec::new(),
+            windows_opened: 1,
+            window_hidden: false,
+            quitting: false,
+            display_scroll: None,
+            display_swipe: egui::Vec2::ZERO,
+            interpretation: None,
+            show_words: false,
+            running_total: cc
+                .storage
+                .and_then(|storage| eframe::get_value(storage, TOTAL_KEY))
+                .unwrap_or_default(),
+            show_total_entries: false,
+            percent: PercentPanel::default(),
+            templates: TemplatesPanel::default(),
+            tip: TipPanel::default(),
+            pricing: PricingPanel::default(),
+            cash_flows: CashFlowPanel::default(),
+            ratio_terms: Default::default(),
+            polynomial: PolynomialPanel::default(),
+            simulation: SimulationPanel::default(),
+            distribution: DistributionPanel::default(),
+            hypothesis: HypothesisPanel::default(),
+            angles: Default::default(),
+            timestamp: TimestampPanel::default(),
+            data_size: DataSizePanel::default(),
+            travel: TravelPanel::default(),
+            cooking: CookingPanel::default(),
+            ingredients,
+            recorder: MacroRecorder::new(),
+            macros,
+            pending_macro: None,
+            macro_name: String::new(),
+            #[cfg(feature = "scripting")]
+            scripts: ScriptEngine::new(),
+            #[cfg(feature = "scripting")]
+            script_path: String::from(DEFAULT_SCRIPT_PATH),
+            #[cfg(feature = "scripting")]
+            script_status: None,
+            #[cfg(feature = "digest")]
+            digest_input: String::new(),
+            #[cfg(feature = "digest")]
+            digests: None,
+        };
+
+        // A missing default script is normal, so only report real errors
+        #[cfg(feature = "scripting")]
+        if std::path::Path::new(DEFAULT_SCRIPT_PATH).exists() {
+            app.reload_scripts();
+        }
+
+        if app.settings.single_instance {
+            app.instance = app.listen(&cc.egui_ctx).ok();
+        }
+        if let Some(value) = launch.value {
+            app.calculator.set_operand(value);
+        }
+        if let Some(source) = launch.expr {
+            app.mode = Mode::Expression;
+            app.expression_input = source;
+            app.evaluate_expression();
+        }
+        if let Some(mode) = launch.mode {
+            app.mode = mode;
+        }
+        app
+    }
+
+    #[cfg(feature = "scripting")]
+    fn reload_scripts(&mut self) {
+        self.script_status = match self.scripts.load_file(&self.script_path) {
+            Ok(()) => Some(format!("Loaded {} function(s)", self.scripts.functions().len())),
+            Err(err) => Some(err.to_string()),
+        };
+        // Watches may call the functions just replaced
+        self.watches.invalidate();
+    }
+
+    #[cfg(feature = "scripting")]
+    fn scripts_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Script functions", |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.script_path);
+                if ui.button("Reload").clicked() {
+                    self.reload_scripts();
+                }
+            });
+            if let Some(status) = &self.script_status {
+                ui.label(status);
+            }
+
+            // One-argument functions act on the displayed value
+            ui.horizontal_wrapped(|ui| {
+                for function in self.scripts.functions() {
+                    let button = ui.add_enabled(function.arity == 1, egui::Button::new(&function.name));
+                    if button.clicked() {
+                        let scripts = &self.scripts;
+                        self.calculator
+                            .apply_function(|value| scripts.call(&function.name, &[value]));
+                    }
+                    button.on_disabled_hover_text(format!("Takes {} arguments", function.arity));
+                }
+            });
+        });
+    }
+
+    fn keypad_ui(&mut self, ui: &mut egui::Ui) {
+        let has_memory = self.calculator.memory().is_some();
+        if let Some(event) = self.keypad.ui(ui, has_memory) {
+            self.dispatch(event);
+        }
+    }
+
+    fn compare_ui(&mut self, ui: &mut egui::Ui) {
+        let panel = &mut self.compare;
+        self.settings.configure(&mut panel.calculator);
+
+        ui.group(|ui| {
+            ui.set_min_width(DISPLAY_WIDTH);
+            ui.horizontal(|ui| {
+                ui.label("B");
+                egui::ScrollArea::horizontal()
+                    .max_width(DISPLAY_WIDTH - 40.0)
+                    .stick_to_right(true)
+                    .show(ui, |ui| {
+                        let shown = self.settings.display_text(&panel.calculator, false);
+                        let text = egui::RichText::new(shown).size(28.0).monospace();
+                        ui.add(egui::Label::new(text).wrap(false));
+                    });
+            });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Type into");
+            ui.selectable_value(&mut panel.active, Side::A, "A");
+            ui.selectable_value(&mut panel.active, Side::B, "B");
+            if ui.small_button("A → B").on_hover_text("Start B from A's value").clicked() {
+                if let Some(value) = self.calculator.display_value() {
+                    panel.calculator.set_operand(value);
+                }
+            }
+        });
+
+        match (self.calculator.display_value(), panel.calculator.display_value()) {
+            (Some(a), Some(b)) => {
+                let (absolute, percent) = percent::difference(a, b);
+                let absolute = ieee754::round_to_significant(absolute, 12);
+                let percent = match percent {
+                    Some(percent) => format!("{:+.2}%", percent),
+                    None => "—".to_string(),
+                };
+                ui.monospace(format!("B − A = {:+}   ({})", absolute, percent));
+            }
+            _ => {
+                ui.weak("Nothing to compare while either side shows an error");
+            }
+        }
+        ui.add_space(10.0);
+
+        let active = panel.active;
+        let has_memory = match active {
+            Side::A => self.calculator.memory().is_some(),
+            Side::B => panel.calculator.memory().is_some(),
+        };
+        match (self.keypad.ui(ui, has_memory), active) {
+            (Some(event), Side::B) => self.compare.calculator.handle_event_with(event, &self.operations),
+            (Some(event), Side::A) => self.dispatch(event),
+            (None, _) => {}
+        }
+    }
+
+    fn expression_ui(&mut self, ui: &mut egui::Ui) {
+        let id = egui::Id::new("expression_input");
+        // The caret from the last frame is close enough for paren matching
+        let mut caret = egui::TextEdit::load_state(ui.ctx(), id)
+            .and_then(|state| state.ccursor_range())
+            .map(|range| range.primary.index);
+
+        // While the completion list is open it takes the arrows, Tab and Enter
+        if let Some(selected) = self.autocomplete {
+            let (up, down, accept) = ui.input_mut(|i| {
+                (
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)
+                        | i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                )
+            });
+            let completions = self.completions(caret);
+            if up {
+                self.autocomplete = Some(selected.saturating_sub(1));
+            }
+            if down {
+                self.autocomplete = Some((selected + 1).min(completions.len().saturating_sub(1)));
+            }
+            if let (true, Some(at), Some(symbol)) = (accept, caret, completions.get(selected)) {
+                let (completed, at) = symbols::complete(&self.expression_input, at, symbol);
+                self.expression_input = completed;
+                let mut state = egui::TextEdit::load_state(ui.ctx(), id).unwrap_or_default();
+                state.set_ccursor_range(Some(egui::text::CCursorRange::one(egui::text::CCursor::new(at))));
+                egui::TextEdit::store_state(ui.ctx(), id, state);
+                caret = Some(at);
+                self.autocomplete = None;
+                self.preview_due = Some(0.0);
+            }
+        } else if ui.memory(|memory| memory.has_focus(id)) {
+            // Otherwise they step through past expressions, caret at the end
+            let (up, down) = ui.input_mut(|i| {
+                (
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                )
+            });
+            let recalled = if up {
+                self.recall.older(self.calculator.history(), &self.expression_input)
+            } else if down {
+                self.recall.newer(self.calculator.history())
+            } else {
+                None
+            };
+            if let Some(source) = recalled {
+                let at = source.chars().count();
+                self.expression_input = source;
+                let mut state = egui::TextEdit::load_state(ui.ctx(), id).unwrap_or_default();
+                state.set_ccursor_range(Some(egui::text::CCursorRange::one(egui::text::CCursor::new(at))));
+                egui::TextEdit::store_state(ui.ctx(), id, state);
+                caret = Some(at);
+                self.expression_error = None;
+                self.preview_due = Some(0.0);
+            }
+        }
+
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let mut job = highlight_job(text, caret, ui);
+            job.wrap.max_width = wrap_width;
+            ui.fonts(|fonts| fonts.layout_job(job))
+        };
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.expression_input)
+                .id(id)
+                .hint_text("e.g. 2 * (3 + 4) or 3x + 5 = 20")
+                .font(egui::TextStyle::Monospace)
+                .desired_width(280.0)
+                .lock_focus(self.autocomplete.is_some())
+                .layouter(&mut layouter),
+        );
+        self.autocomplete_ui(ui, id, &response);
+        let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if response.changed() {
+            // Editing a recalled expression makes it the new draft
+            self.recall.reset();
+            normalize_field(ui.ctx(), id, &mut self.expression_input);
+            // Wait for a pause in typing before evaluating
+            self.expression_error = None;
+            self.preview_due = Some(ui.input(|i| i.time) + PREVIEW_DELAY);
+        }
+        // Recomputed right away when a variable it uses is changed
+        if self.preview_dependencies.changed(self.calculator.variables()) {
+            self.preview_due = Some(0.0);
+        }
+        self.update_preview(ui.input(|i| i.time));
+
+        if let Some(layout) = &self.expression_layout {
+            ui.add_space(6.0);
+            egui::ScrollArea::horizontal()
+                .id_source("expression_typeset")
+                .max_width(280.0)
+                .show(ui, |ui| layout.show(ui, 18.0));
+        }
+
+        ui.add_space(8.0);
+
+        if ui.add_sized([280.0, 40.0], egui::Button::new(egui::RichText::new("=").size(20.0))).clicked()
+            || submitted
+        {
+            self.evaluate_expression();
+            response.request_focus();
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Output").on_hover_text("Base for whole-number results; 0x, 0o and 0b literals compute in integers");
+            for base in [Base::Dec, Base::Hex, Base::Oct, Base::Bin] {
+                ui.selectable_value(&mut self.output_base, base, base.name());
+            }
+        });
+
+        if let Some(error) = &self.expression_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        } else if self.matrix_result.is_some() {
+            self.matrix_ui(ui);
+        } else if let Some(preview) = &self.expression_preview {
+            match preview {
+                Ok(value) => match number::warning(*value) {
+                    Some(warning) => ui.weak(format!("= {} ({})", self.in_output_base(*value), warning)),
+                    None => ui.weak(format!("= {}", self.in_output_base(*value))),
+                },
+                Err(err) => ui.weak(err.to_string()),
+            };
+        }
+
+        ui.add_space(8.0);
+        self.variables_ui(ui);
+        self.watch_ui(ui);
+    }
+
+    fn matrix_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(matrix) = self.matrix_result.clone() else {
+            return;
+        };
+        ui.weak(format!("= {} matrix", matrix.size()));
+        egui::ScrollArea::both().id_source("matrix_result").max_width(280.0).max_height(160.0).show(ui, |ui| {
+            egui::Grid::new("matrix_cells").spacing([12.0, 2.0]).show(ui, |ui| {
+                for row in 0..matrix.rows() {
+                    for col in 0..matrix.cols() {
+                        ui.monospace(self.in_output_base(matrix.get(row, col)));
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        if ui.small_button("Copy").on_hover_text("As a matrix literal").clicked() {
+            self.clipboard_request = Some(matrix.to_string());
+        }
+    }
+
+    // Names matching the one being typed at `caret`, unless it's already
+    // complete and nothing longer matches
+    fn completions(&self, caret: Option<usize>) -> Vec<Symbol> {
+        const MAX_COMPLETIONS: usize = 8;
+
+        let Some((start, end)) = caret.and_then(|caret| symbols::word_before(&self.expression_input, caret)) else {
+            return Vec::new();
+        };
+        let word = &self.expression_input[start..end];
+        let variables: Vec<(&str, f64)> = self
+            .calculator
+            .history()
+            .last()
+            .map(|last| ("ans", last.result))
+            .into_iter()
+            .chain(self.calculator.variables().iter())
+            .collect();
+        let catalog = symbols::catalog(self.user_functions(), &variables);
+        let completions = symbols::completions(&catalog, word);
+        if let [only] = completions.as_slice() {
+            if only.name == word {
+                return Vec::new();
+            }
+        }
+        completions.into_iter().take(MAX_COMPLETIONS).cloned().collect()
+    }
+
+    fn autocomplete_ui(&mut self, ui: &mut egui::Ui, id: egui::Id, response: &egui::Response) {
+        let caret = egui::TextEdit::load_state(ui.ctx(), id)
+            .and_then(|state| state.ccursor_range())
+            .map(|range| range.primary.index);
+        let completions = if response.has_focus() { self.completions(caret) } else { Vec::new() };
+        if completions.is_empty() {
+            self.autocomplete = None;
+            return;
+        }
+        // Typing starts over from the best match
+        let selected = match self.autocomplete {
+            Some(selected) if !response.changed() => selected.min(completions.len() - 1),
+            _ => 0,
+        };
+        self.autocomplete = Some(selected);
+
+        egui::Area::new("autocomplete")
+            .order(egui::Order::Foreground)
+            .fixed_pos(response.rect.left_bottom())
+            .interactable(false)
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for (index, symbol) in completions.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let mut name = egui::RichText::new(&symbol.signature).monospace();
+                            if index == selected {
+                                name = name.background_color(ui.visuals().selection.bg_fill);
+                            }
+                            ui.label(name);
+                            ui.weak(&symbol.doc);
+                        });
+                    }
+                    ui.weak("Tab or Enter to insert");
+                });
+            });
+    }
+
+    fn update_preview(&mut self, now: f64) {
+        match self.preview_due {
+            Some(due) if now >= due => self.preview_due = None,
+            _ => return,
+        }
+        self.expression_preview = if self.expression_input.trim().is_empty() {
+            None
+        } else {
+            Some(self.calculator.preview_expression(&self.expression_input, self.user_functions()))
+        };
+        self.matrix_result = match &self.expression_preview {
+            Some(Err(CalculatorError::Dimension(_))) => self.matrix_value(&self.expression_input),
+            _ => None,
+        };
+        self.preview_dependencies = self.calculator.dependencies(&self.expression_input);
+        let options = self.parse_options();
+        self.expression_layout = match expression::parse_equation(&self.expression_input, options) {
+            Ok(Some((left, right))) => Some(typeset::layout_equation(&left, &right)),
+            Ok(None) => expression::parse_with(&self.expression_input, options)
+                .ok()
+                .map(|expr| typeset::layout(&expr))
+                // A lone number or name looks the same typeset
+                .filter(|layout| !matches!(layout, Layout::Text(_))),
+            Err(_) => None,
+        };
+    }
+
+    fn variables_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Variables", |ui| {
+            let variables = self.calculator.variables();
+            let defined: Vec<(String, f64)> = variables.iter().map(|(name, value)| (name.to_string(), value)).collect();
+            // Names in the expression that nothing gives a value yet
+            let env = Environment::new();
+            let undefined: Vec<String> = self
+                .preview_dependencies
+                .names()
+                .filter(|name| variables.get(name).is_none() && env.lookup(name).is_err() && variables::is_name(name))
+                .map(str::to_string)
+                .collect();
+
+            egui::Grid::new("variables").num_columns(3).show(ui, |ui| {
+                for (name, mut value) in defined {
+                    let used = self.preview_dependencies.names().any(|used| used.eq_ignore_ascii_case(&name));
+                    if used {
+                        ui.strong(&name);
+                    } else {
+                        ui.monospace(&name);
+                    }
+                    if ui.add(egui::DragValue::new(&mut value).speed(0.1)).changed() {
+                        self.calculator.variables_mut().set(&name, value);
+                    }
+                    if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                        self.calculator.variables_mut().remove(&name);
+                    }
+                    ui.end_row();
+                }
+            });
+
+            ui.horizontal_wrapped(|ui| {
+                for name in undefined {
+                    if ui.small_button(format!("+ {}", name)).on_hover_text("Define as 0").clicked() {
+                        self.calculator.variables_mut().set(&name, 0.0);
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.new_variable)
+                        .hint_text("name")
+                        .desired_width(100.0),
+                );
+                let name = self.new_variable.trim();
+                if ui.add_enabled(variables::is_name(name), egui::Button::new("Add")).clicked() {
+                    let name = name.to_string();
+                    self.calculator.variables_mut().set(&name, 0.0);
+                    self.new_variable.clear();
+                }
+            });
+        });
+    }
+
+    fn watch_ui(&mut self, ui: &mut egui::Ui) {
+        let mut watches = std::mem::take(&mut self.watches);
+        let functions = self.user_functions();
+        watches.refresh(self.calculator.variables(), |source| {
+            (self.calculator.preview_expression(source, functions), self.calculator.dependencies(source))
+        });
+        self.watches = watches;
+
+        ui.collapsing("Watch", |ui| {
+            let mut removed = None;
+            egui::Grid::new("watches").num_columns(3).show(ui, |ui| {
+                for (index, watch) in self.watches.watches().iter().enumerate() {
+                    ui.monospace(&watch.source);
+                    match watch.result() {
+                        Some(Ok(value)) => ui.label(format!("= {}", self.in_output_base(*value))),
+                        Some(Err(err)) => ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            err.to_string().trim_start_matches("Error: "),
+                        ),
+                        None => ui.label(""),
+                    };
+                    if ui.small_button("✖").on_hover_text("Stop watching").clicked() {
+                        removed = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(index) = removed {
+                self.watches.remove(index);
+            }
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.new_watch)
+                        .hint_text("subtotal * 1.19")
+                        .desired_width(140.0),
+                );
+                if ui.add_enabled(!self.new_watch.trim().is_empty(), egui::Button::new("Watch")).clicked() {
+                    self.watches.add(&self.new_watch);
+                    self.new_watch.clear();
+                }
+                let current = !self.expression_input.trim().is_empty();
+                if ui
+                    .add_enabled(current, egui::Button::new("Watch this"))
+                    .on_hover_text("Watch the expression being edited")
+                    .clicked()
+                {
+                    self.watches.add(&self.expression_input);
+                }
+            });
+        });
+    }
+
+    fn worksheet_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_top(|ui| {
+            let response = ui.add(
+                egui::TextEdit::multiline(&mut self.worksheet_text)
+                    .code_editor()
+                    .hint_text("price: 19.99\nans * 3\nprice * 1.19")
+                    .desired_rows(10)
+                    .desired_width(170.0),
+            );
+            if response.changed() {
+                normalize_field(ui.ctx(), response.id, &mut self.worksheet_text);
+                self.worksheet_results = None;
+            }
+
+            if self.worksheet_results.is_none() {
+                let results = worksheet::evaluate(&self.worksheet_text, self.user_functions(), self.parse_options());
+                self.worksheet_results = Some(results);
+            }
+
+            // One output row per input row, in the same font so they line up
+            let rows: Vec<String> = self
+                .worksheet_results
+                .iter()
+                .flatten()
+                .map(|line| match &line.result {
+                    Some(Ok(value)) => format!("= {}", value),
+                    Some(Err(err)) => format!("⚠ {}", err.to_string().trim_start_matches("Error: ")),
+                    None => String::new(),
+                })
+                .collect();
+            ui.vertical(|ui| {
+                // Match the text edit's inner margin
+                ui.add_space(2.0);
+                ui.add(egui::Label::new(egui::RichText::new(rows.join("\n")).monospace().weak()).wrap(false));
+            });
+        });
+    }
+
+    fn grid_ui(&mut self, ui: &mut egui::Ui) {
+        if self.grid.results.is_none() {
+            let results = grid::evaluate(&self.grid.cells, self.user_functions(), self.parse_options());
+            self.grid.results = Some(results);
+        }
+        let panel = &mut self.grid;
+        let selected = panel.selected;
+
+        // The formula bar edits the selected cell
+        ui.horizontal(|ui| {
+            ui.monospace(CellRef::from_index(selected).name());
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut panel.cells[selected])
+                    .font(egui::TextStyle::Monospace)
+                    .hint_text("42 or =A1 * 2")
+                    .desired_width(DISPLAY_WIDTH - 40.0),
+            );
+            if response.changed() {
+                normalize_field(ui.ctx(), response.id, &mut panel.cells[selected]);
+                panel.results = None;
+            }
+        });
+        ui.add_space(6.0);
+
+        let results = panel.results.as_deref().unwrap_or_default();
+        egui::Grid::new("grid_cells").spacing([2.0, 2.0]).show(ui, |ui| {
+            ui.label("");
+            for column in 0..grid::COLUMNS {
+                ui.vertical_centered(|ui| ui.strong(grid::column_name(column).to_string()));
+            }
+            ui.end_row();
+            for row in 0..grid::ROWS {
+                ui.weak((row + 1).to_string());
+                for column in 0..grid::COLUMNS {
+                    let index = CellRef { column, row }.index();
+                    let (text, error) = match results.get(index).cloned().flatten() {
+                        Some(Ok(value)) => (value.to_string(), None),
+                        Some(Err(err)) => ("⚠".to_string(), Some(err.to_string())),
+                        None => (panel.cells[index].trim().to_string(), None),
+                    };
+                    let mut response = ui.add_sized(
+                        [54.0, 20.0],
+                        egui::SelectableLabel::new(index == selected, egui::RichText::new(text).monospace()),
+                    );
+                    if let Some(error) = error {
+                        response = response.on_hover_text(error);
+                    }
+                    if response.clicked() {
+                        panel.selected = index;
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    fn programmer_ui(&mut self, ui: &mut egui::Ui) {
+        const OPERATIONS: [IntOp; 9] = [
+            IntOp::Add,
+            IntOp::Subtract,
+            IntOp::Multiply,
+            IntOp::Divide,
+            IntOp::And,
+            IntOp::Or,
+            IntOp::Xor,
+            IntOp::ShiftLeft,
+            IntOp::ShiftRight,
+        ];
+
+        let panel = &mut self.programmer;
+        let (old_size, old_base) = (panel.word_size, panel.base);
+        ui.horizontal(|ui| {
+            for size in WordSize::ALL {
+                ui.selectable_value(&mut panel.word_size, size, format!("{}-bit", size.bits()));
+            }
+        });
+        ui.horizontal(|ui| {
+            for base in Base::ALL {
+                ui.selectable_value(&mut panel.base, base, base.name());
+            }
+            ui.checkbox(&mut panel.signed, "Signed");
+        });
+
+        // Keep the operands' values when their notation changes
+        if panel.base != old_base || panel.word_size != old_size {
+            for text in [&mut panel.a, &mut panel.b] {
+                if let Ok(value) = old_base.parse(text, old_size) {
+                    *text = panel.base.format(value, panel.word_size, panel.signed);
+                }
+            }
+            panel.result = None;
+        }
+
+        egui::Grid::new("programmer_operands").show(ui, |ui| {
+            for (label, text) in [("A", &mut panel.a), ("B", &mut panel.b)] {
+                ui.label(label);
+                ui.add(egui::TextEdit::singleline(text).font(egui::TextStyle::Monospace).desired_width(220.0));
+                ui.end_row();
+            }
+        });
+        self.bitfield_ui(ui);
+        self.byte_view_ui(ui);
+        let panel = &mut self.programmer;
+
+        ui.horizontal_wrapped(|ui| {
+            for op in OPERATIONS {
+                if ui.add_sized([40.0, 30.0], egui::Button::new(op.symbol())).clicked() {
+                    let result = panel
+                        .base
+                        .parse(&panel.a, panel.word_size)
+                        .and_then(|a| Ok((a, panel.base.parse(&panel.b, panel.word_size)?)))
+                        .and_then(|(a, b)| programmer::apply(op, a, b, panel.word_size, panel.signed))
+                        .and_then(|outcome| programmer::check(outcome, panel.signed, self.settings.integer_overflow));
+                    panel.result = Some((op, result));
+                }
+            }
+        });
+
+        ui.add_space(8.0);
+
+        match &panel.result {
+            Some((op, Ok(outcome))) => {
+                egui::Grid::new("programmer_result").show(ui, |ui| {
+                    for base in Base::ALL {
+                        ui.weak(base.name());
+                        ui.monospace(base.format(outcome.value, panel.word_size, panel.signed));
+                        ui.end_row();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let flag = |ui: &mut egui::Ui, name: &str, set: bool, hint: &str| {
+                        let text = egui::RichText::new(name).monospace();
+                        let text = if set { text.strong() } else { text.weak() };
+                        ui.label(text).on_hover_text(hint);
+                    };
+                    let carry_hint = if *op == IntOp::Subtract { "Borrow" } else { "Carry" };
+                    flag(ui, "C", outcome.carry, carry_hint);
+                    flag(ui, "V", outcome.overflow, "Signed overflow");
+                    if outcome.wrapped(panel.signed) {
+                        ui.colored_label(ui.visuals().warn_fg_color, "overflow (wrapped)");
+                    }
+                });
+                if ui.small_button("Use as A").clicked() {
+                    panel.a = panel.base.format(outcome.value, panel.word_size, panel.signed);
+                    panel.b.clear();
+                }
+            }
+            Some((_, Err(err))) => {
+                ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+            }
+            None => {}
+        }
+
+        ui.add_space(8.0);
+        self.base_converter_ui(ui);
+        self.color_ui(ui);
+        #[cfg(feature = "digest")]
+        self.digest_ui(ui);
+    }
+
+    // The bits of operand A, highest first and grouped by byte. Clicking a bit
+    // flips it and writes the new value back into A.
+    fn bitfield_ui(&mut self, ui: &mut egui::Ui) {
+        let panel = &mut self.programmer;
+        let value = if panel.a.trim().is_empty() {
+            0
+        } else {
+            match panel.base.parse(&panel.a, panel.word_size) {
+                Ok(value) => value,
+                Err(_) => {
+                    ui.weak("Enter a valid A to see its bits");
+                    return;
+                }
+            }
+        };
+
+        let bits = panel.word_size.bits();
+        let mut toggled = None;
+        // Four bytes to a row, so 64 bits take two
+        for row in (0..bits.div_ceil(32)).rev() {
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 1.0;
+                let high = (row * 32 + 31).min(bits - 1);
+                for bit in (row * 32..=high).rev() {
+                    let set = value & (1 << bit) != 0;
+                    let text = egui::RichText::new(if set { "1" } else { "0" }).monospace();
+                    let text = if set { text.strong() } else { text.weak() };
+                    if ui.selectable_label(set, text).on_hover_text(format!("Bit {}", bit)).clicked() {
+                        toggled = Some(bit);
+                    }
+                    if bit % 8 == 0 && bit != row * 32 {
+                        ui.add_space(6.0);
+                    }
+                }
+                ui.weak(format!(" {}", row * 32));
+            });
+        }
+
+        if let Some(bit) = toggled {
+            let value = panel.word_size.toggle_bit(value, bit);
+            panel.a = panel.base.format(value, panel.word_size, panel.signed);
+            panel.result = None;
+        }
+    }
+
+    // Operand A as bytes in both orders and as each narrower integer type
+    fn byte_view_ui(&mut self, ui: &mut egui::Ui) {
+        let panel = &self.programmer;
+        let Ok(value) = panel.base.parse(&panel.a, panel.word_size) else {
+            return;
+        };
+        let size = panel.word_size;
+        ui.collapsing("Bytes", |ui| {
+            egui::Grid::new("byte_view").show(ui, |ui| {
+                for (name, little_endian) in [("Little-endian", true), ("Big-endian", false)] {
+                    let bytes = programmer::bytes(value, size, little_endian);
+                    let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+                    ui.weak(name);
+                    ui.monospace(hex.join(" "));
+                    ui.monospace(programmer::ascii(&bytes)).on_hover_text("As ASCII, . where not printable");
+                    ui.end_row();
+                }
+                let swapped = programmer::swap_bytes(value, size);
+                ui.weak("Byte-swapped");
+                ui.monospace(Base::Hex.format(swapped, size, false));
+                ui.monospace(swapped.to_string());
+                ui.end_row();
+            });
+
+            ui.add_space(4.0);
+            egui::Grid::new("byte_view_widths").striped(true).show(ui, |ui| {
+                ui.weak("Width");
+                ui.weak("Unsigned");
+                ui.weak("Signed");
+                ui.end_row();
+                for width in WordSize::ALL.into_iter().filter(|width| width.bits() <= size.bits()) {
+                    let low = value & width.mask();
+                    ui.weak(format!("{}-bit", width.bits()));
+                    ui.monospace(low.to_string());
+                    ui.monospace(width.to_signed(low).to_string());
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    #[cfg(feature = "digest")]
+    fn digest_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Checksums", |ui| {
+            ui.add(
+                egui::TextEdit::multiline(&mut self.digest_input)
+                    .hint_text("Text to hash, or leave empty for the display")
+                    .desired_rows(2)
+                    .desired_width(280.0),
+            );
+            if ui.button("CRC32 / MD5 / SHA-256").clicked() {
+                let text = if self.digest_input.is_empty() {
+                    self.calculator.get_display_text().to_string()
+                } else {
+                    self.digest_input.clone()
+                };
+                self.digests = Some((text.clone(), digest::digests(&text)));
+            }
+        });
+    }
+
+    #[cfg(feature = "digest")]
+    fn digests_window_ui(&mut self, ctx: &egui::Context) {
+        let Some((text, digests)) = &self.digests else {
+            return;
+        };
+        let mut open = true;
+        let mut copied = None;
+        egui::Window::new("Checksums")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                let preview: String = text.chars().take(40).collect();
+                let ellipsis = if preview.len() < text.len() { "…" } else { "" };
+                ui.weak(format!("Of \"{}{}\" ({} bytes)", preview, ellipsis, text.len()));
+                egui::Grid::new("digests").show(ui, |ui| {
+                    for (name, value) in digests {
+                        ui.weak(*name);
+                        ui.monospace(value);
+                        if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                            copied = Some(value.clone());
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        if copied.is_some() {
+            self.clipboard_request = copied;
+        }
+        if !open {
+            self.digests = None;
+        }
+    }
+
+    fn color_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Color", |ui| {
+            let panel = &mut self.programmer;
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut panel.color)
+                        .hint_text("#FF8000, 255, 128, 0 or hsl(30, 100%, 50%)")
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(220.0),
+                );
+                // Operand A as 0xRRGGBB
+                let from_a = panel
+                    .base
+                    .parse(&panel.a, panel.word_size)
+                    .ok()
+                    .and_then(|value| u32::try_from(value).ok())
+                    .and_then(color::Rgb::from_integer);
+                if ui
+                    .add_enabled(from_a.is_some(), egui::Button::new("From A").small())
+                    .on_hover_text("Read operand A as 0xRRGGBB")
+                    .clicked()
+                {
+                    if let Some(rgb) = from_a {
+                        panel.color = rgb.hex();
+                    }
+                }
+            });
+            if panel.color.trim().is_empty() {
+                return;
+            }
+            let rgb = match color::parse(&panel.color) {
+                Ok(rgb) => rgb,
+                Err(err) => {
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                    return;
+                }
+            };
+
+            let mut copied = None;
+            ui.horizontal(|ui| {
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(64.0, 64.0), egui::Sense::hover());
+                ui.painter().rect_filled(rect, 4.0, egui::Color32::from_rgb(rgb.r, rgb.g, rgb.b));
+                ui.painter().rect_stroke(rect, 4.0, ui.visuals().widgets.noninteractive.bg_stroke);
+                egui::Grid::new("color_forms").show(ui, |ui| {
+                    for (label, text) in [
+                        ("Hex", rgb.hex()),
+                        ("RGB", rgb.to_string()),
+                        ("HSL", rgb.to_hsl().to_string()),
+                        ("Integer", rgb.to_integer().to_string()),
+                    ] {
+                        ui.weak(label);
+                        ui.monospace(&text);
+                        if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                            copied = Some(text);
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+            if copied.is_some() {
+                self.clipboard_request = copied;
+            }
+        });
+    }
+
+    fn base_converter_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Base converter", |ui| {
+            let converter = &mut self.programmer.converter;
+            ui.horizontal(|ui| {
+                ui.label("From base");
+                ui.add(egui::DragValue::new(&mut converter.from_radix).clamp_range(2..=36));
+                ui.label("to");
+                ui.add(egui::DragValue::new(&mut converter.to_radix).clamp_range(2..=36));
+                if ui.small_button("⇄").on_hover_text("Swap bases").clicked() {
+                    // Convert the input too so the value stays the same
+                    if let Ok(value) = programmer::from_radix(&converter.input, converter.from_radix) {
+                        converter.input = programmer::to_radix(value, converter.to_radix, converter.uppercase);
+                    }
+                    std::mem::swap(&mut converter.from_radix, &mut converter.to_radix);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut converter.input)
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(200.0),
+                );
+                // Whole numbers on the main display can be converted directly
+                let current = self.calculator.display_value().filter(|value| value.fract() == 0.0);
+                if ui
+                    .add_enabled(current.is_some(), egui::Button::new("Display").small())
+                    .on_hover_text("Convert the integer on the display")
+                    .clicked()
+                {
+                    if let Some(value) = current {
+                        converter.input = programmer::to_radix(value as i128, converter.from_radix, converter.uppercase);
+                    }
+                }
+            });
+            ui.checkbox(&mut converter.uppercase, "Uppercase letters");
+
+            if converter.input.trim().is_empty() {
+                return;
+            }
+            match programmer::from_radix(&converter.input, converter.from_radix) {
+                Ok(value) => {
+                    let output = programmer::to_radix(value, converter.to_radix, converter.uppercase);
+                    ui.horizontal(|ui| {
+                        ui.monospace(format!("= {}", output));
+                        if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                            self.clipboard_request = Some(output);
+                        }
+                    });
+                }
+                Err(err) => {
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                }
+            }
+        });
+    }
+
+    fn evaluate_expression(&mut self) {
+        let last_id = self.last_history_id();
+        let source = self.expression_input.clone();
+        let options = self.parse_options();
+        let (calculator, functions) = self.calculator_with_functions();
+        let mut error = calculator.evaluate_expression(&source, functions).err();
+        // A matrix result is shown, though only numbers go into the history
+        self.matrix_result = match error {
+            Some(CalculatorError::Dimension(_)) => self.matrix_value(&source),
+            _ => None,
+        };
+        if self.matrix_result.is_some() {
+            error = None;
+        }
+        if let Some(err) = &error {
+            // Read what was typed when it doesn't parse
+            let operation = expression::parse_with(&source, options)
+                .map_or_else(|_| source.trim().to_string(), |expr| announce::spoken(&expr));
+            self.announcement = Announcement::new(&operation, &Err(err.clone()));
+        }
+        self.expression_error = error.map(|err| err.to_string());
+        self.expression_preview = None;
+        self.preview_due = None;
+        self.recall.reset();
+        self.after_calculation(last_id);
+    }
+
+    fn matrix_value(&self, source: &str) -> Option<Matrix> {
+        match self.calculator.evaluate_matrix(source, self.user_functions()) {
+            Ok(Value::Matrix(matrix)) => Some(matrix),
+            _ => None,
+        }
+    }
+
+    fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            implicit_multiplication: self.settings.implicit_multiplication,
+        }
+    }
+
+    fn user_functions(&self) -> Option<&dyn Functions> {
+        #[cfg(feature = "scripting")]
+        return Some(&self.scripts);
+        #[cfg(not(feature = "scripting"))]
+        return None;
+    }
+
+    // The calculator together with the user's script functions, borrowed apart
+    fn calculator_with_functions(&mut self) -> (&mut Calculator, Option<&dyn Functions>) {
+        #[cfg(feature = "scripting")]
+        return (&mut self.calculator, Some(&self.scripts));
+        #[cfg(not(feature = "scripting"))]
+        return (&mut self.calculator, None);
+    }
+
+    fn paste(&mut self, ctx: &egui::Context, text: &str) {
+        let last_id = self.last_history_id();
+        let (calculator, functions) = self.calculator_with_functions();
+        let message = match calculator.paste(text, functions) {
+            Ok(Pasted::Number(value)) => format!("Pasted {}", value),
+            Ok(Pasted::Expression { source, result }) => format!("{} = {}", source, result),
+            Err(err) => format!("Couldn't paste: {}", err),
+        };
+        self.after_calculation(last_id);
+        self.show_toast(ctx, message);
+    }
+
+    fn history_ui(&mut self, ui: &mut egui::Ui) {
+        self.sparkline_ui(ui);
+        ui.collapsing("History", |ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.history_query)
+                    .hint_text("🔍 Search, e.g. sqrt or > 1000")
+                    .desired_width(280.0),
+            );
+            let filter = HistoryFilter::parse(&self.history_query);
+
+            egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                let mut recalled = None;
+                let mut pinned = None;
+                let mut deleted = None;
+                let mut operand = None;
+                let mut copied = None;
+                let mut shown = 0;
+                let entries = self.calculator.history().entries().iter().rev();
+                for entry in entries.filter(|entry| filter.matches(entry)) {
+                    shown += 1;
+                    ui.horizontal(|ui| {
+                        let is_pinned = self.favorites.is_pinned(entry);
+                        let star = if is_pinned { "★" } else { "☆" };
+                        if ui.add_enabled(!is_pinned, egui::Button::new(star).small()).clicked() {
+                            pinned = Some(entry.clone());
+                        }
+                        let text = format!("{} = {}", entry.source, self.rounded(entry.result));
+                        let response = ui.selectable_label(false, egui::RichText::new(text).monospace());
+                        if response.clicked() {
+                            recalled = Some(entry.clone());
+                        }
+                        response.context_menu(|ui| {
+                            if ui.button("Copy result").clicked() {
+                                copied = Some(entry.result.to_string());
+                                ui.close_menu();
+                            }
+                            if ui.button("Copy expression").clicked() {
+                                copied = Some(entry.source.clone());
+                                ui.close_menu();
+                            }
+                            if ui.button("Use as first operand").clicked() {
+                                operand = Some(entry.result);
+                                ui.close_menu();
+                            }
+                            if ui.add_enabled(!is_pinned, egui::Button::new("Pin")).clicked() {
+                                pinned = Some(entry.clone());
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            if ui.button("Delete").clicked() {
+                                deleted = Some(entry.id);
+                                ui.close_menu();
+                            }
+                        });
+                    });
+                }
+
+                if shown == 0 && !filter.is_empty() {
+                    ui.weak("No matching entries");
+                }
+
+                if let Some(entry) = pinned {
+                    self.favorites.pin(entry);
+                }
+                if let Some(entry) = recalled {
+                    self.recall(entry);
+                }
+                if let Some(id) = deleted {
+                    self.calculator.history_mut().remove(id);
+                }
+                if let Some(value) = operand {
+                    // A fresh calculation starting from the value
+                    self.calculator.clear();
+                    self.calculator.set_operand(value);
+                }
+                if copied.is_some() {
+                    self.clipboard_request = copied;
+                }
+            });
+
+            let count = self.calculator.history().entries().len();
+            if self.confirm_clear_history {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Delete {} entries?", count));
+                    if ui.button("Yes").clicked() {
+                        self.calculator.history_mut().clear();
+                        self.confirm_clear_history = false;
+                    }
+                    if ui.button("No").clicked() {
+                        self.confirm_clear_history = false;
+                    }
+                });
+            } else if ui.add_enabled(count > 0, egui::Button::new("Clear history")).clicked() {
+                self.confirm_clear_history = true;
+            }
+        });
+    }
+
+    // The trend of recent results as a small line, with the hovered one labelled
+    fn sparkline_ui(&self, ui: &mut egui::Ui) {
+        let results = self.calculator.history().recent_results(SPARKLINE_POINTS);
+        if results.len() < 2 {
+            return;
+        }
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(DISPLAY_WIDTH, 32.0), egui::Sense::hover());
+        let rect = rect.shrink(3.0);
+        let data: Vec<(f64, f64)> = results.iter().enumerate().map(|(index, &result)| (index as f64, result)).collect();
+        let frame = PlotFrame::fit(rect, &data);
+        let step = rect.width() / (results.len() - 1) as f32;
+
+        let painter = ui.painter_at(response.rect);
+        let color = ui.visuals().selection.stroke.color;
+        let points = frame.line(&painter, &data, egui::Stroke::new(1.5, color));
+        painter.circle_filled(points[points.len() - 1], 2.5, color);
+
+        let hovered = response
+            .hover_pos()
+            .map(|pointer| (((pointer.x - rect.left()) / step).round().max(0.0) as usize).min(results.len() - 1));
+        match hovered {
+            Some(index) => {
+                painter.circle_filled(points[index], 3.0, ui.visuals().strong_text_color());
+                response.on_hover_text(results[index].to_string());
+            }
+            None => {
+                response.on_hover_text(format!("Last {} results", results.len()));
+            }
+        }
+    }
+
+    fn settings_ui(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_settings;
+        egui::Window::new("Settings").open(&mut open).resizable(false).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("History size:");
+                let changed = ui
+                    .add(egui::DragValue::new(&mut self.settings.max_history).clamp_range(1..=1000))
+                    .changed();
+                if changed {
+                    let limit = Some(self.settings.max_history);
+                    self.calculator.history_mut().set_limit(limit);
+                }
+            });
+            ui.checkbox(&mut self.settings.auto_copy_result, "Copy results to clipboard");
+            ui.horizontal(|ui| {
+                ui.label("Keypad:");
+                for preset in KeypadPreset::ALL {
+                    ui.selectable_value(&mut self.settings.keypad, preset, preset.label());
+                }
+            });
+            if ui
+                .checkbox(&mut self.settings.touch_mode, "Touch mode")
+                .on_hover_text("Larger tap targets; swipe left on the display to delete a digit")
+                .changed()
+            {
+                ctx.set_style(app_style(&self.settings));
+            }
+            ui.horizontal(|ui| {
+                ui.label("Numbers:");
+                let format = &mut self.settings.number_format;
+                ui.selectable_value(format, NumberFormat::Plain, "Plain");
+                ui.selectable_value(format, NumberFormat::SiPrefix, "SI prefix");
+                ui.selectable_value(format, NumberFormat::Engineering, "Engineering");
+                ui.selectable_value(format, NumberFormat::Money, "Money");
+            });
+            if self.settings.number_format == NumberFormat::Money {
+                let money = &mut self.settings.money;
+                ui.horizontal(|ui| {
+                    ui.label("Currency:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut money.symbol)
+                            .hint_text("e.g. €")
+                            .desired_width(60.0),
+                    );
+                    for symbol in ["$", "€", "£", "¥", "₽"] {
+                        if ui.small_button(symbol).clicked() {
+                            money.symbol = symbol.to_string();
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut money.bankers_rounding, "Banker's rounding")
+                        .on_hover_text("Round half a cent to the even cent: 0.125 → 0.12, 0.135 → 0.14");
+                    ui.checkbox(&mut money.accounting, "Negatives in parentheses")
+                        .on_hover_text("Accounting style: ($5.00) instead of -$5.00");
+                });
+            }
+            let scaled = matches!(self.settings.number_format, NumberFormat::SiPrefix | NumberFormat::Engineering);
+            ui.add_enabled_ui(scaled, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Unit:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.settings.unit_suffix)
+                            .hint_text("e.g. Ω")
+                            .desired_width(60.0),
+                    );
+                    for unit in ["Ω", "V", "A", "F", "Hz"] {
+                        if ui.small_button(unit).clicked() {
+                            self.settings.unit_suffix = unit.to_string();
+                        }
+                    }
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.settings.smart_cleanup, "Smart cleanup")
+                    .on_hover_text("Show results a hair away from a short decimal as that decimal, e.g. 2.9999999999999996 as 3");
+                ui.add_enabled_ui(self.settings.smart_cleanup, |ui| {
+                    ui.label("within");
+                    for epsilon in [1e-15, 1e-14, 1e-12, 1e-10] {
+                        ui.selectable_value(&mut self.settings.cleanup_epsilon, epsilon, format!("{:e}", epsilon))
+                            .on_hover_text("Relative to the result");
+                    }
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.label("Rounding:")
+                    .on_hover_text("A desk calculator's switch: F shows results as computed, CUT drops decimals, 5/4 rounds");
+                for switch in RoundingSwitch::ALL {
+                    ui.selectable_value(&mut self.settings.rounding, switch, switch.label());
+                }
+                for places in DecimalPlaces::ALL {
+                    let hint = match places {
+                        DecimalPlaces::Add2 => "Two places, and numbers typed without a point are cents: 1995 is 19.95",
+                        _ => "Places to cut or round to; F ignores them",
+                    };
+                    if ui
+                        .selectable_value(&mut self.settings.decimal_places, places, places.label())
+                        .on_hover_text(hint)
+                        .changed()
+                    {
+                        self.calculator.set_add_mode(self.settings.add_mode());
+                    }
+                }
+            });
+            ui.checkbox(&mut self.settings.running_total, "Running total")
+                .on_hover_text("Add every result to a grand total under the display, like an adding machine");
+            if ui.checkbox(&mut self.settings.decimal_mode, "Decimal arithmetic").changed() {
+                self.calculator.set_decimal_mode(self.settings.decimal_mode);
+            }
+            if ui
+                .checkbox(&mut self.settings.implicit_multiplication, "Implicit multiplication")
+                .on_hover_text("Read 2(3+4) and 3π as products")
+                .changed()
+            {
+                self.calculator.set_parse_options(self.parse_options());
+                self.worksheet_results = None;
+                self.grid.results = None;
+                self.preview_due = Some(0.0);
+            }
+            if ui
+                .checkbox(&mut self.settings.frameless_window, "Frameless window")
+                .on_hover_text("Replace the system title bar with a compact one")
+                .changed()
+            {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Decorations(!self.settings.frameless_window));
+            }
+            let single_instance = ui
+                .checkbox(&mut self.settings.single_instance, "Single instance")
+                .on_hover_text("Launching the calculator again brings this window forward");
+            if single_instance.changed() {
+                self.instance = None;
+                if self.settings.single_instance {
+                    match self.listen(ctx) {
+                        Ok(listener) => self.instance = Some(listener),
+                        Err(err) => {
+                            self.settings.single_instance = false;
+                            self.show_toast(ctx, format!("Couldn't listen for other launches: {}", err));
+                        }
+                    }
+                }
+            }
+            ui.add_enabled_ui(self.tray.is_some(), |ui| {
+                ui.checkbox(&mut self.settings.minimize_to_tray, "Minimize to tray")
+                    .on_hover_text("Keep running in the tray when the window is closed or minimized")
+                    .on_disabled_hover_text("Needs the tray feature on Windows or macOS");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Integer overflow:");
+                ui.radio_value(&mut self.settings.integer_overflow, OverflowBehavior::Wrap, "Wrap");
+                ui.radio_value(&mut self.settings.integer_overflow, OverflowBehavior::Error, "Error");
+            });
+        });
+        self.show_settings = open;
+    }
+
+    // Stands in for the system title bar of a frameless window: drag to move,
+    // double-click to maximize. The window buttons sit on the left on macOS
+    // and on the right elsewhere, as the platforms do it.
+    fn title_bar_ui(&mut self, ui: &mut egui::Ui) {
+        let rect = ui.max_rect();
+        let bar = ui.interact(rect, egui::Id::new("title_bar"), egui::Sense::click());
+        if bar.double_clicked() {
+            let maximized = ui.input(|i| i.viewport().maximized.unwrap_or(false));
+            ui.ctx().send_viewport_cmd(egui::ViewportCommand::Maximized(!maximized));
+        } else if bar.is_pointer_button_down_on() {
+            ui.ctx().send_viewport_cmd(egui::ViewportCommand::StartDrag);
+        }
+
+        ui.painter().text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            TITLE,
+            egui::FontId::proportional(14.0),
+            ui.visuals().text_color(),
+        );
+
+        let layout = if cfg!(target_os = "macos") {
+            egui::Layout::left_to_right(egui::Align::Center)
+        } else {
+            egui::Layout::right_to_left(egui::Align::Center)
+        };
+        ui.with_layout(layout, |ui| {
+            if ui.small_button("❌").on_hover_text("Close").clicked() {
+                ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+            if ui.small_button("🗕").on_hover_text("Minimize").clicked() {
+                ui.ctx().send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+            }
+        });
+    }
+
+    // File, Edit, View, Mode and Help along the top of the window
+    fn menu_bar_ui(&mut self, ui: &mut egui::Ui) {
+        egui::menu::bar(ui, |ui| {
+            ui.menu_button("File", |ui| {
+                if ui.button("New Window").clicked() {
+                    self.windows_opened += 1;
+                    self.windows.push(CalculatorWindow::new(self.windows_opened, &self.operations, &self.settings));
+                    ui.close_menu();
+                }
+                ui.separator();
+                let has_history = !self.calculator.history().entries().is_empty();
+                if ui.add_enabled(has_history, egui::Button::new("Export history as CSV")).clicked() {
+                    self.export_history(ui.ctx());
+                    ui.close_menu();
+                }
+                for format in ReportFormat::ALL {
+                    let label = format!("Export history as {}", format.label());
+                    if ui.add_enabled(has_history, egui::Button::new(label)).clicked() {
+                        let rows = report::history_rows(self.calculator.history());
+                        self.export_report(ui.ctx(), "history", &rows, format);
+                        ui.close_menu();
+                    }
+                }
+                if ui
+                    .add_enabled(has_history, egui::Button::new("Print tape…"))
+                    .on_hover_text(format!("Save the history as {} to print", TAPE_PATH))
+                    .clicked()
+                {
+                    self.print_tape(ui.ctx());
+                    ui.close_menu();
+                }
+                let has_worksheet = !self.worksheet_text.trim().is_empty();
+                for format in ReportFormat::ALL {
+                    let label = format!("Export worksheet as {}", format.label());
+                    if ui.add_enabled(has_worksheet, egui::Button::new(label)).clicked() {
+                        let lines = worksheet::evaluate(&self.worksheet_text, self.user_functions(), self.parse_options());
+                        let rows = report::worksheet_rows(&self.worksheet_text, &lines);
+                        self.export_report(ui.ctx(), "worksheet", &rows, format);
+                        ui.close_menu();
+                    }
+                }
+                ui.separator();
+                if ui.button("Quit").clicked() {
+                    ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+            ui.menu_button("Edit", |ui| self.display_menu_ui(ui));
+            ui.menu_button("View", |ui| {
+                for (theme, label) in [(Theme::Dark, "Dark theme"), (Theme::Light, "Light theme")] {
+                    if ui.radio_value(&mut self.settings.theme, theme, label).clicked() {
+                        ui.ctx().set_style(app_style(&self.settings));
+                    }
+                }
+                ui.separator();
+                ui.checkbox(&mut self.show_float_inspector, "IEEE-754 inspector");
+                ui.checkbox(&mut self.show_precision, "Precision comparison");
+                ui.checkbox(&mut self.show_words, "Number in words");
+                ui.separator();
+                if ui.button("Settings…").clicked() {
+                    self.show_settings = true;
+                    ui.close_menu();
+                }
+            });
+            ui.menu_button("Mode", |ui| {
+                for mode in Mode::ALL {
+                    if ui.radio_value(&mut self.mode, mode, mode.label()).clicked() {
+                        ui.close_menu();
+                    }
+                }
+            });
+            ui.menu_button("Help", |ui| {
+                if ui.button("About").clicked() {
+                    self.show_about = true;
+                    ui.close_menu();
+                }
+            });
+        });
+    }
+
+    fn listen(&self, ctx: &egui::Context) -> std::io::Result<Listener> {
+        let ctx = ctx.clone();
+        Listener::start(move || ctx.request_repaint())
+    }
+
+    // What later launches asked for while single-instance is on
+    fn instance_messages(&mut self, ctx: &egui::Context) {
+        let Some(instance) = &self.instance else {
+            return;
+        };
+        for message in instance.poll() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            self.set_window_hidden(ctx, false);
+            if let Message::Expression(source) = message {
+                self.mode = Mode::Expression;
+                self.expression_input = source;
+                self.evaluate_expression();
+            }
+        }
+    }
+
+    fn set_window_hidden(&mut self, ctx: &egui::Context, hidden: bool) {
+        self.window_hidden = hidden;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Visible(!hidden));
+        if !hidden {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+    }
+
+    // Tray menu picks, and closing or minimizing to the tray
+    fn tray_events(&mut self, ctx: &egui::Context) {
+        let Some(tray) = &self.tray else {
+            return;
+        };
+        for action in tray.poll() {
+            match action {
+                TrayAction::ToggleWindow => self.set_window_hidden(ctx, !self.window_hidden),
+                TrayAction::CopyLastResult => {
+                    if let Some(entry) = self.calculator.history().last() {
+                        // Straight to the clipboard, as the window may be hidden
+                        let text = entry.result.to_string();
+                        let _ = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text));
+                    }
+                }
+                TrayAction::Quit => {
+                    self.quitting = true;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            }
+        }
+
+        if !self.settings.minimize_to_tray || self.quitting {
+            return;
+        }
+        let (close, minimized) = ctx.input(|i| (i.viewport().close_requested(), i.viewport().minimized == Some(true)));
+        if close {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.set_window_hidden(ctx, true);
+        } else if minimized {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            self.set_window_hidden(ctx, true);
+        }
+    }
+
+    fn export_history(&mut self, ctx: &egui::Context) {
+        let message = match std::fs::write(HISTORY_EXPORT_PATH, self.calculator.history().to_csv()) {
+            Ok(()) => format!("Saved {}", HISTORY_EXPORT_PATH),
+            Err(err) => format!("Couldn't save {}: {}", HISTORY_EXPORT_PATH, err),
+        };
+        self.show_toast(ctx, message);
+    }
+
+    fn print_tape(&mut self, ctx: &egui::Context) {
+        let title = format!("Calculator tape, {}", chrono::Local::now().format("%Y-%m-%d %H:%M"));
+        let message = match std::fs::write(TAPE_PATH, print::tape_pdf(self.calculator.history(), &title)) {
+            Ok(()) => format!("Saved {}, ready to print", TAPE_PATH),
+            Err(err) => format!("Couldn't save {}: {}", TAPE_PATH, err),
+        };
+        self.show_toast(ctx, message);
+    }
+
+    // Writes e.g. history.md or worksheet.tex to the working directory
+    fn export_report(&mut self, ctx: &egui::Context, name: &str, rows: &[report::Row], format: ReportFormat) {
+        let path = format!("{}.{}", name, format.extension());
+        let message = match std::fs::write(&path, report::render(rows, format, self.parse_options())) {
+            Ok(()) => format!("Saved {}", path),
+            Err(err) => format!("Couldn't save {}: {}", path, err),
+        };
+        self.show_toast(ctx, message);
+    }
+
+    fn about_ui(&mut self, ctx: &egui::Context) {
+        let mut copy = false;
+        egui::Window::new("About")
+            .open(&mut self.show_about)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.heading("Rust Calculator");
+                ui.label("A desktop calculator built with egui.");
+                ui.add_space(6.0);
+                egui::Grid::new("about").show(ui, |ui| {
+                    ui.label("Version");
+                    ui.monospace(diagnostics::VERSION);
+                    ui.end_row();
+                    ui.label("Backend");
+                    ui.monospace(diagnostics::BACKEND);
+                    ui.end_row();
+                    ui.label("Arithmetic");
+                    ui.monospace(diagnostics::precision_mode(&self.settings));
+                    ui.end_row();
+                });
+                ui.add_space(6.0);
+                copy = ui
+                    .button("Copy diagnostic info")
+                    .on_hover_text("For pasting into a bug report")
+                    .clicked();
+            });
+        if copy {
+            self.clipboard_request = Some(diagnostics::report(&self.settings));
+            self.show_toast(ctx, String::from("Diagnostic info copied"));
+        }
+    }
+
+    fn recovery_ui(&mut self, ctx: &egui::Context) {
+        let Some(recovered) = &self.recovered else {
+            return;
+        };
+        let mut restore = None;
+        egui::Window::new("Restore session")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("The calculator didn't close properly last time.");
+                let mut preview = Calculator::new();
+                preview.restore_session(recovered.session.clone());
+                egui::Grid::new("recovered").show(ui, |ui| {
+                    ui.label("Display");
+                    ui.monospace(preview.get_display_text());
+                    ui.end_row();
+                    ui.label("Memory");
+                    ui.monospace(preview.memory().map_or(String::from("empty"), |value| value.to_string()));
+                    ui.end_row();
+                    ui.label("History");
+                    ui.label(format!("{} entries", recovered.history.entries().len()));
+                    ui.end_row();
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Restore").clicked() {
+                        restore = Some(true);
+                    }
+                    if ui.button("Start fresh").clicked() {
+                        restore = Some(false);
+                    }
+                });
+            });
+
+        let Some(restore) = restore else {
+            return;
+        };
+        if let Some(recovered) = self.recovered.take().filter(|_| restore) {
+            self.calculator.restore_session(recovered.session);
+            let history = self.calculator.history_mut();
+            *history = recovered.history;
+            history.set_limit(Some(self.settings.max_history));
+        }
+        self.journal = Some(Journal::start(journal_dir(), &self.calculator));
+    }
+
+    fn favorites_ui(&mut self, ui: &mut egui::Ui) {
+        if self.favorites.entries().is_empty() {
+            return;
+        }
+
+        ui.collapsing("Favorites", |ui| {
+            let mut recalled = None;
+            let mut unpinned = None;
+            for (index, entry) in self.favorites.entries().iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.small_button("✖").on_hover_text("Unpin").clicked() {
+                        unpinned = Some(index);
+                    }
+                    let text = format!("{} = {}", entry.source, self.rounded(entry.result));
+                    if ui.selectable_label(false, egui::RichText::new(text).monospace()).clicked() {
+                        recalled = Some(entry.clone());
+                    }
+                });
+            }
+
+            if let Some(index) = unpinned {
+                self.favorites.unpin(index);
+            }
+            if let Some(entry) = recalled {
+                self.recall(entry);
+            }
+        });
+    }
+
+    // Expression mode and the worksheet get the original expression back for
+    // editing, the keypad just continues from the result
+    fn recall(&mut self, entry: HistoryEntry) {
+        match self.mode {
+            Mode::Expression => {
+                self.expression_input = entry.source;
+                self.expression_error = None;
+                // Due right away, nothing is being typed
+                self.preview_due = Some(0.0);
+            }
+            Mode::Compare if self.compare.active == Side::B => self.compare.calculator.set_operand(entry.result),
+            Mode::Basic | Mode::Programmer | Mode::Compare => self.calculator.set_operand(entry.result),
+            Mode::Grid => {
+                self.grid.cells[self.grid.selected] = entry.result.to_string();
+                self.grid.results = None;
+            }
+            Mode::Worksheet => {
+                if !self.worksheet_text.is_empty() && !self.worksheet_text.ends_with('\n') {
+                    self.worksheet_text.push('\n');
+                }
+                self.worksheet_text.push_str(&entry.source);
+                self.worksheet_results = None;
+            }
+        }
+    }
+
+    // All keypad input goes through here so it can be recorded
+    fn dispatch(&mut self, event: InputEvent) {
+        self.recorder.record(event);
+        // A typed operand is about to be used
+        let uses_operand = matches!(event, InputEvent::Operation(_) | InputEvent::Binary(_) | InputEvent::Equals);
+        if uses_operand && self.calculator.is_entering() {
+            if let Some(value) = self.calculator.display_value() {
+                self.recent.push(value);
+            }
+        }
+        let last_id = self.last_history_id();
+        let pending = self.calculator.pending_expression();
+        let had_error = self.calculator.error().is_some();
+        self.calculator.handle_event_with(event, &self.operations);
+        if let (false, Some(err)) = (had_error, self.calculator.error()) {
+            let operation = pending.as_ref().map(announce::spoken).unwrap_or_default();
+            self.announcement = Announcement::new(&operation, &Err(err.clone()));
+        }
+        self.after_calculation(last_id);
+    }
+
+    fn last_history_id(&self) -> Option<u64> {
+        self.calculator.history().last().map(|entry| entry.id)
+    }
+
+    // Runs the "result computed" side effects if a new history entry appeared
+    fn after_calculation(&mut self, previous_id: Option<u64>) {
+        if self.last_history_id() == previous_id {
+            return;
+        }
+        if let Some(entry) = self.calculator.history().last() {
+            self.recent.push(entry.result);
+            if self.settings.running_total {
+                self.running_total.add(entry.id, entry.result);
+            }
+            self.announcement = Announcement::new(&announce::spoken(&entry.expression), &Ok(entry.result));
+        }
+        if self.settings.auto_copy_result {
+            self.clipboard_request = Some(self.display_text().into_owned());
+        }
+    }
+
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        for file in dropped {
+            let name = file
+                .path
+                .as_ref()
+                .and_then(|path| path.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| file.name.clone());
+            let extension = name.rsplit('.').next().unwrap_or_default().to_lowercase();
+            if extension != "txt" && extension != "csv" {
+                self.show_toast(ctx, format!("{}: only .txt and .csv files are supported", name));
+                continue;
+            }
+
+            let text = match (&file.path, &file.bytes) {
+                (Some(path), _) => std::fs::read_to_string(path).map_err(|err| err.to_string()),
+                (None, Some(bytes)) => Ok(String::from_utf8_lossy(bytes).into_owned()),
+                (None, None) => Err(String::from("no contents")),
+            };
+            let text = match text {
+                Ok(text) => text,
+                Err(err) => {
+                    self.show_toast(ctx, format!("{}: {}", name, err));
+                    continue;
+                }
+            };
+
+            self.batch = Some(BatchResults {
+                name,
+                path: file.path,
+                lines: Vec::new(),
+                running: Some(self.spawn_batch(ctx, text)),
+            });
+        }
+    }
+
+    fn worker_functions(&self) -> WorkerFunctions {
+        WorkerFunctions {
+            #[cfg(feature = "scripting")]
+            source: self.scripts.source().to_string(),
+        }
+    }
+
+    // Evaluates a batch file on a worker thread
+    fn spawn_batch(&self, ctx: &egui::Context, text: String) -> Task<Option<Vec<BatchLine>>> {
+        let options = self.parse_options();
+        let functions = self.worker_functions();
+        let ctx = ctx.clone();
+        Task::spawn(
+            move |progress| functions.with(|functions| batch::evaluate_lines(&text, functions, options, progress)),
+            move || ctx.request_repaint(),
+        )
+    }
+
+    fn batch_ui(&mut self, ctx: &egui::Context) {
+        let Some(results) = &mut self.batch else {
+            return;
+        };
+        if let Some(finished) = results.running.as_ref().and_then(|task| task.poll()) {
+            results.running = None;
+            match finished {
+                Some(lines) => results.lines = lines,
+                None => {
+                    self.batch = None;
+                    return;
+                }
+            }
+        }
+
+        let mut open = true;
+        let mut export = false;
+        egui::Window::new(format!("Results: {}", results.name))
+            .open(&mut open)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+                if let Some(task) = &results.running {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        let (done, total) = task.progress().counts();
+                        ui.label(format!("Evaluating… {} / {} lines", done, total));
+                        if ui.button("Cancel").clicked() {
+                            task.cancel();
+                        }
+                    });
+                    return;
+                }
+                let errors = results.lines.iter().filter(|line| line.result.is_err()).count();
+                ui.label(format!("{} lines, {} errors", results.lines.len(), errors));
+
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    egui::Grid::new("batch_results").striped(true).show(ui, |ui| {
+                        for line in &results.lines {
+                            ui.weak(line.line.to_string());
+                            ui.monospace(&line.source);
+                            match &line.result {
+                                Ok(value) => ui.monospace(value.to_string()),
+                                Err(err) => ui.colored_label(ui.visuals().error_fg_color, err.to_string()),
+                            };
+                            ui.end_row();
+                        }
+                    });
+                });
+
+                export = ui.button("Export CSV").clicked();
+            });
+
+        if export {
+            // Saved next to the dropped file when we know where that is
+            let file_name = format!("{}.results.csv", results.name);
+            let target = match &results.path {
+                Some(path) => path.with_file_name(file_name),
+                None => PathBuf::from(file_name),
+            };
+            let message = match std::fs::write(&target, batch::to_csv(&results.lines)) {
+                Ok(()) => format!("Saved {}", target.display()),
+                Err(err) => format!("Couldn't save {}: {}", target.display(), err),
+            };
+            self.show_toast(ctx, message);
+        }
+        // Dropping a running task cancels it
+        if !open {
+            self.batch = None;
+        }
+    }
+
+    // A thin strip of indicators under everything else
+    fn status_bar_ui(&self, ui: &mut egui::Ui) {
+        let status = self.calculator.status(self.mode.label(), self.settings.rounding, self.settings.decimal_places);
+        ui.horizontal(|ui| {
+            ui.small(status.mode);
+            ui.separator();
+            ui.small(status.angle_unit).on_hover_text("Trigonometric functions use radians");
+            ui.separator();
+            ui.small(status.rounding).on_hover_text(if status.rounding == "DEC" {
+                "+ − × ÷ round in decimal"
+            } else {
+                "+ − × ÷ round in binary floating point"
+            });
+            if let Some(desk) = status.desk_rounding() {
+                ui.separator();
+                ui.small(desk).on_hover_text(if status.rounding_switch == RoundingSwitch::Float {
+                    "Numbers typed without a point are cents; see Settings"
+                } else {
+                    "Results are shown cut or rounded; see Settings"
+                });
+            }
+            if let Some(memory) = status.memory {
+                ui.separator();
+                ui.small("M").on_hover_text(format!("Memory: {}", memory));
+            }
+            if let Some(pending) = &status.pending {
+                ui.separator();
+                ui.small(pending).on_hover_text("Waiting for the second operand");
+            }
+            if status.error {
+                ui.separator();
+                ui.small(egui::RichText::new("ERR").color(ui.visuals().error_fg_color));
+            }
+        });
+    }
+
+    // Recently used numbers, one click from being used again
+    fn recent_ui(&mut self, ui: &mut egui::Ui) {
+        let mut picked = None;
+        ui.add_enabled_ui(!self.recent.values().is_empty(), |ui| {
+            ui.menu_button("🕘", |ui| {
+                for &value in self.recent.values() {
+                    if ui.button(egui::RichText::new(value.to_string()).monospace()).clicked() {
+                        picked = Some(value);
+                        ui.close_menu();
+                    }
+                }
+            })
+            .response
+            .on_hover_text("Recent values");
+        });
+        let Some(value) = picked else {
+            return;
+        };
+        match self.mode {
+            Mode::Expression => {
+                self.expression_input.push_str(&value.to_string());
+                self.preview_due = Some(0.0);
+            }
+            _ => self.calculator.set_operand(value),
+        }
+    }
+
+    // Picks how the result on the display is read; the value stays as it is
+    fn interpretation_ui(&mut self, ui: &mut egui::Ui) {
+        let text = if self.interpretation.is_some() { "🔁 as…" } else { "as…" };
+        ui.menu_button(text, |ui| {
+            if ui.selectable_value(&mut self.interpretation, None, "Number").clicked() {
+                ui.close_menu();
+            }
+            for interpretation in Interpretation::ALL {
+                if ui
+                    .selectable_value(&mut self.interpretation, Some(interpretation), interpretation.label())
+                    .clicked()
+                {
+                    ui.close_menu();
+                }
+            }
+        })
+        .response
+        .on_hover_text("Read the result as a time, angle or percentage");
+    }
+
+    // A small line above the display with what `=` will do, e.g. "128 ÷".
+    // It keeps its height when empty so the display doesn't jump.
+    fn pending_readout_ui(&mut self, ui: &mut egui::Ui) {
+        let text = match (self.calculator.stored_value(), self.calculator.pending_symbol()) {
+            (Some(stored), Some(symbol)) => format!("{} {}", stored, symbol),
+            _ => String::from(" "),
+        };
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+            ui.label(egui::RichText::new(text).monospace().weak());
+        });
+    }
+
+    // A left swipe across the display deletes the last digit typed
+    fn display_swipe_ui(&mut self, display: &egui::Response) {
+        if display.dragged() {
+            self.display_swipe += display.drag_delta();
+        }
+        if display.drag_released() {
+            if let Some(event) = input::from_swipe(std::mem::take(&mut self.display_swipe)) {
+                self.dispatch(event);
+            }
+        }
+    }
+
+    // The main display. Text too long for the smallest font scrolls sideways
+    // (drag, or arrow keys while hovered), starting at the newest digits, with
+    // an ellipsis on whichever side is cut off.
+    fn display_label_ui(&mut self, ui: &mut egui::Ui) {
+        let text = self.display_text();
+        let fitting = display_font_size(&text);
+        let size = fitting.unwrap_or(DISPLAY_FONT_STEPS[DISPLAY_FONT_STEPS.len() - 1].1);
+        // In touch mode a drag across the display is a swipe, not a scroll
+        let touch = self.settings.touch_mode;
+        let sense = if touch {
+            egui::Sense::click_and_drag()
+        } else {
+            egui::Sense::click()
+        };
+        let label = egui::Label::new(egui::RichText::new(text).size(size).monospace())
+            .wrap(false)
+            .sense(sense);
+        if fitting.is_some() {
+            let display = ui.add(label);
+            self.display_swipe_ui(&display);
+            display.context_menu(|ui| self.display_menu_ui(ui));
+            return;
+        }
+
+        let mut area = egui::ScrollArea::horizontal()
+            .id_source("display_scroll")
+            .max_width(DISPLAY_WIDTH)
+            .stick_to_right(true)
+            .drag_to_scroll(!touch);
+        if let Some(offset) = self.display_scroll.take() {
+            area = area.horizontal_scroll_offset(offset);
+        }
+        let output = area.show(ui, |ui| ui.add(label));
+        let display = output.inner;
+        let offset = output.state.offset.x;
+        self.display_swipe_ui(&display);
+
+        if display.hovered() && !ui.ctx().wants_keyboard_input() {
+            let step = ui.input(|i| {
+                i.key_pressed(egui::Key::ArrowRight) as i32 - i.key_pressed(egui::Key::ArrowLeft) as i32
+            });
+            if step != 0 {
+                self.display_scroll = Some((offset + step as f32 * size * 2.0).max(0.0));
+                ui.ctx().request_repaint();
+            }
+        }
+
+        let rect = output.inner_rect;
+        let painter = ui.painter_at(rect);
+        let mark = |anchor: egui::Align2, at: egui::Pos2| {
+            let galley = painter.layout_no_wrap(
+                String::from("…"),
+                egui::FontId::monospace(size),
+                ui.visuals().strong_text_color(),
+            );
+            let area = anchor.anchor_rect(egui::Rect::from_min_size(at, galley.size()));
+            painter.rect_filled(area, 0.0, ui.visuals().panel_fill);
+            painter.galley(area.min, galley);
+        };
+        if offset > 0.5 {
+            mark(egui::Align2::LEFT_CENTER, rect.left_center());
+        }
+        if output.content_size.x - rect.width() - offset > 0.5 {
+            mark(egui::Align2::RIGHT_CENTER, rect.right_center());
+        }
+
+        display.context_menu(|ui| self.display_menu_ui(ui));
+    }
+
+    // An expression result in the chosen output base, when it's a whole number
+    fn in_output_base(&self, value: f64) -> String {
+        match self.output_base {
+            Base::Dec => self.rounded(value),
+            base => programmer::format_literal(value, base).unwrap_or_else(|| self.rounded(value)),
+        }
+    }
+
+    // A result as the rounding switch shows it
+    fn rounded(&self, value: f64) -> String {
+        format::format_desk(value, self.settings.rounding, self.settings.decimal_places)
+    }
+
+    // The display as the settings want it shown; numbers being typed stay as typed
+    fn display_text(&self) -> Cow<'_, str> {
+        let cleaned = self.cleaned_value();
+        match (cleaned.or(self.calculator.display_value()), self.interpretation) {
+            (Some(value), Some(interpretation)) if !self.calculator.is_entering() => {
+                Cow::Owned(format::format_interpretation(value, interpretation))
+            }
+            (Some(value), _) if self.mode == Mode::Expression && self.output_base != Base::Dec => {
+                Cow::Owned(self.in_output_base(value))
+            }
+            _ => self.settings.display_text(&self.calculator, self.show_raw_value),
+        }
+    }
+
+    // The adding machine's item counter, while a sum is going on
+    fn item_count_ui(&mut self, ui: &mut egui::Ui) {
+        let count = self.calculator.item_count();
+        if count == 0 || !matches!(self.mode, Mode::Basic | Mode::Compare) {
+            return;
+        }
+        let label = if count == 1 { String::from("1 item") } else { format!("{} items", count) };
+        ui.weak(label).on_hover_text("Numbers added or subtracted in this sum");
+    }
+
+    // The second display: the grand total with its own clear key, and the
+    // results in it to leave out or put back
+    fn running_total_ui(&mut self, ui: &mut egui::Ui) {
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.weak("GT");
+            let total = self.rounded(self.running_total.total());
+            ui.label(egui::RichText::new(total).size(18.0).monospace());
+            ui.weak(format!("({})", self.running_total.count()))
+                .on_hover_text("Results in the total");
+            if ui.small_button("CGT").on_hover_text("Clear the grand total").clicked() {
+                self.running_total.clear();
+                self.show_total_entries = false;
+            }
+            if !self.running_total.is_empty() {
+                ui.toggle_value(&mut self.show_total_entries, "☰")
+                    .on_hover_text("Choose which results count");
+            }
+        });
+        if !self.show_total_entries {
+            return;
+        }
+        let mut changed = None;
+        egui::ScrollArea::vertical()
+            .id_source("total_entries")
+            .max_height(120.0)
+            .show(ui, |ui| {
+                for entry in self.running_total.entries().iter().rev() {
+                    let mut included = entry.included;
+                    if ui.checkbox(&mut included, egui::RichText::new(entry.value.to_string()).monospace()).changed() {
+                        changed = Some((entry.id, included));
+                    }
+                }
+            });
+        if let Some((id, included)) = changed {
+            self.running_total.set_included(id, included);
+        }
+    }
+
+    // The result as smart cleanup shows it, when cleanup changes it
+    fn cleaned_value(&self) -> Option<f64> {
+        if self.show_raw_value {
+            return None;
+        }
+        self.settings.cleaned_value(&self.calculator)
+    }
+
+    // Marks a cleaned-up display; clicking shows the result as computed
+    fn cleanup_indicator_ui(&mut self, ui: &mut egui::Ui) {
+        if !self.settings.smart_cleanup || self.calculator.is_entering() {
+            return;
+        }
+        let Some(value) = self.calculator.display_value() else {
+            return;
+        };
+        if format::clean_up(value, self.settings.cleanup_epsilon).is_none() {
+            return;
+        }
+        let hint = if self.show_raw_value {
+            String::from("Showing the result as computed; click to clean it up again")
+        } else {
+            format!("Cleaned up for display; the result is {}. Click to show it", value)
+        };
+        ui.toggle_value(&mut self.show_raw_value, "≈").on_hover_text(hint);
+    }
+
+    fn display_menu_ui(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Copy").clicked() {
+            self.clipboard_request = Some(self.display_text().into_owned());
+            ui.close_menu();
+        }
+        if ui
+            .button("Copy as expression")
+            .on_hover_text("Copy the calculation that gave the result")
+            .clicked()
+        {
+            self.clipboard_request = Some(self.display_expression());
+            ui.close_menu();
+        }
+        if ui
+            .button("Copy share link")
+            .on_hover_text("A calc:// link that opens the calculator with this calculation")
+            .clicked()
+        {
+            self.clipboard_request = Some(link::encode(&self.display_expression()));
+            ui.close_menu();
+        }
+        let value = self.cleaned_value().or(self.calculator.display_value());
+        ui.add_enabled_ui(value.is_some(), |ui| {
+            ui.menu_button("Copy as", |ui| {
+                for copy_format in CopyFormat::ALL {
+                    let text = value.and_then(|value| format::format_copy(value, copy_format));
+                    let button = egui::Button::new(copy_format.label()).shortcut_text(text.clone().unwrap_or_default());
+                    if ui.add_enabled(text.is_some(), button).clicked() {
+                        self.clipboard_request = text;
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+        if ui.button("Paste").clicked() {
+            self.paste_from_clipboard(ui.ctx());
+            ui.close_menu();
+        }
+        if ui.button("Clear").clicked() {
+            self.dispatch(InputEvent::Clear);
+            ui.close_menu();
+        }
+        // Changes the value itself, unlike the rounding switch, so macros can
+        // end with it
+        ui.add_enabled_ui(self.calculator.display_value().is_some(), |ui| {
+            ui.menu_button("Round to", |ui| {
+                for places in 0..=3 {
+                    let label = match places {
+                        0 => String::from("Whole number"),
+                        1 => String::from("1 place"),
+                        places => format!("{} places", places),
+                    };
+                    if ui.button(label).clicked() {
+                        self.dispatch(InputEvent::Round(places));
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+        ui.separator();
+        let has_value = self.calculator.display_value().is_some();
+        if ui.add_enabled(has_value, egui::Button::new("View full precision")).clicked() {
+            self.show_full_precision = true;
+            ui.close_menu();
+        }
+        let convert = egui::Button::new("Convert…").shortcut_text(ui.ctx().format_shortcut(&CONVERT_SHORTCUT));
+        if ui.add_enabled(has_value, convert).clicked() {
+            self.show_convert = true;
+            ui.close_menu();
+        }
+    }
+
+    // The displayed number in the units it's most likely wanted in; picking
+    // one puts the converted value on the display
+    fn convert_ui(&mut self, ctx: &egui::Context) {
+        let Some(value) = self.calculator.display_value() else {
+            self.show_convert = false;
+            return;
+        };
+        let mut picked = None;
+        egui::Window::new("Convert")
+            .open(&mut self.show_convert)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                for conversion in self.conversions.top(QUICK_CONVERSIONS) {
+                    let converted = conversion.apply(value);
+                    let text = format!("{} {} = {} {}", value, conversion.from, converted, conversion.to);
+                    if ui.button(text).clicked() {
+                        picked = Some((conversion, converted));
+                    }
+                }
+            });
+        if let Some((conversion, converted)) = picked {
+            self.conversions.record(conversion);
+            self.calculator.set_operand(converted);
+            self.show_convert = false;
+        }
+    }
+
+    // The calculation behind the displayed result, or just the number while
+    // one is being typed
+    fn display_expression(&self) -> String {
+        let text = self.calculator.get_display_text();
+        match self.calculator.history().last() {
+            Some(entry) if !self.calculator.is_entering() && self.calculator.display_value() == Some(entry.result) => {
+                entry.source.clone()
+            }
+            _ => text.to_string(),
+        }
+    }
+
+    fn paste_from_clipboard(&mut self, ctx: &egui::Context) {
+        match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(text) => self.paste(ctx, &text),
+            Err(err) => self.show_toast(ctx, format!("Couldn't paste: {}", err)),
+        }
+    }
+
+    fn full_precision_ui(&mut self, ctx: &egui::Context) {
+        let Some(value) = self.calculator.display_value() else {
+            return;
+        };
+
+        let exact = ieee754::exact_decimal(value);
+        let mut copied = None;
+        egui::Window::new("Full precision")
+            .open(&mut self.show_full_precision)
+            .default_width(280.0)
+            .show(ctx, |ui| {
+                ui.label("Every digit of the binary number the display rounds:");
+                egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    ui.add(egui::Label::new(egui::RichText::new(&exact).monospace()).wrap(true));
+                });
+                ui.horizontal(|ui| {
+                    let digits = exact.chars().filter(char::is_ascii_digit).count();
+                    ui.weak(format!("{} digits", digits));
+                    if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                        copied = Some(exact.clone());
+                    }
+                });
+            });
+        if copied.is_some() {
+            self.clipboard_request = copied;
+        }
+    }
+
+    // The displayed number in words, e.g. for writing a cheque
+    fn words_ui(&mut self, ui: &mut egui::Ui) {
+        let Ok(text) = words::spell(self.calculator.get_display_text(), &English) else {
+            return;
+        };
+        ui.horizontal_wrapped(|ui| {
+            ui.label(egui::RichText::new(&text).italics());
+            if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                self.clipboard_request = Some(text);
+            }
+        });
+    }
+
+    // Flags results that binary floating point got slightly wrong
+    fn float_warning_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(exact) = self.calculator.exact_value() else {
+            return;
+        };
+        let explanation = format!(
+            "Binary floating point can't store most decimal fractions exactly, \
+             so this result is off in the last digits. The exact decimal result is {}.",
+            exact
+        );
+        ui.label(egui::RichText::new("⚠").color(ui.visuals().warn_fg_color))
+            .on_hover_text(explanation);
+        if ui
+            .small_button("Use decimal")
+            .on_hover_text("Switch to decimal arithmetic and fix this result")
+            .clicked()
+        {
+            self.calculator.switch_to_decimal();
+            self.settings.decimal_mode = true;
+        }
+    }
+
+    // Flags results that doubles can only hold with reduced precision
+    fn range_warning_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(warning) = self.calculator.warning() else {
+            return;
+        };
+        let explanation = format!(
+            "{}: below about 2.2e-308, doubles have fewer significant digits the \
+             closer a number gets to zero, until it rounds to 0 at about 4.9e-324.",
+            warning
+        );
+        ui.colored_label(ui.visuals().warn_fg_color, "⚠ tiny").on_hover_text(explanation);
+    }
+
+    fn float_inspector_ui(&mut self, ctx: &egui::Context) {
+        let Some(value) = self.calculator.display_value() else {
+            return;
+        };
+
+        egui::Window::new("IEEE-754")
+            .open(&mut self.show_float_inspector)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let bits = FloatBits::of(value);
+                let mantissa_hint = "The implicit leading 1 of normal numbers isn't stored";
+                egui::Grid::new("float_fields").striped(true).show(ui, |ui| {
+                    ui.label("Value");
+                    ui.monospace(format!("{:e}", value));
+                    ui.end_row();
+                    ui.label("Hex");
+                    ui.monospace(ieee754::to_hex(value));
+                    ui.end_row();
+                    ui.label("Sign");
+                    ui.monospace(if bits.sign { "1 (−)" } else { "0 (+)" });
+                    ui.end_row();
+                    ui.label("Exponent");
+                    ui.monospace(format!("{} (2^{})", bits.exponent, bits.unbiased_exponent()));
+                    ui.end_row();
+                    ui.label("Mantissa").on_hover_text(mantissa_hint);
+                    ui.monospace(format!("0x{:013X}", bits.mantissa));
+                    ui.end_row();
+                    ui.label("Class");
+                    ui.monospace(format!("{:?}", value.classify()));
+                    ui.end_row();
+                    ui.label("Previous");
+                    ui.monospace(format!("{:e}", ieee754::next_down(value)));
+                    ui.end_row();
+                    ui.label("Next");
+                    ui.monospace(format!("{:e}", ieee754::next_up(value)));
+                    ui.end_row();
+                    ui.label("ULP");
+                    ui.monospace(format!("{:e}", ieee754::ulp(value)));
+                    ui.end_row();
+                });
+
+                ui.add(egui::Label::new(egui::RichText::new(bits.to_binary()).monospace().small()).wrap(true));
+
+                // Explains results like 0.1 + 0.2 = 0.30000000000000004
+                let intended = ieee754::round_to_significant(value, 15);
+                if let Some(distance) = ieee754::ulp_distance(value, intended).filter(|&distance| distance > 0) {
+                    ui.separator();
+                    ui.label(format!(
+                        "Likely meant {}: the result is {} ULP away from it because decimal \
+                         fractions can't be stored exactly in binary.",
+                        intended, distance
+                    ));
+                }
+            });
+    }
+
+    fn precision_ui(&mut self, ctx: &egui::Context) {
+        if !self.show_precision {
+            return;
+        }
+        let readings = (!self.expression_input.trim().is_empty())
+            .then(|| self.calculator.compare_precision(&self.expression_input, self.user_functions()));
+
+        egui::Window::new("Precision")
+            .open(&mut self.show_precision)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                let readings = match readings {
+                    None => {
+                        ui.label("Type an expression in expression mode to compare how it is computed.");
+                        return;
+                    }
+                    Some(Err(err)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                        return;
+                    }
+                    Some(Ok(readings)) => readings,
+                };
+                let reference = precision::reference(&readings).unwrap_or_default();
+                let warn = ui.visuals().warn_fg_color;
+                egui::Grid::new("precision_readings").striped(true).show(ui, |ui| {
+                    for reading in &readings {
+                        ui.label(reading.backend.label());
+                        match &reading.text {
+                            Ok(text) => {
+                                // Highlight from where this one parts from the most exact reading
+                                let (agreed, diverged) = text.split_at(precision::divergence(text, reference));
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.spacing_mut().item_spacing.x = 0.0;
+                                    ui.monospace(agreed);
+                                    ui.label(egui::RichText::new(diverged).monospace().color(warn));
+                                });
+                            }
+                            Err(reason) => {
+                                ui.weak(reason);
+                            }
+                        }
+                        ui.end_row();
+                        if let Some(fraction) = &reading.fraction {
+                            ui.label("");
+                            ui.monospace(format!("= {}", fraction));
+                            ui.end_row();
+                        }
+                    }
+                });
+                ui.separator();
+                ui.weak("Highlighted digits differ from the most exact result available.");
+            });
+    }
+
+    fn show_toast(&mut self, ctx: &egui::Context, message: String) {
+        self.toast = Some((message, ctx.input(|i| i.time)));
+    }
+
+    fn toast_ui(&mut self, ctx: &egui::Context) {
+        const TOAST_SECONDS: f64 = 1.5;
+
+        let Some((message, shown_at)) = &self.toast else {
+            return;
+        };
+        let remaining = TOAST_SECONDS - (ctx.input(|i| i.time) - shown_at);
+        if remaining <= 0.0 {
+            self.toast = None;
+            return;
+        }
+
+        egui::Area::new("toast")
+            .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -20.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(message.as_str());
+                });
+            });
+        ctx.request_repaint_after(std::time::Duration::from_secs_f64(remaining));
+    }
+
+    fn templates_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Templates", |ui| {
+            let options = self.parse_options();
+            let count = self.settings.templates.len();
+            let panel = &mut self.templates;
+            panel.values.resize(count, Vec::new());
+            panel.results.resize(count, None);
+
+            let (mut run, mut removed, mut used) = (None, None, None);
+            for (index, template) in self.settings.templates.iter().enumerate() {
+                let values = &mut panel.values[index];
+                values.resize(template.inputs.len(), 0.0);
+                ui.group(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.strong(&template.name);
+                        ui.weak(&template.source);
+                        if ui.small_button("✖").on_hover_text("Delete template").clicked() {
+                            removed = Some(index);
+                        }
+                    });
+                    egui::Grid::new(("template", index)).show(ui, |ui| {
+                        for (input, value) in template.inputs.iter().zip(values.iter_mut()) {
+                            ui.label(&input.label);
+                            ui.add(egui::DragValue::new(value).speed(0.1));
+                            ui.end_row();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Run").clicked() {
+                            run = Some(index);
+                        }
+                        match &panel.results[index] {
+                            Some(Ok(value)) => {
+                                ui.monospace(format!("= {}", value));
+                                if ui.small_button("Use").on_hover_text("Put the result on the display").clicked() {
+                                    used = Some(*value);
+                                }
+                            }
+                            Some(Err(err)) => {
+                                ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                            }
+                            None => {}
+                        }
+                    });
+                });
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Name");
+                ui.add(egui::TextEdit::singleline(&mut panel.name).desired_width(180.0));
+            });
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut panel.source)
+                    .hint_text("e.g. price * (1 + vat)")
+                    .font(egui::TextStyle::Monospace)
+                    .desired_width(240.0),
+            );
+            if response.changed() {
+                normalize_field(ui.ctx(), response.id, &mut panel.source);
+                let mut draft = Template::new("", &panel.source, options);
+                if let (Ok(draft), Some(Ok(previous))) = (&mut draft, &panel.draft) {
+                    draft.keep_labels(previous);
+                }
+                panel.draft = (!panel.source.trim().is_empty()).then_some(draft);
+            }
+            match &mut panel.draft {
+                Some(Ok(draft)) => {
+                    egui::Grid::new("template_labels").show(ui, |ui| {
+                        for input in &mut draft.inputs {
+                            ui.monospace(&input.name);
+                            ui.add(egui::TextEdit::singleline(&mut input.label).desired_width(160.0));
+                            ui.end_row();
+                        }
+                    });
+                    let name = panel.name.trim();
+                    if ui.add_enabled(!name.is_empty(), egui::Button::new("Save template")).clicked() {
+                        let mut template = draft.clone();
+                        template.name = name.to_string();
+                        self.settings.templates.push(template);
+                        panel.name.clear();
+                        panel.source.clear();
+                        panel.draft = None;
+                    }
+                }
+                Some(Err(err)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                }
+                None => {}
+            }
+
+            if let Some(index) = run {
+                let result = self.settings.templates[index].run(
+                    &self.templates.values[index],
+                    self.user_functions(),
+                    options,
+                );
+                self.templates.results[index] = Some(result);
+            }
+            if let Some(value) = used {
+                self.calculator.set_operand(value);
+            }
+            if let Some(index) = removed {
+                self.settings.templates.remove(index);
+                self.templates.values.remove(index);
+                self.templates.results.remove(index);
+            }
+        });
+    }
+
+    fn percent_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Percent", |ui| {
+            let panel = &mut self.percent;
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut panel.question, PercentQuestion::PercentOf, "% of");
+                ui.selectable_value(&mut panel.question, PercentQuestion::Change, "% change");
+                ui.selectable_value(&mut panel.question, PercentQuestion::IncreaseBy, "+ %");
+            });
+
+            let (first, second) = match panel.question {
+                PercentQuestion::PercentOf => ("Value", "is what % of"),
+                PercentQuestion::Change => ("From", "to"),
+                PercentQuestion::IncreaseBy => ("Value", "increased by %"),
+            };
+            egui::Grid::new("percent_inputs").show(ui, |ui| {
+                ui.label(first);
+                ui.add(egui::DragValue::new(&mut panel.a).speed(0.1));
+                ui.end_row();
+                ui.label(second);
+                ui.add(egui::DragValue::new(&mut panel.b).speed(0.1));
+                ui.end_row();
+            });
+
+            let result = match panel.question {
+                PercentQuestion::PercentOf => percent::percent_of(panel.a, panel.b),
+                PercentQuestion::Change => percent::percent_change(panel.a, panel.b),
+                PercentQuestion::IncreaseBy => Ok(percent::increase_by(panel.a, panel.b)),
+            };
+            match result {
+                Ok(value) => {
+                    ui.horizontal(|ui| {
+                        let unit = if panel.question == PercentQuestion::IncreaseBy { "" } else { "%" };
+                        ui.monospace(format!("= {}{}", value, unit));
+                        if ui.small_button("Use").on_hover_text("Put the result on the display").clicked() {
+                            self.calculator.set_operand(value);
+                        }
+                    });
+                }
+                Err(err) => {
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                }
+            }
+        });
+    }
+
+    fn tip_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Tip & split", |ui| {
+            let panel = &mut self.tip;
+            egui::Grid::new("tip_inputs").show(ui, |ui| {
+                ui.label("Bill");
+                ui.add(egui::DragValue::new(&mut panel.bill).speed(0.1).clamp_range(0.0..=f64::MAX));
+                ui.end_row();
+                ui.label("Tip %");
+                ui.add(egui::DragValue::new(&mut panel.tip_percent).speed(0.5).clamp_range(0.0..=100.0));
+                ui.end_row();
+                ui.label("People");
+                ui.add(egui::DragValue::new(&mut panel.people).clamp_range(1..=100));
+                ui.end_row();
+            });
+            ui.checkbox(&mut panel.round_up, "Round each share up");
+
+            match tip::split(panel.bill, panel.tip_percent, panel.people, panel.round_up) {
+                Ok(split) => {
+                    ui.monospace(format!("Tip: {:.2}  Total: {:.2}", split.tip, split.total));
+                    ui.horizontal(|ui| {
+                        ui.monospace(format!("Each: {:.2}", split.per_person));
+                        if ui.small_button("Use").on_hover_text("Put the share on the display").clicked() {
+                            self.calculator.set_operand(split.per_person);
+                        }
+                    });
+                }
+                Err(err) => {
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                }
+            }
+        });
+    }
+
+    fn pricing_ui(&mut self, ui: &mut egui::Ui) {
+        const MARGIN_HINT: &str = "Profit as a percentage of the price";
+        const MARKUP_HINT: &str = "Profit as a percentage of the cost";
+
+        ui.collapsing("Margin & markup", |ui| {
+            let panel = &mut self.pricing;
+            let (first, second) = match panel.known {
+                Known::CostAndPrice => ("Cost", "Price"),
+                Known::CostAndMargin => ("Cost", "Margin %"),
+                Known::PriceAndMargin => ("Price", "Margin %"),
+                Known::CostAndMarkup => ("Cost", "Markup %"),
+                Known::PriceAndMarkup => ("Price", "Markup %"),
+            };
+            egui::ComboBox::from_id_source("pricing_known")
+                .selected_text(format!("{} + {}", first, second))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut panel.known, Known::CostAndPrice, "Cost + Price");
+                    ui.selectable_value(&mut panel.known, Known::CostAndMargin, "Cost + Margin %");
+                    ui.selectable_value(&mut panel.known, Known::PriceAndMargin, "Price + Margin %");
+                    ui.selectable_value(&mut panel.known, Known::CostAndMarkup, "Cost + Markup %");
+                    ui.selectable_value(&mut panel.known, Known::PriceAndMarkup, "Price + Markup %");
+                });
+
+            egui::Grid::new("pricing_inputs").show(ui, |ui| {
+                ui.label(first);
+                ui.add(egui::DragValue::new(&mut panel.first).speed(0.1));
+                ui.end_row();
+                ui.label(second);
+                ui.add(egui::DragValue::new(&mut panel.second).speed(0.1));
+                ui.end_row();
+            });
+
+            match business::solve(panel.known, panel.first, panel.second) {
+                Ok(pricing) => {
+                    egui::Grid::new("pricing_results").show(ui, |ui| {
+                        ui.label("Cost");
+                        ui.monospace(format!("{:.2}", pricing.cost));
+                        ui.end_row();
+                        ui.label("Price");
+                        ui.monospace(format!("{:.2}", pricing.price));
+                        ui.end_row();
+                        ui.label("Margin").on_hover_text(MARGIN_HINT);
+                        ui.monospace(format!("{:.2}%", pricing.margin));
+                        ui.end_row();
+                        ui.label("Markup").on_hover_text(MARKUP_HINT);
+                        ui.monospace(format!("{:.2}%", pricing.markup));
+                        ui.end_row();
+                    });
+                }
+                Err(err) => {
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                }
+            }
+        });
+    }
+
+    fn cash_flow_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("NPV & IRR", |ui| {
+            let panel = &mut self.cash_flows;
+            let mut removed = None;
+            egui::Grid::new("cash_flows").num_columns(3).show(ui, |ui| {
+                let count = panel.flows.len();
+                for (period, flow) in panel.flows.iter_mut().enumerate() {
+                    ui.label(format!("CF{}", period));
+                    ui.add(egui::DragValue::new(flow).speed(1.0));
+                    if ui.add_enabled(count > 1, egui::Button::new("✖").small()).on_hover_text("Remove").clicked() {
+                        removed = Some(period);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(period) = removed {
+                panel.flows.remove(period);
+            }
+            if ui.small_button("+ Period").clicked() {
+                panel.flows.push(0.0);
+            }
+            ui.horizontal(|ui| {
+                ui.label("Rate % per period");
+                ui.add(egui::DragValue::new(&mut panel.rate_percent).speed(0.1).clamp_range(-99.99..=1000.0));
+            });
+
+            let mut used = None;
+            egui::Grid::new("cash_flow_results").num_columns(3).show(ui, |ui| {
+                let results = [
+                    ("NPV", cashflow::npv(panel.rate_percent, &panel.flows), "Present value of all the flows at the rate"),
+                    ("IRR %", cashflow::irr(&panel.flows), "The rate at which the NPV is zero"),
+                ];
+                for (label, result, hint) in results {
+                    ui.label(label).on_hover_text(hint);
+                    match result {
+                        Ok(value) => {
+                            ui.monospace(format!("{:.2}", value));
+                            if ui.small_button("Use").on_hover_text("Put it on the display").clicked() {
+                                used = Some(value);
+                            }
+                        }
+                        Err(err) => {
+                            ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(value) = used {
+                self.calculator.set_operand(value);
+            }
+        });
+    }
+
+    fn ratio_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Proportion", |ui| {
+            ui.horizontal(|ui| {
+                for (index, term) in self.ratio_terms.iter_mut().enumerate() {
+                    ui.add(egui::TextEdit::singleline(term).hint_text("x").desired_width(45.0));
+                    match index {
+                        0 | 2 => ui.label(":"),
+                        1 => ui.label("="),
+                        _ => continue,
+                    };
+                }
+            });
+
+            let mut terms = [None; 4];
+            for (term, text) in terms.iter_mut().zip(&self.ratio_terms) {
+                let text = text.trim();
+                if text.is_empty() {
+                    continue;
+                }
+                match text.parse::<f64>() {
+                    Ok(value) => *term = Some(value),
+                    Err(_) => {
+                        ui.colored_label(ui.visuals().error_fg_color, format!("Not a number: {}", text));
+                        return;
+                    }
+                }
+            }
+            let Some(missing) = terms.iter().position(Option::is_none) else {
+                ui.weak("Leave one field empty to solve for it");
+                return;
+            };
+
+            match ratio::solve(terms) {
+                Ok(solved) => {
+                    ui.horizontal(|ui| {
+                        ui.monospace(format!("x = {}", solved[missing]));
+                        if ui.small_button("Use").on_hover_text("Put the result on the display").clicked() {
+                            self.calculator.set_operand(solved[missing]);
+                        }
+                    });
+                }
+                Err(CalculatorError::Domain(_)) => {
+                    ui.weak("Fill in three of the four fields");
+                }
+                Err(err) => {
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                }
+            }
+        });
+    }
+
+    fn polynomial_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Polynomial roots", |ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.polynomial.coefficients)
+                    .hint_text("Coefficients, highest power first: 1, -3, 2")
+                    .desired_width(280.0),
+            );
+            ui.checkbox(&mut self.polynomial.show_complex, "Show complex roots");
+
+            let mut coefficients = Vec::new();
+            for text in self.polynomial.coefficients.split([',', ' ']).filter(|text| !text.is_empty()) {
+                match text.parse::<f64>() {
+                    Ok(value) => coefficients.push(value),
+                    Err(_) => {
+                        ui.colored_label(ui.visuals().error_fg_color, format!("Not a number: {}", text));
+                        return;
+                    }
+                }
+            }
+            let roots = match polynomial::roots(&coefficients) {
+                Ok(roots) => roots,
+                Err(CalculatorError::Domain(_)) => {
+                    ui.weak("Enter at least a linear polynomial, e.g. 2, -6");
+                    return;
+                }
+                Err(err) => {
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                    return;
+                }
+            };
+
+            let real: Vec<f64> = roots.iter().filter(|root| root.is_real()).map(|root| root.re).collect();
+            if real.is_empty() {
+                ui.weak("No real roots");
+            }
+            for root in roots.iter().filter(|root| root.is_real() || self.polynomial.show_complex) {
+                ui.horizontal(|ui| {
+                    ui.monospace(format!("x = {}", root));
+                    if root.is_real()
+                        && ui.small_button("Use").on_hover_text("Put the root on the display").clicked()
+                    {
+                        self.calculator.set_operand(root.re);
+                    }
+                });
+            }
+            polynomial_plot_ui(ui, &coefficients, &real);
+        });
+    }
+
+    fn simulation_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("What if (Monte Carlo)", |ui| {
+            ui.add(
+                egui::TextEdit::multiline(&mut self.simulation.definitions)
+                    .hint_text("x ~ normal(10, 2)\nrate ~ uniform(0.05, 0.08)\ndays ~ triangular(3, 5, 10)")
+                    .font(egui::TextStyle::Monospace)
+                    .desired_rows(3)
+                    .desired_width(280.0),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut self.simulation.expression)
+                    .hint_text("Expression, e.g. x * (1 + rate)")
+                    .desired_width(280.0),
+            );
+            ui.horizontal(|ui| {
+                ui.label("Samples:");
+                ui.add(
+                    egui::DragValue::new(&mut self.simulation.samples)
+                        .clamp_range(1..=MAX_SIMULATION_SAMPLES)
+                        .speed(100.0),
+                );
+                if ui.add_enabled(self.simulation.running.is_none(), egui::Button::new("Run")).clicked() {
+                    self.simulation.seed += 1;
+                    match self.simulation_definitions() {
+                        Ok(definitions) => self.simulation.running = Some(self.spawn_simulation(ui.ctx(), definitions)),
+                        Err(message) => self.simulation.result = Some(Err(message)),
+                    }
+                }
+            });
+
+            if let Some(finished) = self.simulation.running.as_ref().and_then(|task| task.poll()) {
+                self.simulation.running = None;
+                // A cancelled run leaves the last result showing
+                if finished.is_some() {
+                    self.simulation.result = finished;
+                }
+            }
+            if let Some(task) = &self.simulation.running {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    let (done, total) = task.progress().counts();
+                    ui.label(format!("Sampling… {} / {}", done, total));
+                    if ui.button("Cancel").clicked() {
+                        task.cancel();
+                    }
+                });
+                return;
+            }
+
+            let summary = match &self.simulation.result {
+                None => return,
+                Some(Err(message)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, message);
+                    return;
+                }
+                Some(Ok(summary)) => summary,
+            };
+            let mut used = None;
+            egui::Grid::new("simulation_summary").show(ui, |ui| {
+                ui.label("Mean");
+                ui.horizontal(|ui| {
+                    ui.monospace(format!("{:.6}", summary.mean));
+                    if ui.small_button("Use").on_hover_text("Put the mean on the display").clicked() {
+                        used = Some(summary.mean);
+                    }
+                });
+                ui.end_row();
+                ui.label("Std. deviation");
+                ui.monospace(format!("{:.6}", summary.std_dev));
+                ui.end_row();
+                for (p, value) in montecarlo::PERCENTILES.iter().zip(&summary.percentiles) {
+                    ui.label(format!("P{}", p));
+                    ui.monospace(format!("{:.6}", value));
+                    ui.end_row();
+                }
+            });
+            if summary.failed > 0 {
+                ui.weak(format!("{} of {} draws had no result", summary.failed, summary.failed + summary.count));
+            }
+            histogram_plot_ui(ui, &summary.histogram);
+            if let Some(value) = used {
+                self.calculator.set_operand(value);
+            }
+        });
+    }
+
+    fn distribution_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Probability distributions", |ui| {
+            let panel = &mut self.distribution;
+            egui::ComboBox::from_id_source("distribution_kind")
+                .selected_text(panel.distribution.name())
+                .show_ui(ui, |ui| {
+                    for default in Distribution::DEFAULTS {
+                        let same = std::mem::discriminant(&default) == std::mem::discriminant(&panel.distribution);
+                        if ui.selectable_label(same, default.name()).clicked() && !same {
+                            panel.distribution = default;
+                        }
+                    }
+                });
+
+            egui::Grid::new("distribution_parameters").show(ui, |ui| {
+                match &mut panel.distribution {
+                    Distribution::Normal { mean, std_dev } => {
+                        ui.label("Mean μ");
+                        ui.add(egui::DragValue::new(mean).speed(0.1));
+                        ui.end_row();
+                        ui.label("Std. deviation σ");
+                        ui.add(egui::DragValue::new(std_dev).speed(0.1));
+                    }
+                    Distribution::Binomial { trials, p } => {
+                        ui.label("Trials n");
+                        ui.add(egui::DragValue::new(trials).clamp_range(0..=100_000));
+                        ui.end_row();
+                        ui.label("Success probability p");
+                        ui.add(egui::DragValue::new(p).speed(0.01).clamp_range(0.0..=1.0));
+                    }
+                    Distribution::Poisson { rate } => {
+                        ui.label("Rate λ");
+                        ui.add(egui::DragValue::new(rate).speed(0.1));
+                    }
+                    Distribution::StudentT { df } => {
+                        ui.label("Degrees of freedom ν");
+                        ui.add(egui::DragValue::new(df).speed(0.1));
+                    }
+                }
+                ui.end_row();
+                ui.label("x");
+                ui.add(egui::DragValue::new(&mut panel.x).speed(0.1));
+                ui.end_row();
+                ui.label("Probability");
+                ui.add(egui::DragValue::new(&mut panel.probability).speed(0.01).clamp_range(0.0..=1.0));
+                ui.end_row();
+            });
+
+            let distribution = panel.distribution;
+            if let Err(err) = distribution.validate() {
+                ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                return;
+            }
+            let x = panel.x;
+            let density = if distribution.is_discrete() {
+                format!("P(X = {})", x)
+            } else {
+                format!("f({})", x)
+            };
+            let mut used = None;
+            egui::Grid::new("distribution_results").striped(true).show(ui, |ui| {
+                let rows = [
+                    (density, Ok(distribution.density(x))),
+                    (format!("P(X ≤ {})", x), Ok(distribution.cdf(x))),
+                    (format!("P(X > {})", x), Ok(1.0 - distribution.cdf(x))),
+                    (format!("Quantile at {}", panel.probability), distribution.quantile(panel.probability)),
+                ];
+                for (label, value) in rows {
+                    ui.label(label);
+                    match value {
+                        Ok(value) => {
+                            ui.horizontal(|ui| {
+                                ui.monospace(format!("{:.10}", value));
+                                if ui.small_button("Use").on_hover_text("Put it on the display").clicked() {
+                                    used = Some(value);
+                                }
+                            });
+                        }
+                        Err(_) => {
+                            ui.weak("Needs a probability between 0 and 1");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+            ui.checkbox(&mut panel.show_plot, "Show density plot");
+            if panel.show_plot {
+                density_plot_ui(ui, &distribution, x);
+            }
+            if let Some(value) = used {
+                self.calculator.set_operand(value);
+            }
+        });
     }
-}
 
-impl eframe::App for CalculatorApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.vertical_centered(|ui| {
-                ui.add_space(20.0);
-                
-                // Display area with background
-                ui.group(|ui| {
-                    ui.set_min_width(280.0);
-                    ui.set_min_height(60.0);
-                    ui.vertical_centered(|ui| {
-                        ui.add_space(10.0);
-                        ui.label(
-                            egui::RichText::new(self.calculator.get_display_text())
-                                .size(36.0)
-                                .monospace()
-                        );
-                        ui.add_space(10.0);
-                    });
+    fn hypothesis_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Hypothesis tests", |ui| {
+            let panel = &mut self.hypothesis;
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut panel.known_sd, false, "t test")
+                    .on_hover_text("The standard deviation comes from the sample");
+                ui.radio_value(&mut panel.known_sd, true, "z test")
+                    .on_hover_text("The population's standard deviation is known");
+            });
+            egui::Grid::new("hypothesis_inputs").show(ui, |ui| {
+                ui.label("Sample mean x̄");
+                ui.add(egui::DragValue::new(&mut panel.sample_mean).speed(0.1));
+                ui.end_row();
+                ui.label("Hypothesized mean μ₀");
+                ui.add(egui::DragValue::new(&mut panel.null_mean).speed(0.1));
+                ui.end_row();
+                ui.label(if panel.known_sd { "Population std. dev. σ" } else { "Sample std. dev. s" });
+                ui.add(egui::DragValue::new(&mut panel.std_dev).speed(0.1));
+                ui.end_row();
+                ui.label("Sample size n");
+                ui.add(egui::DragValue::new(&mut panel.n).clamp_range(1..=u32::MAX));
+                ui.end_row();
+                ui.label("Alternative");
+                ui.horizontal(|ui| {
+                    for tail in Tail::ALL {
+                        ui.radio_value(&mut panel.tail, tail, tail.label());
+                    }
                 });
-                
-                ui.add_space(20.0);
-                
-                // Button grid (4x4)
-                egui::Grid::new("calculator_grid")
-                    .spacing([8.0, 8.0])
-                    .show(ui, |ui| {
-                        // Row 1: 7, 8, 9, ÷
-                        for digit in 7..=9 {
-                            if ui.add_sized([65.0, 65.0], 
-                                egui::Button::new(egui::RichText::new(digit.to_string()).size(24.0))
-                            ).clicked() {
-                                self.calculator.input_digit(digit);
+                ui.end_row();
+            });
+
+            let test = if panel.known_sd { stats::z_test } else { stats::t_test };
+            match test(panel.sample_mean, panel.null_mean, panel.std_dev, panel.n, panel.tail) {
+                Ok(result) => {
+                    let mut used = None;
+                    egui::Grid::new("hypothesis_result").striped(true).show(ui, |ui| {
+                        let (symbol, p_value) = (if panel.known_sd { "z" } else { "t" }, result.p_value);
+                        ui.label(match result.df {
+                            Some(df) => format!("{} ({} df)", symbol, df),
+                            None => symbol.to_string(),
+                        });
+                        ui.monospace(format!("{:.6}", result.statistic));
+                        ui.end_row();
+                        ui.label("p-value");
+                        ui.horizontal(|ui| {
+                            ui.monospace(format!("{:.6}", p_value));
+                            if ui.small_button("Use").on_hover_text("Put the p-value on the display").clicked() {
+                                used = Some(p_value);
+                            }
+                        });
+                        ui.end_row();
+                    });
+                    ui.weak(if result.p_value < 0.05 {
+                        "Significant at the 5% level"
+                    } else {
+                        "Not significant at the 5% level"
+                    });
+                    if let Some(value) = used {
+                        self.calculator.set_operand(value);
+                    }
+                }
+                Err(_) if panel.known_sd => {
+                    ui.weak("Needs σ above 0 and at least one observation");
+                }
+                Err(_) => {
+                    ui.weak("Needs s above 0 and at least two observations");
+                }
+            }
+        });
+    }
+
+    fn simulation_definitions(&self) -> Result<Vec<Definition>, String> {
+        let env = Environment::new();
+        let mut definitions = Vec::new();
+        for (index, line) in self.simulation.definitions.lines().enumerate() {
+            match montecarlo::parse_definition(line, &env) {
+                Ok(definition) => definitions.extend(definition),
+                Err(err) => return Err(format!("Line {}: {}", index + 1, err)),
+            }
+        }
+        if definitions.is_empty() {
+            return Err(String::from("Define at least one variable, e.g. x ~ normal(10, 2)"));
+        }
+        Ok(definitions)
+    }
+
+    // Runs the simulation on a worker thread with a copy of the calculator,
+    // as a million samples would freeze the window
+    fn spawn_simulation(
+        &self,
+        ctx: &egui::Context,
+        definitions: Vec<Definition>,
+    ) -> Task<Option<Result<Summary, String>>> {
+        let calculator = self.calculator.clone();
+        let source = self.simulation.expression.clone();
+        let (samples, seed) = (self.simulation.samples, self.simulation.seed);
+        let functions = self.worker_functions();
+        let ctx = ctx.clone();
+        Task::spawn(
+            move |progress| {
+                functions.with(|functions| {
+                    let summary = calculator.simulate(&source, &definitions, samples, seed, functions, progress)?;
+                    Some(summary.map_err(|err| err.to_string()))
+                })
+            },
+            move || ctx.request_repaint(),
+        )
+    }
+
+    fn dms_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Degrees, minutes, seconds", |ui| {
+            egui::Grid::new("dms_inputs").show(ui, |ui| {
+                for (label, text) in ["A", "B"].into_iter().zip(self.angles.iter_mut()) {
+                    ui.label(label);
+                    ui.add(
+                        egui::TextEdit::singleline(text)
+                            .hint_text("12°34'56\" or 12.5825")
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(160.0),
+                    );
+                    ui.end_row();
+                }
+            });
+            if let Some(value) = self.calculator.display_value() {
+                if ui.small_button("A from display").clicked() {
+                    self.angles[0] = dms::format(value, dms::SECONDS_PRECISION);
+                }
+            }
+
+            let [a, b] = &self.angles;
+            if a.trim().is_empty() {
+                return;
+            }
+            let mut results = vec![("A", dms::parse(a))];
+            if !b.trim().is_empty() {
+                results.push(("A + B", dms::add(a, b, false)));
+                results.push(("A − B", dms::add(a, b, true)));
+            }
+
+            let mut used = None;
+            egui::Grid::new("dms_results").show(ui, |ui| {
+                for (label, result) in results {
+                    ui.weak(label);
+                    match result {
+                        Ok(degrees) => {
+                            ui.monospace(dms::format(degrees, dms::SECONDS_PRECISION));
+                            ui.monospace(format!("{}°", degrees));
+                            if ui.small_button("Use").on_hover_text("Put the decimal degrees on the display").clicked() {
+                                used = Some(degrees);
                             }
                         }
-                        if ui.add_sized([65.0, 65.0], 
-                            egui::Button::new(egui::RichText::new("÷").size(24.0))
-                        ).clicked() {
-                            self.calculator.input_operation(Operation::Divide);
+                        Err(err) => {
+                            ui.colored_label(ui.visuals().error_fg_color, err.to_string());
                         }
-                        ui.end_row();
-                        
-                        // Row 2: 4, 5, 6, ×
-                        for digit in 4..=6 {
-                            if ui.add_sized([65.0, 65.0], 
-                                egui::Button::new(egui::RichText::new(digit.to_string()).size(24.0))
-                            ).clicked() {
-                                self.calculator.input_digit(digit);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(degrees) = used {
+                self.calculator.set_operand(degrees);
+            }
+        });
+    }
+
+    fn timestamp_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Unix timestamp", |ui| {
+            // The display as seconds or milliseconds since 1970, if it's a whole number
+            let displayed = self
+                .calculator
+                .display_value()
+                .filter(|value| value.fract() == 0.0 && value.abs() < i64::MAX as f64)
+                .map(|value| value as i64);
+            match displayed {
+                Some(value) => {
+                    let precision = Precision::detect(value);
+                    ui.weak(format!("{} read as {}", value, precision.name()));
+                    match timestamp::to_datetime(value, precision) {
+                        Some(datetime) => {
+                            egui::Grid::new("timestamp_dates").show(ui, |ui| {
+                                for (label, text) in [
+                                    ("UTC", timestamp::format_utc(datetime)),
+                                    ("Local", timestamp::format_local(datetime)),
+                                ] {
+                                    ui.weak(label);
+                                    ui.monospace(&text);
+                                    if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                                        self.clipboard_request = Some(text);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                        }
+                        None => {
+                            ui.colored_label(ui.visuals().error_fg_color, "Out of the supported range of dates");
+                        }
+                    }
+                }
+                None => {
+                    ui.weak("Put a whole number on the display to read it as a date");
+                }
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.timestamp.date)
+                        .hint_text("2023-11-14 22:13:20")
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(180.0),
+                );
+                ui.checkbox(&mut self.timestamp.local, "Local time")
+                    .on_hover_text("Read dates without an offset as this computer's time instead of UTC");
+            });
+            if self.timestamp.date.trim().is_empty() {
+                return;
+            }
+            let mut used = None;
+            match timestamp::parse(&self.timestamp.date, self.timestamp.local) {
+                Ok(datetime) => {
+                    egui::Grid::new("timestamp_values").show(ui, |ui| {
+                        for precision in [Precision::Seconds, Precision::Milliseconds] {
+                            let value = match precision {
+                                Precision::Seconds => datetime.timestamp(),
+                                Precision::Milliseconds => datetime.timestamp_millis(),
+                            };
+                            ui.weak(precision.name());
+                            ui.monospace(value.to_string());
+                            if ui.small_button("Use").on_hover_text("Put the timestamp on the display").clicked() {
+                                used = Some(value as f64);
                             }
+                            ui.end_row();
                         }
-                        if ui.add_sized([65.0, 65.0], 
-                            egui::Button::new(egui::RichText::new("×").size(24.0))
-                        ).clicked() {
-                            self.calculator.input_operation(Operation::Multiply);
+                    });
+                }
+                Err(err) => {
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                }
+            }
+            if let Some(value) = used {
+                self.calculator.set_operand(value);
+            }
+        });
+    }
+
+    fn data_size_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Data size", |ui| {
+            let panel = &mut self.data_size;
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut panel.amount)
+                        .hint_text("500 GB, 1.5 GiB or 100 Mbit")
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(180.0),
+                );
+                ui.checkbox(&mut panel.per_second, "per second");
+            });
+            if panel.amount.trim().is_empty() {
+                return;
+            }
+            let (value, from) = match datasize::parse(&panel.amount) {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                    return;
+                }
+            };
+            let rate = if panel.per_second { "/s" } else { "" };
+
+            // The mix-up this converter exists for: GB read as GiB or the other way round
+            if let Some(other) = from.counterpart() {
+                let (si, binary) = if from.binary { (other, from) } else { (from, other) };
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!(
+                        "⚠ {} {}{} is {} {}{}. {} counts in 1000s and {} in 1024s, but both are often labelled {}.",
+                        value,
+                        from.symbol,
+                        rate,
+                        datasize::format_amount(datasize::convert(value, from, other)),
+                        other.symbol,
+                        rate,
+                        si.symbol,
+                        binary.symbol,
+                        si.symbol
+                    ),
+                );
+            }
+
+            let mut used = None;
+            egui::Grid::new("data_size_table").striped(true).show(ui, |ui| {
+                for heading in ["Bytes (1000)", "Bytes (1024)", "Bits (1000)", "Bits (1024)"] {
+                    ui.weak(heading);
+                }
+                ui.end_row();
+                for power in 0..=5 {
+                    for (bits, binary) in [(false, false), (false, true), (true, false), (true, true)] {
+                        // Plain bits and bytes only have the 1000 column
+                        let unit = datasize::UNITS
+                            .iter()
+                            .copied()
+                            .find(|unit| unit.power == power && unit.bits == bits && unit.binary == binary);
+                        match unit {
+                            Some(unit) => {
+                                let amount = datasize::convert(value, from, unit);
+                                let text = format!("{} {}{}", datasize::format_amount(amount), unit.symbol, rate);
+                                let text = if unit == from {
+                                    egui::RichText::new(text).monospace().strong()
+                                } else {
+                                    egui::RichText::new(text).monospace()
+                                };
+                                if ui
+                                    .add(egui::Label::new(text).sense(egui::Sense::click()))
+                                    .on_hover_text("Put the number on the display")
+                                    .clicked()
+                                {
+                                    used = Some(amount);
+                                }
+                            }
+                            None => {
+                                ui.label("");
+                            }
                         }
-                        ui.end_row();
-                        
-                        // Row 3: 1, 2, 3, -
-                        for digit in 1..=3 {
-                            if ui.add_sized([65.0, 65.0], 
-                                egui::Button::new(egui::RichText::new(digit.to_string()).size(24.0))
-                            ).clicked() {
-                                self.calculator.input_digit(digit);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(amount) = used {
+                self.calculator.set_operand(amount);
+            }
+        });
+    }
+
+    fn travel_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Fuel economy & pace", |ui| {
+            let panel = &mut self.travel;
+            let mut used = None;
+
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut panel.economy).hint_text("30").desired_width(80.0));
+                egui::ComboBox::from_id_source("economy_unit")
+                    .selected_text(panel.economy_unit.symbol())
+                    .show_ui(ui, |ui| {
+                        for unit in Economy::ALL {
+                            ui.selectable_value(&mut panel.economy_unit, unit, unit.symbol());
+                        }
+                    });
+            });
+            if !panel.economy.trim().is_empty() {
+                let value = panel.economy.trim().parse::<f64>().map_err(|_| CalculatorError::Syntax {
+                    message: String::from("Expected a number"),
+                    column: 1,
+                });
+                match value.and_then(|value| {
+                    Economy::ALL
+                        .into_iter()
+                        .filter(|&unit| unit != panel.economy_unit)
+                        .map(|unit| Ok((unit, travel::convert_economy(value, panel.economy_unit, unit)?)))
+                        .collect::<Result<Vec<_>, CalculatorError>>()
+                }) {
+                    Ok(conversions) => {
+                        egui::Grid::new("economy_results").show(ui, |ui| {
+                            for (unit, converted) in conversions {
+                                ui.monospace(ieee754::round_to_significant(converted, 6).to_string());
+                                ui.weak(unit.symbol());
+                                if ui.small_button("Use").on_hover_text("Put the value on the display").clicked() {
+                                    used = Some(converted);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    }
+                    Err(err) => {
+                        ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                    }
+                }
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut panel.speed).hint_text("5:30").desired_width(80.0));
+                egui::ComboBox::from_id_source("speed_unit")
+                    .selected_text(panel.speed_unit.symbol())
+                    .show_ui(ui, |ui| {
+                        for unit in Speed::ALL {
+                            ui.selectable_value(&mut panel.speed_unit, unit, unit.symbol());
+                        }
+                    });
+            });
+            if !panel.speed.trim().is_empty() {
+                match travel::parse_speed(&panel.speed, panel.speed_unit).and_then(|value| {
+                    Speed::ALL
+                        .into_iter()
+                        .filter(|&unit| unit != panel.speed_unit)
+                        .map(|unit| Ok((unit, travel::convert_speed(value, panel.speed_unit, unit)?)))
+                        .collect::<Result<Vec<_>, CalculatorError>>()
+                }) {
+                    Ok(conversions) => {
+                        egui::Grid::new("speed_results").show(ui, |ui| {
+                            for (unit, converted) in conversions {
+                                ui.monospace(travel::format_speed(converted, unit));
+                                ui.weak(unit.symbol());
+                                // Paces go on the display as decimal minutes
+                                if ui.small_button("Use").on_hover_text("Put the value on the display").clicked() {
+                                    used = Some(converted);
+                                }
+                                ui.end_row();
                             }
+                        });
+                    }
+                    Err(err) => {
+                        ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                    }
+                }
+            }
+
+            if let Some(value) = used {
+                self.calculator.set_operand(value);
+            }
+        });
+    }
+
+    fn cooking_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Cooking measures", |ui| {
+            let panel = &mut self.cooking;
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut panel.amount).hint_text("1.5").desired_width(80.0));
+                egui::ComboBox::from_id_source("cooking_unit")
+                    .selected_text(panel.unit.symbol())
+                    .show_ui(ui, |ui| {
+                        for unit in Measure::ALL {
+                            ui.selectable_value(&mut panel.unit, unit, unit.symbol());
                         }
-                        if ui.add_sized([65.0, 65.0], 
-                            egui::Button::new(egui::RichText::new("-").size(24.0))
-                        ).clicked() {
-                            self.calculator.input_operation(Operation::Subtract);
+                    });
+            });
+
+            ui.add(
+                egui::TextEdit::singleline(&mut panel.query)
+                    .hint_text("🔍 Ingredient, e.g. flour")
+                    .desired_width(220.0),
+            );
+            let mut removed = None;
+            egui::ScrollArea::vertical().max_height(120.0).id_source("ingredients").show(ui, |ui| {
+                for (name, density) in cooking::search(&panel.query, &self.ingredients) {
+                    ui.horizontal(|ui| {
+                        let selected = panel.ingredient.as_deref() == Some(name);
+                        if ui
+                            .selectable_label(selected, name)
+                            .on_hover_text(format!("{:.0} g per cup", cooking::convert(1.0, Measure::Cup, Measure::Gram, Some(density)).unwrap_or_default()))
+                            .clicked()
+                        {
+                            panel.ingredient = if selected { None } else { Some(name.to_string()) };
                         }
-                        ui.end_row();
-                        
-                        // Row 4: 0, ., =, +
-                        if ui.add_sized([65.0, 65.0], 
-                            egui::Button::new(egui::RichText::new("0").size(24.0))
-                        ).clicked() {
-                            self.calculator.input_digit(0);
-                        }
-                        if ui.add_sized([65.0, 65.0], 
-                            egui::Button::new(egui::RichText::new(".").size(24.0))
-                        ).clicked() {
-                            self.calculator.input_decimal_point();
-                        }
-                        if ui.add_sized([65.0, 65.0], 
-                            egui::Button::new(egui::RichText::new("=").size(24.0))
-                        ).clicked() {
-                            self.calculator.calculate();
-                        }
-                        if ui.add_sized([65.0, 65.0], 
-                            egui::Button::new(egui::RichText::new("+").size(24.0))
-                        ).clicked() {
-                            self.calculator.input_operation(Operation::Add);
+                        if self.ingredients.iter().any(|ingredient| ingredient.name == name)
+                            && ui.small_button("🗑").on_hover_text("Remove this ingredient").clicked()
+                        {
+                            removed = Some(name.to_string());
                         }
-                        ui.end_row();
+                    });
+                }
+            });
+            if let Some(name) = removed {
+                self.ingredients.retain(|ingredient| ingredient.name != name);
+                if panel.ingredient.as_ref() == Some(&name) {
+                    panel.ingredient = None;
+                }
+            }
+
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut panel.new_name).hint_text("New ingredient").desired_width(120.0));
+                ui.add(egui::TextEdit::singleline(&mut panel.new_grams_per_cup).hint_text("g per cup").desired_width(70.0));
+                if ui.button("Add").clicked() {
+                    let grams = panel.new_grams_per_cup.trim().parse().unwrap_or(f64::NAN);
+                    panel.error = cooking::add(&mut self.ingredients, &panel.new_name, grams).err();
+                    if panel.error.is_none() {
+                        panel.ingredient = Some(panel.new_name.trim().to_string());
+                        panel.new_name.clear();
+                        panel.new_grams_per_cup.clear();
+                    }
+                }
+            });
+            if let Some(err) = &panel.error {
+                ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+            }
+
+            let Ok(amount) = panel.amount.trim().parse::<f64>() else {
+                return;
+            };
+            let density = panel
+                .ingredient
+                .as_deref()
+                .and_then(|name| cooking::search("", &self.ingredients).into_iter().find(|(found, _)| *found == name))
+                .map(|(_, density)| density);
+            let mut used = None;
+            egui::Grid::new("cooking_results").show(ui, |ui| {
+                for unit in Measure::ALL.into_iter().filter(|&unit| unit != panel.unit) {
+                    // Between volume and weight only once an ingredient is picked
+                    let Ok(converted) = cooking::convert(amount, panel.unit, unit, density) else {
+                        continue;
+                    };
+                    ui.monospace(converted.to_string());
+                    ui.weak(unit.symbol());
+                    if ui.small_button("Use").on_hover_text("Put the value on the display").clicked() {
+                        used = Some(converted);
+                    }
+                    ui.end_row();
+                }
+            });
+            if density.is_none() {
+                ui.weak("Pick an ingredient to convert between volume and weight");
+            }
+            if let Some(value) = used {
+                self.calculator.set_operand(value);
+            }
+        });
+    }
+
+    fn constants_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Constants", |ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.constants_query)
+                    .hint_text("🔍 Search, e.g. planck")
+                    .desired_width(280.0),
+            );
+
+            let mut used = None;
+            let mut inserted = None;
+            egui::ScrollArea::vertical().max_height(150.0).id_source("constants").show(ui, |ui| {
+                for constant in constants::search(&self.constants_query) {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("Use").on_hover_text("Put the value on the display").clicked() {
+                            used = Some(constant.value);
+                        }
+                        if ui
+                            .small_button("Insert")
+                            .on_hover_text(format!("Insert {} into the expression", constant.name))
+                            .clicked()
+                        {
+                            inserted = Some(constant.name);
+                        }
+                        let uncertainty = match constant.uncertainty {
+                            Some(uncertainty) => format!(" ± {:e}", uncertainty),
+                            None => String::from(" (exact)"),
+                        };
+                        ui.monospace(format!("{} = {:e} {}", constant.symbol, constant.value, constant.unit))
+                            .on_hover_text(format!("{}{}\nIn expressions: {}", constant.description, uncertainty, constant.name));
+                    });
+                }
+            });
+
+            if let Some(value) = used {
+                self.calculator.set_operand(value);
+            }
+            if let Some(name) = inserted {
+                self.mode = Mode::Expression;
+                self.expression_input.push_str(name);
+                self.preview_due = Some(0.0);
+            }
+        });
+    }
+
+    fn macros_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Macros", |ui| {
+            if self.recorder.is_recording() {
+                if ui.button("⏹ Stop recording").clicked() {
+                    let events = self.recorder.stop();
+                    if !events.is_empty() {
+                        self.pending_macro = Some(events);
+                    }
+                }
+            } else if self.pending_macro.is_some() {
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut self.macro_name);
+                });
+                ui.horizontal(|ui| {
+                    let name = self.macro_name.trim().to_string();
+                    if ui.add_enabled(!name.is_empty(), egui::Button::new("Save")).clicked() {
+                        if let Some(events) = self.pending_macro.take() {
+                            self.macros.push(Macro::new(name, events));
+                            self.macro_name.clear();
+                        }
+                    }
+                    if ui.button("Discard").clicked() {
+                        self.pending_macro = None;
+                        self.macro_name.clear();
+                    }
+                });
+            } else if ui.button("⏺ Record").clicked() {
+                self.recorder.start();
+            }
+
+            ui.separator();
+
+            // Replay acts on whatever value is currently displayed
+            let mut to_remove = None;
+            for (index, saved) in self.macros.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.button(format!("▶ {}", saved.name)).clicked() {
+                        saved.replay(&mut self.calculator, &self.operations);
+                    }
+                    if ui.small_button("🗑").clicked() {
+                        to_remove = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = to_remove {
+                self.macros.remove(index);
+            }
+        });
+    }
+}
+
+// Rewrites math typography (×, −, ², ...) in a text field as it's typed or
+// pasted, keeping the caret after the same character
+// Data bounds fitted to a screen rectangle, with y growing upwards
+struct PlotFrame {
+    rect: egui::Rect,
+    min: (f64, f64),
+    max: (f64, f64),
+}
+
+impl PlotFrame {
+    fn fit(rect: egui::Rect, points: &[(f64, f64)]) -> Self {
+        let mut min = (f64::INFINITY, f64::INFINITY);
+        let mut max = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for &(x, y) in points {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        Self { rect, min, max }
+    }
+
+    fn to_screen(&self, (x, y): (f64, f64)) -> egui::Pos2 {
+        // A flat range sits in the middle instead of dividing by zero
+        let fraction = |value: f64, min: f64, max: f64| {
+            if max > min {
+                ((value - min) / (max - min)) as f32
+            } else {
+                0.5
+            }
+        };
+        egui::pos2(
+            self.rect.left() + fraction(x, self.min.0, self.max.0) * self.rect.width(),
+            self.rect.bottom() - fraction(y, self.min.1, self.max.1) * self.rect.height(),
+        )
+    }
+
+    // Draws `points` as one line, returning where they landed on screen
+    fn line(&self, painter: &egui::Painter, points: &[(f64, f64)], stroke: egui::Stroke) -> Vec<egui::Pos2> {
+        let points: Vec<egui::Pos2> = points.iter().map(|&point| self.to_screen(point)).collect();
+        painter.add(egui::Shape::line(points.clone(), stroke));
+        points
+    }
+}
+
+// The polynomial around its real roots, with the x axis and the roots marked
+fn polynomial_plot_ui(ui: &mut egui::Ui, coefficients: &[f64], real_roots: &[f64]) {
+    let (low, high) = match (real_roots.first(), real_roots.last()) {
+        (Some(&low), Some(&high)) => {
+            let margin = ((high - low) * 0.25).max(1.0);
+            (low - margin, high + margin)
+        }
+        _ => (-5.0, 5.0),
+    };
+    let samples: Vec<(f64, f64)> = (0..=POLYNOMIAL_SAMPLES)
+        .map(|index| low + (high - low) * index as f64 / POLYNOMIAL_SAMPLES as f64)
+        .map(|x| (x, polynomial::eval(coefficients, x)))
+        .filter(|(_, y)| y.is_finite())
+        .collect();
+    if samples.len() < 2 {
+        return;
+    }
+
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(DISPLAY_WIDTH, 140.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_stroke(rect, 2.0, ui.visuals().widgets.noninteractive.bg_stroke);
+    let frame = PlotFrame::fit(rect.shrink(4.0), &samples);
+    if frame.min.1 <= 0.0 && frame.max.1 >= 0.0 {
+        let axis = [frame.to_screen((low, 0.0)), frame.to_screen((high, 0.0))];
+        painter.line_segment(axis, ui.visuals().widgets.noninteractive.bg_stroke);
+    }
+    let color = ui.visuals().selection.stroke.color;
+    frame.line(&painter, &samples, egui::Stroke::new(1.5, color));
+    for &root in real_roots {
+        painter.circle_filled(frame.to_screen((root, 0.0)), 3.0, ui.visuals().strong_text_color());
+    }
+    response.on_hover_text(format!("x from {:.3} to {:.3}", low, high));
+}
+
+// The simulated results as bars, with the hovered bar's range and count
+fn histogram_plot_ui(ui: &mut egui::Ui, histogram: &Histogram) {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(DISPLAY_WIDTH, 120.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_stroke(rect, 2.0, ui.visuals().widgets.noninteractive.bg_stroke);
+    let area = rect.shrink(4.0);
+    let tallest = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+    let width = area.width() / histogram.counts.len() as f32;
+    let color = ui.visuals().selection.bg_fill;
+    for (index, &count) in histogram.counts.iter().enumerate() {
+        let height = area.height() * count as f32 / tallest as f32;
+        let left = area.left() + width * index as f32;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(left, area.bottom() - height),
+            egui::pos2(left + width - 1.0, area.bottom()),
+        );
+        painter.rect_filled(bar, 0.0, color);
+    }
+
+    let bin_width = (histogram.high - histogram.low) / histogram.counts.len() as f64;
+    let hovered = response
+        .hover_pos()
+        .map(|pointer| (((pointer.x - area.left()) / width).max(0.0) as usize).min(histogram.counts.len() - 1));
+    match hovered {
+        Some(index) => {
+            let from = histogram.low + bin_width * index as f64;
+            response.on_hover_text(format!(
+                "{:.4} to {:.4}: {} draws",
+                from,
+                from + bin_width,
+                histogram.counts[index]
+            ));
+        }
+        None => {
+            response.on_hover_text(format!("{:.4} to {:.4}", histogram.low, histogram.high));
+        }
+    }
+}
+
+// The density over its likely range, with the area up to `x` shaded; stems
+// for a discrete distribution
+fn density_plot_ui(ui: &mut egui::Ui, distribution: &Distribution, x: f64) {
+    let (low, high) = distribution.plot_range();
+    let points: Vec<(f64, f64)> = if distribution.is_discrete() {
+        (low as u64..=high as u64).map(|k| (k as f64, distribution.density(k as f64))).collect()
+    } else {
+        (0..=POLYNOMIAL_SAMPLES)
+            .map(|index| low + (high - low) * index as f64 / POLYNOMIAL_SAMPLES as f64)
+            .map(|x| (x, distribution.density(x)))
+            .collect()
+    };
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(DISPLAY_WIDTH, 120.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_stroke(rect, 2.0, ui.visuals().widgets.noninteractive.bg_stroke);
+    // Zero at the bottom rather than the smallest density
+    let mut bounds = points.clone();
+    bounds.push((low, 0.0));
+    let frame = PlotFrame::fit(rect.shrink(4.0), &bounds);
+
+    let color = ui.visuals().selection.stroke.color;
+    let shade = ui.visuals().selection.bg_fill;
+    for &(at, density) in &points {
+        let top = frame.to_screen((at, density));
+        let bottom = frame.to_screen((at, 0.0));
+        if distribution.is_discrete() {
+            let stroke = egui::Stroke::new(2.0, if at <= x { color } else { shade });
+            painter.line_segment([bottom, top], stroke);
+        } else if at <= x {
+            painter.line_segment([bottom, top], egui::Stroke::new(1.0, shade));
+        }
+    }
+    if !distribution.is_discrete() {
+        frame.line(&painter, &points, egui::Stroke::new(1.5, color));
+    }
+    response.on_hover_text(format!("Shaded: P(X ≤ {}) = {:.6}", x, distribution.cdf(x)));
+}
+
+// Where each instance keeps its crash journal: beside the saved settings,
+// or the working directory where there's no storage directory
+fn journal_dir() -> PathBuf {
+    eframe::storage_dir(TITLE).unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn theme_visuals(theme: Theme) -> egui::Visuals {
+    match theme {
+        Theme::Dark => egui::Visuals::dark(),
+        Theme::Light => egui::Visuals::light(),
+    }
+}
+
+// The theme, with fingertip-sized targets and no hover highlight in touch mode
+fn app_style(settings: &Settings) -> egui::Style {
+    let mut style = egui::Style {
+        visuals: theme_visuals(settings.theme),
+        ..Default::default()
+    };
+    if settings.touch_mode {
+        style.spacing.interact_size.y = TOUCH_TARGET;
+        style.spacing.button_padding = egui::vec2(12.0, 10.0);
+        style.spacing.item_spacing = egui::vec2(10.0, 10.0);
+        style.visuals.widgets.hovered = style.visuals.widgets.inactive;
+        // Tooltips then take a long press
+        style.interaction.tooltip_delay = 0.6;
+    }
+    style
+}
+
+// The largest display font step that fits `text` without clipping, or
+// `None` if even the smallest doesn't
+fn display_font_size(text: &str) -> Option<f32> {
+    let length = text.chars().count();
+    DISPLAY_FONT_STEPS
+        .iter()
+        .find(|&&(fits, _)| length <= fits)
+        .map(|&(_, size)| size)
+}
+
+fn normalize_field(ctx: &egui::Context, id: egui::Id, text: &mut String) {
+    let normalized = expression::normalize(text);
+    if normalized == *text {
+        return;
+    }
+    if let Some(mut state) = egui::TextEdit::load_state(ctx, id) {
+        if let Some(range) = state.ccursor_range() {
+            let before: String = text.chars().take(range.primary.index).collect();
+            let caret = expression::normalize(&before).chars().count();
+            state.set_ccursor_range(Some(egui::text::CCursorRange::one(egui::text::CCursor::new(caret))));
+            egui::TextEdit::store_state(ctx, id, state);
+        }
+    }
+    *text = normalized;
+}
+
+// Colors the expression field from the tokenizer, and marks the parenthesis
+// at the caret together with its partner
+fn highlight_job(text: &str, caret: Option<usize>, ui: &egui::Ui) -> egui::text::LayoutJob {
+    let visuals = ui.visuals();
+    let color = |class: Class| {
+        let (dark, light) = match class {
+            Class::Number => (egui::Color32::from_rgb(181, 206, 168), egui::Color32::from_rgb(9, 134, 88)),
+            Class::Operator => (egui::Color32::from_rgb(212, 160, 100), egui::Color32::from_rgb(175, 80, 0)),
+            Class::Function => (egui::Color32::from_rgb(220, 220, 170), egui::Color32::from_rgb(121, 94, 38)),
+            Class::Name => (egui::Color32::from_rgb(156, 220, 254), egui::Color32::from_rgb(0, 16, 128)),
+            Class::Paren | Class::Comma => (visuals.text_color(), visuals.text_color()),
+            Class::Invalid => (visuals.error_fg_color, visuals.error_fg_color),
+        };
+        if visuals.dark_mode {
+            dark
+        } else {
+            light
+        }
+    };
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let plain = egui::TextFormat::simple(font.clone(), visuals.text_color());
+    let parens = caret.and_then(|caret| highlight::matching_paren(text, caret));
+
+    let mut job = egui::text::LayoutJob::default();
+    let mut position = 0;
+    for span in highlight::classify(text) {
+        job.append(&text[position..span.start], 0.0, plain.clone());
+        let mut format = egui::TextFormat::simple(font.clone(), color(span.class));
+        if span.class == Class::Invalid {
+            format.underline = egui::Stroke::new(1.0, visuals.error_fg_color);
+        }
+        if parens.is_some_and(|(at, partner)| span.start == at || span.start == partner) {
+            format.background = visuals.selection.bg_fill;
+        }
+        job.append(&text[span.start..span.end], 0.0, format);
+        position = span.end;
+    }
+    job.append(&text[position..], 0.0, plain);
+    job
+}
+
+impl eframe::App for CalculatorApp {
+    // eframe only runs this on input. Anything that changes without input
+    // asks for its own repaint: the expression preview and toasts when they
+    // fall due, tray clicks and background tasks when they arrive.
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.tray_events(ctx);
+        self.instance_messages(ctx);
+        if ctx.input_mut(|i| i.consume_shortcut(&CONVERT_SHORTCUT)) {
+            self.show_convert = !self.show_convert;
+        }
+        // Escape closes the popup rather than clearing the display
+        if self.show_convert && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            self.show_convert = false;
+        }
+
+        // Pastes into a focused text field belong to that field
+        if !ctx.wants_keyboard_input() {
+            let pasted: Vec<String> = ctx.input(|i| {
+                i.events
+                    .iter()
+                    .filter_map(|event| match event {
+                        egui::Event::Paste(text) => Some(text.clone()),
+                        _ => None,
+                    })
+                    .collect()
+            });
+            for text in pasted {
+                self.paste(ctx, &text);
+            }
+        }
+
+        // Typing on the keypad, main keyboard or numpad, when no field has focus
+        if matches!(self.mode, Mode::Basic | Mode::Compare) && !ctx.wants_keyboard_input() {
+            let (events, now) = ctx.input(|i| {
+                let events: Vec<(InputEvent, bool)> = i
+                    .events
+                    .iter()
+                    .filter_map(|event| {
+                        let escape = matches!(
+                            event,
+                            egui::Event::Key {
+                                key: egui::Key::Escape,
+                                pressed: true,
+                                modifiers: egui::Modifiers::NONE,
+                                ..
+                            }
+                        );
+                        input::from_keyboard(event).map(|input| (input, escape))
+                    })
+                    .collect();
+                (events, i.time)
+            });
+            for (mut event, escape) in events {
+                if escape {
+                    // A second Escape in quick succession clears everything
+                    match self.last_escape.take() {
+                        Some(at) if now - at <= DOUBLE_ESCAPE_SECONDS => event = InputEvent::ClearAll,
+                        _ => self.last_escape = Some(now),
+                    }
+                }
+                if self.mode == Mode::Compare && self.compare.active == Side::B {
+                    self.compare.calculator.handle_event_with(event, &self.operations);
+                } else {
+                    self.dispatch(event);
+                }
+            }
+        }
+
+        if self.settings.frameless_window {
+            egui::TopBottomPanel::top("title_bar")
+                .exact_height(28.0)
+                .show(ctx, |ui| self.title_bar_ui(ui));
+        }
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| self.menu_bar_ui(ui));
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| self.status_bar_ui(ui));
+
+        // The keypad follows the settings, and turns sideways in a wide window
+        let layout = self.settings.keypad_layout().arranged_for(ctx.screen_rect().size());
+        self.keypad.set_layout(layout, &self.operations);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.add_space(10.0);
+
+                    ui.horizontal(|ui| {
+                        for mode in Mode::ALL {
+                            ui.selectable_value(&mut self.mode, mode, mode.label());
+                        }
+                    });
+
+                    ui.add_space(10.0);
+                
+                    // Display area with background
+                    ui.group(|ui| {
+                        ui.set_min_width(280.0);
+                        ui.set_min_height(60.0);
+                        ui.vertical_centered(|ui| {
+                            ui.add_space(4.0);
+                            self.pending_readout_ui(ui);
+                            self.display_label_ui(ui);
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .small_button("IEEE-754")
+                                    .on_hover_text("Show how the value is stored")
+                                    .clicked()
+                                {
+                                    self.show_float_inspector = !self.show_float_inspector;
+                                }
+                                ui.toggle_value(&mut self.show_words, "Aa")
+                                    .on_hover_text("Spell the number out in words");
+                                self.recent_ui(ui);
+                                self.interpretation_ui(ui);
+                                self.cleanup_indicator_ui(ui);
+                                self.float_warning_ui(ui);
+                                self.range_warning_ui(ui);
+                                self.item_count_ui(ui);
+                            });
+                            if self.show_words {
+                                self.words_ui(ui);
+                            }
+                            if self.settings.running_total {
+                                self.running_total_ui(ui);
+                            }
+                            ui.add_space(10.0);
+                        });
                     });
                 
-                ui.add_space(15.0);
+                    ui.add_space(20.0);
                 
-                // Clear button (full width)
-                if ui.add_sized([292.0, 50.0], 
-                    egui::Button::new(egui::RichText::new("Clear").size(20.0))
-                ).clicked() {
-                    self.calculator.clear();
-                }
+                    match self.mode {
+                        Mode::Basic => self.keypad_ui(ui),
+                        Mode::Expression => self.expression_ui(ui),
+                        Mode::Worksheet => self.worksheet_ui(ui),
+                        Mode::Grid => self.grid_ui(ui),
+                        Mode::Programmer => self.programmer_ui(ui),
+                        Mode::Compare => self.compare_ui(ui),
+                    }
+
+                    ui.add_space(15.0);
+
+                    self.favorites_ui(ui);
+
+                    self.history_ui(ui);
+
+                    self.percent_ui(ui);
+
+                    self.templates_ui(ui);
+
+                    self.tip_ui(ui);
+
+                    self.pricing_ui(ui);
+
+                    self.cash_flow_ui(ui);
+
+                    self.ratio_ui(ui);
+
+                    self.polynomial_ui(ui);
+
+                    self.simulation_ui(ui);
+
+                    self.distribution_ui(ui);
+
+                    self.hypothesis_ui(ui);
+
+                    self.dms_ui(ui);
+
+                    self.timestamp_ui(ui);
+
+                    self.data_size_ui(ui);
+
+                    self.travel_ui(ui);
+
+                    self.cooking_ui(ui);
+
+                    self.constants_ui(ui);
+
+                    self.macros_ui(ui);
+
+                    #[cfg(feature = "scripting")]
+                    self.scripts_ui(ui);
+                });
             });
         });
+
+        self.handle_dropped_files(ctx);
+        self.batch_ui(ctx);
+        self.float_inspector_ui(ctx);
+        self.precision_ui(ctx);
+        self.full_precision_ui(ctx);
+        self.settings_ui(ctx);
+        self.about_ui(ctx);
+        if self.show_convert {
+            self.convert_ui(ctx);
+        }
+        self.recovery_ui(ctx);
+        let (operations, settings) = (&self.operations, &self.settings);
+        self.windows.retain_mut(|window| window.show(ctx, operations, settings));
+        #[cfg(feature = "digest")]
+        self.digests_window_ui(ctx);
+
+        if let Some(text) = self.clipboard_request.take() {
+            ctx.copy_text(text.clone());
+            self.show_toast(ctx, format!("Copied {}", text));
+        }
+        self.toast_ui(ctx);
+        self.announcement.publish(ctx);
+
+        if let Some(journal) = &mut self.journal {
+            journal.record(&self.calculator);
+        }
+
+        // Also covers previews due right away, e.g. after a history recall
+        // below the expression field was drawn
+        if let Some(due) = self.preview_due {
+            let remaining = (due - ctx.input(|i| i.time)).max(0.0);
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(remaining));
+        }
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, MACROS_KEY, &self.macros);
+        eframe::set_value(storage, INGREDIENTS_KEY, &self.ingredients);
+        eframe::set_value(storage, FAVORITES_KEY, &self.favorites);
+        eframe::set_value(storage, RECENT_KEY, &self.recent);
+        eframe::set_value(storage, CONVERSIONS_KEY, &self.conversions);
+        eframe::set_value(storage, HISTORY_KEY, self.calculator.history());
+        eframe::set_value(storage, SETTINGS_KEY, &self.settings);
+        storage.set_string(WORKSHEET_KEY, self.worksheet_text.clone());
+        eframe::set_value(storage, GRID_KEY, &self.grid.cells);
+        eframe::set_value(storage, VARIABLES_KEY, self.calculator.variables());
+        eframe::set_value(storage, WATCHES_KEY, &self.watches);
+        eframe::set_value(storage, TOTAL_KEY, &self.running_total);
+        if let Some(journal) = &mut self.journal {
+            journal.checkpoint(&self.calculator);
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Some(journal) = self.journal.take() {
+            journal.close();
+        }
     }
 }
@@ -0,0 +1,142 @@
+// Batch Evaluation
+use crate::error::CalculatorError;
+use crate::expression::{self, Environment, Functions, ParseOptions};
+use crate::number;
+use crate::task::Progress;
+
+/// One evaluated line of a batch file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchLine {
+    /// 1-based line number in the source file
+    pub line: usize,
+    pub source: String,
+    pub result: Result<f64, CalculatorError>,
+}
+
+/// Evaluates every non-empty line of `text` as an independent expression.
+/// Lines starting with `#` are comments. A line wrapped in double quotes
+/// (as spreadsheets export expressions containing commas) is unquoted first.
+///
+/// Meant for a background task: lines are counted off in `progress`, and
+/// the result is `None` as soon as the task is cancelled.
+pub fn evaluate_lines(
+    text: &str,
+    functions: Option<&dyn Functions>,
+    options: ParseOptions,
+    progress: &Progress,
+) -> Option<Vec<BatchLine>> {
+    let mut env = Environment::new();
+    env.functions = functions;
+    progress.set_total(text.lines().count());
+
+    let mut lines = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if progress.is_cancelled() {
+            return None;
+        }
+        progress.advance();
+        let source = unquote(line.trim());
+        if source.is_empty() || source.starts_with('#') {
+            continue;
+        }
+        lines.push(BatchLine {
+            line: index + 1,
+            result: evaluate(&source, &env, options),
+            source,
+        });
+    }
+    Some(lines)
+}
+
+fn evaluate(source: &str, env: &Environment, options: ParseOptions) -> Result<f64, CalculatorError> {
+    let result = number::in_range(expression::parse_with(source, options)?.eval(env)?)?;
+    Ok(number::unsigned_zero(result))
+}
+
+fn unquote(field: &str) -> String {
+    match field.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) {
+        Some(inner) => inner.replace("\"\"", "\""),
+        None => field.to_string(),
+    }
+}
+
+/// Quotes a CSV field if it contains a separator, quote or newline.
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Renders the results as CSV with `line,expression,result,error` columns.
+pub fn to_csv(lines: &[BatchLine]) -> String {
+    let mut csv = String::from("line,expression,result,error\n");
+    for line in lines {
+        let (result, error) = match &line.result {
+            Ok(value) => (value.to_string(), String::new()),
+            Err(err) => (String::new(), err.to_string()),
+        };
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            line.line,
+            csv_field(&line.source),
+            result,
+            csv_field(&error)
+        ));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_batch_lines_and_errors() {
+        let text = "# prices\n2 * 3\n\n\"max(1, 2)\"\n1 / 0\n";
+        let lines = evaluate_lines(text, None, ParseOptions::default(), &Progress::default()).unwrap();
+
+        assert_eq!(
+            lines,
+            vec![
+                BatchLine { line: 2, source: String::from("2 * 3"), result: Ok(6.0) },
+                BatchLine { line: 4, source: String::from("max(1, 2)"), result: Ok(2.0) },
+                BatchLine {
+                    line: 5,
+                    source: String::from("1 / 0"),
+                    result: Err(CalculatorError::DivisionByZero),
+                },
+            ]
+        );
+        assert_eq!(
+            to_csv(&lines),
+            "line,expression,result,error\n\
+             2,2 * 3,6,\n\
+             4,\"max(1, 2)\",2,\n\
+             5,1 / 0,,Error: Division by zero\n"
+        );
+    }
+
+    // Feature: gui-calculator, Property 18: Batch evaluation is line-by-line evaluation
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_batch_matches_single_evaluation(
+            values in prop::collection::vec((-1000.0..1000.0f64, 1.0..1000.0f64), 1..=10)
+        ) {
+            let text: String = values
+                .iter()
+                .map(|(a, b)| format!("{} / {}\n", a, b))
+                .collect();
+            let lines = evaluate_lines(&text, None, ParseOptions::default(), &Progress::default()).unwrap();
+
+            prop_assert_eq!(lines.len(), values.len());
+            for (line, (a, b)) in lines.iter().zip(&values) {
+                prop_assert_eq!(&line.result, &Ok(a / b));
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+// Pricing: Cost, Price, Margin and Markup
+use crate::error::CalculatorError;
+
+/// Which two values the user knows.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Known {
+    #[default]
+    CostAndPrice,
+    CostAndMargin,
+    PriceAndMargin,
+    CostAndMarkup,
+    PriceAndMarkup,
+}
+
+/// A fully solved price. Margin and markup describe the same profit:
+/// margin relative to the selling price, markup relative to the cost.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pricing {
+    pub cost: f64,
+    pub price: f64,
+    /// Profit as a percentage of the price; always below 100
+    pub margin: f64,
+    /// Profit as a percentage of the cost
+    pub markup: f64,
+}
+
+/// Computes the missing values from the two `known` ones, given in the
+/// order they are named (e.g. cost first for `CostAndMargin`).
+pub fn solve(known: Known, first: f64, second: f64) -> Result<Pricing, CalculatorError> {
+    let (cost, price) = match known {
+        Known::CostAndPrice => (first, second),
+        Known::CostAndMargin => (first, first / (1.0 - check_margin(second)? / 100.0)),
+        Known::PriceAndMargin => (first * (1.0 - check_margin(second)? / 100.0), first),
+        Known::CostAndMarkup => (first, first * (1.0 + check_markup(second)? / 100.0)),
+        Known::PriceAndMarkup => (first / (1.0 + check_markup(second)? / 100.0), first),
+    };
+    from_cost_and_price(cost, price)
+}
+
+// A 100% margin would mean the goods cost nothing
+fn check_margin(margin: f64) -> Result<f64, CalculatorError> {
+    if margin >= 100.0 {
+        return Err(CalculatorError::Domain(String::from("margin")));
+    }
+    Ok(margin)
+}
+
+// Marking down by 100% or more would give a zero or negative price
+fn check_markup(markup: f64) -> Result<f64, CalculatorError> {
+    if markup <= -100.0 {
+        return Err(CalculatorError::Domain(String::from("markup")));
+    }
+    Ok(markup)
+}
+
+fn from_cost_and_price(cost: f64, price: f64) -> Result<Pricing, CalculatorError> {
+    if cost <= 0.0 || !cost.is_finite() {
+        return Err(CalculatorError::Domain(String::from("cost")));
+    }
+    if price <= 0.0 || !price.is_finite() {
+        return Err(CalculatorError::Domain(String::from("price")));
+    }
+    let profit = price - cost;
+    Ok(Pricing {
+        cost,
+        price,
+        margin: profit / price * 100.0,
+        markup: profit / cost * 100.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_margin_is_not_markup() {
+        let pricing = solve(Known::CostAndPrice, 80.0, 100.0).unwrap();
+        assert_eq!(pricing.margin, 20.0);
+        assert_eq!(pricing.markup, 25.0);
+
+        assert_eq!(solve(Known::CostAndMarkup, 80.0, 25.0).unwrap().price, 100.0);
+        assert_eq!(solve(Known::PriceAndMargin, 100.0, 20.0).unwrap().cost, 80.0);
+        assert_eq!(solve(Known::CostAndMargin, 80.0, 50.0).unwrap().price, 160.0);
+        assert_eq!(solve(Known::PriceAndMarkup, 150.0, 50.0).unwrap().cost, 100.0);
+    }
+
+    #[test]
+    fn test_impossible_inputs() {
+        let margin = Err(CalculatorError::Domain(String::from("margin")));
+        assert_eq!(solve(Known::CostAndMargin, 80.0, 100.0), margin);
+        assert_eq!(solve(Known::PriceAndMargin, 100.0, 120.0), margin);
+        assert_eq!(
+            solve(Known::CostAndMarkup, 80.0, -100.0),
+            Err(CalculatorError::Domain(String::from("markup")))
+        );
+        assert_eq!(
+            solve(Known::CostAndPrice, 0.0, 100.0),
+            Err(CalculatorError::Domain(String::from("cost")))
+        );
+        assert_eq!(
+            solve(Known::CostAndPrice, 10.0, -1.0),
+            Err(CalculatorError::Domain(String::from("price")))
+        );
+    }
+
+    // Feature: gui-calculator, Property 22: Any two pricing values give back the same price
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_pairs_agree(cost in 0.01..10000.0f64, price in 0.01..10000.0f64) {
+            let pricing = solve(Known::CostAndPrice, cost, price).unwrap();
+            prop_assert!(pricing.margin < 100.0);
+
+            let solutions = [
+                solve(Known::CostAndMargin, cost, pricing.margin).unwrap(),
+                solve(Known::PriceAndMargin, price, pricing.margin).unwrap(),
+                solve(Known::CostAndMarkup, cost, pricing.markup).unwrap(),
+                solve(Known::PriceAndMarkup, price, pricing.markup).unwrap(),
+            ];
+            for solution in solutions {
+                prop_assert!((solution.cost - cost).abs() < 1e-6 * cost.max(price));
+                prop_assert!((solution.price - price).abs() < 1e-6 * cost.max(price));
+            }
+        }
+    }
+}
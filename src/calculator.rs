@@ -1,89 +1,191 @@
 // Calculator Logic Layer
-use crate::state::CalculatorState;
-use crate::operation::Operation;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::decimal;
+use crate::error::{CalculatorError, Warning};
+use crate::expression::{self, Environment, Expr, Functions, ParseOptions};
+use crate::format::{DecimalPlaces, RoundingSwitch};
+use crate::history::{History, HistoryEntry};
+use crate::matrix::{self, Value};
+use crate::montecarlo::{self, Definition, Summary};
+use crate::number::{self, Number};
+use crate::precision::{self, Reading};
+use crate::solver;
+use crate::state::{CalculatorState, EntryState};
+use crate::status::Status;
+use crate::task::Progress;
+use crate::operation::{Operation, OperationRegistry, PendingOperation};
+use crate::input::InputEvent;
+use crate::variables::{Dependencies, Variables};
+
+/// What a paste turned into.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pasted {
+    Number(f64),
+    Expression { source: String, result: f64 },
+}
+
+/// What the calculator shows and holds apart from its history: the
+/// operand or result, what's being typed, the pending operation and the
+/// memory. Values come back as their floats, as they were displayed. Only
+/// the keypad's own operations are kept; a registered one pending is left
+/// out, as a session can't carry its implementation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    value: f64,
+    input: String,
+    stored_value: Option<f64>,
+    operation: Option<Operation>,
+    entry: EntryState,
+    memory: Option<f64>,
+}
 
 #[derive(Clone)]
 pub struct Calculator {
     state: CalculatorState,
+    // Kept outside `state` so clearing the calculator doesn't erase it
+    history: History,
+    // Compute + - * / in decimal so 0.1 + 0.2 is exactly 0.3
+    decimal_mode: bool,
+    // ADD2: numbers typed without a point are cents
+    add_mode: bool,
+    parse_options: ParseOptions,
+    // The memory register (M+, M−, MR); survives C but not AC
+    memory: Option<f64>,
+    // Named values for expressions
+    variables: Variables,
+    // What the display shows, rebuilt whenever `state` changes rather than
+    // formatted again every frame
+    display: String,
+    // Counts changes to the state, memory and history, so the app can tell
+    // without comparing whether anything needs saving
+    revision: u64,
+}
+
+impl Default for Calculator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Calculator {
     pub fn new() -> Self {
         Self {
             state: CalculatorState::new(),
+            history: History::new(),
+            decimal_mode: false,
+            add_mode: false,
+            parse_options: ParseOptions::default(),
+            memory: None,
+            variables: Variables::default(),
+            display: String::from("0"),
+            revision: 0,
         }
     }
 
-    pub fn input_digit(&mut self, digit: u8) {
-        // Block input if there's an error (Requirement 5.2)
-        if self.state.error.is_some() {
-            return;
+    pub fn set_decimal_mode(&mut self, enabled: bool) {
+        self.decimal_mode = enabled;
+    }
+
+    /// Turns ADD2 entry on or off: numbers typed without a decimal point are
+    /// read as cents, so 1995 enters 19.95. Like on an adding machine, it
+    /// doesn't apply to a number multiplied or divided by.
+    pub fn set_add_mode(&mut self, enabled: bool) {
+        self.add_mode = enabled;
+    }
+
+    pub fn set_parse_options(&mut self, options: ParseOptions) {
+        self.parse_options = options;
+    }
+
+    /// The exact decimal result when binary floating point made the
+    /// displayed result differ from it, e.g. 0.3 after 0.1 + 0.2.
+    pub fn exact_value(&self) -> Option<f64> {
+        self.state.value.exact_difference()
+    }
+
+    /// Turns on decimal mode and replaces an inexact result on the display
+    /// with the exact one.
+    pub fn switch_to_decimal(&mut self) {
+        self.decimal_mode = true;
+        let value = self.state.value.to_decimal_mode();
+        if self.state.stored_value == Some(self.state.value) {
+            self.state.stored_value = Some(value);
         }
+        self.state.value = value;
+        self.refresh_display();
+    }
 
+    pub fn input_digit(&mut self, digit: u8) {
         // Validate digit is 0-9
         if digit > 9 {
             return;
         }
 
-        // If waiting for a new operand or fresh start, replace display (Requirement 1.3)
-        if self.state.waiting_for_operand || self.state.fresh_start {
-            self.state.display = digit.to_string();
-            self.state.waiting_for_operand = false;
-            self.state.fresh_start = false;
-        } else {
+        match self.state.entry {
+            // Block input if there's an error (Requirement 5.2)
+            EntryState::Error { .. } => return,
             // Append to accumulate digits (Requirements 1.1, 1.4)
-            self.state.display.push_str(&digit.to_string());
+            EntryState::TypingInteger | EntryState::TypingFraction | EntryState::TypingCents => {
+                self.state.input.push_str(&digit.to_string());
+            }
+            // Start a new number, replacing the display (Requirement 1.3)
+            EntryState::Fresh | EntryState::ResultShown => {
+                self.state.input = digit.to_string();
+                self.state.entry = self.integer_entry();
+                self.end_sum();
+            }
         }
+        self.state.value = self.typed_value();
+        self.refresh_display();
     }
 
     pub fn input_decimal_point(&mut self) {
-        // Block input if there's an error
-        if self.state.error.is_some() {
-            return;
-        }
-
-        // If waiting for a new operand or fresh start, start with "0."
-        if self.state.waiting_for_operand || self.state.fresh_start {
-            self.state.display = String::from("0.");
-            self.state.waiting_for_operand = false;
-            self.state.fresh_start = false;
-            return;
-        }
-
-        // Only add decimal point if one doesn't already exist (Requirement 1.2)
-        if !self.state.display.contains('.') {
-            self.state.display.push('.');
+        match self.state.entry {
+            // Block input if there's an error; only one decimal point (Requirement 1.2)
+            EntryState::Error { .. } | EntryState::TypingFraction => return,
+            // Cents typed so far become whole units
+            EntryState::TypingInteger | EntryState::TypingCents => self.state.input.push('.'),
+            // A new number starts as "0."
+            EntryState::Fresh | EntryState::ResultShown => {
+                self.state.input = String::from("0.");
+                self.end_sum();
+            }
         }
+        self.state.entry = EntryState::TypingFraction;
+        self.state.value = self.typed_value();
+        self.refresh_display();
     }
 
-    pub fn input_operation(&mut self, op: Operation) {
+    /// Starts or chains a calculation with `op`: one of the keypad's four,
+    /// or a binary operation from an `OperationRegistry`.
+    pub fn input_operation(&mut self, op: impl Into<PendingOperation>) {
         // Block input if there's an error (Requirement 5.2)
-        if self.state.error.is_some() {
-            return;
-        }
-
-        // If we have invalid input (empty display or just a decimal point), ignore (Requirement 5.3)
-        if self.state.display.is_empty() || self.state.display == "." {
+        if self.state.entry.is_error() {
             return;
         }
 
-        // Parse current display value
-        let current_value = match self.state.display.parse::<f64>() {
-            Ok(val) => val,
-            Err(_) => return, // Invalid input, ignore (Requirement 5.3)
-        };
+        let op = op.into();
+        let current_value = self.state.value;
+        self.count_item(op.keypad());
 
         // If we already have a stored operation, calculate it first (chain operations)
-        if let (Some(stored), Some(prev_op)) = (self.state.stored_value, self.state.current_operation) {
-            // Only calculate if we're not waiting for operand (i.e., user entered a new number)
-            if !self.state.waiting_for_operand {
-                match prev_op.apply(stored, current_value) {
+        if let (Some(stored), Some(prev_op)) = (self.state.stored_value, &self.state.current_operation) {
+            // Only calculate if a second operand was given since the last operation
+            if self.state.entry != EntryState::ResultShown {
+                let chained = prev_op
+                    .apply(stored, current_value, self.decimal_mode)
+                    .and_then(|result| number::in_range(result.float()).map(|_| result));
+                match chained {
                     Ok(result) => {
-                        self.state.display = result.to_string();
+                        self.state.value = result;
                         self.state.stored_value = Some(result);
                     }
-                    Err(err) => {
-                        self.state.error = Some(err);
+                    Err(error) => {
+                        self.state.entry = EntryState::Error { error };
+                        self.refresh_display();
                         return;
                     }
                 }
@@ -95,12 +197,13 @@ impl Calculator {
 
         // Store the new operation (Requirement 2.1)
         self.state.current_operation = Some(op);
-        self.state.waiting_for_operand = true;
+        self.state.entry = EntryState::ResultShown;
+        self.refresh_display();
     }
 
     pub fn calculate(&mut self) {
         // Block if there's an error (Requirement 5.2)
-        if self.state.error.is_some() {
+        if self.state.entry.is_error() {
             return;
         }
 
@@ -110,60 +213,1040 @@ impl Calculator {
             None => return, // Nothing to calculate
         };
 
-        let operation = match self.state.current_operation {
-            Some(op) => op,
+        let operation = match &self.state.current_operation {
+            Some(op) => op.clone(),
             None => return, // No operation to perform
         };
 
-        // Get current value from display (Requirement 2.2)
-        let current_value = match self.state.display.parse::<f64>() {
-            Ok(val) => val,
-            Err(_) => return, // Invalid display value
-        };
+        // The second operand (Requirement 2.2)
+        let current_value = self.state.value;
 
         // Apply the operation (Requirements 2.2, 5.1)
-        match operation.apply(stored, current_value) {
+        match operation.apply(stored, current_value, self.decimal_mode) {
             Ok(result) => {
                 // Check for overflow/infinity
-                if result.is_infinite() || result.is_nan() {
-                    self.state.error = Some(String::from("Error: Overflow"));
+                if let Err(error) = number::in_range(result.float()) {
+                    self.state.entry = EntryState::Error { error };
                 } else {
+                    // Record the calculation
+                    let expression = operation.expression(stored.float(), current_value.float());
+                    self.history.push(HistoryEntry::from_expression(expression, result.float()));
+                    // The second operand is one more item in a sum
+                    let additive = operation.keypad().is_some_and(Operation::is_additive);
+                    self.state.item_count = if additive { self.state.item_count + 1 } else { 0 };
+
                     // Display result on the display
-                    self.state.display = result.to_string();
+                    self.state.value = result;
                     // Store result for potential chaining
                     self.state.stored_value = Some(result);
                     // Clear the operation
                     self.state.current_operation = None;
-                    // Set waiting flag so next digit starts fresh
-                    self.state.waiting_for_operand = true;
+                    // The next digit starts a new number
+                    self.state.entry = EntryState::ResultShown;
                 }
             }
-            Err(err) => {
+            Err(error) => {
                 // Handle errors like division by zero (Requirement 5.1)
-                self.state.error = Some(err);
+                self.state.entry = EntryState::Error { error };
             }
         }
+        self.refresh_display();
+    }
+
+    /// How many numbers the current sum has added or subtracted, counting
+    /// the first: 3 after 12 + 5 + 7 =, and 4 once + 4 = carries it on.
+    /// 0 outside a sum.
+    pub fn item_count(&self) -> usize {
+        self.state.item_count
+    }
+
+    // Counts the number on the display as an item when `op` goes on with a
+    // sum; anything else, registered operations included, isn't a sum and
+    // resets the count
+    fn count_item(&mut self, op: Option<Operation>) {
+        let count = self.state.item_count;
+        let new_operand = self.state.entry != EntryState::ResultShown;
+        let previous = self.state.current_operation.as_ref().map(PendingOperation::keypad);
+        self.state.item_count = match previous {
+            _ if !op.is_some_and(Operation::is_additive) => 0,
+            Some(Some(previous)) if previous.is_additive() && new_operand => count + 1,
+            // Only the operator changed
+            Some(Some(previous)) if previous.is_additive() => count.max(1),
+            // A product or quotient so far is the sum's first item
+            Some(_) => 1,
+            None if new_operand => 1,
+            // Carrying on from the last result
+            None => count.max(1),
+        };
+    }
+
+    // How digits typed without a point are read: as cents in ADD2 mode,
+    // except for a number to multiply or divide by
+    fn integer_entry(&self) -> EntryState {
+        let scaling = matches!(self.pending_operation(), Some(Operation::Multiply | Operation::Divide));
+        if self.add_mode && !scaling {
+            EntryState::TypingCents
+        } else {
+            EntryState::TypingInteger
+        }
+    }
+
+    // What's being typed, as the display shows it
+    fn typed_text(&self) -> String {
+        match self.state.entry {
+            EntryState::TypingCents => cents(&self.state.input),
+            _ => self.state.input.clone(),
+        }
     }
 
+    fn typed_value(&self) -> Number {
+        Number::new(self.typed_text().parse().unwrap_or(0.0))
+    }
+
+    // A number typed with no operation pending starts over, ending a sum
+    fn end_sum(&mut self) {
+        if self.state.current_operation.is_none() {
+            self.state.item_count = 0;
+        }
+    }
+
+    /// Clears the current calculation (C). Memory is kept.
     pub fn clear(&mut self) {
         // Reset all state fields to initial values (Requirements 3.1, 3.2)
         self.state = CalculatorState::new();
+        self.refresh_display();
+    }
+
+    /// Clears everything (AC): the calculation and the memory register.
+    pub fn clear_all(&mut self) {
+        self.clear();
+        self.memory = None;
+        self.revision += 1;
+    }
+
+    pub fn memory(&self) -> Option<f64> {
+        self.memory
+    }
+
+    /// Adds the displayed number to memory (M+), or subtracts it (M−).
+    pub fn memory_add(&mut self, subtract: bool) {
+        let Some(value) = self.display_value() else {
+            return;
+        };
+        let sign = if subtract { -1.0 } else { 1.0 };
+        self.memory = Some(self.memory.unwrap_or(0.0) + sign * value);
+        self.revision += 1;
+    }
+
+    /// Puts the memory on the display as the current operand (MR).
+    pub fn memory_recall(&mut self) {
+        if let Some(value) = self.memory {
+            self.set_operand(value);
+        }
+    }
+
+    pub fn memory_clear(&mut self) {
+        self.memory = None;
+        self.revision += 1;
     }
 
-    pub fn get_display_text(&self) -> String {
-        if let Some(ref error) = self.state.error {
-            error.clone()
+    /// Removes the last typed character (⌫). Only a number being typed is
+    /// edited, not a result or an error; removing its last character leaves
+    /// 0, as CE would.
+    pub fn backspace(&mut self) {
+        if !self.state.entry.is_typing() {
+            return;
+        }
+        if self.state.input.pop() == Some('.') {
+            self.state.entry = self.integer_entry();
+        }
+        if self.state.input.is_empty() {
+            self.state.value = Number::new(0.0);
+            self.state.entry = EntryState::Fresh;
         } else {
-            self.state.display.clone()
+            self.state.value = self.typed_value();
+        }
+        self.refresh_display();
+    }
+
+    /// Clears only the number being entered (CE). A pending operation and
+    /// its first operand survive, so 12 + 5 CE 7 = gives 19. After an error
+    /// it clears everything, as there is nothing to continue.
+    pub fn clear_entry(&mut self) {
+        if self.state.entry.is_error() {
+            self.clear();
+            return;
+        }
+        self.state.value = Number::new(0.0);
+        // The next digit replaces the 0 instead of appending to it
+        self.state.entry = EntryState::Fresh;
+        self.refresh_display();
+    }
+
+    /// Replaces the current operand with `f(operand)`, like a √ key: any pending
+    /// operation is kept and will use the new value.
+    pub fn apply_function<F>(&mut self, f: F)
+    where
+        F: FnOnce(f64) -> Result<f64, CalculatorError>,
+    {
+        if self.state.entry.is_error() {
+            return;
+        }
+
+        match f(self.state.value.float()).and_then(number::in_range) {
+            Ok(result) => {
+                self.state.value = Number::new(result);
+                // The result is a complete operand: the next digit replaces it,
+                // but a following operation must still consume it
+                self.state.entry = EntryState::Fresh;
+            }
+            Err(error) => {
+                self.state.entry = EntryState::Error { error };
+            }
+        }
+        self.refresh_display();
+    }
+
+    /// Rounds the operand to `places` decimals, half away from zero, like a
+    /// fixed-decimal calculator's round key.
+    pub fn round(&mut self, places: u32) {
+        self.apply_function(|value| Ok(decimal::round(value, places)));
+    }
+
+    /// Makes `value` the current operand, e.g. when recalled from history.
+    pub fn set_operand(&mut self, value: f64) {
+        self.apply_function(|_| Ok(value));
+    }
+
+    /// Parses and evaluates `source`, recording it in the history and showing
+    /// the result. `Ans` refers to the previous result. An equation such as
+    /// `3x + 5 = 20` is solved for its unknown instead. Errors are returned
+    /// rather than entering the error state, so the user can fix the input.
+    pub fn evaluate_expression(
+        &mut self,
+        source: &str,
+        functions: Option<&dyn Functions>,
+    ) -> Result<f64, CalculatorError> {
+        let (expression, result) = self.solve_or_eval(source, functions)?;
+
+        self.history.push(HistoryEntry::new(source.trim(), expression, result.float()));
+
+        // Continue from the result just like after pressing =
+        self.state = CalculatorState::new();
+        self.state.value = result;
+        self.state.stored_value = Some(result);
+        self.state.entry = EntryState::ResultShown;
+        self.refresh_display();
+        Ok(result.float())
+    }
+
+    /// What `evaluate_expression` would give for `source`, without touching the
+    /// display or the history.
+    pub fn preview_expression(
+        &self,
+        source: &str,
+        functions: Option<&dyn Functions>,
+    ) -> Result<f64, CalculatorError> {
+        self.solve_or_eval(source, functions).map(|(_, result)| result.float())
+    }
+
+    /// `source` evaluated in floating point, decimal and exact fractions,
+    /// for comparing their digits.
+    pub fn compare_precision(
+        &self,
+        source: &str,
+        functions: Option<&dyn Functions>,
+    ) -> Result<Vec<Reading>, CalculatorError> {
+        let expression = expression::parse_with(source, self.parse_options)?;
+        Ok(precision::compare(&expression, &self.environment(functions)))
+    }
+
+    /// `source` evaluated as a matrix or a number, without touching the
+    /// display or the history, which only hold numbers.
+    pub fn evaluate_matrix(
+        &self,
+        source: &str,
+        functions: Option<&dyn Functions>,
+    ) -> Result<Value, CalculatorError> {
+        let expression = expression::parse_with(source, self.parse_options)?;
+        matrix::eval(&expression, &self.environment(functions))
+    }
+
+    /// `source` evaluated `samples` times with the defined variables drawn
+    /// afresh each time; the other variables keep their values. `None` if
+    /// the run was cancelled through `progress`.
+    pub fn simulate(
+        &self,
+        source: &str,
+        definitions: &[Definition],
+        samples: usize,
+        seed: u64,
+        functions: Option<&dyn Functions>,
+        progress: &Progress,
+    ) -> Option<Result<Summary, CalculatorError>> {
+        let expression = match expression::parse_with(source, self.parse_options) {
+            Ok(expression) => expression,
+            Err(err) => return Some(Err(err)),
+        };
+        montecarlo::simulate(&expression, definitions, samples, self.environment(functions), seed, progress)
+    }
+
+    // The parsed expression and its result; for an equation, the solution
+    // stands in for both
+    fn solve_or_eval(
+        &self,
+        source: &str,
+        functions: Option<&dyn Functions>,
+    ) -> Result<(Expr, Number), CalculatorError> {
+        if let Some((left, right)) = expression::parse_equation(source, self.parse_options)? {
+            let solution = solver::solve(&left, &right, &self.environment(functions))?;
+            return Ok((Expr::Number(solution.value), Number::new(solution.value)));
+        }
+        let expression = expression::parse_with(source, self.parse_options)?;
+        if expression::has_base_literals(source) {
+            let result = expression.eval_integer(&self.environment(functions))?;
+            return Ok((expression, Number::new(result as f64)));
+        }
+        let result = self.eval_expression(&expression, functions)?;
+        Ok((expression, result))
+    }
+
+    fn environment<'a>(&self, functions: Option<&'a dyn Functions>) -> Environment<'a> {
+        let mut env = Environment::new();
+        env.functions = functions;
+        for (name, value) in self.variables.iter() {
+            env.variables.insert(name.to_string(), value);
+        }
+        if let Some(last) = self.history.last() {
+            env.variables.insert(String::from("ans"), last.result);
+        }
+        env
+    }
+
+    // The result in the current mode, along with its exact decimal value
+    fn eval_expression(
+        &self,
+        expression: &Expr,
+        functions: Option<&dyn Functions>,
+    ) -> Result<Number, CalculatorError> {
+        let env = self.environment(functions);
+        let result = number::in_range(expression.eval(&env)?)?;
+        Ok(Number::computed(result, decimal::eval(expression, &env), self.decimal_mode))
+    }
+
+    /// Accepts pasted text: a plain number becomes the current operand,
+    /// anything else is evaluated as an expression. Math typography like `×`
+    /// or `²` is rewritten first.
+    pub fn paste(
+        &mut self,
+        text: &str,
+        functions: Option<&dyn Functions>,
+    ) -> Result<Pasted, CalculatorError> {
+        let text = expression::normalize(text);
+        let text = text.trim();
+        // "inf" and "NaN" parse as f64 but aren't numbers a user means to paste
+        if let Some(value) = text.parse::<f64>().ok().filter(|value| value.is_finite()) {
+            self.set_operand(value);
+            return Ok(Pasted::Number(value));
+        }
+
+        let result = self.evaluate_expression(text, functions)?;
+        Ok(Pasted::Expression {
+            source: text.to_string(),
+            result,
+        })
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn variables(&self) -> &Variables {
+        &self.variables
+    }
+
+    pub fn variables_mut(&mut self) -> &mut Variables {
+        &mut self.variables
+    }
+
+    /// What `source` reads from the variables, for telling when its result
+    /// is out of date. Empty if it doesn't parse.
+    pub fn dependencies(&self, source: &str) -> Dependencies {
+        let parsed = match expression::parse_equation(source, self.parse_options) {
+            Ok(Some((left, right))) => vec![left, right],
+            Ok(None) => expression::parse_with(source, self.parse_options).into_iter().collect(),
+            Err(_) => Vec::new(),
+        };
+        self.variables.dependencies(&parsed)
+    }
+
+    pub fn history_mut(&mut self) -> &mut History {
+        // Whatever the caller does with it counts as a change
+        self.revision += 1;
+        &mut self.history
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn session(&self) -> Session {
+        Session {
+            value: self.state.value.float(),
+            input: self.state.input.clone(),
+            stored_value: self.stored_value(),
+            operation: self.pending_operation(),
+            entry: self.state.entry.clone(),
+            memory: self.memory,
+        }
+    }
+
+    /// Puts back a session saved with `session`, leaving the history alone.
+    pub fn restore_session(&mut self, session: Session) {
+        self.state = CalculatorState {
+            value: Number::new(session.value),
+            input: session.input,
+            stored_value: session.stored_value.map(Number::new),
+            current_operation: session.operation.map(PendingOperation::Keypad),
+            entry: session.entry,
+            item_count: 0,
+        };
+        self.memory = session.memory;
+        self.refresh_display();
+    }
+
+    /// Applies one keypad event. Registered operations need the registry
+    /// they're numbered in, so they only do something through
+    /// `handle_event_with`.
+    pub fn handle_event(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Digit(digit) => self.input_digit(digit),
+            InputEvent::DecimalPoint => self.input_decimal_point(),
+            InputEvent::Operation(op) => self.input_operation(op),
+            InputEvent::Equals => self.calculate(),
+            InputEvent::ClearEntry => self.clear_entry(),
+            InputEvent::Backspace => self.backspace(),
+            InputEvent::Clear => self.clear(),
+            InputEvent::ClearAll => self.clear_all(),
+            InputEvent::MemoryAdd => self.memory_add(false),
+            InputEvent::MemorySubtract => self.memory_add(true),
+            InputEvent::MemoryRecall => self.memory_recall(),
+            InputEvent::MemoryClear => self.memory_clear(),
+            InputEvent::Round(places) => self.round(places),
+            InputEvent::Binary(_) | InputEvent::Unary(_) | InputEvent::Constant(_) => {}
+        }
+    }
+
+    /// Applies one keypad event, with `operations` being the registry the
+    /// keypad was built from. Positions it doesn't have do nothing.
+    pub fn handle_event_with(&mut self, event: InputEvent, operations: &OperationRegistry) {
+        match event {
+            InputEvent::Binary(index) => {
+                if let Some(op) = operations.binary().nth(index) {
+                    self.input_operation(PendingOperation::Registered(Arc::clone(op)));
+                }
+            }
+            InputEvent::Unary(index) => {
+                if let Some(op) = operations.unary().nth(index) {
+                    self.apply_function(|value| op.apply(value));
+                }
+            }
+            InputEvent::Constant(index) => {
+                if let Some(op) = operations.constants().nth(index) {
+                    self.set_operand(op.value());
+                }
+            }
+            event => self.handle_event(event),
+        }
+    }
+
+    /// The first operand held for the pending operation.
+    pub fn stored_value(&self) -> Option<f64> {
+        self.state.stored_value.map(Number::float)
+    }
+
+    /// The keypad operation `=` will apply, if one was chosen.
+    pub fn pending_operation(&self) -> Option<Operation> {
+        self.state.current_operation.as_ref().and_then(PendingOperation::keypad)
+    }
+
+    /// How the operation `=` will apply is written, registered ones included.
+    pub fn pending_symbol(&self) -> Option<&'static str> {
+        self.state.current_operation.as_ref().map(PendingOperation::symbol)
+    }
+
+    /// The calculation `=` would do now, e.g. `7 / 0`.
+    pub fn pending_expression(&self) -> Option<Expr> {
+        let stored = self.state.stored_value?;
+        let op = self.state.current_operation.as_ref()?;
+        Some(op.expression(stored.float(), self.state.value.float()))
+    }
+
+    /// The error on the display, if any.
+    pub fn error(&self) -> Option<&CalculatorError> {
+        self.state.entry.error()
+    }
+
+    /// What's off about the value on the display, though it's still shown.
+    pub fn warning(&self) -> Option<Warning> {
+        number::warning(self.display_value()?)
+    }
+
+    /// What the status strip shows, with the desk rounding switch and
+    /// decimal places the display is set to.
+    pub fn status(&self, mode: &'static str, rounding: RoundingSwitch, places: DecimalPlaces) -> Status {
+        Status::new(mode, &self.state, self.memory, self.decimal_mode, rounding, places)
+    }
+
+    /// Whether the display shows a number still being typed, as opposed to
+    /// a result or a completed operand.
+    pub fn is_entering(&self) -> bool {
+        self.state.entry.is_typing()
+    }
+
+    /// The number on the display, or `None` while an error is shown.
+    pub fn display_value(&self) -> Option<f64> {
+        if self.state.entry.is_error() {
+            return None;
         }
+        Some(self.state.value.float())
+    }
+
+    pub fn get_display_text(&self) -> &str {
+        &self.display
+    }
+
+    // Every method that changes `state` ends here
+    fn refresh_display(&mut self) {
+        self.revision += 1;
+        self.display = match &self.state.entry {
+            EntryState::Error { error } => error.to_string(),
+            EntryState::TypingInteger | EntryState::TypingFraction | EntryState::TypingCents => self.typed_text(),
+            EntryState::Fresh | EntryState::ResultShown => self.state.value.float().to_string(),
+        };
     }
 }
 
+// Digits read as cents: "1995" is 19.95 and "5" is 0.05
+fn cents(digits: &str) -> String {
+    let padded = format!("{:0>3}", digits);
+    let (units, cents) = padded.split_at(padded.len() - 2);
+    let units = match units.trim_start_matches('0') {
+        "" => "0",
+        units => units,
+    };
+    format!("{}.{}", units, cents)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::expression::BinaryOp;
+    use crate::operation::{BinaryOperation, FnBinary};
+    use crate::testing;
     use proptest::prelude::*;
 
+    #[test]
+    fn test_inexact_result_and_decimal_mode() {
+        let mut calc = Calculator::new();
+        calc.input_digit(0);
+        calc.input_decimal_point();
+        calc.input_digit(1);
+        calc.input_operation(Operation::Add);
+        calc.input_decimal_point();
+        calc.input_digit(2);
+        calc.calculate();
+        assert_eq!(calc.get_display_text(), "0.30000000000000004");
+        assert_eq!(calc.exact_value(), Some(0.3));
+
+        // Switching fixes the result in place, and it chains from there
+        calc.switch_to_decimal();
+        assert_eq!(calc.get_display_text(), "0.3");
+        assert_eq!(calc.exact_value(), None);
+        calc.input_operation(Operation::Subtract);
+        calc.input_decimal_point();
+        calc.input_digit(1);
+        calc.calculate();
+        assert_eq!(calc.get_display_text(), "0.2");
+
+        // Expressions too, and without the mode only the flag is set
+        assert_eq!(calc.evaluate_expression("1.1 * 1.1", None), Ok(1.21));
+        calc.set_decimal_mode(false);
+        assert_eq!(calc.evaluate_expression("1.1 * 1.1", None), Ok(1.1 * 1.1));
+        assert_eq!(calc.exact_value(), Some(1.21));
+        calc.input_digit(5);
+        assert_eq!(calc.exact_value(), None);
+    }
+
+    #[test]
+    fn test_chains_keep_exact_intermediates() {
+        let mut calc = Calculator::new();
+        for event in [
+            InputEvent::DecimalPoint,
+            InputEvent::Digit(1),
+            InputEvent::Operation(Operation::Add),
+            InputEvent::DecimalPoint,
+            InputEvent::Digit(2),
+            InputEvent::Operation(Operation::Add),
+            InputEvent::DecimalPoint,
+            InputEvent::Digit(3),
+            InputEvent::Equals,
+        ] {
+            calc.handle_event(event);
+        }
+        // The flag compares against the exact chain, not 0.30000000000000004 + 0.3
+        assert_eq!(calc.get_display_text(), "0.6000000000000001");
+        assert_eq!(calc.exact_value(), Some(0.6));
+
+        // Decimal mode carries 1 ÷ 3 with all its digits, so × 3 comes back to 1
+        calc.clear();
+        calc.set_decimal_mode(true);
+        for event in [
+            InputEvent::Digit(1),
+            InputEvent::Operation(Operation::Divide),
+            InputEvent::Digit(3),
+            InputEvent::Operation(Operation::Multiply),
+            InputEvent::Digit(3),
+            InputEvent::Equals,
+        ] {
+            calc.handle_event(event);
+        }
+        assert_eq!(calc.get_display_text(), "1");
+    }
+
+    #[test]
+    fn test_equations_are_solved() {
+        let mut calc = Calculator::new();
+        assert_eq!(calc.evaluate_expression("3x + 5 = 20", None), Ok(5.0));
+        assert_eq!(calc.get_display_text(), "5");
+        assert_eq!(calc.history().last().map(|entry| entry.source.as_str()), Some("3x + 5 = 20"));
+
+        // Ans is known, so it's not taken for the unknown
+        assert_eq!(calc.preview_expression("ans * t = 10", None), Ok(2.0));
+        assert_eq!(
+            calc.evaluate_expression("x * x = 4", None),
+            Err(CalculatorError::Unsolvable(String::from("not linear in x")))
+        );
+        assert_eq!(calc.get_display_text(), "5");
+    }
+
+    #[test]
+    fn test_variables_in_expressions() {
+        let mut calc = Calculator::new();
+        calc.variables_mut().set("vat", 0.25);
+        assert_eq!(calc.preview_expression("80 * (1 + vat)", None), Ok(100.0));
+        // A variable with a value isn't the unknown of an equation
+        assert_eq!(calc.preview_expression("vat * x = 5", None), Ok(20.0));
+
+        let dependencies = calc.dependencies("vat * x = 5");
+        assert_eq!(dependencies.names().collect::<Vec<_>>(), ["vat", "x"]);
+        calc.variables_mut().set("vat", 0.5);
+        assert!(dependencies.changed(calc.variables()));
+        assert_eq!(calc.dependencies("1 +"), Dependencies::default());
+    }
+
+    #[test]
+    fn test_base_literals_use_integer_arithmetic() {
+        let mut calc = Calculator::new();
+        assert_eq!(calc.evaluate_expression("0xFF / 2", None), Ok(127.0));
+        assert_eq!(calc.evaluate_expression("255 / 2", None), Ok(127.5));
+        assert!(calc.evaluate_expression("0b11 * 0.5", None).is_err());
+    }
+
+    #[test]
+    fn test_add_mode_entry() {
+        let mut calc = Calculator::new();
+        calc.set_add_mode(true);
+        let type_digits = |calc: &mut Calculator, digits: &str| {
+            for digit in digits.bytes() {
+                calc.input_digit(digit - b'0');
+            }
+        };
+        type_digits(&mut calc, "5");
+        assert_eq!(calc.get_display_text(), "0.05");
+        type_digits(&mut calc, "0995");
+        assert_eq!(calc.get_display_text(), "509.95");
+        calc.backspace();
+        calc.backspace();
+        assert_eq!(calc.get_display_text(), "5.09");
+        calc.input_operation(Operation::Add);
+        type_digits(&mut calc, "1995");
+        assert_eq!(calc.display_value(), Some(19.95));
+        calc.calculate();
+        assert_eq!(calc.get_display_text(), "25.04");
+
+        // A decimal point keeps the number as typed
+        calc.clear();
+        type_digits(&mut calc, "12");
+        calc.input_decimal_point();
+        assert_eq!(calc.get_display_text(), "12.");
+        type_digits(&mut calc, "5");
+        assert_eq!(calc.display_value(), Some(12.5));
+        calc.backspace();
+        calc.backspace();
+        assert_eq!(calc.get_display_text(), "0.12");
+
+        // Multipliers are whole numbers
+        calc.clear();
+        type_digits(&mut calc, "250");
+        calc.input_operation(Operation::Multiply);
+        type_digits(&mut calc, "3");
+        assert_eq!(calc.get_display_text(), "3");
+        calc.calculate();
+        assert_eq!(calc.get_display_text(), "7.5");
+
+        calc.set_add_mode(false);
+        calc.clear();
+        type_digits(&mut calc, "1995");
+        assert_eq!(calc.get_display_text(), "1995");
+    }
+
+    #[test]
+    fn test_item_count() {
+        let mut calc = Calculator::new();
+        let enter = |calc: &mut Calculator, number: u8, op: Option<Operation>| {
+            calc.input_digit(number);
+            match op {
+                Some(op) => calc.input_operation(op),
+                None => calc.calculate(),
+            }
+        };
+        enter(&mut calc, 9, Some(Operation::Add));
+        assert_eq!(calc.item_count(), 1);
+        enter(&mut calc, 5, Some(Operation::Subtract));
+        enter(&mut calc, 2, None);
+        assert_eq!(calc.get_display_text(), "12");
+        assert_eq!(calc.item_count(), 3);
+        // Changing the operator doesn't count twice
+        calc.input_operation(Operation::Subtract);
+        calc.input_operation(Operation::Add);
+        assert_eq!(calc.item_count(), 3);
+        enter(&mut calc, 4, None);
+        assert_eq!(calc.item_count(), 4);
+
+        // A new number after the result starts over
+        calc.input_digit(1);
+        assert_eq!(calc.item_count(), 0);
+        calc.input_operation(Operation::Add);
+        assert_eq!(calc.item_count(), 1);
+
+        // Products aren't sums, though their result can start one
+        calc.clear();
+        enter(&mut calc, 3, Some(Operation::Multiply));
+        assert_eq!(calc.item_count(), 0);
+        enter(&mut calc, 4, Some(Operation::Add));
+        assert_eq!(calc.item_count(), 1);
+        enter(&mut calc, 5, None);
+        assert_eq!(calc.get_display_text(), "17");
+        assert_eq!(calc.item_count(), 2);
+        calc.clear();
+        assert_eq!(calc.item_count(), 0);
+    }
+
+    #[test]
+    fn test_negative_zero_policy() {
+        let mut calc = Calculator::new();
+        // Shown as 0, recorded as 0
+        for source in ["0 * -1", "-0", "round(-0.4)", "-0 / 5"] {
+            let result = calc.evaluate_expression(source, None).unwrap();
+            assert!(result == 0.0 && result.is_sign_positive(), "{}", source);
+            assert_eq!(calc.get_display_text(), "0", "{}", source);
+            assert!(calc.history().last().unwrap().result.is_sign_positive());
+        }
+        calc.input_digit(0);
+        calc.apply_function(|value| Ok(-value));
+        assert_eq!(calc.get_display_text(), "0");
+
+        // Chains on like 0
+        calc.evaluate_expression("0 * -1", None).unwrap();
+        calc.input_operation(Operation::Add);
+        calc.input_digit(5);
+        calc.calculate();
+        assert_eq!(calc.get_display_text(), "5");
+
+        // Where the sign would decide between +∞ and -∞, it's an error instead
+        for source in ["1 / -0", "1 / (0 * -1)", "0 ^ -1", "(-0) ^ -3"] {
+            assert_eq!(calc.evaluate_expression(source, None), Err(CalculatorError::DivisionByZero), "{}", source);
+        }
+        calc.evaluate_expression("0 * -1", None).unwrap();
+        calc.input_operation(Operation::Divide);
+        calc.input_digit(0);
+        calc.calculate();
+        assert_eq!(calc.error(), Some(&CalculatorError::DivisionByZero));
+    }
+
+    #[test]
+    fn test_range_errors_and_warnings() {
+        let mut calc = Calculator::new();
+        assert_eq!(calc.evaluate_expression("1e308 * 10", None), Err(CalculatorError::TooLarge));
+        assert_eq!(calc.evaluate_expression("1e308 * 10 - 1e308 * 10", None), Err(CalculatorError::TooLarge));
+        assert_eq!(calc.evaluate_expression("1.5e308", None), Ok(1.5e308));
+        assert_eq!(calc.warning(), None);
+
+        // Below the smallest normal double it still shows, with a warning
+        assert_eq!(calc.evaluate_expression("1e-300 / 1e10", None), Ok(1e-310));
+        assert_eq!(calc.warning(), Some(Warning::Subnormal));
+        calc.input_operation(Operation::Multiply);
+        calc.input_digit(0);
+        calc.calculate();
+        assert_eq!(calc.warning(), None);
+
+        calc.set_operand(1e308);
+        calc.input_operation(Operation::Add);
+        calc.set_operand(1e308);
+        calc.calculate();
+        assert_eq!(calc.error(), Some(&CalculatorError::TooLarge));
+        assert_eq!(calc.get_display_text(), "Error: Result too large to represent");
+        assert_eq!(calc.warning(), None);
+
+        // Along a keypad chain too, 1e308 × 10 + overflows at the +
+        calc.clear();
+        calc.set_operand(1e308);
+        calc.input_operation(Operation::Multiply);
+        calc.input_digit(1);
+        calc.input_digit(0);
+        calc.input_operation(Operation::Add);
+        assert_eq!(calc.error(), Some(&CalculatorError::TooLarge));
+
+        // An undefined result is a bad argument, not a large one
+        let domain = calc.evaluate_expression("(-8) ^ (1/3)", None);
+        assert_eq!(domain, Err(CalculatorError::Domain(String::from("^"))));
+        calc.clear();
+        calc.apply_function(|_| Ok(f64::NAN));
+        assert!(matches!(calc.error(), Some(CalculatorError::Domain(_))));
+    }
+
+    #[test]
+    fn test_preview_leaves_state_alone() {
+        let mut calc = Calculator::new();
+        calc.evaluate_expression("6 * 7", None).unwrap();
+        calc.input_digit(3);
+
+        assert_eq!(calc.preview_expression("ans + 1", None), Ok(43.0));
+        assert!(matches!(calc.preview_expression("ans +", None), Err(CalculatorError::Syntax { column: 6, .. })));
+        assert_eq!(calc.get_display_text(), "3");
+        assert_eq!(calc.history().entries().len(), 1);
+    }
+
+    #[test]
+    fn test_clear_entry_keeps_pending_operation() {
+        let mut calc = Calculator::new();
+        for event in [
+            InputEvent::Digit(1),
+            InputEvent::Digit(2),
+            InputEvent::Operation(Operation::Add),
+            InputEvent::Digit(5),
+            InputEvent::ClearEntry,
+        ] {
+            calc.handle_event(event);
+        }
+        assert_eq!(calc.get_display_text(), "0");
+        calc.handle_event(InputEvent::Digit(7));
+        calc.handle_event(InputEvent::Equals);
+        assert_eq!(calc.get_display_text(), "19");
+
+        // After an error there is nothing to keep
+        calc.handle_event(InputEvent::Operation(Operation::Divide));
+        calc.handle_event(InputEvent::Digit(0));
+        calc.handle_event(InputEvent::Equals);
+        calc.handle_event(InputEvent::ClearEntry);
+        calc.handle_event(InputEvent::Digit(3));
+        assert_eq!(calc.get_display_text(), "3");
+    }
+
+    #[test]
+    fn test_backspace() {
+        let mut calc = Calculator::new();
+        for event in [InputEvent::Digit(1), InputEvent::Digit(2), InputEvent::DecimalPoint, InputEvent::Digit(5)] {
+            calc.handle_event(event);
+        }
+        calc.handle_event(InputEvent::Backspace);
+        assert_eq!(calc.get_display_text(), "12.");
+        calc.handle_event(InputEvent::Backspace);
+        calc.handle_event(InputEvent::DecimalPoint);
+        calc.handle_event(InputEvent::Digit(7));
+        assert_eq!(calc.display_value(), Some(12.7));
+        for _ in 0..4 {
+            calc.handle_event(InputEvent::Backspace);
+        }
+        assert_eq!(calc.get_display_text(), "0");
+        assert!(!calc.is_entering());
+
+        // A result isn't typed, so there's nothing to take back
+        for event in [InputEvent::Digit(9), InputEvent::Operation(Operation::Add), InputEvent::Digit(6), InputEvent::Equals] {
+            calc.handle_event(event);
+        }
+        calc.handle_event(InputEvent::Backspace);
+        assert_eq!(calc.get_display_text(), "15");
+    }
+
+    #[test]
+    fn test_value_kept_apart_from_display() {
+        let mut calc = Calculator::new();
+        for event in [InputEvent::Digit(1), InputEvent::Digit(2), InputEvent::DecimalPoint] {
+            calc.handle_event(event);
+        }
+        // The display shows what was typed, the value is the number it means
+        assert_eq!(calc.get_display_text(), "12.");
+        assert_eq!(calc.display_value(), Some(12.0));
+
+        calc.handle_event(InputEvent::Operation(Operation::Divide));
+        calc.handle_event(InputEvent::Digit(7));
+        calc.handle_event(InputEvent::Equals);
+        assert_eq!(calc.state.value.float(), 12.0 / 7.0);
+        calc.apply_function(|value| Ok(value * 7.0));
+        assert_eq!(calc.display_value(), Some(12.0 / 7.0 * 7.0));
+    }
+
+    #[test]
+    fn test_entry_state_transitions() {
+        let mut calc = Calculator::new();
+        assert_eq!(calc.state.entry, EntryState::Fresh);
+        calc.input_digit(1);
+        assert_eq!(calc.state.entry, EntryState::TypingInteger);
+        calc.input_decimal_point();
+        calc.input_decimal_point();
+        calc.input_digit(5);
+        assert_eq!((calc.state.entry.clone(), calc.get_display_text()), (EntryState::TypingFraction, "1.5"));
+
+        calc.input_operation(Operation::Divide);
+        assert_eq!(calc.state.entry, EntryState::ResultShown);
+        calc.set_operand(0.0);
+        assert_eq!(calc.state.entry, EntryState::Fresh);
+        calc.calculate();
+        assert_eq!(calc.state.entry.error(), Some(&CalculatorError::DivisionByZero));
+        assert!(!calc.is_entering());
+    }
+
+    #[test]
+    fn test_stored_value_and_pending_operation() {
+        let mut calc = Calculator::new();
+        assert_eq!((calc.stored_value(), calc.pending_operation()), (None, None));
+
+        for event in [InputEvent::Digit(1), InputEvent::Digit(2), InputEvent::Digit(8)] {
+            calc.handle_event(event);
+        }
+        calc.handle_event(InputEvent::Operation(Operation::Divide));
+        assert_eq!(calc.stored_value(), Some(128.0));
+        assert_eq!(calc.pending_operation(), Some(Operation::Divide));
+
+        // Chaining replaces the stored value with the running result
+        calc.handle_event(InputEvent::Digit(2));
+        calc.handle_event(InputEvent::Operation(Operation::Add));
+        assert_eq!(calc.stored_value(), Some(64.0));
+        assert_eq!(calc.pending_operation(), Some(Operation::Add));
+
+        calc.handle_event(InputEvent::Digit(1));
+        calc.handle_event(InputEvent::Equals);
+        assert_eq!(calc.pending_operation(), None);
+    }
+
+    #[test]
+    fn test_registered_binary_operation() {
+        let mut registry = OperationRegistry::default();
+        registry.register_binary(FnBinary {
+            name: "mod",
+            symbol: "mod",
+            precedence: 2,
+            function: |left, right| {
+                if right == 0.0 {
+                    Err(CalculatorError::DivisionByZero)
+                } else {
+                    Ok(left.rem_euclid(right))
+                }
+            },
+        });
+        let modulo = || PendingOperation::Registered(Arc::clone(registry.binary().next().unwrap()));
+
+        let mut calc = Calculator::new();
+        calc.input_digit(1);
+        calc.input_digit(7);
+        calc.input_operation(modulo());
+        assert_eq!((calc.pending_operation(), calc.pending_symbol()), (None, Some("mod")));
+        calc.input_digit(5);
+        assert_eq!(calc.pending_expression().unwrap().to_string(), "mod(17, 5)");
+        // Chains like the keypad's own operations
+        calc.input_operation(Operation::Add);
+        assert_eq!(calc.get_display_text(), "2");
+        calc.input_digit(4);
+        calc.input_operation(modulo());
+        calc.input_digit(4);
+        calc.calculate();
+        assert_eq!(calc.get_display_text(), "2");
+        assert_eq!(calc.history().entries().last().unwrap().source, "mod(6, 4)");
+
+        calc.input_operation(modulo());
+        calc.input_digit(0);
+        calc.calculate();
+        assert_eq!(calc.error(), Some(&CalculatorError::DivisionByZero));
+    }
+
+    #[test]
+    fn test_registered_and_round_events() {
+        let registry = OperationRegistry::basic();
+        let mut calc = Calculator::new();
+        calc.handle_event_with(InputEvent::Digit(2), &registry);
+        calc.handle_event_with(InputEvent::Unary(0), &registry);
+        assert_eq!(calc.display_value(), Some(2f64.sqrt()));
+        calc.handle_event_with(InputEvent::Round(2), &registry);
+        assert_eq!(calc.get_display_text(), "1.41");
+        // Without the registry, and past its end, they do nothing
+        calc.handle_event(InputEvent::Unary(0));
+        calc.handle_event_with(InputEvent::Unary(9), &registry);
+        assert_eq!(calc.get_display_text(), "1.41");
+
+        calc.handle_event_with(InputEvent::Constant(0), &registry);
+        calc.handle_event(InputEvent::Round(4));
+        assert_eq!(calc.get_display_text(), "3.1416");
+        // Halves go away from zero as written, not as stored
+        calc.set_operand(2.675);
+        calc.handle_event(InputEvent::Round(2));
+        assert_eq!(calc.get_display_text(), "2.68");
+    }
+
+    #[test]
+    fn test_memory_survives_clear_but_not_clear_all() {
+        let mut calc = Calculator::new();
+        for event in [
+            InputEvent::Digit(8),
+            InputEvent::MemoryAdd,
+            InputEvent::Clear,
+            InputEvent::Digit(3),
+            InputEvent::MemorySubtract,
+            InputEvent::Clear,
+        ] {
+            calc.handle_event(event);
+        }
+        assert_eq!(calc.memory(), Some(5.0));
+        assert_eq!(calc.get_display_text(), "0");
+
+        // MR becomes the operand of a pending operation
+        for event in [InputEvent::Digit(2), InputEvent::Operation(Operation::Multiply), InputEvent::MemoryRecall] {
+            calc.handle_event(event);
+        }
+        calc.handle_event(InputEvent::Equals);
+        assert_eq!(calc.get_display_text(), "10");
+
+        calc.handle_event(InputEvent::ClearAll);
+        assert_eq!(calc.memory(), None);
+        assert_eq!(calc.get_display_text(), "0");
+        calc.handle_event(InputEvent::MemoryRecall);
+        assert_eq!(calc.get_display_text(), "0");
+    }
+
+    #[test]
+    fn test_paste_normalizes_typography() {
+        let mut calc = Calculator::new();
+        assert_eq!(calc.paste("−12", None), Ok(Pasted::Number(-12.0)));
+        assert_eq!(
+            calc.paste("6 × 7 − 2²", None),
+            Ok(Pasted::Expression {
+                source: String::from("6 * 7 - 2^2"),
+                result: 38.0,
+            })
+        );
+    }
+
     // Feature: gui-calculator, Property 1: Digit input accumulation
     // Validates: Requirements 1.1, 1.4
     proptest! {
@@ -257,11 +1340,11 @@ mod tests {
             calc.input_operation(operation);
             
             // Verify the calculator stored both the operand and operation
-            prop_assert_eq!(calc.state.stored_value, Some(operand_value),
+            prop_assert_eq!(calc.stored_value(), Some(operand_value),
                 "Stored value should be {}", operand_value);
-            prop_assert_eq!(calc.state.current_operation, Some(operation),
+            prop_assert_eq!(calc.pending_operation(), Some(operation),
                 "Current operation should be {:?}", operation);
-            prop_assert!(calc.state.waiting_for_operand,
+            prop_assert_eq!(&calc.state.entry, &EntryState::ResultShown,
                 "Calculator should be waiting for next operand");
         }
 
@@ -307,7 +1390,7 @@ mod tests {
             calc.calculate();
             
             // Should not be in error state
-            prop_assert!(calc.state.error.is_none(), "Should not have error after calculation");
+            prop_assert!(!calc.state.entry.is_error(), "Should not have error after calculation");
             
             // Get the result
             let result_str = calc.get_display_text();
@@ -326,9 +1409,9 @@ mod tests {
             calc_continue.input_operation(continuation_op);
             
             // The stored value should be the result from previous calculation
-            prop_assert_eq!(calc_continue.state.stored_value, Some(result),
+            prop_assert_eq!(calc_continue.stored_value(), Some(result),
                 "After calculation, entering operation should use previous result");
-            prop_assert_eq!(calc_continue.state.current_operation, Some(continuation_op),
+            prop_assert_eq!(calc_continue.pending_operation(), Some(continuation_op),
                 "After calculation, operation should be stored");
         }
 
@@ -397,14 +1480,10 @@ mod tests {
                 "Display should be reset to initial value");
             prop_assert_eq!(calc.state.stored_value, None,
                 "Stored value should be None after clear");
-            prop_assert_eq!(calc.state.current_operation, None,
+            prop_assert_eq!(calc.pending_operation(), None,
                 "Current operation should be None after clear");
-            prop_assert_eq!(calc.state.waiting_for_operand, false,
-                "waiting_for_operand should be false after clear");
-            prop_assert_eq!(calc.state.error, None,
-                "Error should be None after clear");
-            prop_assert_eq!(calc.state.fresh_start, true,
-                "fresh_start should be true after clear");
+            prop_assert_eq!(&calc.state.entry, &EntryState::Fresh,
+                "Entry should be fresh after clear");
         }
 
         // Feature: gui-calculator, Property 8: Error state blocks operations
@@ -433,8 +1512,8 @@ mod tests {
             calc.calculate();
             
             // Verify we're in an error state
-            prop_assert!(calc.state.error.is_some(), "Calculator should be in error state");
-            let error_message = calc.get_display_text();
+            prop_assert!(calc.state.entry.is_error(), "Calculator should be in error state");
+            let error_message = calc.get_display_text().to_string();
             prop_assert!(error_message.contains("Error"), "Display should show error message");
             
             // Try to input a digit - should be blocked
@@ -458,7 +1537,7 @@ mod tests {
                 "Display should still show error after calculate attempt");
             
             // Verify error state is maintained
-            prop_assert!(calc.state.error.is_some(),
+            prop_assert!(calc.state.entry.is_error(),
                 "Error state should be maintained until clear");
         }
 
@@ -488,10 +1567,10 @@ mod tests {
             calc.input_operation(operation);
             
             // Capture the valid state
-            let valid_display = calc.get_display_text();
+            let valid_display = calc.get_display_text().to_string();
             let valid_stored = calc.state.stored_value;
-            let valid_operation = calc.state.current_operation;
-            let valid_waiting = calc.state.waiting_for_operand;
+            let valid_operation = calc.pending_operation();
+            let valid_entry = calc.state.entry.clone();
             
             // Try to input multiple operations in a row without operands (invalid sequence)
             for _ in 0..num_invalid_ops {
@@ -503,14 +1582,91 @@ mod tests {
                 "Display should be preserved after invalid operation sequence");
             prop_assert_eq!(calc.state.stored_value, valid_stored,
                 "Stored value should be preserved after invalid operation sequence");
-            prop_assert_eq!(calc.state.current_operation, valid_operation,
+            prop_assert_eq!(calc.pending_operation(), valid_operation,
                 "Current operation should be preserved after invalid operation sequence");
-            prop_assert_eq!(calc.state.waiting_for_operand, valid_waiting,
-                "waiting_for_operand flag should be preserved after invalid operation sequence");
-            prop_assert!(calc.state.error.is_none(),
+            prop_assert_eq!(&calc.state.entry, &valid_entry,
+                "Entry state should be preserved after invalid operation sequence");
+            prop_assert!(!calc.state.entry.is_error(),
                 "No error should be set for invalid input sequences");
         }
 
+        // Feature: gui-calculator, Property 14: History keeps recallable expressions
+        #[test]
+        fn test_history_records_expressions(
+            left_digits in prop::collection::vec(1u8..=9, 1..=3),
+            right_digits in prop::collection::vec(1u8..=9, 1..=3),
+            operation_idx in 0usize..4
+        ) {
+            let operation = match operation_idx {
+                0 => Operation::Add,
+                1 => Operation::Subtract,
+                2 => Operation::Multiply,
+                _ => Operation::Divide,
+            };
+
+            let mut calc = Calculator::new();
+            for &digit in &left_digits {
+                calc.input_digit(digit);
+            }
+            calc.input_operation(operation);
+            for &digit in &right_digits {
+                calc.input_digit(digit);
+            }
+            calc.calculate();
+
+            // Keypad calculations are recorded as structured expressions
+            let entry = calc.history().last().cloned();
+            prop_assert!(entry.is_some(), "Calculation should be recorded");
+            let entry = entry.unwrap();
+            prop_assert_eq!(entry.result.to_string(), calc.get_display_text());
+
+            // Re-evaluating the recorded source reproduces the result and refers back via Ans
+            let source = format!("{} + ans - ans", entry.source);
+            let result = calc.evaluate_expression(&source, None);
+            prop_assert_eq!(result, Ok(entry.result));
+            prop_assert_eq!(&calc.history().last().unwrap().source, &source);
+            prop_assert_eq!(calc.history().entries().len(), 2);
+
+            // Clearing the calculator keeps the history
+            calc.clear();
+            prop_assert_eq!(calc.history().entries().len(), 2);
+        }
+
+        // Feature: gui-calculator, Property 17: Pasted numbers and expressions
+        #[test]
+        fn test_paste_number_or_expression(
+            left in -1000000.0..1000000.0f64,
+            right in 1.0..1000000.0f64,
+            operation_idx in 0usize..4
+        ) {
+            let operation = match operation_idx {
+                0 => Operation::Add,
+                1 => Operation::Subtract,
+                2 => Operation::Multiply,
+                _ => Operation::Divide,
+            };
+
+            // Plain numbers just replace the operand, without a history entry
+            let mut calc = Calculator::new();
+            let pasted = calc.paste(&format!("  {}\n", left), None);
+            prop_assert_eq!(pasted, Ok(Pasted::Number(left)));
+            prop_assert_eq!(calc.get_display_text(), left.to_string());
+            prop_assert!(calc.history().entries().is_empty());
+
+            // Expressions are evaluated and recorded
+            let source = format!("{} {} {}", left, BinaryOp::from(operation).symbol(), right);
+            let expected = operation.apply(left, right).unwrap();
+            let pasted = calc.paste(&source, None);
+            prop_assert_eq!(pasted, Ok(Pasted::Expression { source: source.clone(), result: expected }));
+            prop_assert_eq!(calc.get_display_text(), expected.to_string());
+            prop_assert_eq!(&calc.history().last().unwrap().source, &source);
+
+            // Garbage is rejected without touching the display
+            prop_assert!(calc.paste("12 +", None).is_err());
+            prop_assert!(calc.paste("inf", None).is_err());
+            prop_assert_eq!(calc.get_display_text(), expected.to_string());
+        }
+
         // Feature: gui-calculator, Property 7: Number formatting consistency
         // Validates: Requirements 4.3
         #[test]
@@ -583,4 +1739,50 @@ mod tests {
                 "Same input sequence should produce consistent display");
         }
     }
+
+    // Feature: gui-calculator, Property 32: Keypad calculations agree with exact rational arithmetic
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_matches_reference_model(events in testing::calculation()) {
+            let mut calc = Calculator::new();
+            for &event in &events {
+                calc.handle_event(event);
+            }
+
+            match testing::reference_result(&events) {
+                Some(expected) => {
+                    let expected = expected.to_f64();
+                    let actual = calc.display_value();
+                    prop_assert!(
+                        actual.is_some_and(|actual| (actual - expected).abs() <= 1e-9 * expected.abs().max(1.0)),
+                        "{:?} gave {:?}, expected {}", events, actual, expected
+                    );
+                }
+                // Division by zero; too-large exact values don't occur with these inputs
+                None => prop_assert_eq!(calc.display_value(), None, "{:?} should be an error", events),
+            }
+        }
+    }
+
+    // Feature: gui-calculator, Property 50: Backspace after a typed character is as if it was never typed
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_backspace_undoes_typing(number in testing::typed_number(), extra in testing::typed_number()) {
+            let mut typed = Calculator::new();
+            let mut expected = Calculator::new();
+            for &event in &number {
+                typed.handle_event(event);
+                expected.handle_event(event);
+            }
+            let extra = extra[0];
+            typed.handle_event(extra);
+            typed.handle_event(InputEvent::Backspace);
+            prop_assert_eq!(typed.get_display_text(), expected.get_display_text());
+            prop_assert_eq!(typed.display_value(), expected.display_value());
+        }
+    }
 }
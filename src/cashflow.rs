@@ -0,0 +1,148 @@
+// Cash-Flow Analysis: NPV and IRR
+//
+// Amounts at regular periods, the first at period 0 and usually the
+// (negative) investment. Rates are percentages per period.
+use crate::error::CalculatorError;
+
+// Newton's method converges in a handful of steps when it converges at all
+const MAX_ITERATIONS: usize = 100;
+// Rates as fractions, so about a hundred-millionth of a percent
+const TOLERANCE: f64 = 1e-12;
+// Where bisection looks for a sign change of the NPV when Newton's method
+// fails, from just above -100% up to 100000%
+const BRACKETS: [f64; 13] = [-0.9999, -0.99, -0.9, -0.5, 0.0, 0.1, 0.5, 1.0, 2.0, 5.0, 10.0, 100.0, 1000.0];
+
+/// The net present value of `flows` at `rate_percent` per period.
+pub fn npv(rate_percent: f64, flows: &[f64]) -> Result<f64, CalculatorError> {
+    if flows.is_empty() {
+        return Err(CalculatorError::Domain(String::from("NPV (there are no cash flows)")));
+    }
+    // At -100% every later amount would be infinitely valuable
+    if rate_percent <= -100.0 || !rate_percent.is_finite() {
+        return Err(CalculatorError::Domain(String::from("NPV (the rate must be above -100%)")));
+    }
+    Ok(value_and_slope(rate_percent / 100.0, flows).0)
+}
+
+/// The internal rate of return of `flows`, in percent per period: the rate
+/// at which their NPV is zero. The flows must include both money paid and
+/// money received, or no rate can balance them.
+pub fn irr(flows: &[f64]) -> Result<f64, CalculatorError> {
+    let paid = flows.iter().any(|&flow| flow < 0.0);
+    let received = flows.iter().any(|&flow| flow > 0.0);
+    if !(paid && received) {
+        return Err(CalculatorError::Domain(String::from("IRR (the cash flows never change sign)")));
+    }
+
+    // Newton's method from 10%, the usual first guess
+    let mut rate = 0.1;
+    for _ in 0..MAX_ITERATIONS {
+        let (value, slope) = value_and_slope(rate, flows);
+        let next = rate - value / slope;
+        if !next.is_finite() || next <= -1.0 {
+            break;
+        }
+        if (next - rate).abs() < TOLERANCE {
+            return Ok(next * 100.0);
+        }
+        rate = next;
+    }
+    bisect(flows)
+        .map(|rate| rate * 100.0)
+        .ok_or_else(|| CalculatorError::Domain(String::from("IRR (no rate makes the NPV zero)")))
+}
+
+// The NPV at `rate` (a fraction) and its derivative with respect to the rate
+fn value_and_slope(rate: f64, flows: &[f64]) -> (f64, f64) {
+    let mut value = 0.0;
+    let mut slope = 0.0;
+    for (period, &flow) in flows.iter().enumerate() {
+        let discount = (1.0 + rate).powi(period as i32);
+        value += flow / discount;
+        slope -= period as f64 * flow / (discount * (1.0 + rate));
+    }
+    (value, slope)
+}
+
+// Bisection between the first two bracketing rates the NPV changes sign across
+fn bisect(flows: &[f64]) -> Option<f64> {
+    let value = |rate| value_and_slope(rate, flows).0;
+    let (mut low, mut high) = BRACKETS
+        .windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .find(|&(low, high)| value(low).signum() != value(high).signum())?;
+    for _ in 0..200 {
+        let middle = (low + high) / 2.0;
+        if value(middle).signum() == value(low).signum() {
+            low = middle;
+        } else {
+            high = middle;
+        }
+        if high - low < TOLERANCE {
+            break;
+        }
+    }
+    Some((low + high) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-8, "{} is not close to {}", actual, expected);
+    }
+
+    #[test]
+    fn test_npv() {
+        let flows = [-1000.0, 300.0, 400.0, 500.0];
+        assert_close(npv(10.0, &flows).unwrap(), -1000.0 + 300.0 / 1.1 + 400.0 / 1.21 + 500.0 / 1.331);
+        assert_close(npv(0.0, &flows).unwrap(), 200.0);
+        assert_close(npv(10.0, &[-100.0, 110.0]).unwrap(), 0.0);
+        assert!(npv(-100.0, &flows).is_err());
+        assert!(npv(10.0, &[]).is_err());
+    }
+
+    #[test]
+    fn test_irr() {
+        assert_close(irr(&[-100.0, 110.0]).unwrap(), 10.0);
+        assert_close(irr(&[-100.0, 0.0, 121.0]).unwrap(), 10.0);
+        assert_close(irr(&[-100.0, 50.0]).unwrap(), -50.0);
+        let flows = [-1000.0, 300.0, 400.0, 500.0];
+        assert_close(npv(irr(&flows).unwrap(), &flows).unwrap(), 0.0);
+        // Borrowing first and repaying later works the same way
+        assert_close(irr(&[100.0, -110.0]).unwrap(), 10.0);
+    }
+
+    #[test]
+    fn test_irr_needs_a_sign_change() {
+        let message = "Error: Invalid argument for IRR (the cash flows never change sign)";
+        assert_eq!(irr(&[100.0, 200.0]).unwrap_err().to_string(), message);
+        assert_eq!(irr(&[-100.0, -200.0]).unwrap_err().to_string(), message);
+        assert_eq!(irr(&[0.0, 0.0]).unwrap_err().to_string(), message);
+        assert!(irr(&[]).is_err());
+        // Both signs, but no rate balances them
+        assert_eq!(
+            irr(&[100.0, -300.0, 300.0]).unwrap_err().to_string(),
+            "Error: Invalid argument for IRR (no rate makes the NPV zero)"
+        );
+    }
+
+    // Feature: gui-calculator, Property 64: An investment followed by returns has an IRR at which the NPV is zero
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_irr_zeroes_npv(
+            investment in 1.0..1000.0f64,
+            returns in prop::collection::vec(1.0..1000.0f64, 1..10)
+        ) {
+            let mut flows = vec![-investment];
+            flows.extend(returns);
+            let rate = irr(&flows).unwrap();
+            prop_assert!(rate > -100.0);
+            prop_assert!((npv(rate, &flows).unwrap() / investment).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+// Command-Line Options
+//
+// Flags for launchers and scripts to open the calculator ready to use, e.g.
+// `--mode expression --expr "2^10" --theme dark`, or a share link.
+use crate::app::Mode;
+use crate::link;
+use crate::settings::Theme;
+
+pub const USAGE: &str = "\
+Usage: calculator [options] [calc://eval?expr=...]
+
+Options:
+  --mode <name>     Start in a mode: basic, expression (or scientific),
+                    worksheet, grid, programmer or compare
+  --value <number>  Start with a number on the display
+  --expr <text>     Evaluate an expression, in expression mode
+  --theme <name>    dark or light
+  -h, --help        Show this help";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LaunchOptions {
+    pub mode: Option<Mode>,
+    pub value: Option<f64>,
+    pub expr: Option<String>,
+    pub theme: Option<Theme>,
+    /// Print `USAGE` instead of starting
+    pub help: bool,
+    /// Run the arithmetic self-check and print its report instead of
+    /// starting; left out of `USAGE`
+    pub selfcheck: bool,
+}
+
+/// Reads the flags, given without the program name. Values follow their flag
+/// as the next argument or after `=`.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<LaunchOptions, String> {
+    let mut options = LaunchOptions::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg, None),
+        };
+        if flag == "-h" || flag == "--help" {
+            options.help = true;
+            continue;
+        }
+        if flag == "--selfcheck" {
+            options.selfcheck = true;
+            continue;
+        }
+        // A share link opened from a browser or a chat
+        if flag.get(..link::SCHEME.len()).is_some_and(|scheme| scheme.eq_ignore_ascii_case(link::SCHEME)) {
+            options.expr = Some(link::decode(&flag)?);
+            continue;
+        }
+        if !matches!(flag.as_str(), "--mode" | "--value" | "--expr" | "--theme") {
+            return Err(format!("Unknown option '{}'", flag));
+        }
+        let value = inline
+            .or_else(|| args.next())
+            .ok_or_else(|| format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--mode" => {
+                let mode = Mode::from_name(&value).ok_or_else(|| format!("Unknown mode '{}'", value))?;
+                options.mode = Some(mode);
+            }
+            "--value" => {
+                let number = value
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|number| number.is_finite())
+                    .ok_or_else(|| format!("Not a number: {}", value))?;
+                options.value = Some(number);
+            }
+            "--expr" => options.expr = Some(value),
+            _ => {
+                let theme = match value.to_ascii_lowercase().as_str() {
+                    "dark" => Theme::Dark,
+                    "light" => Theme::Light,
+                    _ => return Err(format!("Unknown theme '{}'", value)),
+                };
+                options.theme = Some(theme);
+            }
+        }
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn parse_args(args: &[&str]) -> Result<LaunchOptions, String> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_flags() {
+        let options = parse_args(&["--mode", "scientific", "--value", "42", "--expr=2^10", "--theme", "Dark"]).unwrap();
+        assert_eq!(
+            options,
+            LaunchOptions {
+                mode: Some(Mode::Expression),
+                value: Some(42.0),
+                expr: Some(String::from("2^10")),
+                theme: Some(Theme::Dark),
+                help: false,
+                selfcheck: false,
+            }
+        );
+        assert_eq!(parse_args(&["--mode=Grid"]).unwrap().mode, Some(Mode::Grid));
+        assert!(parse_args(&["-h"]).unwrap().help);
+        assert!(parse_args(&["--selfcheck"]).unwrap().selfcheck);
+        let linked = parse_args(&["calc://eval?expr=2%5E10"]).unwrap();
+        assert_eq!(linked.expr, Some(String::from("2^10")));
+        assert_eq!(parse_args(&[]), Ok(LaunchOptions::default()));
+    }
+
+    #[test]
+    fn test_bad_flags() {
+        assert_eq!(parse_args(&["--colour", "red"]), Err(String::from("Unknown option '--colour'")));
+        assert_eq!(parse_args(&["--value"]), Err(String::from("--value needs a value")));
+        assert_eq!(parse_args(&["--value", "forty"]), Err(String::from("Not a number: forty")));
+        assert!(parse_args(&["--value", "inf"]).is_err());
+        assert_eq!(parse_args(&["--mode", "graphing"]), Err(String::from("Unknown mode 'graphing'")));
+        assert!(parse_args(&["--theme", "blue"]).is_err());
+        assert!(parse_args(&["calc://plot?expr=x"]).is_err());
+    }
+
+    // Feature: gui-calculator, Property 51: Any finite --value comes back exactly, in either flag form
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_value_round_trips(value in any::<f64>().prop_filter("finite", |value| value.is_finite())) {
+            let separate = parse_args(&["--value", &value.to_string()]).unwrap();
+            let joined = parse_args(&[&format!("--value={}", value)]).unwrap();
+            prop_assert_eq!(separate.value, Some(value));
+            prop_assert_eq!(joined.value, Some(value));
+        }
+    }
+}
@@ -0,0 +1,203 @@
+// Color Values
+use std::fmt;
+
+use crate::error::CalculatorError;
+
+/// A color as 8-bit red, green and blue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+/// Hue in degrees, saturation and lightness in percent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub h: f64,
+    pub s: f64,
+    pub l: f64,
+}
+
+impl Rgb {
+    /// E.g. `#FF8000`.
+    pub fn hex(self) -> String {
+        format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
+
+    /// The color as the integer `0xRRGGBB`.
+    pub fn to_integer(self) -> u32 {
+        u32::from_be_bytes([0, self.r, self.g, self.b])
+    }
+
+    pub fn from_integer(value: u32) -> Option<Self> {
+        let [high, r, g, b] = value.to_be_bytes();
+        (high == 0).then_some(Self { r, g, b })
+    }
+
+    pub fn to_hsl(self) -> Hsl {
+        let [r, g, b] = [self.r, self.g, self.b].map(|channel| f64::from(channel) / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let l = (max + min) / 2.0;
+        if chroma == 0.0 {
+            return Hsl { h: 0.0, s: 0.0, l: l * 100.0 };
+        }
+        let s = chroma / (1.0 - (2.0 * l - 1.0).abs());
+        let sector = if max == r {
+            ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / chroma + 2.0
+        } else {
+            (r - g) / chroma + 4.0
+        };
+        Hsl {
+            h: sector * 60.0,
+            s: s * 100.0,
+            l: l * 100.0,
+        }
+    }
+}
+
+impl Hsl {
+    pub fn to_rgb(self) -> Rgb {
+        let h = self.h.rem_euclid(360.0) / 60.0;
+        let s = (self.s / 100.0).clamp(0.0, 1.0);
+        let l = (self.l / 100.0).clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let second = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, second, 0.0),
+            1 => (second, chroma, 0.0),
+            2 => (0.0, chroma, second),
+            3 => (0.0, second, chroma),
+            4 => (second, 0.0, chroma),
+            _ => (chroma, 0.0, second),
+        };
+        let lightness = l - chroma / 2.0;
+        let channel = |value: f64| ((value + lightness) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Rgb {
+            r: channel(r),
+            g: channel(g),
+            b: channel(b),
+        }
+    }
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rgb({}, {}, {})", self.r, self.g, self.b)
+    }
+}
+
+impl fmt::Display for Hsl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "hsl({:.0}, {:.0}%, {:.0}%)", self.h, self.s, self.l)
+    }
+}
+
+/// Reads a color in any of the forms it's shown in: `#FF8000` or `#F80`
+/// (the `#` is optional), `rgb(255, 128, 0)` or just `255, 128, 0`, and
+/// `hsl(30, 100%, 50%)`.
+pub fn parse(text: &str) -> Result<Rgb, CalculatorError> {
+    let invalid = || CalculatorError::Domain(String::from("color"));
+    let text = text.trim().to_ascii_lowercase();
+    if let Some(hsl) = text.strip_prefix("hsl") {
+        let [h, s, l] = numbers(hsl).ok_or_else(invalid)?;
+        if !(0.0..=100.0).contains(&s) || !(0.0..=100.0).contains(&l) {
+            return Err(invalid());
+        }
+        return Ok(Hsl { h, s, l }.to_rgb());
+    }
+    if let Some([r, g, b]) = numbers(text.strip_prefix("rgb").unwrap_or(&text)) {
+        let channel = |value: f64| {
+            (value.fract() == 0.0 && (0.0..=255.0).contains(&value)).then_some(value as u8)
+        };
+        return match (channel(r), channel(g), channel(b)) {
+            (Some(r), Some(g), Some(b)) => Ok(Rgb { r, g, b }),
+            _ => Err(invalid()),
+        };
+    }
+
+    let digits = text.strip_prefix('#').unwrap_or(&text);
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    // #F80 is shorthand for #FF8800
+    let digits = match digits.len() {
+        3 => digits.chars().flat_map(|c| [c, c]).collect(),
+        6 => digits.to_string(),
+        _ => return Err(invalid()),
+    };
+    let value = u32::from_str_radix(&digits, 16).map_err(|_| invalid())?;
+    Rgb::from_integer(value).ok_or_else(invalid)
+}
+
+// Three numbers separated by commas or spaces, optionally in parentheses
+// and with % signs
+fn numbers(text: &str) -> Option<[f64; 3]> {
+    let text = text.trim();
+    let text = text
+        .strip_prefix('(')
+        .and_then(|inner| inner.strip_suffix(')'))
+        .unwrap_or(text);
+    let values: Vec<f64> = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.trim_end_matches(['%', '°']).parse::<f64>().ok())
+        .collect::<Option<_>>()?;
+    values.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_parse_forms() {
+        let orange = Rgb { r: 255, g: 128, b: 0 };
+        assert_eq!(parse("#FF8000"), Ok(orange));
+        assert_eq!(parse("ff8000"), Ok(orange));
+        assert_eq!(parse("rgb(255, 128, 0)"), Ok(orange));
+        assert_eq!(parse("255 128 0"), Ok(orange));
+        assert_eq!(parse("#f80"), Ok(Rgb { r: 255, g: 136, b: 0 }));
+        assert_eq!(parse("hsl(30, 100%, 50%)"), Ok(orange));
+
+        let invalid = Err(CalculatorError::Domain(String::from("color")));
+        assert_eq!(parse("#12345"), invalid);
+        assert_eq!(parse("#GG0000"), invalid);
+        assert_eq!(parse("256, 0, 0"), invalid);
+        assert_eq!(parse("1.5, 0, 0"), invalid);
+        assert_eq!(parse("hsl(0, 120%, 50%)"), invalid);
+        assert_eq!(parse(""), invalid);
+    }
+
+    #[test]
+    fn test_conversions() {
+        let orange = Rgb { r: 255, g: 128, b: 0 };
+        assert_eq!(orange.hex(), "#FF8000");
+        assert_eq!(orange.to_string(), "rgb(255, 128, 0)");
+        assert_eq!(orange.to_hsl().to_string(), "hsl(30, 100%, 50%)");
+        assert_eq!(orange.to_integer(), 0xFF8000);
+        assert_eq!(Rgb::from_integer(0x1000000), None);
+
+        let grey = Rgb { r: 128, g: 128, b: 128 }.to_hsl();
+        assert_eq!((grey.h, grey.s), (0.0, 0.0));
+        assert_eq!(Hsl { h: 240.0, s: 100.0, l: 50.0 }.to_rgb(), Rgb { r: 0, g: 0, b: 255 });
+        assert_eq!(Hsl { h: 360.0, s: 100.0, l: 50.0 }.to_rgb(), Rgb { r: 255, g: 0, b: 0 });
+    }
+
+    // Feature: gui-calculator, Property 36: Colors survive the trip through HSL
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_hsl_round_trip(r in any::<u8>(), g in any::<u8>(), b in any::<u8>()) {
+            let rgb = Rgb { r, g, b };
+            prop_assert_eq!(rgb.to_hsl().to_rgb(), rgb);
+            prop_assert_eq!(parse(&rgb.hex()), Ok(rgb));
+        }
+    }
+}
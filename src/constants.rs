@@ -0,0 +1,94 @@
+// Physical Constants (CODATA 2018)
+
+/// A constant with its SI value. Expressions refer to it by `name`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicalConstant {
+    /// Identifier usable in expressions, e.g. `k_B`
+    pub name: &'static str,
+    /// Conventional symbol for display, e.g. `Nₐ`
+    pub symbol: &'static str,
+    pub description: &'static str,
+    pub value: f64,
+    pub unit: &'static str,
+    /// Standard uncertainty; `None` for exact (defined) values
+    pub uncertainty: Option<f64>,
+}
+
+const fn constant(
+    name: &'static str,
+    symbol: &'static str,
+    description: &'static str,
+    value: f64,
+    unit: &'static str,
+    uncertainty: Option<f64>,
+) -> PhysicalConstant {
+    PhysicalConstant {
+        name,
+        symbol,
+        description,
+        value,
+        unit,
+        uncertainty,
+    }
+}
+
+pub const CONSTANTS: &[PhysicalConstant] = &[
+    constant("c", "c", "Speed of light in vacuum", 299_792_458.0, "m s⁻¹", None),
+    constant("G", "G", "Newtonian constant of gravitation", 6.674_30e-11, "m³ kg⁻¹ s⁻²", Some(0.000_15e-11)),
+    constant("h", "h", "Planck constant", 6.626_070_15e-34, "J s", None),
+    constant("hbar", "ħ", "Reduced Planck constant", 1.054_571_817e-34, "J s", None),
+    constant("N_A", "Nₐ", "Avogadro constant", 6.022_140_76e23, "mol⁻¹", None),
+    constant("R", "R", "Molar gas constant", 8.314_462_618, "J mol⁻¹ K⁻¹", None),
+    constant("k_B", "k_B", "Boltzmann constant", 1.380_649e-23, "J K⁻¹", None),
+    constant("q_e", "e", "Elementary charge", 1.602_176_634e-19, "C", None),
+    constant("F", "F", "Faraday constant", 96_485.332_12, "C mol⁻¹", None),
+    constant("m_e", "mₑ", "Electron mass", 9.109_383_701_5e-31, "kg", Some(0.000_000_002_8e-31)),
+    constant("m_p", "mₚ", "Proton mass", 1.672_621_923_69e-27, "kg", Some(0.000_000_000_51e-27)),
+    constant("m_u", "mᵤ", "Atomic mass constant", 1.660_539_066_60e-27, "kg", Some(0.000_000_000_50e-27)),
+    constant("epsilon_0", "ε₀", "Vacuum electric permittivity", 8.854_187_812_8e-12, "F m⁻¹", Some(0.000_000_001_3e-12)),
+    constant("mu_0", "μ₀", "Vacuum magnetic permeability", 1.256_637_062_12e-6, "N A⁻²", Some(0.000_000_000_19e-6)),
+    constant("sigma", "σ", "Stefan–Boltzmann constant", 5.670_374_419e-8, "W m⁻² K⁻⁴", None),
+    constant("a_0", "a₀", "Bohr radius", 5.291_772_109_03e-11, "m", Some(0.000_000_000_80e-11)),
+    constant("R_inf", "R∞", "Rydberg constant", 10_973_731.568_160, "m⁻¹", Some(0.000_021)),
+    constant("g_n", "gₙ", "Standard acceleration of gravity", 9.806_65, "m s⁻²", None),
+];
+
+/// The constant an expression identifier refers to. Names are case-sensitive
+/// since `G` and `g_n`, or `R` and `r`, mean different things.
+pub fn find(name: &str) -> Option<&'static PhysicalConstant> {
+    CONSTANTS.iter().find(|constant| constant.name == name)
+}
+
+/// Constants whose name, symbol or description contains `query`, ignoring case.
+pub fn search(query: &str) -> impl Iterator<Item = &'static PhysicalConstant> + '_ {
+    let query = query.trim().to_lowercase();
+    CONSTANTS.iter().filter(move |constant| {
+        [constant.name, constant.symbol, constant.description]
+            .iter()
+            .any(|text| text.to_lowercase().contains(&query))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::{parse, Environment};
+
+    #[test]
+    fn test_catalog() {
+        assert_eq!(find("N_A").unwrap().value, 6.022_140_76e23);
+        assert!(find("n_a").is_none());
+        assert!(find("q_e").unwrap().uncertainty.is_none());
+
+        let found: Vec<&str> = search("boltz").map(|constant| constant.name).collect();
+        assert_eq!(found, ["k_B", "sigma"]);
+        assert_eq!(search("").count(), CONSTANTS.len());
+
+        // Names are unique, and usable as identifiers in expressions
+        let env = Environment::new();
+        for constant in CONSTANTS {
+            assert_eq!(CONSTANTS.iter().filter(|other| other.name == constant.name).count(), 1);
+            assert_eq!(parse(constant.name).and_then(|expr| expr.eval(&env)), Ok(constant.value));
+        }
+    }
+}
@@ -0,0 +1,168 @@
+// Quick Conversions
+//
+// Everyday unit conversions offered for the number on the display. The ones
+// used most come first, and the kind of quantity converted last leads, so
+// after converting a length the other length conversions are at hand.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ieee754;
+
+const SIGNIFICANT_DIGITS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Quantity {
+    Length,
+    Area,
+    Volume,
+    Mass,
+    Temperature,
+    Speed,
+}
+
+/// `to = from * scale + offset`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Conversion {
+    pub from: &'static str,
+    pub to: &'static str,
+    pub quantity: Quantity,
+    scale: f64,
+    offset: f64,
+}
+
+const fn linear(from: &'static str, to: &'static str, quantity: Quantity, scale: f64) -> Conversion {
+    Conversion {
+        from,
+        to,
+        quantity,
+        scale,
+        offset: 0.0,
+    }
+}
+
+/// In the order offered before anything has been used.
+pub const CONVERSIONS: &[Conversion] = &[
+    linear("m", "ft", Quantity::Length, 1.0 / 0.3048),
+    linear("ft", "m", Quantity::Length, 0.3048),
+    linear("in", "cm", Quantity::Length, 2.54),
+    linear("cm", "in", Quantity::Length, 1.0 / 2.54),
+    linear("km", "mi", Quantity::Length, 1.0 / 1.609344),
+    linear("mi", "km", Quantity::Length, 1.609344),
+    linear("kg", "lb", Quantity::Mass, 1.0 / 0.45359237),
+    linear("lb", "kg", Quantity::Mass, 0.45359237),
+    linear("oz", "g", Quantity::Mass, 28.349523125),
+    linear("g", "oz", Quantity::Mass, 1.0 / 28.349523125),
+    Conversion {
+        from: "°C",
+        to: "°F",
+        quantity: Quantity::Temperature,
+        scale: 1.8,
+        offset: 32.0,
+    },
+    Conversion {
+        from: "°F",
+        to: "°C",
+        quantity: Quantity::Temperature,
+        scale: 5.0 / 9.0,
+        offset: -160.0 / 9.0,
+    },
+    linear("L", "gal", Quantity::Volume, 1.0 / 3.785411784),
+    linear("gal", "L", Quantity::Volume, 3.785411784),
+    linear("km/h", "mph", Quantity::Speed, 1.0 / 1.609344),
+    linear("mph", "km/h", Quantity::Speed, 1.609344),
+    linear("m²", "ft²", Quantity::Area, 1.0 / (0.3048 * 0.3048)),
+    linear("ft²", "m²", Quantity::Area, 0.3048 * 0.3048),
+];
+
+impl Conversion {
+    pub fn apply(&self, value: f64) -> f64 {
+        ieee754::round_to_significant(value * self.scale + self.offset, SIGNIFICANT_DIGITS)
+    }
+
+    /// What usage is counted under, e.g. "m→ft".
+    pub fn id(&self) -> String {
+        format!("{}→{}", self.from, self.to)
+    }
+}
+
+/// How often each conversion was picked, kept between sessions.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConversionUsage {
+    counts: BTreeMap<String, u32>,
+    last: Option<Quantity>,
+}
+
+impl ConversionUsage {
+    pub fn record(&mut self, conversion: &Conversion) {
+        *self.counts.entry(conversion.id()).or_default() += 1;
+        self.last = Some(conversion.quantity);
+    }
+
+    pub fn count(&self, conversion: &Conversion) -> u32 {
+        self.counts.get(&conversion.id()).copied().unwrap_or(0)
+    }
+
+    /// The `limit` conversions to offer: the last quantity's first, then
+    /// by how often they were used, then in table order.
+    pub fn top(&self, limit: usize) -> Vec<&'static Conversion> {
+        let mut ranked: Vec<(usize, &'static Conversion)> = CONVERSIONS.iter().enumerate().collect();
+        ranked.sort_by_key(|&(index, conversion)| {
+            let other_quantity = self.last != Some(conversion.quantity);
+            (other_quantity, std::cmp::Reverse(self.count(conversion)), index)
+        });
+        ranked.into_iter().take(limit).map(|(_, conversion)| conversion).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn find(id: &str) -> &'static Conversion {
+        CONVERSIONS.iter().find(|conversion| conversion.id() == id).unwrap()
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(find("m→ft").apply(1.0), 3.280839895);
+        assert_eq!(find("in→cm").apply(12.0), 30.48);
+        assert_eq!(find("°C→°F").apply(100.0), 212.0);
+        assert_eq!(find("°F→°C").apply(-40.0), -40.0);
+        assert_eq!(find("lb→kg").apply(1.0), 0.45359237);
+        assert_eq!(find("mph→km/h").apply(60.0), 96.56064);
+    }
+
+    #[test]
+    fn test_ranking_learns() {
+        let mut usage = ConversionUsage::default();
+        let ids = |usage: &ConversionUsage, limit| -> Vec<String> {
+            usage.top(limit).iter().map(|conversion| conversion.id()).collect()
+        };
+        assert_eq!(ids(&usage, 3), ["m→ft", "ft→m", "in→cm"]);
+
+        usage.record(find("lb→kg"));
+        usage.record(find("lb→kg"));
+        usage.record(find("oz→g"));
+        assert_eq!(ids(&usage, 3), ["lb→kg", "oz→g", "kg→lb"]);
+
+        // A length converted last brings lengths forward, most used first
+        usage.record(find("km→mi"));
+        assert_eq!(ids(&usage, 7)[..2], ["km→mi", "m→ft"]);
+        assert_eq!(ids(&usage, 7)[6], "lb→kg");
+    }
+
+    // Feature: gui-calculator, Property 53: A conversion and its reverse bring a value back
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_round_trip(index in 0..CONVERSIONS.len() / 2, value in -1e6..1e6f64) {
+            let (there, back) = (&CONVERSIONS[2 * index], &CONVERSIONS[2 * index + 1]);
+            prop_assert_eq!((there.from, there.to), (back.to, back.from));
+            let returned = back.apply(there.apply(value));
+            prop_assert!((returned - value).abs() <= 1e-8 * value.abs().max(1.0), "{} came back as {}", value, returned);
+        }
+    }
+}
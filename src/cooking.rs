@@ -0,0 +1,229 @@
+// Cooking Measures
+use serde::{Deserialize, Serialize};
+
+use crate::error::CalculatorError;
+use crate::ieee754;
+
+// US customary measures in millilitres
+const CUP: f64 = 236.588_236_5;
+const TABLESPOON: f64 = CUP / 16.0;
+const TEASPOON: f64 = TABLESPOON / 3.0;
+const FLUID_OUNCE: f64 = CUP / 8.0;
+const OUNCE: f64 = 28.349_523_125;
+const POUND: f64 = OUNCE * 16.0;
+const SIGNIFICANT_DIGITS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Measure {
+    #[default]
+    Cup,
+    Tablespoon,
+    Teaspoon,
+    FluidOunce,
+    Milliliter,
+    Liter,
+    Gram,
+    Kilogram,
+    Ounce,
+    Pound,
+}
+
+impl Measure {
+    pub const ALL: [Measure; 10] = [
+        Measure::Cup,
+        Measure::Tablespoon,
+        Measure::Teaspoon,
+        Measure::FluidOunce,
+        Measure::Milliliter,
+        Measure::Liter,
+        Measure::Gram,
+        Measure::Kilogram,
+        Measure::Ounce,
+        Measure::Pound,
+    ];
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Measure::Cup => "cup",
+            Measure::Tablespoon => "tbsp",
+            Measure::Teaspoon => "tsp",
+            Measure::FluidOunce => "fl oz",
+            Measure::Milliliter => "ml",
+            Measure::Liter => "l",
+            Measure::Gram => "g",
+            Measure::Kilogram => "kg",
+            Measure::Ounce => "oz",
+            Measure::Pound => "lb",
+        }
+    }
+
+    pub fn is_volume(self) -> bool {
+        matches!(
+            self,
+            Measure::Cup
+                | Measure::Tablespoon
+                | Measure::Teaspoon
+                | Measure::FluidOunce
+                | Measure::Milliliter
+                | Measure::Liter
+        )
+    }
+
+    // Millilitres for volumes, grams for weights
+    fn size(self) -> f64 {
+        match self {
+            Measure::Cup => CUP,
+            Measure::Tablespoon => TABLESPOON,
+            Measure::Teaspoon => TEASPOON,
+            Measure::FluidOunce => FLUID_OUNCE,
+            Measure::Milliliter | Measure::Gram => 1.0,
+            Measure::Liter | Measure::Kilogram => 1000.0,
+            Measure::Ounce => OUNCE,
+            Measure::Pound => POUND,
+        }
+    }
+}
+
+/// An ingredient the user added, with its weight per volume.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ingredient {
+    pub name: String,
+    /// Grams per millilitre
+    pub density: f64,
+}
+
+/// Typical densities in grams per millilitre, for ingredients spooned and
+/// levelled rather than packed unless the name says so.
+pub const INGREDIENTS: &[(&str, f64)] = &[
+    ("Water", 1.0),
+    ("Milk", 1.03),
+    ("Cream", 1.01),
+    ("Vegetable oil", 0.92),
+    ("Honey", 1.42),
+    ("Butter", 0.96),
+    ("All-purpose flour", 0.53),
+    ("Whole wheat flour", 0.51),
+    ("Granulated sugar", 0.85),
+    ("Brown sugar, packed", 0.93),
+    ("Powdered sugar", 0.51),
+    ("Cocoa powder", 0.36),
+    ("Table salt", 1.22),
+    ("Baking soda", 0.93),
+    ("Rolled oats", 0.38),
+    ("White rice, uncooked", 0.78),
+    ("Grated parmesan", 0.42),
+];
+
+/// Built-in and user ingredients whose name contains `query`, ignoring
+/// case, as `(name, density)`. A user entry replaces a built-in one of the
+/// same name.
+pub fn search<'a>(query: &str, custom: &'a [Ingredient]) -> Vec<(&'a str, f64)> {
+    let query = query.trim().to_lowercase();
+    let builtin = INGREDIENTS
+        .iter()
+        .filter(|(name, _)| !custom.iter().any(|ingredient| ingredient.name.eq_ignore_ascii_case(name)))
+        .copied();
+    builtin
+        .chain(custom.iter().map(|ingredient| (ingredient.name.as_str(), ingredient.density)))
+        .filter(|(name, _)| name.to_lowercase().contains(&query))
+        .collect()
+}
+
+/// Adds or updates a user ingredient from its weight per US cup, which is
+/// what recipes and packaging usually give.
+pub fn add(custom: &mut Vec<Ingredient>, name: &str, grams_per_cup: f64) -> Result<(), CalculatorError> {
+    let name = name.trim();
+    if name.is_empty() || !(grams_per_cup > 0.0 && grams_per_cup.is_finite()) {
+        return Err(CalculatorError::Domain(String::from("ingredient")));
+    }
+    let density = grams_per_cup / CUP;
+    match custom.iter_mut().find(|ingredient| ingredient.name.eq_ignore_ascii_case(name)) {
+        Some(existing) => existing.density = density,
+        None => custom.push(Ingredient {
+            name: name.to_string(),
+            density,
+        }),
+    }
+    Ok(())
+}
+
+/// `amount` of `from` in `to`. Going between volume and weight needs the
+/// ingredient's `density` in grams per millilitre.
+pub fn convert(amount: f64, from: Measure, to: Measure, density: Option<f64>) -> Result<f64, CalculatorError> {
+    let base = amount * from.size();
+    let base = match (from.is_volume(), to.is_volume()) {
+        (true, true) | (false, false) => base,
+        (volume, _) => {
+            let density = density
+                .filter(|&density| density > 0.0)
+                .ok_or_else(|| CalculatorError::Domain(String::from("density")))?;
+            if volume {
+                base * density
+            } else {
+                base / density
+            }
+        }
+    };
+    Ok(ieee754::round_to_significant(base / to.size(), SIGNIFICANT_DIGITS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(convert(1.0, Measure::Cup, Measure::Tablespoon, None), Ok(16.0));
+        assert_eq!(convert(1.0, Measure::Tablespoon, Measure::Teaspoon, None), Ok(3.0));
+        assert_eq!(convert(1.0, Measure::Cup, Measure::Milliliter, None), Ok(236.6));
+        assert_eq!(convert(1.0, Measure::Pound, Measure::Gram, None), Ok(453.6));
+
+        let flour = Some(0.53);
+        assert_eq!(convert(1.0, Measure::Cup, Measure::Gram, flour), Ok(125.4));
+        assert_eq!(convert(250.0, Measure::Gram, Measure::Cup, flour), Ok(1.994));
+        assert_eq!(
+            convert(1.0, Measure::Cup, Measure::Gram, None),
+            Err(CalculatorError::Domain(String::from("density")))
+        );
+    }
+
+    #[test]
+    fn test_custom_ingredients() {
+        let mut custom = Vec::new();
+        add(&mut custom, "Almond flour", 96.0).unwrap();
+        add(&mut custom, "Butter", 227.0).unwrap();
+        assert_eq!(add(&mut custom, " ", 100.0), Err(CalculatorError::Domain(String::from("ingredient"))));
+        assert_eq!(add(&mut custom, "Air", 0.0), Err(CalculatorError::Domain(String::from("ingredient"))));
+
+        let flours: Vec<&str> = search("FLOUR", &custom).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(flours, ["All-purpose flour", "Whole wheat flour", "Almond flour"]);
+        // The user's butter replaces the built-in one
+        let butter = search("butter", &custom);
+        assert_eq!(butter.len(), 1);
+        assert_eq!(convert(1.0, Measure::Cup, Measure::Gram, Some(butter[0].1)), Ok(227.0));
+
+        add(&mut custom, "almond flour", 112.0).unwrap();
+        assert_eq!(custom.len(), 2);
+    }
+
+    // Feature: gui-calculator, Property 39: Volume to weight and back gives the amount back
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_volume_weight_round_trip(
+            amount in 0.1f64..100.0,
+            ingredient in 0..INGREDIENTS.len(),
+            from in 0..Measure::ALL.len(),
+            to in 0..Measure::ALL.len()
+        ) {
+            let density = Some(INGREDIENTS[ingredient].1);
+            let (from, to) = (Measure::ALL[from], Measure::ALL[to]);
+            let there = convert(amount, from, to, density).unwrap();
+            let back = convert(there, to, from, density).unwrap();
+            // Each step keeps four significant digits
+            prop_assert!((back - amount).abs() <= amount * 2e-3, "{} {:?} → {} {:?} → {}", amount, from, there, to, back);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+// Data Sizes
+use crate::error::CalculatorError;
+use crate::format;
+use crate::ieee754;
+
+const SIGNIFICANT_DIGITS: usize = 12;
+
+/// A unit of data: a number of bits or bytes scaled by a power of 1000
+/// (kB, MB, …) or of 1024 (KiB, MiB, …).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataUnit {
+    pub symbol: &'static str,
+    /// 0 for plain bits or bytes, 1 for kilo/kibi, 2 for mega/mebi, …
+    pub power: i32,
+    pub binary: bool,
+    pub bits: bool,
+}
+
+const fn unit(symbol: &'static str, power: i32, binary: bool, bits: bool) -> DataUnit {
+    DataUnit {
+        symbol,
+        power,
+        binary,
+        bits,
+    }
+}
+
+/// Every unit, smallest first within each power.
+pub const UNITS: &[DataUnit] = &[
+    unit("bit", 0, false, true),
+    unit("B", 0, false, false),
+    unit("kbit", 1, false, true),
+    unit("Kibit", 1, true, true),
+    unit("kB", 1, false, false),
+    unit("KiB", 1, true, false),
+    unit("Mbit", 2, false, true),
+    unit("Mibit", 2, true, true),
+    unit("MB", 2, false, false),
+    unit("MiB", 2, true, false),
+    unit("Gbit", 3, false, true),
+    unit("Gibit", 3, true, true),
+    unit("GB", 3, false, false),
+    unit("GiB", 3, true, false),
+    unit("Tbit", 4, false, true),
+    unit("Tibit", 4, true, true),
+    unit("TB", 4, false, false),
+    unit("TiB", 4, true, false),
+    unit("Pbit", 5, false, true),
+    unit("Pibit", 5, true, true),
+    unit("PB", 5, false, false),
+    unit("PiB", 5, true, false),
+];
+
+impl DataUnit {
+    pub fn find(symbol: &str) -> Option<DataUnit> {
+        UNITS.iter().copied().find(|unit| unit.symbol == symbol)
+    }
+
+    /// How many bits one of this unit holds.
+    pub fn in_bits(self) -> f64 {
+        let base: f64 = if self.binary { 1024.0 } else { 1000.0 };
+        let size = base.powi(self.power);
+        if self.bits {
+            size
+        } else {
+            size * 8.0
+        }
+    }
+
+    /// The same prefix in the other system, e.g. GiB for GB, which is what
+    /// the unit tends to be mistaken for.
+    pub fn counterpart(self) -> Option<DataUnit> {
+        if self.power == 0 {
+            return None;
+        }
+        UNITS
+            .iter()
+            .copied()
+            .find(|unit| unit.power == self.power && unit.bits == self.bits && unit.binary != self.binary)
+    }
+}
+
+/// `value` of `from` expressed in `to`.
+pub fn convert(value: f64, from: DataUnit, to: DataUnit) -> f64 {
+    ieee754::round_to_significant(value * from.in_bits() / to.in_bits(), SIGNIFICANT_DIGITS)
+}
+
+/// Reads an amount like `500 GB`, `1.5GiB` or `100 Mbit`. Case matters, as
+/// `Mb` (megabit) is an eighth of `MB` (megabyte); the few spellings that
+/// can't be confused, like `K` for `k` or `bits` for `bit`, are also taken.
+pub fn parse(text: &str) -> Result<(f64, DataUnit), CalculatorError> {
+    let syntax = |message: String, column: usize| CalculatorError::Syntax { message, column };
+    let text = text.trim();
+    let split = text
+        .find(|c: char| c.is_alphabetic())
+        .ok_or_else(|| syntax(String::from("Expected a unit like MB"), text.chars().count() + 1))?;
+    let (number, symbol) = text.split_at(split);
+    let value: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| syntax(String::from("Expected a number"), 1))?;
+    let unit = match symbol.trim() {
+        "b" | "bits" => DataUnit::find("bit"),
+        "byte" | "bytes" => DataUnit::find("B"),
+        "KB" => DataUnit::find("kB"),
+        "Kbit" | "Kb" => DataUnit::find("kbit"),
+        // Mb, Gib, … are bits
+        symbol => DataUnit::find(symbol).or_else(|| {
+            symbol
+                .strip_suffix('b')
+                .and_then(|prefix| DataUnit::find(&format!("{}bit", prefix)))
+        }),
+    }
+    .ok_or_else(|| syntax(format!("Unknown unit '{}'", symbol.trim()), number.chars().count() + 1))?;
+    Ok((value, unit))
+}
+
+/// An amount for the converter's table: up to 12 significant digits, in
+/// engineering notation once it's too small or too large to read plainly.
+pub fn format_amount(value: f64) -> String {
+    if value != 0.0 && !(1e-6..1e15).contains(&value.abs()) {
+        format::format_engineering(value, "")
+    } else {
+        ieee754::round_to_significant(value, SIGNIFICANT_DIGITS).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn unit(symbol: &str) -> DataUnit {
+        DataUnit::find(symbol).unwrap()
+    }
+
+    #[test]
+    fn test_si_and_binary_differ() {
+        assert_eq!(convert(1.0, unit("KiB"), unit("B")), 1024.0);
+        assert_eq!(convert(1.0, unit("kB"), unit("B")), 1000.0);
+        // A "500 GB" drive as an operating system counting in GiB shows it
+        assert_eq!(convert(500.0, unit("GB"), unit("GiB")), 465.661287308);
+        assert_eq!(convert(100.0, unit("Mbit"), unit("MB")), 12.5);
+        assert_eq!(convert(1.0, unit("MiB"), unit("Mibit")), 8.0);
+
+        assert_eq!(unit("GB").counterpart(), Some(unit("GiB")));
+        assert_eq!(unit("Mibit").counterpart(), Some(unit("Mbit")));
+        assert_eq!(unit("B").counterpart(), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("500 GB"), Ok((500.0, unit("GB"))));
+        assert_eq!(parse("1.5GiB"), Ok((1.5, unit("GiB"))));
+        assert_eq!(parse("100 Mb"), Ok((100.0, unit("Mbit"))));
+        assert_eq!(parse("64 KB"), Ok((64.0, unit("kB"))));
+        assert_eq!(parse("8 bits"), Ok((8.0, unit("bit"))));
+        let syntax = |message: &str, column| Err(CalculatorError::Syntax { message: message.to_string(), column });
+        assert_eq!(parse("3 XB"), syntax("Unknown unit 'XB'", 3));
+        assert_eq!(parse("512"), syntax("Expected a unit like MB", 4));
+        assert_eq!(parse("lots of MB"), syntax("Expected a number", 1));
+
+        assert_eq!(format_amount(465.661287308), "465.661287308");
+        assert_eq!(format_amount(8e15), "8e15");
+    }
+
+    // Feature: gui-calculator, Property 37: Converting a data size there and back is lossless
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_data_size_round_trip(
+            value in 1u32..1_000_000,
+            from in 0..UNITS.len(),
+            to in 0..UNITS.len()
+        ) {
+            let (from, to) = (UNITS[from], UNITS[to]);
+            let there = convert(f64::from(value), from, to);
+            let back = convert(there, to, from);
+            prop_assert!((back - f64::from(value)).abs() <= f64::from(value) * 1e-10,
+                "{} {} → {} {} → {}", value, from.symbol, there, to.symbol, back);
+        }
+    }
+}
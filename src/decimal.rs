@@ -0,0 +1,95 @@
+// Exact Decimal Arithmetic
+use std::str::FromStr;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::expression::{BinaryOp, Environment, Expr};
+
+/// The decimal the user sees for `value`; Rust prints the shortest string
+/// that round-trips, so 0.1 becomes exactly 0.1 rather than its binary value.
+pub fn to_decimal(value: f64) -> Option<Decimal> {
+    Decimal::from_str(&value.to_string()).ok()
+}
+
+/// The closest f64, correctly rounded.
+pub fn to_float(value: Decimal) -> Option<f64> {
+    value.normalize().to_string().parse().ok()
+}
+
+/// `value` rounded to `places` decimals, half away from zero as on paper:
+/// 2.675 is 2.68 even though its f64 is a little below it. Values beyond
+/// what a decimal holds have no decimals and stay as they are.
+pub fn round(value: f64, places: u32) -> f64 {
+    to_decimal(value)
+        .map(|exact| exact.round_dp_with_strategy(places, RoundingStrategy::MidpointAwayFromZero))
+        .and_then(to_float)
+        .unwrap_or(value)
+}
+
+/// `left op right` in decimal; `None` for powers or when the result
+/// doesn't fit.
+pub fn apply(op: BinaryOp, left: Decimal, right: Decimal) -> Option<Decimal> {
+    match op.scalar() {
+        BinaryOp::Add => left.checked_add(right),
+        BinaryOp::Subtract => left.checked_sub(right),
+        BinaryOp::Multiply => left.checked_mul(right),
+        BinaryOp::Divide => left.checked_div(right),
+        _ => None,
+    }
+}
+
+/// Evaluates `expr` in decimal. `None` for anything without an exact
+/// decimal counterpart, such as function calls and powers.
+pub fn eval(expr: &Expr, env: &Environment) -> Option<Decimal> {
+    match expr {
+        Expr::Number(value) => to_decimal(*value),
+        Expr::Variable(name) => to_decimal(env.lookup(name).ok()?),
+        Expr::Negate(operand) => Some(-eval(operand, env)?),
+        Expr::Binary { op, left, right } => apply(*op, eval(left, env)?, eval(right, env)?),
+        Expr::Call { .. } | Expr::Matrix(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::parse;
+    use proptest::prelude::*;
+
+    // `left op right` in decimal, as the closest f64
+    fn apply_floats(op: BinaryOp, left: f64, right: f64) -> Option<f64> {
+        to_float(apply(op, to_decimal(left)?, to_decimal(right)?)?)
+    }
+
+    fn eval_float(expr: &Expr, env: &Environment) -> Option<f64> {
+        to_float(eval(expr, env)?)
+    }
+
+    #[test]
+    fn test_decimal_results() {
+        assert_eq!(apply_floats(BinaryOp::Add, 0.1, 0.2), Some(0.3));
+        assert_ne!(0.1 + 0.2, 0.3);
+        assert_eq!(apply_floats(BinaryOp::Multiply, 1.1, 1.1), Some(1.21));
+        assert_eq!(apply_floats(BinaryOp::Divide, 1.0, 3.0), Some(1.0 / 3.0));
+        assert_eq!(apply_floats(BinaryOp::Divide, 1.0, 0.0), None);
+
+        let env = Environment::new();
+        assert_eq!(eval_float(&parse("-(0.1 + 0.2) * 3").unwrap(), &env), Some(-0.9));
+        assert_eq!(eval_float(&parse("sqrt(4)").unwrap(), &env), None);
+        assert_eq!(eval_float(&parse("2 ^ 3").unwrap(), &env), None);
+    }
+
+    // Feature: gui-calculator, Property 25: Decimal sums of cents are exact
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_cents_add_exactly(a in -1000000i64..1000000, b in -1000000i64..1000000) {
+            let left = a as f64 / 100.0;
+            let right = b as f64 / 100.0;
+            let exact = (a + b) as f64 / 100.0;
+
+            prop_assert_eq!(apply_floats(BinaryOp::Add, left, right), Some(exact));
+        }
+    }
+}
@@ -0,0 +1,49 @@
+// Build and Runtime Diagnostics
+use crate::settings::Settings;
+
+/// The crate version, taken from Cargo.toml at build time.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The native renderer eframe is built with.
+pub const BACKEND: &str = "eframe (glow)";
+
+/// How + - * / are computed with the given settings.
+pub fn precision_mode(settings: &Settings) -> &'static str {
+    if settings.decimal_mode {
+        "decimal"
+    } else {
+        "binary floating point (f64)"
+    }
+}
+
+/// A plain-text summary to paste into a bug report.
+pub fn report(settings: &Settings) -> String {
+    let lines = [
+        format!("{} {}", env!("CARGO_PKG_NAME"), VERSION),
+        format!("OS: {} ({})", std::env::consts::OS, std::env::consts::ARCH),
+        format!("Backend: {}", BACKEND),
+        format!("Arithmetic: {}", precision_mode(settings)),
+        format!("Number format: {:?}", settings.number_format),
+        format!("Implicit multiplication: {}", settings.implicit_multiplication),
+        format!("Integer overflow: {:?}", settings.integer_overflow),
+        format!("Scripting: {}", cfg!(feature = "scripting")),
+    ];
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let settings = Settings {
+            decimal_mode: true,
+            ..Settings::default()
+        };
+        let report = report(&settings);
+        assert!(report.starts_with(&format!("helloProject {}\n", VERSION)));
+        assert!(report.contains("Arithmetic: decimal\n"));
+        assert!(report.contains(&format!("OS: {}", std::env::consts::OS)));
+    }
+}
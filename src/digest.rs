@@ -0,0 +1,36 @@
+// Checksums and Hashes
+//
+// Built with the `digest` feature.
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+/// CRC32, MD5 and SHA-256 of `text`'s UTF-8 bytes, named and in lowercase hex.
+pub fn digests(text: &str) -> [(&'static str, String); 3] {
+    let bytes = text.as_bytes();
+    [
+        ("CRC32", format!("{:08x}", crc32fast::hash(bytes))),
+        ("MD5", hex(&Md5::digest(bytes))),
+        ("SHA-256", hex(&Sha256::digest(bytes))),
+    ]
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_digests() {
+        let [crc32, md5, sha256] = digests("abc");
+        assert_eq!(crc32, ("CRC32", String::from("352441c2")));
+        assert_eq!(md5, ("MD5", String::from("900150983cd24fb0d6963f7d28e17f72")));
+        assert_eq!(
+            sha256,
+            ("SHA-256", String::from("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"))
+        );
+        assert_eq!(digests("")[0].1, "00000000");
+    }
+}
@@ -0,0 +1,406 @@
+// Probability Distributions
+//
+// Density, cumulative probability and quantiles for the distributions met in
+// an introductory statistics course. The special functions underneath (log
+// gamma, regularized incomplete gamma and beta) are accurate to about 1e-14.
+use crate::error::CalculatorError;
+
+// Stopping point for the series and continued fractions
+const EPSILON: f64 = 1e-16;
+// Stands in for zero in the continued fractions' denominators
+const TINY: f64 = 1e-300;
+const MAX_ITERATIONS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    Normal { mean: f64, std_dev: f64 },
+    Binomial { trials: u64, p: f64 },
+    Poisson { rate: f64 },
+    StudentT { df: f64 },
+}
+
+impl Distribution {
+    /// One of each, with textbook parameters, in the order offered.
+    pub const DEFAULTS: [Distribution; 4] = [
+        Distribution::Normal { mean: 0.0, std_dev: 1.0 },
+        Distribution::Binomial { trials: 10, p: 0.5 },
+        Distribution::Poisson { rate: 3.0 },
+        Distribution::StudentT { df: 10.0 },
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Distribution::Normal { .. } => "Normal",
+            Distribution::Binomial { .. } => "Binomial",
+            Distribution::Poisson { .. } => "Poisson",
+            Distribution::StudentT { .. } => "Student's t",
+        }
+    }
+
+    /// Whether it only takes whole values, so `density` is a probability.
+    pub fn is_discrete(&self) -> bool {
+        matches!(self, Distribution::Binomial { .. } | Distribution::Poisson { .. })
+    }
+
+    /// Checks the parameters, naming the distribution if one is out of range.
+    pub fn validate(&self) -> Result<(), CalculatorError> {
+        let valid = match *self {
+            Distribution::Normal { mean, std_dev } => mean.is_finite() && std_dev > 0.0 && std_dev.is_finite(),
+            Distribution::Binomial { p, .. } => (0.0..=1.0).contains(&p),
+            Distribution::Poisson { rate } => rate > 0.0 && rate.is_finite(),
+            Distribution::StudentT { df } => df > 0.0 && df.is_finite(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(CalculatorError::Domain(self.name().to_ascii_lowercase()))
+        }
+    }
+
+    /// The density at `x`, or for a discrete distribution P(X = x).
+    pub fn density(&self, x: f64) -> f64 {
+        match *self {
+            Distribution::Normal { mean, std_dev } => {
+                let z = (x - mean) / std_dev;
+                (-0.5 * z * z).exp() / (std_dev * (2.0 * std::f64::consts::PI).sqrt())
+            }
+            Distribution::Binomial { trials, p } => {
+                let n = trials as f64;
+                if x.fract() != 0.0 || x < 0.0 || x > n {
+                    return 0.0;
+                }
+                // Powers of zero are 1 here, which the logarithms can't express
+                match p {
+                    _ if p == 0.0 => f64::from(u8::from(x == 0.0)),
+                    _ if p == 1.0 => f64::from(u8::from(x == n)),
+                    _ => (ln_gamma(n + 1.0) - ln_gamma(x + 1.0) - ln_gamma(n - x + 1.0)
+                        + x * p.ln()
+                        + (n - x) * (1.0 - p).ln())
+                    .exp(),
+                }
+            }
+            Distribution::Poisson { rate } => {
+                if x.fract() != 0.0 || x < 0.0 {
+                    return 0.0;
+                }
+                (x * rate.ln() - rate - ln_gamma(x + 1.0)).exp()
+            }
+            Distribution::StudentT { df } => {
+                let scale = (ln_gamma((df + 1.0) / 2.0) - ln_gamma(df / 2.0)).exp() / (df * std::f64::consts::PI).sqrt();
+                scale * (1.0 + x * x / df).powf(-(df + 1.0) / 2.0)
+            }
+        }
+    }
+
+    /// P(X ≤ x).
+    pub fn cdf(&self, x: f64) -> f64 {
+        match *self {
+            Distribution::Normal { mean, std_dev } => {
+                let z = (x - mean) / std_dev;
+                // The tail beyond |z|, which is erfc(|z| / √2) / 2
+                let tail = 0.5 * upper_gamma(0.5, 0.5 * z * z);
+                if z < 0.0 {
+                    tail
+                } else {
+                    1.0 - tail
+                }
+            }
+            Distribution::Binomial { trials, p } => {
+                let k = x.floor();
+                if k < 0.0 {
+                    0.0
+                } else if k >= trials as f64 {
+                    1.0
+                } else {
+                    incomplete_beta(trials as f64 - k, k + 1.0, 1.0 - p)
+                }
+            }
+            Distribution::Poisson { rate } => {
+                let k = x.floor();
+                if k < 0.0 {
+                    0.0
+                } else {
+                    upper_gamma(k + 1.0, rate)
+                }
+            }
+            Distribution::StudentT { df } => {
+                let tail = 0.5 * incomplete_beta(df / 2.0, 0.5, df / (df + x * x));
+                if x > 0.0 {
+                    1.0 - tail
+                } else {
+                    tail
+                }
+            }
+        }
+    }
+
+    /// The `x` with P(X ≤ x) = `p`; for a discrete distribution, the
+    /// smallest whole `x` with P(X ≤ x) ≥ `p`. `p` must be strictly between
+    /// 0 and 1.
+    pub fn quantile(&self, p: f64) -> Result<f64, CalculatorError> {
+        if !(p > 0.0 && p < 1.0) {
+            return Err(CalculatorError::Domain(String::from("quantile")));
+        }
+        let quantile = match *self {
+            Distribution::Normal { mean, std_dev } => self.continuous_quantile(p, mean, std_dev),
+            Distribution::StudentT { .. } => self.continuous_quantile(p, 0.0, 1.0),
+            Distribution::Binomial { trials, .. } => self.discrete_quantile(p, trials as f64),
+            Distribution::Poisson { rate } => {
+                let mut high = rate.ceil() + 1.0;
+                while self.cdf(high) < p {
+                    high *= 2.0;
+                }
+                self.discrete_quantile(p, high)
+            }
+        };
+        Ok(quantile)
+    }
+
+    // Widens a bracket around `center` until it holds the quantile, then halves it
+    fn continuous_quantile(&self, p: f64, center: f64, scale: f64) -> f64 {
+        let (mut low, mut high) = (center - scale, center + scale);
+        while self.cdf(low) > p {
+            low = center - 2.0 * (center - low);
+        }
+        while self.cdf(high) < p {
+            high = center + 2.0 * (high - center);
+        }
+        for _ in 0..MAX_ITERATIONS {
+            let middle = 0.5 * (low + high);
+            if middle <= low || middle >= high {
+                break;
+            }
+            if self.cdf(middle) < p {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        0.5 * (low + high)
+    }
+
+    // The smallest whole number up to `high` whose P(X ≤ k) reaches `p`
+    fn discrete_quantile(&self, p: f64, high: f64) -> f64 {
+        let (mut low, mut high) = (0.0, high);
+        while low < high {
+            let middle = ((low + high) / 2.0).floor();
+            if self.cdf(middle) < p {
+                low = middle + 1.0;
+            } else {
+                high = middle;
+            }
+        }
+        low
+    }
+
+    /// Where nearly all the probability lies, for plotting.
+    pub fn plot_range(&self) -> (f64, f64) {
+        match *self {
+            Distribution::Normal { mean, std_dev } => (mean - 4.0 * std_dev, mean + 4.0 * std_dev),
+            Distribution::StudentT { .. } => {
+                let high = self.quantile(0.999).unwrap_or(1.0);
+                (-high, high)
+            }
+            Distribution::Binomial { .. } | Distribution::Poisson { .. } => {
+                (self.quantile(0.001).unwrap_or(0.0), self.quantile(0.999).unwrap_or(0.0))
+            }
+        }
+    }
+}
+
+/// ln Γ(x) for x > 0, by the Lanczos approximation.
+pub fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (index, c)| sum + c / (x + index as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// The regularized upper incomplete gamma function Q(a, x) = Γ(a, x) / Γ(a).
+pub fn upper_gamma(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let front = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        // The series for the lower function converges quickly here
+        let (mut term, mut sum, mut denominator) = (1.0 / a, 1.0 / a, a);
+        for _ in 0..MAX_ITERATIONS {
+            denominator += 1.0;
+            term *= x / denominator;
+            sum += term;
+            if term.abs() < sum.abs() * EPSILON {
+                break;
+            }
+        }
+        1.0 - front * sum
+    } else {
+        // Lentz's method for the continued fraction
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..=MAX_ITERATIONS {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = nonzero(an * d + b).recip();
+            c = nonzero(b + an / c);
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < EPSILON {
+                break;
+            }
+        }
+        front * h
+    }
+}
+
+/// The regularized incomplete beta function I_x(a, b).
+pub fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges fastest on this side of the mean
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_fraction(a: f64, b: f64, x: f64) -> f64 {
+    let mut c = 1.0;
+    let mut d = nonzero(1.0 - (a + b) * x / (a + 1.0)).recip();
+    let mut h = d;
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = nonzero(1.0 + even * d).recip();
+        c = nonzero(1.0 + even / c);
+        h *= d * c;
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = nonzero(1.0 + odd * d).recip();
+        c = nonzero(1.0 + odd / c);
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    h
+}
+
+fn nonzero(value: f64) -> f64 {
+    if value.abs() < TINY {
+        TINY
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= 1e-12 * expected.abs().max(1.0),
+            "{} is not close to {}",
+            actual,
+            expected
+        );
+    }
+
+    const STANDARD_NORMAL: Distribution = Distribution::DEFAULTS[0];
+
+    #[test]
+    fn test_normal() {
+        assert_close(STANDARD_NORMAL.density(0.0), 0.398_942_280_401_432_7);
+        assert_close(STANDARD_NORMAL.cdf(1.96), 0.975_002_104_851_780);
+        assert_close(STANDARD_NORMAL.cdf(-1.0), 0.158_655_253_931_457_05);
+        assert_close(STANDARD_NORMAL.quantile(0.975).unwrap(), 1.959_963_984_540_054);
+        let scores = Distribution::Normal { mean: 100.0, std_dev: 15.0 };
+        assert_close(scores.cdf(130.0), 0.977_249_868_051_820_8);
+        assert!(STANDARD_NORMAL.quantile(1.0).is_err());
+    }
+
+    #[test]
+    fn test_binomial() {
+        let coin = Distribution::Binomial { trials: 10, p: 0.5 };
+        assert_close(coin.density(5.0), 0.246_093_75);
+        assert_close(coin.cdf(5.0), 0.623_046_875);
+        assert_close(coin.cdf(5.5), 0.623_046_875);
+        assert_eq!((coin.density(2.5), coin.cdf(-1.0), coin.cdf(10.0)), (0.0, 0.0, 1.0));
+        assert_eq!(coin.quantile(0.5), Ok(5.0));
+        let certain = Distribution::Binomial { trials: 4, p: 1.0 };
+        assert_eq!((certain.density(4.0), certain.cdf(3.0)), (1.0, 0.0));
+    }
+
+    #[test]
+    fn test_poisson() {
+        let arrivals = Distribution::Poisson { rate: 3.0 };
+        assert_close(arrivals.density(2.0), 0.224_041_807_655_387_75);
+        assert_close(arrivals.cdf(2.0), 0.423_190_081_126_843_5);
+        assert_eq!(arrivals.quantile(0.95), Ok(6.0));
+        assert_eq!(Distribution::Poisson { rate: 0.0 }.validate(), Err(CalculatorError::Domain(String::from("poisson"))));
+    }
+
+    #[test]
+    fn test_student_t() {
+        let cauchy = Distribution::StudentT { df: 1.0 };
+        assert_close(cauchy.density(0.0), 1.0 / std::f64::consts::PI);
+        assert_close(cauchy.cdf(1.0), 0.75);
+        let t = Distribution::StudentT { df: 10.0 };
+        assert_close(t.quantile(0.975).unwrap(), 2.228_138_851_986_522);
+        assert_close(t.cdf(-2.228_138_851_986_522), 0.025);
+        // Many degrees of freedom approach the normal
+        assert!((Distribution::StudentT { df: 1e6 }.cdf(1.96) - 0.975).abs() < 1e-4);
+    }
+
+    // Feature: gui-calculator, Property 57: The quantile of a cumulative probability is the value it came from
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_quantile_inverts_cdf(
+            z in -5.0..5.0f64,
+            df in 1.0..50.0f64,
+            trials in 1u64..60,
+            p in 0.05..0.95f64,
+            rate in 0.5..40.0f64,
+            k in 0u64..60
+        ) {
+            for distribution in [STANDARD_NORMAL, Distribution::StudentT { df }] {
+                let back = distribution.quantile(distribution.cdf(z)).unwrap();
+                prop_assert!((back - z).abs() < 1e-6, "{:?}: {} came back as {}", distribution, z, back);
+            }
+            for distribution in [Distribution::Binomial { trials, p }, Distribution::Poisson { rate }] {
+                let k = k as f64;
+                let cumulative = distribution.cdf(k);
+                // Past where rounding leaves any probability to tell values apart
+                if cumulative < 1.0 - 1e-9 && distribution.density(k) > 1e-9 {
+                    prop_assert_eq!(distribution.quantile(cumulative), Ok(k), "{:?}", distribution);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+// Degrees, Minutes and Seconds
+use crate::error::CalculatorError;
+
+/// Decimal places kept for the seconds when formatting.
+pub const SECONDS_PRECISION: usize = 2;
+
+/// Formats decimal degrees as `12°34'56.78"`. Rounding happens on the
+/// seconds, carrying into minutes and degrees, so 59.999" never shows as 60".
+pub fn format(degrees: f64, precision: usize) -> String {
+    let scale = 10u64.pow(precision as u32);
+    // Whole units of the last shown decimal of a second
+    let units = (degrees.abs() * 3600.0 * scale as f64).round() as u64;
+    let (seconds, minutes) = (units % (60 * scale), units / (60 * scale));
+    let (minutes, whole_degrees) = (minutes % 60, minutes / 60);
+
+    let sign = if degrees < 0.0 && units > 0 { "-" } else { "" };
+    let seconds = seconds as f64 / scale as f64;
+    format!("{}{}°{}'{:.*}\"", sign, whole_degrees, minutes, precision, seconds)
+}
+
+/// Reads an angle as DMS (`12°34'56.7"`, `12 34 56.7`, `12d 34m 56.7s`) or
+/// as plain decimal degrees. A trailing S or W hemisphere makes it negative.
+pub fn parse(text: &str) -> Result<f64, CalculatorError> {
+    let invalid = |message: &str| CalculatorError::Syntax {
+        message: message.to_string(),
+        column: 1,
+    };
+
+    let mut text = text.trim().to_string();
+    // Uppercase only, since a lowercase s is the seconds unit
+    let hemisphere = text.chars().last().filter(|ch| "NSEW".contains(*ch));
+    if hemisphere.is_some() {
+        text.pop();
+    }
+    let text = text.trim();
+    let (negative, unsigned) = match (text.strip_prefix('-'), hemisphere) {
+        (Some(_), Some(_)) => return Err(invalid("Use either a sign or a hemisphere")),
+        (Some(rest), None) => (true, rest),
+        (None, hemisphere) => (matches!(hemisphere, Some('S' | 'W')), text),
+    };
+
+    let parts: Vec<&str> = unsigned
+        .split(|ch: char| ch.is_whitespace() || "°'\"′″dms".contains(ch))
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.is_empty() || parts.len() > 3 {
+        return Err(invalid("Expected degrees, minutes and seconds"));
+    }
+
+    let values = parts
+        .iter()
+        .map(|part| part.parse::<f64>().map_err(|_| invalid("Invalid angle")))
+        .collect::<Result<Vec<f64>, CalculatorError>>()?;
+    // Only the last part may have a fraction, and minutes/seconds stay below 60
+    if values[..values.len() - 1].iter().any(|value| value.fract() != 0.0)
+        || values.iter().any(|value| *value < 0.0)
+        || values.iter().skip(1).any(|value| *value >= 60.0)
+    {
+        return Err(invalid("Invalid angle"));
+    }
+
+    let degrees = values
+        .iter()
+        .zip([1.0, 60.0, 3600.0])
+        .map(|(value, divisor)| value / divisor)
+        .sum::<f64>();
+    Ok(if negative { -degrees } else { degrees })
+}
+
+/// Adds or subtracts two angles given in any notation `parse` accepts.
+pub fn add(a: &str, b: &str, subtract: bool) -> Result<f64, CalculatorError> {
+    let (a, b) = (parse(a)?, parse(b)?);
+    Ok(if subtract { a - b } else { a + b })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_format_and_parse() {
+        assert_eq!(format(12.5825, 2), "12°34'57.00\"");
+        assert_eq!(format(-0.5, 0), "-0°30'0\"");
+        // Rounds up through the seconds and minutes
+        assert_eq!(format(9.9999999, 1), "10°0'0.0\"");
+
+        assert_eq!(parse("12°34'57\""), Ok(12.5825));
+        assert_eq!(parse("12 34 57"), Ok(12.5825));
+        assert_eq!(parse("12d 34m 57s"), Ok(12.5825));
+        assert_eq!(parse("12.5825"), Ok(12.5825));
+        assert_eq!(parse("12°30' S"), Ok(-12.5));
+        assert!(parse("-12°30'W").is_err());
+        assert!(parse("12°61'").is_err());
+        assert!(parse("12.5°30'").is_err());
+        assert!(parse("").is_err());
+        assert!(parse("1 2 3 4").is_err());
+    }
+
+    #[test]
+    fn test_add_subtract() {
+        assert_eq!(format(add("10°40'50\"", "5°30'20\"", false).unwrap(), 0), "16°11'10\"");
+        assert_eq!(format(add("10°0'0\"", "0°0'1\"", true).unwrap(), 0), "9°59'59\"");
+    }
+
+    // Feature: gui-calculator, Property 29: Formatted DMS parses back to the same angle
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_dms_round_trip(degrees in -360.0..360.0f64) {
+            let text = format(degrees, SECONDS_PRECISION);
+            let parsed = parse(&text).unwrap();
+            // Within the rounding of the last shown digit of the seconds
+            prop_assert!((parsed - degrees).abs() <= 0.005 / 3600.0 + 1e-12,
+                "{} formatted as {} parsed back as {}", degrees, text, parsed);
+        }
+    }
+}
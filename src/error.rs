@@ -0,0 +1,84 @@
+// Error Model
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Everything that can put the calculator into its error state.
+/// The `Display` form is exactly what appears on the calculator display.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CalculatorError {
+    DivisionByZero,
+    /// A whole number too large for its integer type
+    Overflow,
+    /// A floating-point result beyond the largest double, about ±1.8e308
+    TooLarge,
+    /// The expression could not be parsed; `column` is 1-based
+    Syntax { message: String, column: usize },
+    UnknownFunction(String),
+    UnknownVariable(String),
+    WrongArgumentCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    /// A function was called outside its domain, e.g. `sqrt(-1)`
+    Domain(String),
+    /// An equation the solver can't handle, with the reason
+    Unsolvable(String),
+    /// Matrices of the wrong sizes for an operation
+    Dimension(String),
+    /// A grid cell that depends on itself, directly or through others
+    CircularReference(String),
+    /// A user script failed to compile or run
+    #[cfg(feature = "scripting")]
+    Script(String),
+}
+
+impl fmt::Display for CalculatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalculatorError::DivisionByZero => write!(f, "Error: Division by zero"),
+            CalculatorError::Overflow => write!(f, "Error: Overflow"),
+            CalculatorError::TooLarge => write!(f, "Error: Result too large to represent"),
+            CalculatorError::Syntax { message, column } => {
+                write!(f, "Error: {} at column {}", message, column)
+            }
+            CalculatorError::UnknownFunction(name) => write!(f, "Error: Unknown function '{}'", name),
+            CalculatorError::UnknownVariable(name) => write!(f, "Error: Unknown variable '{}'", name),
+            CalculatorError::WrongArgumentCount {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Error: {} takes {} argument(s), got {}",
+                name, expected, found
+            ),
+            CalculatorError::Domain(name) => write!(f, "Error: Invalid argument for {}", name),
+            CalculatorError::Unsolvable(reason) => write!(f, "Error: Cannot solve, {}", reason),
+            CalculatorError::Dimension(message) => write!(f, "Error: {}", message),
+            CalculatorError::CircularReference(cell) => write!(f, "Error: Circular reference in {}", cell),
+            #[cfg(feature = "scripting")]
+            CalculatorError::Script(message) => write!(f, "Error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for CalculatorError {}
+
+/// Something off about a result that is still shown, where an error would
+/// replace it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Warning {
+    /// Closer to zero than about 2.2e-308, where doubles keep fewer
+    /// significant digits the smaller the number gets
+    Subnormal,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::Subnormal => write!(f, "Result too small to represent exactly"),
+        }
+    }
+}
@@ -0,0 +1,1263 @@
+// Expression Parsing and Evaluation
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants;
+use crate::error::CalculatorError;
+use crate::format;
+use crate::matrix;
+use crate::operation::{BinaryOperation, Operation};
+use crate::programmer;
+
+/// Built-in functions with their argument counts and a short description.
+pub const BUILTIN_FUNCTIONS: &[(&str, usize, &str)] = &[
+    ("sqrt", 1, "Square root"),
+    ("abs", 1, "Absolute value"),
+    ("sin", 1, "Sine of an angle in radians"),
+    ("cos", 1, "Cosine of an angle in radians"),
+    ("tan", 1, "Tangent of an angle in radians"),
+    ("asin", 1, "Inverse sine, in radians"),
+    ("acos", 1, "Inverse cosine, in radians"),
+    ("atan", 1, "Inverse tangent, in radians"),
+    ("ln", 1, "Natural logarithm"),
+    ("log", 1, "Base-10 logarithm"),
+    ("exp", 1, "e raised to a power"),
+    ("round", 1, "Nearest integer, halves away from zero"),
+    ("floor", 1, "Largest integer not above"),
+    ("ceil", 1, "Smallest integer not below"),
+    ("min", 2, "Smaller of two values"),
+    ("max", 2, "Larger of two values"),
+];
+
+/// Built-in named constants with a short description.
+pub const BUILTIN_CONSTANTS: &[(&str, f64, &str)] = &[
+    ("pi", std::f64::consts::PI, "Ratio of a circle's circumference to its diameter"),
+    ("e", std::f64::consts::E, "Base of the natural logarithm"),
+    ("π", std::f64::consts::PI, "Same as pi"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+    /// `.*`, `./` and `.^`: element by element on matrices, plain on numbers
+    ElementMultiply,
+    ElementDivide,
+    ElementPower,
+}
+
+impl BinaryOp {
+    // The keypad operation this is, so the two share their semantics
+    fn basic(self) -> Option<Operation> {
+        match self {
+            BinaryOp::Add => Some(Operation::Add),
+            BinaryOp::Subtract => Some(Operation::Subtract),
+            BinaryOp::Multiply | BinaryOp::ElementMultiply => Some(Operation::Multiply),
+            BinaryOp::Divide | BinaryOp::ElementDivide => Some(Operation::Divide),
+            BinaryOp::Power | BinaryOp::ElementPower => None,
+        }
+    }
+
+    /// The operator on numbers this is, `*` for `.*` and so on.
+    pub fn scalar(self) -> BinaryOp {
+        match self {
+            BinaryOp::ElementMultiply => BinaryOp::Multiply,
+            BinaryOp::ElementDivide => BinaryOp::Divide,
+            BinaryOp::ElementPower => BinaryOp::Power,
+            op => op,
+        }
+    }
+
+    /// How tightly the operator binds; higher binds tighter.
+    pub fn precedence(self) -> u8 {
+        self.basic().map_or(4, |op| op.precedence())
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Subtract => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Power => "^",
+            BinaryOp::ElementMultiply => ".*",
+            BinaryOp::ElementDivide => "./",
+            BinaryOp::ElementPower => ".^",
+        }
+    }
+
+    pub fn apply(self, left: f64, right: f64) -> Result<f64, CalculatorError> {
+        let result = match self.basic() {
+            Some(op) => op.apply(left, right)?,
+            // 0 ^ -n is 1 / 0 ^ n: a division by zero, whichever zero, not ±∞
+            None if left == 0.0 && right < 0.0 => return Err(CalculatorError::DivisionByZero),
+            None => left.powf(right),
+        };
+        if result.is_nan() && !left.is_nan() && !right.is_nan() {
+            // Out of finite operands, like (-8) ^ (1/3), the operands are
+            // outside the domain; out of ±∞, an earlier step overflowed
+            return Err(if left.is_finite() && right.is_finite() {
+                CalculatorError::Domain(self.symbol().to_string())
+            } else {
+                CalculatorError::TooLarge
+            });
+        }
+        Ok(result)
+    }
+}
+
+impl From<Operation> for BinaryOp {
+    fn from(op: Operation) -> Self {
+        match op {
+            Operation::Add => BinaryOp::Add,
+            Operation::Subtract => BinaryOp::Subtract,
+            Operation::Multiply => BinaryOp::Multiply,
+            Operation::Divide => BinaryOp::Divide,
+        }
+    }
+}
+
+// Precedence of unary minus: binds tighter than * and /, looser than ^
+const NEGATE_PRECEDENCE: u8 = 3;
+const ATOM_PRECEDENCE: u8 = 5;
+// Parentheses, signs and powers inside one another; each level is a few
+// stack frames in the parser and in evaluation
+const MAX_NESTING: usize = 256;
+// Operators in the whole expression. A chain like 1+1+…+1 is a tree as deep
+// as it is long, which evaluating, printing and dropping all recurse into.
+const MAX_OPERATORS: usize = 1000;
+
+/// Parsed expression tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Expr {
+    Number(f64),
+    Variable(String),
+    Negate(Box<Expr>),
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Call {
+        name: String,
+        args: Vec<Expr>,
+    },
+    /// `[1, 2; 3, 4]`, row by row; the parser makes every row the same length
+    Matrix(Vec<Vec<Expr>>),
+}
+
+impl Expr {
+    pub fn binary(op: BinaryOp, left: Expr, right: Expr) -> Self {
+        Expr::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    /// Names of the variables the expression uses, in order of appearance,
+    /// repeats included.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_variables(&mut names);
+        names
+    }
+
+    fn collect_variables<'e>(&'e self, names: &mut Vec<&'e str>) {
+        match self {
+            Expr::Number(_) => {}
+            Expr::Variable(name) => names.push(name),
+            Expr::Negate(operand) => operand.collect_variables(names),
+            Expr::Binary { left, right, .. } => {
+                left.collect_variables(names);
+                right.collect_variables(names);
+            }
+            Expr::Call { args, .. } => {
+                for arg in args {
+                    arg.collect_variables(names);
+                }
+            }
+            Expr::Matrix(rows) => {
+                for element in rows.iter().flatten() {
+                    element.collect_variables(names);
+                }
+            }
+        }
+    }
+
+    /// Whether the expression has a matrix literal or a matrix function in it.
+    pub fn uses_matrices(&self) -> bool {
+        match self {
+            Expr::Number(_) | Expr::Variable(_) => false,
+            Expr::Negate(operand) => operand.uses_matrices(),
+            Expr::Binary { left, right, .. } => left.uses_matrices() || right.uses_matrices(),
+            Expr::Call { name, args } => matrix::is_matrix_function(name) || args.iter().any(Expr::uses_matrices),
+            Expr::Matrix(_) => true,
+        }
+    }
+
+    /// How tightly the expression's outermost operator binds, comparable
+    /// with `BinaryOp::precedence`; numbers, names and calls bind tightest.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Expr::Number(value) if value.is_sign_negative() => NEGATE_PRECEDENCE,
+            Expr::Negate(_) => NEGATE_PRECEDENCE,
+            Expr::Binary { op, .. } => op.precedence(),
+            _ => ATOM_PRECEDENCE,
+        }
+    }
+
+    /// The value as a number; matrices may appear along the way, as in
+    /// `det([1, 2; 3, 4])`, but the result must be a single number.
+    pub fn eval(&self, env: &Environment) -> Result<f64, CalculatorError> {
+        // Decided once for the whole tree; asking at every node would walk
+        // each subtree again and make long expressions quadratic
+        if self.uses_matrices() {
+            return matrix::eval(self, env)?.number();
+        }
+        self.eval_scalar(env)
+    }
+
+    // `eval` for a tree known to have no matrices in it
+    fn eval_scalar(&self, env: &Environment) -> Result<f64, CalculatorError> {
+        match self {
+            Expr::Number(value) => Ok(*value),
+            Expr::Variable(name) => env.lookup(name),
+            Expr::Negate(operand) => Ok(-operand.eval_scalar(env)?),
+            Expr::Binary { op, left, right } => op.apply(left.eval_scalar(env)?, right.eval_scalar(env)?),
+            Expr::Call { name, args } => {
+                let values = args
+                    .iter()
+                    .map(|arg| arg.eval_scalar(env))
+                    .collect::<Result<Vec<f64>, CalculatorError>>()?;
+                env.call(name, &values)
+            }
+            Expr::Matrix(_) => unreachable!("matrices are evaluated by the matrix module"),
+        }
+    }
+
+    /// Evaluates in whole numbers, as expressions with `0x`, `0b` or `0o`
+    /// literals are: `/` truncates toward zero, and anything fractional or
+    /// any function call is an error.
+    pub fn eval_integer(&self, env: &Environment) -> Result<i128, CalculatorError> {
+        let whole = |value: f64| {
+            if value.fract() == 0.0 && value.abs() < 2f64.powi(64) {
+                Ok(value as i128)
+            } else {
+                Err(CalculatorError::Domain(String::from("integer mode")))
+            }
+        };
+        match self {
+            Expr::Number(value) => whole(*value),
+            Expr::Variable(name) => whole(env.lookup(name)?),
+            Expr::Negate(operand) => operand.eval_integer(env)?.checked_neg().ok_or(CalculatorError::Overflow),
+            Expr::Binary { op, left, right } => {
+                let (left, right) = (left.eval_integer(env)?, right.eval_integer(env)?);
+                let result = match op.scalar() {
+                    BinaryOp::Add => left.checked_add(right),
+                    BinaryOp::Subtract => left.checked_sub(right),
+                    BinaryOp::Multiply => left.checked_mul(right),
+                    BinaryOp::Divide if right == 0 => return Err(CalculatorError::DivisionByZero),
+                    BinaryOp::Divide => left.checked_div(right),
+                    BinaryOp::Power => {
+                        let exponent = u32::try_from(right)
+                            .map_err(|_| CalculatorError::Domain(String::from("integer power")))?;
+                        left.checked_pow(exponent)
+                    }
+                    _ => unreachable!("scalar operators only"),
+                };
+                result.ok_or(CalculatorError::Overflow)
+            }
+            Expr::Call { name, .. } => Err(CalculatorError::Domain(format!("{} in integer mode", name))),
+            Expr::Matrix(_) => Err(CalculatorError::Domain(String::from("matrices in integer mode"))),
+        }
+    }
+
+    fn fmt_child(&self, f: &mut fmt::Formatter<'_>, parenthesize: bool) -> fmt::Result {
+        if parenthesize {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+/// Prints the expression back as parseable text with only the parentheses
+/// needed to keep the same tree.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(value) => write!(f, "{}", value),
+            Expr::Variable(name) => write!(f, "{}", name),
+            Expr::Negate(operand) => {
+                write!(f, "-")?;
+                operand.fmt_child(f, operand.precedence() < BinaryOp::Power.precedence())
+            }
+            Expr::Binary { op, left, right } => {
+                let precedence = op.precedence();
+                // ^ is right-associative, everything else is left-associative
+                let (left_parens, right_parens) = if matches!(op, BinaryOp::Power | BinaryOp::ElementPower) {
+                    (left.precedence() <= precedence, right.precedence() < NEGATE_PRECEDENCE)
+                } else {
+                    (left.precedence() < precedence, right.precedence() <= precedence)
+                };
+                left.fmt_child(f, left_parens)?;
+                write!(f, " {} ", op.symbol())?;
+                right.fmt_child(f, right_parens)
+            }
+            Expr::Call { name, args } => {
+                write!(f, "{}(", name)?;
+                for (index, arg) in args.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Matrix(rows) => {
+                write!(f, "[")?;
+                for (index, row) in rows.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, "; ")?;
+                    }
+                    for (index, element) in row.iter().enumerate() {
+                        if index > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", element)?;
+                    }
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// Functions supplied from outside the expression engine (e.g. user scripts).
+pub trait Functions {
+    /// Returns `None` when no function called `name` takes `args.len()` arguments.
+    fn call(&self, name: &str, args: &[f64]) -> Option<Result<f64, CalculatorError>>;
+    /// Names and argument counts of the functions on offer, e.g. for autocomplete.
+    fn signatures(&self) -> Vec<(String, usize)>;
+}
+
+/// Names and functions visible while evaluating an expression.
+#[derive(Default)]
+pub struct Environment<'a> {
+    pub variables: HashMap<String, f64>,
+    pub functions: Option<&'a dyn Functions>,
+}
+
+impl<'a> Environment<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lookup(&self, name: &str) -> Result<f64, CalculatorError> {
+        let lower = name.to_ascii_lowercase();
+        if let Some(&value) = self.variables.get(name).or_else(|| self.variables.get(&lower)) {
+            return Ok(value);
+        }
+        BUILTIN_CONSTANTS
+            .iter()
+            .find(|(constant, _, _)| *constant == lower)
+            .map(|&(_, value, _)| value)
+            .or_else(|| constants::find(name).map(|constant| constant.value))
+            .ok_or_else(|| CalculatorError::UnknownVariable(name.to_string()))
+    }
+
+    pub fn call(&self, name: &str, args: &[f64]) -> Result<f64, CalculatorError> {
+        let lower = name.to_ascii_lowercase();
+        if let Some(&(builtin, arity, _)) = BUILTIN_FUNCTIONS.iter().find(|(builtin, _, _)| *builtin == lower) {
+            if args.len() != arity {
+                return Err(CalculatorError::WrongArgumentCount {
+                    name: builtin.to_string(),
+                    expected: arity,
+                    found: args.len(),
+                });
+            }
+            let result = call_builtin(builtin, args);
+            // NaN out of finite inputs means the argument was outside the domain
+            if result.is_nan() && args.iter().all(|arg| !arg.is_nan()) {
+                return Err(CalculatorError::Domain(builtin.to_string()));
+            }
+            return Ok(result);
+        }
+
+        self.functions
+            .and_then(|functions| functions.call(name, args))
+            .unwrap_or_else(|| Err(CalculatorError::UnknownFunction(name.to_string())))
+    }
+}
+
+fn call_builtin(name: &str, args: &[f64]) -> f64 {
+    match name {
+        "sqrt" => args[0].sqrt(),
+        "abs" => args[0].abs(),
+        "sin" => args[0].sin(),
+        "cos" => args[0].cos(),
+        "tan" => args[0].tan(),
+        "asin" => args[0].asin(),
+        "acos" => args[0].acos(),
+        "atan" => args[0].atan(),
+        "ln" if args[0] > 0.0 => args[0].ln(),
+        "log" if args[0] > 0.0 => args[0].log10(),
+        "exp" => args[0].exp(),
+        "round" => args[0].round(),
+        "floor" => args[0].floor(),
+        "ceil" => args[0].ceil(),
+        "min" => args[0].min(args[1]),
+        "max" => args[0].max(args[1]),
+        _ => f64::NAN,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Number(f64),
+    Identifier(String),
+    Operator(BinaryOp),
+    LeftParen,
+    RightParen,
+    /// `[` and `]` around a matrix
+    LeftBracket,
+    RightBracket,
+    Comma,
+    /// Ends a matrix row
+    Semicolon,
+    /// Separates the sides of an equation
+    Equals,
+}
+
+/// A token and its byte range in the source text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+fn syntax_error(input: &str, byte_offset: usize, message: impl Into<String>) -> CalculatorError {
+    CalculatorError::Syntax {
+        message: message.into(),
+        column: input[..byte_offset].chars().count() + 1,
+    }
+}
+
+// Superscripts and the plain characters they stand for
+const SUPERSCRIPTS: [(char, char); 12] = [
+    ('⁰', '0'),
+    ('¹', '1'),
+    ('²', '2'),
+    ('³', '3'),
+    ('⁴', '4'),
+    ('⁵', '5'),
+    ('⁶', '6'),
+    ('⁷', '7'),
+    ('⁸', '8'),
+    ('⁹', '9'),
+    ('⁻', '-'),
+    ('⁺', '+'),
+];
+
+fn superscript(c: char) -> Option<char> {
+    SUPERSCRIPTS.iter().find(|(sup, _)| *sup == c).map(|&(_, plain)| plain)
+}
+
+/// Rewrites math typography from documents and the web into the syntax the
+/// tokenizer reads: `×` and `÷`, minus signs and dashes, fancy quotes, odd
+/// spaces, and superscripts as powers (`x²` becomes `x^2`, `10⁻³` `10^(-3)`).
+pub fn normalize(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some(plain) = superscript(c) {
+            let mut exponent = String::from(plain);
+            while let Some(plain) = chars.peek().copied().and_then(superscript) {
+                exponent.push(plain);
+                chars.next();
+            }
+            if exponent.len() == 1 {
+                output.push('^');
+                output.push_str(&exponent);
+            } else {
+                output.push_str(&format!("^({})", exponent));
+            }
+            continue;
+        }
+
+        output.push(match c {
+            '×' | '⋅' | '·' | '∙' | '✕' => '*',
+            '÷' | '∕' | '⁄' => '/',
+            '−' | '–' | '—' | '‒' | '﹣' | '－' => '-',
+            '＋' => '+',
+            '‘' | '’' | '′' => '\'',
+            '“' | '”' | '″' => '"',
+            '\u{00A0}' | '\u{2009}' | '\u{202F}' => ' ',
+            other => other,
+        });
+    }
+    output
+}
+
+// The radix of a `0x`, `0o` or `0b` prefix at the start of `text`
+fn literal_radix(text: &str) -> Option<u32> {
+    let mut chars = text.chars();
+    if chars.next() != Some('0') {
+        return None;
+    }
+    let radix = match chars.next()? {
+        'x' | 'X' => 16,
+        'o' | 'O' => 8,
+        'b' | 'B' => 2,
+        _ => return None,
+    };
+    // 0b alone could still be 0 times a name; a digit of the base makes it a literal
+    chars.next()?.is_digit(radix).then_some(radix)
+}
+
+/// Whether `input` writes any number in hex, octal or binary, which puts
+/// the whole expression in integer mode.
+pub fn has_base_literals(input: &str) -> bool {
+    tokenize(input).is_ok_and(|tokens| {
+        tokens
+            .iter()
+            .any(|token| matches!(token.kind, TokenKind::Number(_)) && literal_radix(&input[token.start..]).is_some())
+    })
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<Token>, CalculatorError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        if let Some(radix) = literal_radix(&input[start..]) {
+            // The prefix, then every letter or digit so 0xFG reports the G
+            chars.next();
+            chars.next();
+            let mut end = start + 2;
+            while let Some(&(index, c)) = chars.peek() {
+                if c.is_ascii_alphanumeric() || c == '_' {
+                    chars.next();
+                    end = index + 1;
+                } else {
+                    break;
+                }
+            }
+            let value = programmer::from_radix(&input[start + 2..end], radix).map_err(|err| match err {
+                CalculatorError::Overflow => err,
+                _ => syntax_error(input, start, format!("Invalid number '{}'", &input[start..end])),
+            })?;
+            tokens.push(Token {
+                kind: TokenKind::Number(value as f64),
+                start,
+                end,
+            });
+            continue;
+        }
+
+        // .* ./ .^ before numbers, which may start with a point
+        let element_op = match input[start..].strip_prefix('.').and_then(|rest| rest.chars().next()) {
+            Some('*') => Some(BinaryOp::ElementMultiply),
+            Some('/') => Some(BinaryOp::ElementDivide),
+            Some('^') => Some(BinaryOp::ElementPower),
+            _ => None,
+        };
+        if let Some(op) = element_op {
+            chars.next();
+            chars.next();
+            tokens.push(Token {
+                kind: TokenKind::Operator(op),
+                start,
+                end: start + 2,
+            });
+            continue;
+        }
+
+        if c.is_ascii_digit() || c == '.' {
+            let mut end = start;
+            let mut seen_exponent = false;
+            while let Some(&(index, c)) = chars.peek() {
+                if c.is_ascii_digit() || c == '.' {
+                    chars.next();
+                    end = index + 1;
+                } else if (c == 'e' || c == 'E') && !seen_exponent {
+                    // Only an exponent when digits follow, otherwise it's the constant e
+                    let rest = &input[index + 1..];
+                    let digits = rest.strip_prefix(['+', '-']).unwrap_or(rest);
+                    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+                        break;
+                    }
+                    seen_exponent = true;
+                    chars.next();
+                    if rest.len() != digits.len() {
+                        chars.next();
+                    }
+                    end = index + 1 + (rest.len() - digits.len());
+                } else {
+                    break;
+                }
+            }
+            // An SI prefix right after the number scales it, as in 4.7k or 10µ,
+            // unless it starts a name like `min`
+            if let Some(&(index, c)) = chars.peek() {
+                let next = input[index + c.len_utf8()..].chars().next();
+                let starts_name = next.is_some_and(|next| next.is_alphanumeric() || next == '_');
+                if !seen_exponent && format::si_exponent(c).is_some() && !starts_name {
+                    chars.next();
+                    end = index + c.len_utf8();
+                }
+            }
+            let text = &input[start..end];
+            let value = format::parse_si(text)
+                .ok_or_else(|| syntax_error(input, start, format!("Invalid number '{}'", text)))?;
+            tokens.push(Token {
+                kind: TokenKind::Number(value),
+                start,
+                end,
+            });
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let mut end = start;
+            while let Some(&(index, c)) = chars.peek() {
+                if c.is_alphanumeric() || c == '_' {
+                    chars.next();
+                    end = index + c.len_utf8();
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token {
+                kind: TokenKind::Identifier(input[start..end].to_string()),
+                start,
+                end,
+            });
+            continue;
+        }
+
+        let kind = match c {
+            '+' => TokenKind::Operator(BinaryOp::Add),
+            '-' => TokenKind::Operator(BinaryOp::Subtract),
+            '*' => TokenKind::Operator(BinaryOp::Multiply),
+            '/' => TokenKind::Operator(BinaryOp::Divide),
+            '^' => TokenKind::Operator(BinaryOp::Power),
+            '(' => TokenKind::LeftParen,
+            ')' => TokenKind::RightParen,
+            '[' => TokenKind::LeftBracket,
+            ']' => TokenKind::RightBracket,
+            ',' => TokenKind::Comma,
+            ';' => TokenKind::Semicolon,
+            '=' => TokenKind::Equals,
+            _ => return Err(syntax_error(input, start, format!("Unexpected '{}'", c))),
+        };
+
+        chars.next();
+        tokens.push(Token {
+            kind,
+            start,
+            end: start + c.len_utf8(),
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Syntax choices for `parse_with`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Read `2(3+4)`, `3π` and `(1+2)(3+4)` as products
+    pub implicit_multiplication: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            implicit_multiplication: true,
+        }
+    }
+}
+
+// The app always passes the user's options; tests mostly want the defaults
+#[cfg(test)]
+pub fn parse(input: &str) -> Result<Expr, CalculatorError> {
+    parse_with(input, ParseOptions::default())
+}
+
+pub fn parse_with(input: &str, options: ParseOptions) -> Result<Expr, CalculatorError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser {
+        input,
+        tokens,
+        position: 0,
+        options,
+        depth: 0,
+        operators: 0,
+    };
+
+    let expr = parser.expression()?;
+    parser.end()?;
+    Ok(expr)
+}
+
+/// Parses `left = right` into its two sides, or `None` if `input` has no
+/// `=` and is a plain expression.
+pub fn parse_equation(input: &str, options: ParseOptions) -> Result<Option<(Expr, Expr)>, CalculatorError> {
+    let tokens = tokenize(input)?;
+    if !tokens.iter().any(|token| token.kind == TokenKind::Equals) {
+        return Ok(None);
+    }
+    let mut parser = Parser {
+        input,
+        tokens,
+        position: 0,
+        options,
+        depth: 0,
+        operators: 0,
+    };
+
+    let left = parser.expression()?;
+    parser.expect(TokenKind::Equals, "Expected '='")?;
+    let right = parser.expression()?;
+    parser.end()?;
+    Ok(Some((left, right)))
+}
+
+// Recursive descent parser over the token list:
+//   expression := term (('+' | '-') term)*
+//   term       := unary (('*' | '/' | '.*' | './') unary | power)*   (bare power only with implicit multiplication)
+//   unary      := ('-' | '+') unary | power
+//   power      := primary (('^' | '.^') unary)?
+//   primary    := number | identifier | identifier '(' arguments ')' | '(' expression ')' | '[' rows ']'
+//   rows       := expression (',' expression)* (';' expression (',' expression)*)*
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+    options: ParseOptions,
+    depth: usize,
+    // Binary operators and signs so far, implicit multiplications included
+    operators: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn next_operator_in(&mut self, ops: &[BinaryOp]) -> Option<BinaryOp> {
+        match self.peek() {
+            Some(Token {
+                kind: TokenKind::Operator(op),
+                ..
+            }) if ops.contains(op) => {
+                let op = *op;
+                self.position += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn error_here(&self, message: &str) -> CalculatorError {
+        let offset = self.peek().map_or(self.input.len(), |token| token.start);
+        let message = if self.peek().is_none() { "Unexpected end of expression" } else { message };
+        syntax_error(self.input, offset, message)
+    }
+
+    fn expect(&mut self, kind: TokenKind, message: &str) -> Result<(), CalculatorError> {
+        if self.peek().map(|token| &token.kind) == Some(&kind) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error_here(message))
+        }
+    }
+
+    // Counts the operator just read, stopping expressions too long to
+    // evaluate without overflowing the stack. Implicit multiplication has no
+    // operator token, so the error points at the token before it.
+    fn count_operator(&mut self) -> Result<(), CalculatorError> {
+        if self.operators == MAX_OPERATORS {
+            let offset = self.tokens.get(self.position.wrapping_sub(1)).map_or(self.input.len(), |token| token.start);
+            return Err(syntax_error(self.input, offset, "Expression is too long"));
+        }
+        self.operators += 1;
+        Ok(())
+    }
+
+    fn end(&self) -> Result<(), CalculatorError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(token) => Err(syntax_error(self.input, token.start, "Unexpected input")),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expr, CalculatorError> {
+        let mut left = self.term()?;
+        while let Some(op) = self.next_operator_in(&[BinaryOp::Add, BinaryOp::Subtract]) {
+            self.count_operator()?;
+            left = Expr::binary(op, left, self.term()?);
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Expr, CalculatorError> {
+        let mut left = self.unary()?;
+        loop {
+            let products = [BinaryOp::Multiply, BinaryOp::Divide, BinaryOp::ElementMultiply, BinaryOp::ElementDivide];
+            if let Some(op) = self.next_operator_in(&products) {
+                self.count_operator()?;
+                left = Expr::binary(op, left, self.unary()?);
+            } else if self.implicit_factor_follows() {
+                self.count_operator()?;
+                // Binds like `*`, so 2x^2 is 2*(x^2) and 1/2(3) is (1/2)*3
+                left = Expr::binary(BinaryOp::Multiply, left, self.power()?);
+            } else {
+                return Ok(left);
+            }
+        }
+    }
+
+    // Whether the next token starts a factor written straight after the last
+    // one. Two numbers in a row stay an error, as `2 3` is more likely a typo.
+    fn implicit_factor_follows(&self) -> bool {
+        if !self.options.implicit_multiplication {
+            return false;
+        }
+        let previous = self.position.checked_sub(1).and_then(|index| self.tokens.get(index));
+        match self.peek().map(|token| &token.kind) {
+            Some(TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::Identifier(_)) => true,
+            Some(TokenKind::Number(_)) => !matches!(previous.map(|token| &token.kind), Some(TokenKind::Number(_))),
+            _ => false,
+        }
+    }
+
+    // Every nested parenthesis, sign or power comes through here, so this is
+    // where runaway nesting is stopped before it overflows the stack
+    fn unary(&mut self) -> Result<Expr, CalculatorError> {
+        if self.depth == MAX_NESTING {
+            return Err(self.error_here("Expression is nested too deeply"));
+        }
+        self.depth += 1;
+        let result = match self.next_operator_in(&[BinaryOp::Add, BinaryOp::Subtract]) {
+            Some(BinaryOp::Subtract) => self
+                .count_operator()
+                .and_then(|_| self.unary())
+                .map(|operand| Expr::Negate(Box::new(operand))),
+            Some(_) => self.unary(),
+            None => self.power(),
+        };
+        self.depth -= 1;
+        result
+    }
+
+    fn power(&mut self) -> Result<Expr, CalculatorError> {
+        let base = self.primary()?;
+        if let Some(op) = self.next_operator_in(&[BinaryOp::Power, BinaryOp::ElementPower]) {
+            self.count_operator()?;
+            Ok(Expr::binary(op, base, self.unary()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, CalculatorError> {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return Err(self.error_here("")),
+        };
+
+        match token.kind {
+            TokenKind::Number(value) => {
+                self.position += 1;
+                Ok(Expr::Number(value))
+            }
+            TokenKind::Identifier(name) => {
+                self.position += 1;
+                if self.peek().map(|token| &token.kind) != Some(&TokenKind::LeftParen) {
+                    return Ok(Expr::Variable(name));
+                }
+                self.position += 1;
+
+                let mut args = Vec::new();
+                if self.peek().map(|token| &token.kind) != Some(&TokenKind::RightParen) {
+                    args.push(self.expression()?);
+                    while self.peek().map(|token| &token.kind) == Some(&TokenKind::Comma) {
+                        self.position += 1;
+                        args.push(self.expression()?);
+                    }
+                }
+                self.expect(TokenKind::RightParen, "Expected ')'")?;
+                Ok(Expr::Call { name, args })
+            }
+            TokenKind::LeftParen => {
+                self.position += 1;
+                let inner = self.expression()?;
+                self.expect(TokenKind::RightParen, "Expected ')'")?;
+                Ok(inner)
+            }
+            TokenKind::LeftBracket => {
+                self.position += 1;
+                let mut rows = vec![vec![self.expression()?]];
+                loop {
+                    match self.peek().map(|token| token.kind.clone()) {
+                        Some(TokenKind::Comma) => {
+                            self.position += 1;
+                            rows.last_mut().unwrap().push(self.expression()?);
+                        }
+                        Some(TokenKind::Semicolon) => {
+                            if rows.len() > 1 && rows.last().unwrap().len() != rows[0].len() {
+                                return Err(self.error_here("Rows of a matrix must be the same length"));
+                            }
+                            self.position += 1;
+                            rows.push(vec![self.expression()?]);
+                        }
+                        _ => break,
+                    }
+                }
+                if rows.last().unwrap().len() != rows[0].len() {
+                    return Err(self.error_here("Rows of a matrix must be the same length"));
+                }
+                self.expect(TokenKind::RightBracket, "Expected ']'")?;
+                Ok(Expr::Matrix(rows))
+            }
+            _ => {
+                let message = match self.next().map(|token| token.kind) {
+                    Some(TokenKind::RightParen) => "Unexpected ')'",
+                    Some(TokenKind::RightBracket) => "Unexpected ']'",
+                    Some(TokenKind::Comma) => "Unexpected ','",
+                    Some(TokenKind::Semicolon) => "Unexpected ';'",
+                    _ => "Expected a number",
+                };
+                self.position -= 1;
+                Err(self.error_here(message))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculator::Calculator;
+    use crate::testing::operation;
+    use proptest::prelude::*;
+
+    fn eval(input: &str) -> Result<f64, CalculatorError> {
+        parse(input)?.eval(&Environment::new())
+    }
+
+    #[test]
+    fn test_precedence_and_associativity() {
+        assert_eq!(eval("2 + 3 * 4"), Ok(14.0));
+        assert_eq!(eval("(2 + 3) * 4"), Ok(20.0));
+        assert_eq!(eval("10 - 4 - 3"), Ok(3.0));
+        assert_eq!(eval("2 ^ 3 ^ 2"), Ok(512.0));
+        assert_eq!(eval("-2 ^ 2"), Ok(-4.0));
+        assert_eq!(eval("2 ^ -1"), Ok(0.5));
+        assert_eq!(eval("1.5e3 + e - e"), Ok(1500.0));
+        assert_eq!(eval("max(2, sqrt(16))"), Ok(4.0));
+    }
+
+    #[test]
+    fn test_implicit_multiplication() {
+        assert_eq!(eval("2(3+4)"), Ok(14.0));
+        assert_eq!(eval("3π"), eval("3 * pi"));
+        assert_eq!(eval("(1+2)(3+4)"), Ok(21.0));
+        assert_eq!(eval("(1+2)4"), Ok(12.0));
+        assert_eq!(eval("2sqrt(16)"), Ok(8.0));
+        // Powers bind tighter, and it's as strong as * otherwise
+        assert_eq!(eval("2(3)^2"), Ok(18.0));
+        assert_eq!(eval("2^2(3)"), Ok(12.0));
+        assert_eq!(eval("-2(3)^2"), Ok(-18.0));
+        assert_eq!(eval("1/2(4)"), Ok(2.0));
+        assert_eq!(eval("2 - (3)"), Ok(-1.0));
+        assert!(eval("2 3").is_err());
+
+        let strict = ParseOptions {
+            implicit_multiplication: false,
+        };
+        assert_eq!(
+            parse_with("2(3+4)", strict),
+            Err(CalculatorError::Syntax {
+                message: String::from("Unexpected input"),
+                column: 2,
+            })
+        );
+        assert!(parse_with("2 * (3+4)", strict).is_ok());
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("6 × 7 ÷ 2 − 1"), "6 * 7 / 2 - 1");
+        assert_eq!(normalize("2³ + x²"), "2^3 + x^2");
+        assert_eq!(normalize("10⁻³"), "10^(-3)");
+        assert_eq!(normalize("2¹⁰"), "2^(10)");
+        assert_eq!(normalize("12°30′15″"), "12°30'15\"");
+        assert_eq!(normalize("1\u{00A0}000 – 1"), "1 000 - 1");
+        assert_eq!(eval(&normalize("(2 + 3)² ÷ 5 − 10⁻¹")), Ok(4.9));
+    }
+
+    #[test]
+    fn test_si_prefixes() {
+        assert_eq!(eval("4.7k + 300"), Ok(5000.0));
+        assert_eq!(eval("10µ * 2"), Ok(2e-5));
+        assert_eq!(eval("2.2M / 2u"), Ok(1.1e12));
+        assert_eq!(eval("-5m"), Ok(-0.005));
+        assert_eq!(eval("min(1k, 2k)"), Ok(1000.0));
+        // A space or a following letter means it isn't a prefix
+        assert!(eval("5 k").is_err());
+        assert!(eval("5min").is_err());
+        assert!(eval("1e3k").is_err());
+    }
+
+    #[test]
+    fn test_base_literals() {
+        let integer = |input: &str| parse(input).and_then(|expr| expr.eval_integer(&Environment::new()));
+        assert_eq!(eval("0xFF + 0b1010 + 0o17"), Ok(280.0));
+        assert_eq!(integer("0x10 / 3"), Ok(5));
+        assert_eq!(integer("-7 / 2"), Ok(-3));
+        assert_eq!(integer("0b1_0000 ^ 2"), Ok(256));
+        assert_eq!(integer("0xFF * 1.5"), Err(CalculatorError::Domain(String::from("integer mode"))));
+        assert_eq!(integer("sqrt(0x10)"), Err(CalculatorError::Domain(String::from("sqrt in integer mode"))));
+        assert_eq!(integer("0x1 / 0"), Err(CalculatorError::DivisionByZero));
+        // i128::MIN has no positive counterpart
+        assert_eq!(
+            integer("-((-0x8000000000000000 * 2) * 0x8000000000000000)"),
+            Err(CalculatorError::Overflow)
+        );
+        assert!(has_base_literals("1 + 0x1F"));
+        assert!(!has_base_literals("10 + 0"));
+        // Without a digit of the base after it, the prefix isn't one
+        assert!(!has_base_literals("0b"));
+        assert_eq!(
+            eval("0xFG"),
+            Err(CalculatorError::Syntax {
+                message: String::from("Invalid number '0xFG'"),
+                column: 1,
+            })
+        );
+        assert_eq!(eval("0x1_0000_0000_0000_0000"), Err(CalculatorError::Overflow));
+    }
+
+    #[test]
+    fn test_expression_errors() {
+        assert_eq!(eval("1 / 0"), Err(CalculatorError::DivisionByZero));
+        assert_eq!(eval("sqrt(-1)"), Err(CalculatorError::Domain(String::from("sqrt"))));
+        assert_eq!(eval("foo(1)"), Err(CalculatorError::UnknownFunction(String::from("foo"))));
+        assert_eq!(eval("x + 1"), Err(CalculatorError::UnknownVariable(String::from("x"))));
+        assert_eq!(
+            eval("1 + * 2"),
+            Err(CalculatorError::Syntax {
+                message: String::from("Expected a number"),
+                column: 5,
+            })
+        );
+        assert_eq!(
+            eval("(1 + 2"),
+            Err(CalculatorError::Syntax {
+                message: String::from("Unexpected end of expression"),
+                column: 7,
+            })
+        );
+        assert!(matches!(eval("min(1)"), Err(CalculatorError::WrongArgumentCount { .. })));
+    }
+
+    #[test]
+    fn test_matrix_syntax() {
+        let matrix = |rows: Vec<Vec<f64>>| {
+            Expr::Matrix(rows.into_iter().map(|row| row.into_iter().map(Expr::Number).collect()).collect())
+        };
+        assert_eq!(parse("[1, 2; 3, 4]"), Ok(matrix(vec![vec![1.0, 2.0], vec![3.0, 4.0]])));
+        assert_eq!(
+            parse("[1, 2] .* [3, 4]"),
+            Ok(Expr::binary(BinaryOp::ElementMultiply, matrix(vec![vec![1.0, 2.0]]), matrix(vec![vec![3.0, 4.0]])))
+        );
+        assert_eq!(parse("2[1]"), parse("2 * [1]"));
+        assert_eq!(parse("[1; 2] .^ 2 ./ .5").unwrap().to_string(), "[1; 2] .^ 2 ./ 0.5");
+        assert_eq!(eval("det([1, 2; 3, 4]) + 1"), Ok(-1.0));
+        assert_eq!(eval("6 .* 7"), Ok(42.0));
+        assert_eq!(
+            parse("[1, 2; 3]"),
+            Err(CalculatorError::Syntax {
+                message: String::from("Rows of a matrix must be the same length"),
+                column: 9,
+            })
+        );
+        assert_eq!(
+            parse("[1; 2, 3]"),
+            Err(CalculatorError::Syntax {
+                message: String::from("Rows of a matrix must be the same length"),
+                column: 9,
+            })
+        );
+        assert!(matches!(parse("1]"), Err(CalculatorError::Syntax { .. })));
+        assert!(matches!(parse("[1, 2"), Err(CalculatorError::Syntax { .. })));
+        assert!(matches!(eval("[1, 2]"), Err(CalculatorError::Dimension(_))));
+    }
+
+    #[test]
+    fn test_deep_nesting_is_an_error() {
+        let nested = |depth| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(eval(&nested(200)), Ok(1.0));
+        assert_eq!(
+            eval(&nested(10_000)),
+            Err(CalculatorError::Syntax {
+                message: String::from("Expression is nested too deeply"),
+                column: 257,
+            })
+        );
+        assert!(eval(&format!("{}1", "-".repeat(10_000))).is_err());
+        assert!(eval(&format!("1{}", "^2".repeat(10_000))).is_err());
+    }
+
+    #[test]
+    fn test_long_chains_are_an_error() {
+        let chain = |length| format!("1{}", "+1".repeat(length));
+        assert_eq!(eval(&chain(MAX_OPERATORS)), Ok(1001.0));
+        assert_eq!(
+            eval(&chain(10_000)),
+            Err(CalculatorError::Syntax {
+                message: String::from("Expression is too long"),
+                column: 2002,
+            })
+        );
+        // The longest chain allowed, as deep as allowed, still evaluates,
+        // prints and drops within a test thread's stack
+        let deepest = format!("{}{}{}", "(".repeat(MAX_NESTING - 1), chain(MAX_OPERATORS), ")".repeat(MAX_NESTING - 1));
+        let parsed = parse(&deepest).unwrap();
+        assert_eq!(parsed.eval(&Environment::new()), Ok(1001.0));
+        assert_eq!(parse(&parsed.to_string()), Ok(parsed));
+        assert_eq!(Calculator::new().preview_expression(&deepest, None), Ok(1001.0));
+    }
+
+    fn expr() -> impl Strategy<Value = Expr> {
+        let leaf = prop_oneof![
+            (-1000.0..1000.0f64).prop_map(Expr::Number),
+            Just(Expr::Variable(String::from("pi"))),
+        ];
+        leaf.prop_recursive(4, 32, 2, |inner| {
+            prop_oneof![
+                inner.clone().prop_map(|operand| Expr::Negate(Box::new(operand))),
+                (
+                    prop_oneof![
+                        Just(BinaryOp::Add),
+                        Just(BinaryOp::Subtract),
+                        Just(BinaryOp::Multiply),
+                        Just(BinaryOp::Divide),
+                        Just(BinaryOp::Power),
+                    ],
+                    inner.clone(),
+                    inner.clone()
+                )
+                    .prop_map(|(op, left, right)| Expr::binary(op, left, right)),
+                inner.prop_map(|arg| Expr::Call {
+                    name: String::from("abs"),
+                    args: vec![arg],
+                }),
+            ]
+        })
+    }
+
+    // Feature: gui-calculator, Property 12: Expressions agree with keypad arithmetic
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_expression_matches_operation(
+            left in -1000000.0..1000000.0f64,
+            right in -1000000.0..1000000.0f64,
+            op in operation()
+        ) {
+            let source = Expr::binary(op.into(), Expr::Number(left), Expr::Number(right)).to_string();
+            prop_assert_eq!(eval(&source), op.apply(left, right));
+        }
+
+        // Feature: gui-calculator, Property 13: Printed expressions reparse to the same value
+        #[test]
+        fn test_expression_display_roundtrip(expr in expr()) {
+            let env = Environment::new();
+            let printed = expr.to_string();
+            let reparsed = parse(&printed);
+            prop_assert!(reparsed.is_ok(), "'{}' should parse", printed);
+            let reparsed = reparsed.unwrap();
+
+            // Printing is stable once the tree went through the parser
+            prop_assert_eq!(reparsed.to_string(), printed.clone());
+
+            match (expr.eval(&env), reparsed.eval(&env)) {
+                (Ok(a), Ok(b)) if a.is_nan() => prop_assert!(b.is_nan()),
+                (a, b) => prop_assert_eq!(a, b, "'{}'", printed),
+            }
+        }
+    }
+
+    // Pieces of expressions, valid and not, for inputs closer to what the
+    // parser expects than random text
+    const FRAGMENTS: &[&str] = &[
+        "1", "2.5", "1e3", "0x1F", "0b2", "4.7k", ".", "x", "pi", "ans", "sqrt", "sin", "min", "(", ")", ",", "+",
+        "-", "*", "/", "^", "=", "%", "!", "²", "×", "−", " ", "é", "\u{0}",
+    ];
+
+    fn garbage() -> impl Strategy<Value = String> {
+        prop_oneof![
+            prop::collection::vec(any::<u8>(), 0..64).prop_map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+            any::<String>(),
+            prop::collection::vec(prop::sample::select(FRAGMENTS), 0..32).prop_map(|fragments| fragments.concat()),
+            // Long flat chains, which nest as deeply as they are long
+            (
+                prop::sample::select(&["1", "x", "2.5", "(1)"][..]),
+                prop::sample::select(&["+", "-", "*", "/", "^", ""][..]),
+                0..3000usize
+            )
+                .prop_map(|(operand, operator, length)| {
+                    format!("{}{}", operand, format!("{}{}", operator, operand).repeat(length))
+                }),
+        ]
+    }
+
+    // Feature: gui-calculator, Property 40: Any input gives a result or an error fit for the display
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_arbitrary_input_never_panics(input in garbage()) {
+            let env = Environment::new();
+            let results = [
+                parse(&input).and_then(|expr| expr.eval(&env)),
+                parse(&input).and_then(|expr| expr.eval_integer(&env)).map(|value| value as f64),
+                parse_equation(&input, ParseOptions::default()).map(|_| 0.0),
+                Calculator::new().preview_expression(&input, None),
+                Calculator::new().preview_expression(&normalize(&input), None),
+            ];
+            let columns = normalize(&input).chars().count().max(input.chars().count()) + 1;
+            for error in results.into_iter().filter_map(Result::err) {
+                if let CalculatorError::Syntax { message, column } = &error {
+                    prop_assert!(!message.is_empty(), "{:?}", input);
+                    // Points into the input or just past its end
+                    prop_assert!((1..=columns).contains(column), "{:?}: column {}", input, column);
+                }
+                prop_assert!(error.to_string().starts_with("Error: "), "{:?}: {}", input, error);
+            }
+        }
+    }
+}
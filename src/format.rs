@@ -0,0 +1,647 @@
+// Number Formatting
+use rust_decimal::RoundingStrategy;
+use serde::{Deserialize, Serialize};
+
+use crate::decimal;
+use crate::dms;
+use crate::ieee754;
+use crate::programmer::{self, Base};
+use crate::rational::Rational;
+
+/// How results are written on the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NumberFormat {
+    /// As computed, e.g. 4700
+    #[default]
+    Plain,
+    /// With the nearest SI prefix, e.g. 4.7 k
+    SiPrefix,
+    /// Powers of ten in steps of three, e.g. 4.7e3
+    Engineering,
+    /// An amount of money, e.g. $4,700.00
+    Money,
+}
+
+/// How amounts are written in the money format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoneyStyle {
+    /// Written before the amount, e.g. $ or "CHF "
+    pub symbol: String,
+    /// Round halves to the even cent, as banks do, instead of away from zero
+    pub bankers_rounding: bool,
+    /// Negative amounts in parentheses, as accountants write them: ($5.00)
+    pub accounting: bool,
+}
+
+impl Default for MoneyStyle {
+    fn default() -> Self {
+        Self {
+            symbol: String::from("$"),
+            bankers_rounding: false,
+            accounting: false,
+        }
+    }
+}
+
+/// The rounding switch of a desk calculator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RoundingSwitch {
+    /// F: results as computed; the decimal places don't matter
+    #[default]
+    Float,
+    /// CUT: decimals beyond the places dropped
+    Cut,
+    /// 5/4: rounded to the places, halves away from zero
+    Round,
+}
+
+impl RoundingSwitch {
+    pub const ALL: [RoundingSwitch; 3] = [RoundingSwitch::Float, RoundingSwitch::Cut, RoundingSwitch::Round];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RoundingSwitch::Float => "F",
+            RoundingSwitch::Cut => "CUT",
+            RoundingSwitch::Round => "5/4",
+        }
+    }
+}
+
+/// The decimal-place selector next to the rounding switch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DecimalPlaces {
+    Zero,
+    One,
+    #[default]
+    Two,
+    Three,
+    /// Two places for amounts of money
+    Add2,
+}
+
+impl DecimalPlaces {
+    pub const ALL: [DecimalPlaces; 5] = [
+        DecimalPlaces::Zero,
+        DecimalPlaces::One,
+        DecimalPlaces::Two,
+        DecimalPlaces::Three,
+        DecimalPlaces::Add2,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DecimalPlaces::Zero => "0",
+            DecimalPlaces::One => "1",
+            DecimalPlaces::Two => "2",
+            DecimalPlaces::Three => "3",
+            DecimalPlaces::Add2 => "ADD2",
+        }
+    }
+
+    pub fn places(self) -> u32 {
+        match self {
+            DecimalPlaces::Zero => 0,
+            DecimalPlaces::One => 1,
+            DecimalPlaces::Two | DecimalPlaces::Add2 => 2,
+            DecimalPlaces::Three => 3,
+        }
+    }
+}
+
+/// Another way to read a result, shown in place of the number without
+/// changing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpretation {
+    /// Hours as hours and minutes, e.g. 1.75 → 1:45
+    Time,
+    /// Decimal degrees as degrees, minutes and seconds
+    Angle,
+    /// A fraction as a percentage, e.g. 0.07 → 7%
+    Percent,
+}
+
+impl Interpretation {
+    pub const ALL: [Interpretation; 3] = [Interpretation::Time, Interpretation::Angle, Interpretation::Percent];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Interpretation::Time => "Hours → h:mm",
+            Interpretation::Angle => "Degrees → DMS",
+            Interpretation::Percent => "Fraction → %",
+        }
+    }
+}
+
+/// The forms "Copy as" offers for a result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// As shown, e.g. 1234.5
+    Plain,
+    /// 1,234.5
+    Thousands,
+    /// 1.2345e3
+    Scientific,
+    /// 0x4D2, for whole numbers
+    Hex,
+    /// 5/8, when a short fraction is exactly the result
+    Fraction,
+    /// 1_234.5_f64
+    RustLiteral,
+}
+
+impl CopyFormat {
+    pub const ALL: [CopyFormat; 6] = [
+        CopyFormat::Plain,
+        CopyFormat::Thousands,
+        CopyFormat::Scientific,
+        CopyFormat::Hex,
+        CopyFormat::Fraction,
+        CopyFormat::RustLiteral,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CopyFormat::Plain => "Plain",
+            CopyFormat::Thousands => "Thousands separators",
+            CopyFormat::Scientific => "Scientific",
+            CopyFormat::Hex => "Hex",
+            CopyFormat::Fraction => "Fraction",
+            CopyFormat::RustLiteral => "Rust literal",
+        }
+    }
+}
+
+// Largest denominator "Copy as fraction" looks for
+const FRACTION_DENOMINATOR_LIMIT: i128 = 1_000_000;
+
+/// SI prefixes from femto to peta with their powers of ten.
+pub const SI_PREFIXES: &[(char, i32)] = &[
+    ('f', -15),
+    ('p', -12),
+    ('n', -9),
+    ('µ', -6),
+    ('m', -3),
+    ('k', 3),
+    ('M', 6),
+    ('G', 9),
+    ('T', 12),
+    ('P', 15),
+];
+
+// Enough to hide binary noise like 3.3000000000000003 without losing real digits
+const SIGNIFICANT_DIGITS: usize = 12;
+
+/// How far smart cleanup may move a result, relative to its size, unless
+/// the user picks otherwise: a few dozen steps of the last binary digit.
+pub const DEFAULT_CLEANUP_EPSILON: f64 = 1e-14;
+
+/// The power of ten for a prefix letter. `u` is accepted for `µ` since it's
+/// easier to type, and the Greek letter mu for the micro sign.
+pub fn si_exponent(prefix: char) -> Option<i32> {
+    let prefix = match prefix {
+        'u' | 'μ' => 'µ',
+        other => other,
+    };
+    SI_PREFIXES
+        .iter()
+        .find(|(symbol, _)| *symbol == prefix)
+        .map(|&(_, exponent)| exponent)
+}
+
+/// Reads a number with an optional SI prefix, e.g. `2.2M` or `10µ`.
+pub fn parse_si(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let last = text.chars().last()?;
+    match si_exponent(last) {
+        // Scaled through the exponent so 10µ is exactly 1e-5
+        Some(exponent) => format!("{}e{}", &text[..text.len() - last.len_utf8()], exponent)
+            .parse()
+            .ok(),
+        None => text.parse().ok(),
+    }
+}
+
+// Splits `value` into a mantissa in [1, 1000) and a power of ten that is a
+// multiple of three, kept within `limit` either way
+fn engineering_parts(value: f64, limit: i32) -> (f64, i32) {
+    if value == 0.0 || !value.is_finite() {
+        return (value, 0);
+    }
+
+    let mut exponent = (value.abs().log10() / 3.0).floor() as i32 * 3;
+    exponent = exponent.clamp(-limit, limit);
+    let mut mantissa = scale(value, exponent);
+    // log10 and rounding can land just across a boundary, e.g. 999.9999999999999
+    if mantissa.abs() >= 1000.0 && exponent < limit {
+        exponent += 3;
+        mantissa = scale(value, exponent);
+    }
+    (mantissa, exponent)
+}
+
+fn scale(value: f64, exponent: i32) -> f64 {
+    // Dividing by 10^-n loses less than multiplying by an inexact 10^n.
+    // Subnormals need more than 10^308, so they're scaled up in two steps.
+    let scaled = if exponent < -300 {
+        value * 1e300 * 10f64.powi(-exponent - 300)
+    } else if exponent < 0 {
+        value * 10f64.powi(-exponent)
+    } else {
+        value / 10f64.powi(exponent)
+    };
+    ieee754::round_to_significant(scaled, SIGNIFICANT_DIGITS)
+}
+
+/// Splits `value` into a mantissa in [1, 1000) and the prefix for the rest.
+/// Values outside the prefix range keep the femto or peta prefix.
+pub fn si_parts(value: f64) -> (f64, Option<char>) {
+    let (mantissa, exponent) = engineering_parts(value, 15);
+    let prefix = SI_PREFIXES
+        .iter()
+        .find(|&&(_, power)| power == exponent)
+        .map(|&(symbol, _)| symbol);
+    (mantissa, prefix)
+}
+
+/// Formats `value` with the nearest SI prefix and a unit, e.g. `4.7 kΩ`.
+pub fn format_si(value: f64, unit: &str) -> String {
+    match si_parts(value) {
+        (mantissa, Some(prefix)) => format!("{} {}{}", mantissa, prefix, unit),
+        (mantissa, None) if unit.is_empty() => mantissa.to_string(),
+        (mantissa, None) => format!("{} {}", mantissa, unit),
+    }
+}
+
+/// Formats `value` in engineering notation with a unit, e.g. `4.7e3 Ω`.
+pub fn format_engineering(value: f64, unit: &str) -> String {
+    let (mantissa, exponent) = engineering_parts(value, i32::MAX - 3);
+    let number = if exponent == 0 {
+        mantissa.to_string()
+    } else {
+        format!("{}e{}", mantissa, exponent)
+    };
+    if unit.is_empty() {
+        number
+    } else {
+        format!("{} {}", number, unit)
+    }
+}
+
+/// Formats a result as the settings ask for. `unit` only applies to the
+/// scaled formats, where it reads naturally after the prefix or exponent.
+/// Money comes out in the default style; `format_money` takes any.
+pub fn format_number(value: f64, format: NumberFormat, unit: &str) -> String {
+    let unit = unit.trim();
+    match format {
+        NumberFormat::Plain => value.to_string(),
+        NumberFormat::SiPrefix => format_si(value, unit),
+        NumberFormat::Engineering => format_engineering(value, unit),
+        NumberFormat::Money => format_money(value, &MoneyStyle::default()),
+    }
+}
+
+/// `value` as an amount of money: rounded to the cent in decimal, with
+/// thousands separators and the currency symbol, e.g. -$1,234.50 or, in
+/// accounting style, ($1,234.50).
+pub fn format_money(value: f64, style: &MoneyStyle) -> String {
+    let strategy = if style.bankers_rounding {
+        RoundingStrategy::MidpointNearestEven
+    } else {
+        RoundingStrategy::MidpointAwayFromZero
+    };
+    let amount = match decimal::to_decimal(value) {
+        Some(exact) => {
+            let mut cents = exact.round_dp_with_strategy(2, strategy);
+            cents.rescale(2);
+            // Less than half a cent below zero is no debt
+            if cents.is_zero() {
+                cents.set_sign_positive(true);
+            }
+            cents.to_string()
+        }
+        // Too large for a decimal, so there are no cents to round
+        None => value.to_string(),
+    };
+    let grouped = group_thousands(&amount, ',');
+    match grouped.strip_prefix('-') {
+        Some(magnitude) if style.accounting => format!("({}{})", style.symbol, magnitude),
+        Some(magnitude) => format!("-{}{}", style.symbol, magnitude),
+        None => format!("{}{}", style.symbol, grouped),
+    }
+}
+
+/// `value` as a desk calculator with this switch and selector shows it: as
+/// computed with F, otherwise cut or rounded to exactly that many places,
+/// 12.5 as 12.50. Works on the decimal shown, so 1.005 rounds up as it looks
+/// like it should even though the double is a hair below.
+pub fn format_desk(value: f64, switch: RoundingSwitch, places: DecimalPlaces) -> String {
+    let strategy = match switch {
+        RoundingSwitch::Float => return value.to_string(),
+        RoundingSwitch::Cut => RoundingStrategy::ToZero,
+        RoundingSwitch::Round => RoundingStrategy::MidpointAwayFromZero,
+    };
+    // Beyond what a decimal holds there are no decimals to cut anyway
+    let Some(exact) = decimal::to_decimal(value) else {
+        return value.to_string();
+    };
+    let mut rounded = exact.round_dp_with_strategy(places.places(), strategy);
+    rounded.rescale(places.places());
+    // -0.004 rounds to 0.00, not -0.00
+    if rounded.is_zero() {
+        rounded.set_sign_positive(true);
+    }
+    rounded.to_string()
+}
+
+/// The shortest decimal of at most 12 significant digits within `epsilon`
+/// of `value`, relative to its size, when that isn't `value` itself:
+/// 2.9999999999999996 is 3 and 0.30000000000000004 is 0.3, while 1/3 has no
+/// such neighbour and stays as it is. Only for showing a result; it goes on
+/// being computed with unchanged. Results near 0 like sin(π) aren't cleaned,
+/// as next to 0 every tiny number is relatively far away.
+pub fn clean_up(value: f64, epsilon: f64) -> Option<f64> {
+    if !value.is_finite() || value == 0.0 {
+        return None;
+    }
+    (1..=SIGNIFICANT_DIGITS)
+        .map(|digits| ieee754::round_to_significant(value, digits))
+        .find(|clean| ((clean - value) / value).abs() <= epsilon)
+        .filter(|&clean| clean != value)
+}
+
+/// `value` written as `format`, or `None` where that doesn't fit: hex for a
+/// number that isn't whole, or a fraction for one no short fraction gives.
+pub fn format_copy(value: f64, format: CopyFormat) -> Option<String> {
+    if !value.is_finite() {
+        return None;
+    }
+    match format {
+        CopyFormat::Plain => Some(value.to_string()),
+        CopyFormat::Thousands => Some(group_thousands(&value.to_string(), ',')),
+        CopyFormat::Scientific => Some(format!("{:e}", value)),
+        CopyFormat::Hex => programmer::format_literal(value, Base::Hex),
+        CopyFormat::Fraction => simplest_fraction(value).map(|fraction| fraction.to_string()),
+        CopyFormat::RustLiteral => {
+            let grouped = group_thousands(&value.to_string(), '_');
+            let point = if grouped.contains('.') { "" } else { ".0" };
+            Some(format!("{}{}_f64", grouped, point))
+        }
+    }
+}
+
+// The whole part's digits in threes, e.g. 1234567.5 → 1,234,567.5
+fn group_thousands(text: &str, separator: char) -> String {
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text),
+    };
+    let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let mut grouped = String::from(sign);
+    for (index, digit) in whole.chars().enumerate() {
+        if index > 0 && (whole.len() - index) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    if !fraction.is_empty() {
+        grouped.push('.');
+        grouped.push_str(fraction);
+    }
+    grouped
+}
+
+// The fraction with the smallest denominator that gives exactly `value` as
+// a double, from its continued fraction, so 0.1 is 1/10 and 1/3 is 1/3
+fn simplest_fraction(value: f64) -> Option<Rational> {
+    let target = value.abs();
+    let (mut numerators, mut denominators) = ((0i128, 1i128), (1i128, 0i128));
+    let mut rest = target;
+    loop {
+        let term = rest.floor();
+        // Past 2^53 the terms themselves are no longer exact
+        if term > 2f64.powi(53) {
+            return None;
+        }
+        let numerator = term as i128 * numerators.1 + numerators.0;
+        let denominator = term as i128 * denominators.1 + denominators.0;
+        if denominator > FRACTION_DENOMINATOR_LIMIT {
+            return None;
+        }
+        if numerator as f64 / denominator as f64 == target {
+            return Rational::new(if value < 0.0 { -numerator } else { numerator }, denominator);
+        }
+        numerators = (numerators.1, numerator);
+        denominators = (denominators.1, denominator);
+        if rest == term {
+            return None;
+        }
+        rest = 1.0 / (rest - term);
+    }
+}
+
+/// `value` read as `interpretation`.
+pub fn format_interpretation(value: f64, interpretation: Interpretation) -> String {
+    match interpretation {
+        Interpretation::Time => format_hours(value),
+        Interpretation::Angle => dms::format(value, dms::SECONDS_PRECISION),
+        Interpretation::Percent => {
+            format!("{}%", ieee754::round_to_significant(value * 100.0, SIGNIFICANT_DIGITS))
+        }
+    }
+}
+
+// Rounds to the minute before splitting, so 1.9999 h is 2:00 rather than 1:60
+fn format_hours(hours: f64) -> String {
+    let minutes = (hours.abs() * 60.0).round() as u64;
+    let sign = if hours < 0.0 && minutes > 0 { "-" } else { "" };
+    format!("{}{}:{:02}", sign, minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_si_formatting() {
+        assert_eq!(format_si(4700.0, ""), "4.7 k");
+        assert_eq!(format_si(0.0033, ""), "3.3 m");
+        assert_eq!(format_si(-2_200_000.0, ""), "-2.2 M");
+        assert_eq!(format_si(12.5, ""), "12.5");
+        assert_eq!(format_si(0.0, ""), "0");
+        assert_eq!(format_si(1e-5, ""), "10 µ");
+        assert_eq!(format_si(999.9999999999999, ""), "1 k");
+        assert_eq!(format_si(2e18, ""), "2000 P");
+    }
+
+    #[test]
+    fn test_units_and_engineering() {
+        assert_eq!(format_number(4700.0, NumberFormat::SiPrefix, "Ω"), "4.7 kΩ");
+        assert_eq!(format_number(0.0033, NumberFormat::SiPrefix, " V "), "3.3 mV");
+        assert_eq!(format_number(12.0, NumberFormat::SiPrefix, "A"), "12 A");
+        assert_eq!(format_number(4700.0, NumberFormat::Engineering, ""), "4.7e3");
+        assert_eq!(format_number(0.0033, NumberFormat::Engineering, "V"), "3.3e-3 V");
+        assert_eq!(format_number(2e18, NumberFormat::Engineering, ""), "2e18");
+        assert_eq!(format_number(-1.5e-20, NumberFormat::Engineering, ""), "-15e-21");
+        assert_eq!(format_number(5e-324, NumberFormat::Engineering, ""), "4.94065645841e-324");
+        assert_eq!(format_number(-1e-310, NumberFormat::Engineering, "s"), "-100e-312 s");
+        assert_eq!(format_number(f64::MAX, NumberFormat::Engineering, ""), "179.769313486e306");
+        assert_eq!(format_number(4700.0, NumberFormat::Plain, "Ω"), "4700");
+    }
+
+    #[test]
+    fn test_interpretations() {
+        assert_eq!(format_interpretation(1.75, Interpretation::Time), "1:45");
+        assert_eq!(format_interpretation(1.9999, Interpretation::Time), "2:00");
+        assert_eq!(format_interpretation(-0.5, Interpretation::Time), "-0:30");
+        assert_eq!(format_interpretation(26.25, Interpretation::Time), "26:15");
+        assert_eq!(format_interpretation(12.5, Interpretation::Angle), "12°30'0.00\"");
+        assert_eq!(format_interpretation(0.07, Interpretation::Percent), "7%");
+        assert_eq!(format_interpretation(1.5, Interpretation::Percent), "150%");
+    }
+
+    #[test]
+    fn test_clean_up() {
+        let epsilon = DEFAULT_CLEANUP_EPSILON;
+        assert_eq!(clean_up(2.9999999999999996, epsilon), Some(3.0));
+        assert_eq!(clean_up(0.1 + 0.2, epsilon), Some(0.3));
+        assert_eq!(clean_up(1.1 * 1.1, epsilon), Some(1.21));
+        assert_eq!(clean_up(-4.000000000000001e20, epsilon), Some(-4e20));
+        // Already clean, or no clean decimal close enough
+        assert_eq!(clean_up(0.3, epsilon), None);
+        assert_eq!(clean_up(1.0 / 3.0, epsilon), None);
+        assert_eq!(clean_up(std::f64::consts::PI, epsilon), None);
+        assert_eq!(clean_up(1.2246467991473532e-16, epsilon), None);
+        assert_eq!(clean_up(0.0, epsilon), None);
+        // A looser tolerance reaches further
+        assert_eq!(clean_up(1.0 / 3.0, 1e-9), Some(0.333333333));
+        assert_eq!(clean_up(2.0001, 1e-4), Some(2.0));
+    }
+
+    #[test]
+    fn test_copy_formats() {
+        let copy = |value, format| format_copy(value, format);
+        assert_eq!(copy(1234.5, CopyFormat::Plain).as_deref(), Some("1234.5"));
+        assert_eq!(copy(1234567.5, CopyFormat::Thousands).as_deref(), Some("1,234,567.5"));
+        assert_eq!(copy(-123456.0, CopyFormat::Thousands).as_deref(), Some("-123,456"));
+        assert_eq!(copy(999.0, CopyFormat::Thousands).as_deref(), Some("999"));
+        assert_eq!(copy(1234.5, CopyFormat::Scientific).as_deref(), Some("1.2345e3"));
+        assert_eq!(copy(0.00025, CopyFormat::Scientific).as_deref(), Some("2.5e-4"));
+        assert_eq!(copy(1234.0, CopyFormat::Hex).as_deref(), Some("0x4D2"));
+        assert_eq!(copy(-255.0, CopyFormat::Hex).as_deref(), Some("-0xFF"));
+        assert_eq!(copy(1234.5, CopyFormat::Hex), None);
+        assert_eq!(copy(0.625, CopyFormat::Fraction).as_deref(), Some("5/8"));
+        assert_eq!(copy(0.1, CopyFormat::Fraction).as_deref(), Some("1/10"));
+        assert_eq!(copy(-1.0 / 3.0, CopyFormat::Fraction).as_deref(), Some("-1/3"));
+        assert_eq!(copy(7.0, CopyFormat::Fraction).as_deref(), Some("7"));
+        assert_eq!(copy(std::f64::consts::PI, CopyFormat::Fraction), None);
+        assert_eq!(copy(1234.5, CopyFormat::RustLiteral).as_deref(), Some("1_234.5_f64"));
+        assert_eq!(copy(-1000000.0, CopyFormat::RustLiteral).as_deref(), Some("-1_000_000.0_f64"));
+        assert_eq!(copy(0.5, CopyFormat::RustLiteral).as_deref(), Some("0.5_f64"));
+        assert_eq!(copy(f64::NAN, CopyFormat::Plain), None);
+    }
+
+    // Feature: gui-calculator, Property 61: Copied formats read back as the same number
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_copy_formats_round_trip(numerator in -100_000i64..100_000, denominator in 1i64..1000) {
+            let value = numerator as f64 / denominator as f64;
+            let read = |format| format_copy(value, format).unwrap();
+            prop_assert_eq!(read(CopyFormat::Plain).parse::<f64>().unwrap(), value);
+            prop_assert_eq!(read(CopyFormat::Thousands).replace(',', "").parse::<f64>().unwrap(), value);
+            prop_assert_eq!(read(CopyFormat::Scientific).parse::<f64>().unwrap(), value);
+            let literal = read(CopyFormat::RustLiteral);
+            prop_assert_eq!(literal.trim_end_matches("_f64").replace('_', "").parse::<f64>().unwrap(), value);
+            let fraction = simplest_fraction(value).unwrap();
+            prop_assert_eq!(fraction.to_f64(), value);
+        }
+    }
+
+    #[test]
+    fn test_money() {
+        let dollars = MoneyStyle::default();
+        assert_eq!(format_money(1234.5, &dollars), "$1,234.50");
+        assert_eq!(format_money(-1234567.891, &dollars), "-$1,234,567.89");
+        assert_eq!(format_money(0.125, &dollars), "$0.13");
+        assert_eq!(format_money(1.005, &dollars), "$1.01");
+        assert_eq!(format_money(-0.004, &dollars), "$0.00");
+        assert_eq!(format_money(7.0, &dollars), "$7.00");
+        assert_eq!(format_number(99.9, NumberFormat::Money, "Ω"), "$99.90");
+
+        let bank = MoneyStyle {
+            symbol: String::from("€"),
+            bankers_rounding: true,
+            accounting: true,
+        };
+        assert_eq!(format_money(0.125, &bank), "€0.12");
+        assert_eq!(format_money(0.135, &bank), "€0.14");
+        assert_eq!(format_money(-2.5, &bank), "(€2.50)");
+        assert_eq!(format_money(-1234.565, &bank), "(€1,234.56)");
+        let francs = MoneyStyle {
+            symbol: String::from("CHF "),
+            ..MoneyStyle::default()
+        };
+        assert_eq!(format_money(5.0, &francs), "CHF 5.00");
+    }
+
+    #[test]
+    fn test_desk_rounding() {
+        use DecimalPlaces::*;
+        use RoundingSwitch::*;
+        assert_eq!(format_desk(2.0 / 3.0, Float, Two), "0.6666666666666666");
+        assert_eq!(format_desk(2.0 / 3.0, Cut, Two), "0.66");
+        assert_eq!(format_desk(2.0 / 3.0, Round, Two), "0.67");
+        assert_eq!(format_desk(1.005, Round, Two), "1.01");
+        assert_eq!(format_desk(-2.5, Round, Zero), "-3");
+        assert_eq!(format_desk(-2.59, Cut, One), "-2.5");
+        assert_eq!(format_desk(12.5, Round, Add2), "12.50");
+        assert_eq!(format_desk(7.0, Cut, Three), "7.000");
+        assert_eq!(format_desk(-0.004, Round, Two), "0.00");
+        // Too large for a decimal, and nothing after the point to round
+        assert_eq!(format_desk(1e300, Round, Two), 1e300.to_string());
+    }
+
+    #[test]
+    fn test_si_parsing() {
+        assert_eq!(parse_si("5k"), Some(5000.0));
+        assert_eq!(parse_si("2.2M"), Some(2_200_000.0));
+        assert_eq!(parse_si("10µ"), Some(1e-5));
+        assert_eq!(parse_si("10u"), Some(1e-5));
+        assert_eq!(parse_si("42"), Some(42.0));
+        assert_eq!(parse_si("k"), None);
+        assert_eq!(parse_si("5x"), None);
+    }
+
+    // Feature: gui-calculator, Property 30: SI formatting parses back to the same value
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_si_round_trip(mantissa in 1.0..1000.0f64, power in -15i32..=15) {
+            let value = mantissa * 10f64.powi(power);
+            let (scaled, prefix) = si_parts(value);
+            prop_assert!((1.0..1000.0).contains(&scaled.abs()), "{} gave mantissa {}", value, scaled);
+
+            let text = match prefix {
+                Some(prefix) => format!("{}{}", scaled, prefix),
+                None => scaled.to_string(),
+            };
+            let parsed = parse_si(&text).unwrap();
+            prop_assert!((parsed - value).abs() <= value.abs() * 1e-11, "{} formatted as {}", value, text);
+        }
+
+        // Feature: gui-calculator, Property 60: Cleanup stays within epsilon and only ever shortens
+        #[test]
+        fn test_clean_up_bounded(value in any::<f64>(), exponent in 4i32..16) {
+            let epsilon = 10f64.powi(-exponent);
+            if let Some(clean) = clean_up(value, epsilon) {
+                prop_assert!(((clean - value) / value).abs() <= epsilon);
+                let digits = |value: f64| format!("{:e}", value).len();
+                prop_assert!(digits(clean) < digits(value), "{} cleaned to {}", value, clean);
+            }
+        }
+    }
+}
@@ -0,0 +1,270 @@
+// Spreadsheet Grid
+//
+// A small grid of cells, each empty, a number, a text label or a formula
+// starting with `=` that refers to other cells by name (`=A1+B2`). Formulas
+// are evaluated in dependency order, so each sees the values of the cells it
+// uses, whatever their position.
+use std::collections::HashMap;
+
+use crate::error::CalculatorError;
+use crate::expression::{self, Environment, Expr, Functions, ParseOptions};
+use crate::number;
+
+pub const COLUMNS: usize = 5;
+pub const ROWS: usize = 10;
+pub const CELLS: usize = COLUMNS * ROWS;
+
+/// The position of a cell; `A1` is column 0, row 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CellRef {
+    pub column: usize,
+    pub row: usize,
+}
+
+impl CellRef {
+    /// The cell at `index` in a row-by-row list of the grid.
+    pub fn from_index(index: usize) -> Self {
+        Self {
+            column: index % COLUMNS,
+            row: index / COLUMNS,
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self.row * COLUMNS + self.column
+    }
+
+    /// The cell called `name`, e.g. `B7` or `b7`, if it's in the grid.
+    pub fn parse(name: &str) -> Option<Self> {
+        let mut chars = name.chars();
+        let letter = chars.next()?.to_ascii_uppercase();
+        let digits = chars.as_str();
+        if !letter.is_ascii_uppercase() || digits.starts_with('0') || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let column = usize::from(letter as u8 - b'A');
+        let row = digits.parse::<usize>().ok()?.checked_sub(1)?;
+        (column < COLUMNS && row < ROWS).then_some(Self { column, row })
+    }
+
+    pub fn name(self) -> String {
+        format!("{}{}", column_name(self.column), self.row + 1)
+    }
+}
+
+pub fn column_name(column: usize) -> char {
+    char::from(b'A' + column as u8)
+}
+
+enum Content {
+    Empty,
+    Text,
+    Number(f64),
+    Formula(Result<Expr, CalculatorError>),
+}
+
+fn content(cell: &str, options: ParseOptions) -> Content {
+    let cell = cell.trim();
+    if cell.is_empty() {
+        Content::Empty
+    } else if let Some(formula) = cell.strip_prefix('=') {
+        Content::Formula(expression::parse_with(formula, options))
+    } else {
+        match cell.parse::<f64>() {
+            Ok(value) if value.is_finite() => Content::Number(value),
+            _ => Content::Text,
+        }
+    }
+}
+
+/// Evaluates the cells of a grid, given row by row, producing one entry per
+/// cell: `None` for empty and text cells, otherwise the cell's value.
+///
+/// Empty cells count as 0 in formulas and text cells can't be used in them.
+/// A formula using a cell that failed fails the same way, and formulas that
+/// depend on themselves fail with a circular reference.
+pub fn evaluate(
+    cells: &[String],
+    functions: Option<&dyn Functions>,
+    options: ParseOptions,
+) -> Vec<Option<Result<f64, CalculatorError>>> {
+    let contents: Vec<Content> = cells.iter().map(|cell| content(cell, options)).collect();
+    let mut results: Vec<Option<Result<f64, CalculatorError>>> = contents
+        .iter()
+        .map(|content| match content {
+            Content::Empty | Content::Text => None,
+            Content::Number(value) => Some(Ok(*value)),
+            Content::Formula(Err(err)) => Some(Err(err.clone())),
+            Content::Formula(Ok(_)) => None,
+        })
+        .collect();
+
+    // The formulas each formula waits on, and which formulas wait on each
+    let mut waiting_on = vec![0usize; cells.len()];
+    let mut dependents: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut references: Vec<Vec<usize>> = vec![Vec::new(); cells.len()];
+    for (index, content) in contents.iter().enumerate() {
+        let Content::Formula(Ok(expr)) = content else {
+            continue;
+        };
+        let mut used: Vec<usize> = expr
+            .variables()
+            .into_iter()
+            .filter_map(CellRef::parse)
+            .map(CellRef::index)
+            .filter(|&used| used < cells.len())
+            .collect();
+        used.sort_unstable();
+        used.dedup();
+        for &used in &used {
+            if matches!(contents[used], Content::Formula(Ok(_))) {
+                waiting_on[index] += 1;
+                dependents.entry(used).or_default().push(index);
+            }
+        }
+        references[index] = used;
+    }
+
+    // Formulas become ready once everything they use has a value
+    let mut ready: Vec<usize> = (0..cells.len())
+        .filter(|&index| matches!(contents[index], Content::Formula(Ok(_))) && waiting_on[index] == 0)
+        .collect();
+    let mut env = Environment::new();
+    env.functions = functions;
+    while let Some(index) = ready.pop() {
+        let Content::Formula(Ok(expr)) = &contents[index] else {
+            continue;
+        };
+        let result = evaluate_formula(expr, &references[index], &contents, &results, &mut env);
+        results[index] = Some(result);
+        for &dependent in dependents.get(&index).into_iter().flatten() {
+            waiting_on[dependent] -= 1;
+            if waiting_on[dependent] == 0 {
+                ready.push(dependent);
+            }
+        }
+    }
+
+    // Whatever never became ready is in a cycle or uses one
+    for (index, result) in results.iter_mut().enumerate() {
+        if matches!(contents[index], Content::Formula(Ok(_))) && result.is_none() {
+            *result = Some(Err(CalculatorError::CircularReference(CellRef::from_index(index).name())));
+        }
+    }
+    results
+}
+
+fn evaluate_formula(
+    expr: &Expr,
+    references: &[usize],
+    contents: &[Content],
+    results: &[Option<Result<f64, CalculatorError>>],
+    env: &mut Environment,
+) -> Result<f64, CalculatorError> {
+    env.variables.clear();
+    for &used in references {
+        let value = match (&contents[used], &results[used]) {
+            (Content::Empty, _) => 0.0,
+            (_, Some(Ok(value))) => *value,
+            (_, Some(Err(err))) => return Err(err.clone()),
+            // Text isn't a value; leaving it out reports the cell as unknown
+            _ => continue,
+        };
+        // Lowercase, as lookups fall back to the lowercase name
+        env.variables.insert(CellRef::from_index(used).name().to_ascii_lowercase(), value);
+    }
+    let value = number::in_range(expr.eval(env)?)?;
+    Ok(number::unsigned_zero(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn grid(cells: &[(&str, &str)]) -> Vec<String> {
+        let mut grid = vec![String::new(); CELLS];
+        for (name, text) in cells {
+            grid[CellRef::parse(name).unwrap().index()] = text.to_string();
+        }
+        grid
+    }
+
+    fn value(results: &[Option<Result<f64, CalculatorError>>], name: &str) -> Option<Result<f64, CalculatorError>> {
+        results[CellRef::parse(name).unwrap().index()].clone()
+    }
+
+    #[test]
+    fn test_cell_names() {
+        assert_eq!(CellRef::parse("A1"), Some(CellRef { column: 0, row: 0 }));
+        assert_eq!(CellRef::parse("e10"), Some(CellRef { column: 4, row: 9 }));
+        assert_eq!(CellRef::parse("F1"), None);
+        assert_eq!(CellRef::parse("A11"), None);
+        assert_eq!(CellRef::parse("A0"), None);
+        assert_eq!(CellRef::parse("A01"), None);
+        assert_eq!(CellRef::parse("pi"), None);
+        assert_eq!(CellRef::from_index(7).name(), "C2");
+    }
+
+    #[test]
+    fn test_formulas_in_dependency_order() {
+        // D1 uses cells further down and right, B2 uses D1
+        let cells = grid(&[
+            ("A1", "Price"),
+            ("D1", "=d2 * (1 + E2)"),
+            ("D2", "120"),
+            ("E2", "0.25"),
+            ("B2", "=D1 + A3 + sqrt(16)"),
+        ]);
+        let results = evaluate(&cells, None, ParseOptions::default());
+        assert_eq!(value(&results, "A1"), None);
+        assert_eq!(value(&results, "D1"), Some(Ok(150.0)));
+        // A3 is empty and counts as 0
+        assert_eq!(value(&results, "B2"), Some(Ok(154.0)));
+        assert_eq!(value(&results, "C5"), None);
+    }
+
+    #[test]
+    fn test_errors_and_cycles() {
+        let cells = grid(&[
+            ("A1", "=1 / 0"),
+            ("A2", "=A1 + 1"),
+            ("B1", "=B2"),
+            ("B2", "=B1 * 2"),
+            ("B3", "=B2"),
+            ("C1", "=C1"),
+            ("C2", "Label"),
+            ("C3", "=C2 + 1"),
+            ("D1", "=1 +"),
+        ]);
+        let results = evaluate(&cells, None, ParseOptions::default());
+        assert_eq!(value(&results, "A2"), Some(Err(CalculatorError::DivisionByZero)));
+        assert_eq!(value(&results, "B1"), Some(Err(CalculatorError::CircularReference("B1".into()))));
+        assert_eq!(value(&results, "B3"), Some(Err(CalculatorError::CircularReference("B3".into()))));
+        assert_eq!(value(&results, "C1"), Some(Err(CalculatorError::CircularReference("C1".into()))));
+        assert_eq!(value(&results, "C3"), Some(Err(CalculatorError::UnknownVariable("C2".into()))));
+        assert!(matches!(value(&results, "D1"), Some(Err(CalculatorError::Syntax { .. }))));
+    }
+
+    // Feature: gui-calculator, Property 43: A chain of formulas gives the same total wherever its cells are
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_chain_in_any_layout(
+            layout in Just((0..CELLS).collect::<Vec<usize>>()).prop_shuffle(),
+            length in 1usize..CELLS,
+            start in -1000i32..1000
+        ) {
+            // Each cell of the chain adds 1 to the one before it
+            let mut cells = vec![String::new(); CELLS];
+            cells[layout[0]] = start.to_string();
+            for step in 1..length {
+                cells[layout[step]] = format!("={} + 1", CellRef::from_index(layout[step - 1]).name());
+            }
+            let results = evaluate(&cells, None, ParseOptions::default());
+            let last = results[layout[length - 1]].clone();
+            prop_assert_eq!(last, Some(Ok(f64::from(start) + (length - 1) as f64)));
+        }
+    }
+}
@@ -0,0 +1,177 @@
+// Expression Syntax Highlighting
+use crate::error::CalculatorError;
+use crate::expression::{tokenize, Token, TokenKind};
+
+/// What a stretch of expression text is, for coloring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Number,
+    Operator,
+    /// An identifier followed by `(`
+    Function,
+    /// A variable or constant
+    Name,
+    Paren,
+    Comma,
+    /// From the first character the tokenizer rejects to the end
+    Invalid,
+}
+
+/// A classified byte range of the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub class: Class,
+    pub start: usize,
+    pub end: usize,
+}
+
+// The byte offset of a 1-based character column
+fn byte_offset(input: &str, column: usize) -> usize {
+    input
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map_or(input.len(), |(index, _)| index)
+}
+
+// Tokens up to the first tokenizer error, and where that error starts
+fn tokens(input: &str) -> (Vec<Token>, Option<usize>) {
+    match tokenize(input) {
+        Ok(tokens) => (tokens, None),
+        Err(CalculatorError::Syntax { column, .. }) => {
+            let offset = byte_offset(input, column);
+            (tokenize(&input[..offset]).unwrap_or_default(), Some(offset))
+        }
+        Err(_) => (Vec::new(), Some(0)),
+    }
+}
+
+/// Classifies `input` with the same tokenizer the parser uses, so the colors
+/// never disagree with what gets evaluated. Whitespace is left out.
+pub fn classify(input: &str) -> Vec<Span> {
+    let (tokens, error) = tokens(input);
+    let mut spans: Vec<Span> = tokens
+        .iter()
+        .enumerate()
+        .map(|(index, token)| {
+            let class = match token.kind {
+                TokenKind::Number(_) => Class::Number,
+                TokenKind::Operator(_) | TokenKind::Equals => Class::Operator,
+                TokenKind::Identifier(_) => {
+                    let called = tokens.get(index + 1).map(|next| &next.kind) == Some(&TokenKind::LeftParen);
+                    if called {
+                        Class::Function
+                    } else {
+                        Class::Name
+                    }
+                }
+                TokenKind::LeftParen | TokenKind::RightParen => Class::Paren,
+                TokenKind::LeftBracket | TokenKind::RightBracket => Class::Paren,
+                TokenKind::Comma | TokenKind::Semicolon => Class::Comma,
+            };
+            Span {
+                class,
+                start: token.start,
+                end: token.end,
+            }
+        })
+        .collect();
+
+    if let Some(start) = error {
+        spans.push(Span {
+            class: Class::Invalid,
+            start,
+            end: input.len(),
+        });
+    }
+    spans
+}
+
+/// The byte offsets of the parenthesis next to the caret and its partner.
+/// The caret is a character index; the paren just before it wins over the one
+/// just after, as in most editors. Unbalanced parens have no partner.
+pub fn matching_paren(input: &str, caret: usize) -> Option<(usize, usize)> {
+    let (tokens, _) = tokens(input);
+    let caret = byte_offset(input, caret + 1);
+    let is_paren = |token: &Token| matches!(token.kind, TokenKind::LeftParen | TokenKind::RightParen);
+    let index = tokens
+        .iter()
+        .position(|token| is_paren(token) && token.end == caret)
+        .or_else(|| tokens.iter().position(|token| is_paren(token) && token.start == caret))?;
+
+    let mut depth = 0i32;
+    let step = |token: &Token| match token.kind {
+        TokenKind::LeftParen => 1,
+        TokenKind::RightParen => -1,
+        _ => 0,
+    };
+    if tokens[index].kind == TokenKind::LeftParen {
+        for token in &tokens[index..] {
+            depth += step(token);
+            if depth == 0 {
+                return Some((tokens[index].start, token.start));
+            }
+        }
+    } else {
+        for token in tokens[..=index].iter().rev() {
+            depth += step(token);
+            if depth == 0 {
+                return Some((tokens[index].start, token.start));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn classes(input: &str) -> Vec<Class> {
+        classify(input).iter().map(|span| span.class).collect()
+    }
+
+    #[test]
+    fn test_classify() {
+        use Class::*;
+        assert_eq!(
+            classes("sqrt(x) + 2.5k"),
+            [Function, Paren, Name, Paren, Operator, Number]
+        );
+        assert_eq!(classes("min(1, pi)"), [Function, Paren, Number, Comma, Name, Paren]);
+        // Everything from the rejected character on is invalid
+        let spans = classify("1 + $x");
+        assert_eq!(spans.last(), Some(&Span { class: Invalid, start: 4, end: 6 }));
+        assert_eq!(classes("1.2.3 + 4"), [Invalid]);
+        assert!(classify("").is_empty());
+    }
+
+    #[test]
+    fn test_matching_paren() {
+        let input = "(1 + (2 * 3))";
+        // Caret after the last paren, before the first, and between inner ones
+        assert_eq!(matching_paren(input, 13), Some((12, 0)));
+        assert_eq!(matching_paren(input, 0), Some((0, 12)));
+        assert_eq!(matching_paren(input, 6), Some((5, 11)));
+        assert_eq!(matching_paren(input, 3), None);
+        assert_eq!(matching_paren("(1 + 2", 1), None);
+        assert_eq!(matching_paren("µ(1)", 1), Some((2, 4)));
+    }
+
+    // Feature: gui-calculator, Property 31: Highlight spans are ordered, disjoint and cover all non-space text
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_spans_cover_input(input in "[0-9a-z+*/^(),. $-]{0,20}") {
+            let spans = classify(&input);
+            let mut covered = 0;
+            for span in &spans {
+                prop_assert!(span.start >= covered && span.start < span.end);
+                prop_assert!(input[covered..span.start].trim().is_empty(), "gap in {:?}", input);
+                covered = span.end;
+            }
+            prop_assert!(input[covered..].trim().is_empty(), "uncovered tail in {:?}", input);
+        }
+    }
+}
@@ -0,0 +1,534 @@
+// Calculation History
+use serde::{Deserialize, Serialize};
+
+use crate::batch::csv_field;
+use crate::expression::Expr;
+
+/// One completed calculation, keeping the parsed expression so it can be
+/// recalled for editing rather than just its result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unique within a history; assigned when the entry is pushed
+    #[serde(default)]
+    pub id: u64,
+    /// The expression as the user typed it
+    pub source: String,
+    pub expression: Expr,
+    pub result: f64,
+}
+
+impl HistoryEntry {
+    pub fn new(source: impl Into<String>, expression: Expr, result: f64) -> Self {
+        Self {
+            id: 0,
+            source: source.into(),
+            expression,
+            result,
+        }
+    }
+
+    /// Builds an entry for an expression that has no typed form, e.g. from the keypad.
+    pub fn from_expression(expression: Expr, result: f64) -> Self {
+        Self::new(expression.to_string(), expression, result)
+    }
+}
+
+/// Completed calculations, oldest first, optionally capped in length.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    #[serde(default)]
+    next_id: u64,
+    // Comes from the settings, so it isn't persisted with the entries
+    #[serde(skip)]
+    limit: Option<usize>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, mut entry: HistoryEntry) {
+        self.next_id += 1;
+        entry.id = self.next_id;
+        self.entries.push(entry);
+        self.trim();
+    }
+
+    /// Caps the history at `limit` entries, dropping the oldest ones first.
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+        self.trim();
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Deletes the entry with `id`. Ids of the other entries don't change,
+    /// and aren't reused.
+    pub fn remove(&mut self, id: u64) -> Option<HistoryEntry> {
+        let index = self.entries.iter().position(|entry| entry.id == id)?;
+        Some(self.entries.remove(index))
+    }
+
+    fn trim(&mut self) {
+        if let Some(limit) = self.limit {
+            if self.entries.len() > limit {
+                let excess = self.entries.len() - limit;
+                self.entries.drain(..excess);
+            }
+        }
+    }
+
+    /// Entries from oldest to newest.
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn last(&self) -> Option<&HistoryEntry> {
+        self.entries.last()
+    }
+
+    /// The last `count` finite results, oldest first, for plotting a trend.
+    pub fn recent_results(&self, count: usize) -> Vec<f64> {
+        let mut results: Vec<f64> = self
+            .entries
+            .iter()
+            .rev()
+            .map(|entry| entry.result)
+            .filter(|result| result.is_finite())
+            .take(count)
+            .collect();
+        results.reverse();
+        results
+    }
+
+    /// The entries as CSV with `expression,result` columns, oldest first.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("expression,result\n");
+        for entry in &self.entries {
+            csv.push_str(&format!("{},{}\n", csv_field(&entry.source), entry.result));
+        }
+        csv
+    }
+}
+
+/// Pinned calculations. Kept apart from `History` so they outlive it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Favorites {
+    entries: Vec<HistoryEntry>,
+}
+
+impl Favorites {
+    /// Pins the entry unless the same calculation is already pinned.
+    pub fn pin(&mut self, entry: HistoryEntry) {
+        if !self.is_pinned(&entry) {
+            self.entries.push(entry);
+        }
+    }
+
+    pub fn unpin(&mut self, index: usize) {
+        if index < self.entries.len() {
+            self.entries.remove(index);
+        }
+    }
+
+    pub fn is_pinned(&self, entry: &HistoryEntry) -> bool {
+        self.entries
+            .iter()
+            .any(|pinned| pinned.source == entry.source && pinned.result == entry.result)
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+}
+
+/// How many values `RecentValues` keeps.
+pub const RECENT_LIMIT: usize = 10;
+
+/// The last few distinct numbers used, newest first. Kept apart from
+/// `History` so typed operands count too and clearing the history keeps them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecentValues {
+    values: Vec<f64>,
+}
+
+impl RecentValues {
+    /// Moves `value` to the front, dropping the oldest beyond the limit.
+    /// Infinities and NaN aren't worth reinserting and are skipped.
+    pub fn push(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        self.values.retain(|&recent| recent != value);
+        self.values.insert(0, value);
+        self.values.truncate(RECENT_LIMIT);
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+}
+
+/// Shell-style Up/Down recall of past expressions. Up steps back from the
+/// newest entry, Down forward again; stepping past the newest gives back
+/// whatever was being typed before browsing started.
+#[derive(Debug, Clone, Default)]
+pub struct Recall {
+    // Index into the history's entries; None when not browsing
+    position: Option<usize>,
+    draft: String,
+}
+
+impl Recall {
+    /// The next older source, or None at the oldest entry. `current` is the
+    /// text being shown, kept as the draft when browsing starts; entries with
+    /// the same source as the one shown are skipped, like a shell's ignoredups.
+    pub fn older(&mut self, history: &History, current: &str) -> Option<String> {
+        let entries = history.entries();
+        let end = self.position.unwrap_or(entries.len()).min(entries.len());
+        let index = (0..end).rev().find(|&index| entries[index].source != current)?;
+        if self.position.is_none() {
+            self.draft = current.to_string();
+        }
+        self.position = Some(index);
+        Some(entries[index].source.clone())
+    }
+
+    /// The next newer source, the draft past the newest entry, or None when
+    /// not browsing.
+    pub fn newer(&mut self, history: &History) -> Option<String> {
+        let entries = history.entries();
+        let position = self.position?;
+        let shown = entries.get(position).map(|entry| entry.source.as_str());
+        match (position + 1..entries.len()).find(|&index| Some(entries[index].source.as_str()) != shown) {
+            Some(index) => {
+                self.position = Some(index);
+                Some(entries[index].source.clone())
+            }
+            None => {
+                self.position = None;
+                Some(std::mem::take(&mut self.draft))
+            }
+        }
+    }
+
+    pub fn is_browsing(&self) -> bool {
+        self.position.is_some()
+    }
+
+    /// Stops browsing, once the recalled text is edited or evaluated.
+    pub fn reset(&mut self) {
+        self.position = None;
+        self.draft.clear();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+}
+
+impl Comparison {
+    fn holds(self, value: f64, bound: f64) -> bool {
+        match self {
+            Comparison::Less => value < bound,
+            Comparison::LessOrEqual => value <= bound,
+            Comparison::Greater => value > bound,
+            Comparison::GreaterOrEqual => value >= bound,
+            Comparison::Equal => value == bound,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterTerm {
+    /// Case-insensitive substring of the expression or the result
+    Text(String),
+    /// Condition on the result, e.g. `> 1000`
+    Compare(Comparison, f64),
+    /// Inclusive range of results, e.g. `10..20`
+    Range(f64, f64),
+}
+
+impl FilterTerm {
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        match self {
+            FilterTerm::Text(text) => {
+                entry.source.to_lowercase().contains(text.as_str())
+                    || entry.result.to_string().contains(text.as_str())
+            }
+            FilterTerm::Compare(comparison, bound) => comparison.holds(entry.result, *bound),
+            FilterTerm::Range(low, high) => *low <= entry.result && entry.result <= *high,
+        }
+    }
+}
+
+/// A history search query; every term has to match.
+///
+/// Queries are whitespace-separated terms such as `sqrt`, `> 1000`,
+/// `results >= 5` or `10..20`. Anything that isn't a comparison or a range
+/// is searched for as text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryFilter {
+    terms: Vec<FilterTerm>,
+}
+
+impl HistoryFilter {
+    pub fn parse(query: &str) -> Self {
+        let mut terms = Vec::new();
+        let mut words = query.split_whitespace().peekable();
+
+        while let Some(word) = words.next() {
+            let lower = word.to_lowercase();
+            // "results > 1000" reads naturally, the word itself adds nothing
+            let before_comparison = words.peek().is_some_and(|next| split_comparison(next).is_some());
+            if (lower == "result" || lower == "results") && before_comparison {
+                continue;
+            }
+
+            if let Some((comparison, rest)) = split_comparison(word) {
+                // Allow a space between the operator and the number
+                let number = if rest.is_empty() { words.peek().copied() } else { Some(rest) };
+                if let Some(bound) = number.and_then(|number| number.parse::<f64>().ok()) {
+                    if rest.is_empty() {
+                        words.next();
+                    }
+                    terms.push(FilterTerm::Compare(comparison, bound));
+                    continue;
+                }
+            }
+
+            if let Some((low, high)) = word.split_once("..") {
+                if let (Ok(low), Ok(high)) = (low.parse::<f64>(), high.parse::<f64>()) {
+                    terms.push(FilterTerm::Range(low.min(high), low.max(high)));
+                    continue;
+                }
+            }
+
+            terms.push(FilterTerm::Text(lower));
+        }
+
+        Self { terms }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        self.terms.iter().all(|term| term.matches(entry))
+    }
+}
+
+fn split_comparison(word: &str) -> Option<(Comparison, &str)> {
+    // Two-character operators first so ">=" isn't read as ">"
+    [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+        ("=", Comparison::Equal),
+    ]
+    .into_iter()
+    .find_map(|(symbol, comparison)| word.strip_prefix(symbol).map(|rest| (comparison, rest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::parse;
+    use proptest::prelude::*;
+
+    fn entry(source: &str, result: f64) -> HistoryEntry {
+        HistoryEntry::new(source, parse(source).unwrap(), result)
+    }
+
+    #[test]
+    fn test_recent_values() {
+        let mut recent = RecentValues::default();
+        for value in [1.0, 2.0, 1.0, f64::NAN, 3.0] {
+            recent.push(value);
+        }
+        assert_eq!(recent.values(), &[3.0, 1.0, 2.0]);
+
+        for value in 0..20 {
+            recent.push(value as f64);
+        }
+        assert_eq!(recent.values().len(), RECENT_LIMIT);
+        assert_eq!(recent.values()[0], 19.0);
+    }
+
+    #[test]
+    fn test_recall_steps_through_sources() {
+        let mut history = History::new();
+        for (source, result) in [("1 + 1", 2.0), ("2 * 3", 6.0), ("2 * 3", 6.0), ("sqrt(16)", 4.0)] {
+            history.push(entry(source, result));
+        }
+        let mut recall = Recall::default();
+        assert_eq!(recall.newer(&history), None);
+        assert_eq!(recall.older(&history, "5 -").as_deref(), Some("sqrt(16)"));
+        // The repeated entry is only visited once
+        assert_eq!(recall.older(&history, "sqrt(16)").as_deref(), Some("2 * 3"));
+        assert_eq!(recall.older(&history, "2 * 3").as_deref(), Some("1 + 1"));
+        assert_eq!(recall.older(&history, "1 + 1"), None);
+        assert!(recall.is_browsing());
+        assert_eq!(recall.newer(&history).as_deref(), Some("2 * 3"));
+        assert_eq!(recall.newer(&history).as_deref(), Some("sqrt(16)"));
+        // Past the newest entry the unfinished input comes back
+        assert_eq!(recall.newer(&history).as_deref(), Some("5 -"));
+        assert!(!recall.is_browsing());
+        assert_eq!(recall.newer(&history), None);
+
+        assert_eq!(recall.older(&history, "").as_deref(), Some("sqrt(16)"));
+        recall.reset();
+        assert_eq!(recall.newer(&history), None);
+        assert_eq!(Recall::default().older(&History::new(), "1"), None);
+    }
+
+    #[test]
+    fn test_recent_results() {
+        let mut history = History::new();
+        for (source, result) in [("1", 1.0), ("1 / 0", f64::INFINITY), ("2", 2.0), ("3", 3.0)] {
+            history.push(entry(source, result));
+        }
+        assert_eq!(history.recent_results(2), &[2.0, 3.0]);
+        assert_eq!(history.recent_results(10), &[1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let mut history = History::new();
+        history.push(entry("1 + 2", 3.0));
+        history.push(entry("max(1, 2)", 2.0));
+        assert_eq!(history.to_csv(), "expression,result\n1 + 2,3\n\"max(1, 2)\",2\n");
+    }
+
+    #[test]
+    fn test_filter_query_parsing() {
+        assert_eq!(
+            HistoryFilter::parse("results > 1000").terms,
+            &[FilterTerm::Compare(Comparison::Greater, 1000.0)]
+        );
+        assert_eq!(
+            HistoryFilter::parse("SQRT >=5 20..10").terms,
+            &[
+                FilterTerm::Text(String::from("sqrt")),
+                FilterTerm::Compare(Comparison::GreaterOrEqual, 5.0),
+                FilterTerm::Range(10.0, 20.0),
+            ]
+        );
+        // An operator without a number is just text
+        assert_eq!(HistoryFilter::parse(">").terms, &[FilterTerm::Text(String::from(">"))]);
+        assert!(HistoryFilter::parse("   ").is_empty());
+    }
+
+    #[test]
+    fn test_filter_matches_expression_or_result() {
+        let sqrt = entry("sqrt(16)", 4.0);
+        let big = entry("1500 * 2", 3000.0);
+
+        assert!(HistoryFilter::parse("sqrt").matches(&sqrt));
+        assert!(!HistoryFilter::parse("sqrt").matches(&big));
+        assert!(HistoryFilter::parse("3000").matches(&big));
+        assert!(HistoryFilter::parse("results > 1000").matches(&big));
+        assert!(!HistoryFilter::parse("results > 1000").matches(&sqrt));
+        assert!(HistoryFilter::parse("* 1..5000").matches(&big));
+        assert!(HistoryFilter::parse("").matches(&sqrt));
+    }
+
+    #[test]
+    fn test_remove_by_id() {
+        let mut history = History::new();
+        history.push(entry("1 + 1", 2.0));
+        history.push(entry("2 * 3", 6.0));
+        let first = history.entries()[0].id;
+
+        assert_eq!(history.remove(first).map(|entry| entry.result), Some(2.0));
+        assert_eq!(history.remove(first), None);
+        assert_eq!(history.entries().len(), 1);
+        history.push(entry("3 - 1", 2.0));
+        assert!(history.entries()[1].id > history.entries()[0].id);
+    }
+
+    // Feature: gui-calculator, Property 16: History length stays within its limit
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_history_limit(
+            results in prop::collection::vec(-1000.0..1000.0f64, 0..=40),
+            limit in 1usize..=20,
+            limit_first in prop::bool::ANY
+        ) {
+            let mut history = History::new();
+            if limit_first {
+                history.set_limit(Some(limit));
+            }
+            for &result in &results {
+                history.push(entry("1 + 1", result));
+            }
+            if !limit_first {
+                history.set_limit(Some(limit));
+            }
+
+            // Only the newest entries survive, in their original order
+            let kept: Vec<f64> = history.entries().iter().map(|entry| entry.result).collect();
+            let start = results.len().saturating_sub(limit);
+            prop_assert_eq!(kept, results[start..].to_vec());
+            prop_assert!(history.entries().windows(2).all(|pair| pair[0].id < pair[1].id),
+                "Entry ids should be unique and increasing");
+
+            history.clear();
+            prop_assert!(history.entries().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_favorites_pin_once() {
+        let mut favorites = Favorites::default();
+        favorites.pin(entry("0.19 * 100", 19.0));
+        favorites.pin(entry("0.19 * 100", 19.0));
+        favorites.pin(entry("sqrt(2)", std::f64::consts::SQRT_2));
+
+        assert_eq!(favorites.entries().len(), 2);
+        assert!(favorites.is_pinned(&entry("sqrt(2)", std::f64::consts::SQRT_2)));
+
+        favorites.unpin(0);
+        favorites.unpin(5);
+        assert_eq!(favorites.entries().len(), 1);
+        assert!(!favorites.is_pinned(&entry("0.19 * 100", 19.0)));
+    }
+
+    // Feature: gui-calculator, Property 15: Numeric history filters agree with comparisons
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_numeric_filters(
+            result in -1000000.0..1000000.0f64,
+            bound in -1000000.0..1000000.0f64,
+            width in 0.0..1000.0f64
+        ) {
+            let entry = entry("1 + 1", result);
+
+            prop_assert_eq!(HistoryFilter::parse(&format!("> {}", bound)).matches(&entry), result > bound);
+            prop_assert_eq!(HistoryFilter::parse(&format!("results <={}", bound)).matches(&entry), result <= bound);
+            prop_assert_eq!(
+                HistoryFilter::parse(&format!("{}..{}", bound, bound + width)).matches(&entry),
+                bound <= result && result <= bound + width
+            );
+        }
+    }
+}
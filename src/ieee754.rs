@@ -0,0 +1,173 @@
+// IEEE-754 Double Precision Layout
+const SIGN_BIT: u64 = 1 << 63;
+const MANTISSA_BITS: u32 = 52;
+const EXPONENT_BIAS: i32 = 1023;
+
+/// The three fields of an `f64`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatBits {
+    pub sign: bool,
+    /// The biased 11-bit exponent as stored
+    pub exponent: u16,
+    /// The 52 stored mantissa bits, without the implicit leading 1
+    pub mantissa: u64,
+}
+
+impl FloatBits {
+    pub fn of(value: f64) -> Self {
+        let bits = value.to_bits();
+        Self {
+            sign: bits & SIGN_BIT != 0,
+            exponent: ((bits >> MANTISSA_BITS) & 0x7FF) as u16,
+            mantissa: bits & ((1 << MANTISSA_BITS) - 1),
+        }
+    }
+
+    /// The power of two the mantissa is scaled by. Subnormals share the
+    /// exponent of the smallest normal number.
+    pub fn unbiased_exponent(&self) -> i32 {
+        if self.exponent == 0 {
+            1 - EXPONENT_BIAS
+        } else {
+            self.exponent as i32 - EXPONENT_BIAS
+        }
+    }
+
+    /// The bits grouped as `sign exponent mantissa`.
+    pub fn to_binary(self) -> String {
+        format!("{} {:011b} {:052b}", self.sign as u8, self.exponent, self.mantissa)
+    }
+}
+
+pub fn to_hex(value: f64) -> String {
+    format!("0x{:016X}", value.to_bits())
+}
+
+/// The smallest `f64` greater than `value`.
+pub fn next_up(value: f64) -> f64 {
+    if value.is_nan() || value == f64::INFINITY {
+        return value;
+    }
+    if value == 0.0 {
+        // The smallest subnormal, for both +0 and -0
+        return f64::from_bits(1);
+    }
+    let bits = value.to_bits();
+    f64::from_bits(if value > 0.0 { bits + 1 } else { bits - 1 })
+}
+
+/// The largest `f64` less than `value`.
+pub fn next_down(value: f64) -> f64 {
+    -next_up(-value)
+}
+
+/// The gap between `value` and the next representable number away from zero.
+pub fn ulp(value: f64) -> f64 {
+    let magnitude = value.abs();
+    next_up(magnitude) - magnitude
+}
+
+/// How many representable numbers apart `a` and `b` are; `None` for NaN.
+pub fn ulp_distance(a: f64, b: f64) -> Option<u64> {
+    if a.is_nan() || b.is_nan() {
+        return None;
+    }
+    // Maps floats onto integers in the same order, with +0 and -0 both at 0
+    let ordered = |value: f64| {
+        let bits = value.to_bits();
+        if bits & SIGN_BIT != 0 {
+            -((bits & !SIGN_BIT) as i128)
+        } else {
+            bits as i128
+        }
+    };
+    Some((ordered(a) - ordered(b)).unsigned_abs() as u64)
+}
+
+/// Every decimal digit of the number `value` actually stores, e.g.
+/// 0.1000000000000000055511151231257827021181583404541015625 for 0.1.
+/// Binary fractions always end, after at most 1074 decimal places.
+pub fn exact_decimal(value: f64) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    // The last stored bit is worth 2^(exponent - 52), which needs as many
+    // decimal places as it has fractional bits
+    let places = (MANTISSA_BITS as i32 - FloatBits::of(value).unbiased_exponent()).max(0) as usize;
+    let text = format!("{:.*}", places, value);
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+/// `value` rounded to `digits` significant decimal digits, i.e. the number
+/// the user most likely meant when the binary result shows a long tail.
+pub fn round_to_significant(value: f64, digits: usize) -> f64 {
+    if !value.is_finite() || digits == 0 {
+        return value;
+    }
+    format!("{:.*e}", digits - 1, value).parse().unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_layout_of_one_tenth() {
+        let bits = FloatBits::of(0.1);
+        assert!(!bits.sign);
+        assert_eq!(bits.exponent, 1019);
+        assert_eq!(bits.unbiased_exponent(), -4);
+        assert_eq!(bits.mantissa, 0x999999999999A);
+        assert_eq!(to_hex(0.1), "0x3FB999999999999A");
+        assert_eq!(to_hex(-2.0), "0xC000000000000000");
+        assert_eq!(FloatBits::of(f64::MIN_POSITIVE / 2.0).unbiased_exponent(), -1022);
+        assert_eq!(FloatBits::of(-1.0).to_binary(), format!("1 01111111111 {}", "0".repeat(52)));
+    }
+
+    #[test]
+    fn test_famous_surprise() {
+        let sum = 0.1 + 0.2;
+        assert_eq!(round_to_significant(sum, 15), 0.3);
+        assert_eq!(ulp_distance(sum, 0.3), Some(1));
+        assert_eq!(ulp(1.0), f64::EPSILON);
+        assert_eq!(ulp_distance(0.0, -0.0), Some(0));
+        assert_eq!(ulp_distance(f64::NAN, 1.0), None);
+        assert_eq!(next_up(-0.0), f64::from_bits(1));
+        assert_eq!(next_down(0.0), -f64::from_bits(1));
+    }
+
+    #[test]
+    fn test_exact_decimal() {
+        assert_eq!(exact_decimal(0.1), "0.1000000000000000055511151231257827021181583404541015625");
+        assert_eq!(exact_decimal(2.5), "2.5");
+        assert_eq!(exact_decimal(-3.0), "-3");
+        assert_eq!(exact_decimal(1e22), "10000000000000000000000");
+        assert_eq!(exact_decimal(1e23), "99999999999999991611392");
+        let smallest = exact_decimal(f64::from_bits(1));
+        assert_eq!(smallest.len(), 2 + 1074);
+        assert!(smallest.ends_with("4940656458412465441765687928682213723650598026143247644255856825006755072702087518652998363616359923797965646954457177309266567103559397963987747960107818781263007131903114045278458171678489821036887186360569987307230500063874091535649843873124733972731696151400317153853980741262385655911710266585566867681870395603106249319452715914924553293054565444011274801297099995419319894090804165633245247571478690147267801593552386115501348035264934720193790268107107491703332226844753335720832431936092382893458368060106011506169809753078342277318329247904982524730776375927247874656084778203734469699533647017972677717585125660551199131504891101451037862738167250955837389733598993664809941164205702637090279242767544565229087538682506419718265533447265625"));
+        assert_eq!(exact_decimal(f64::INFINITY), "inf");
+    }
+
+    // Feature: gui-calculator, Property 24: Neighbouring floats are one ULP apart
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_neighbours(value in prop::num::f64::NORMAL | prop::num::f64::SUBNORMAL) {
+            let up = next_up(value);
+            let down = next_down(value);
+
+            prop_assert!(down < value && value < up);
+            prop_assert_eq!(ulp_distance(value, up), Some(1));
+            prop_assert_eq!(ulp_distance(down, up), Some(2));
+            prop_assert_eq!(next_down(up), value);
+            prop_assert_eq!(next_up(down), value);
+        }
+    }
+}
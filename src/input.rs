@@ -0,0 +1,154 @@
+// Input Event Model
+use serde::{Deserialize, Serialize};
+
+use crate::operation::Operation;
+
+/// A single user action on the keypad, independent of where it came from
+/// (button click, keyboard, macro replay).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    Digit(u8),
+    DecimalPoint,
+    Operation(Operation),
+    Equals,
+    /// Clears the number being entered but keeps the pending operation (CE)
+    ClearEntry,
+    /// Removes the last typed character (⌫)
+    Backspace,
+    /// Clears the calculation, keeping memory (C)
+    Clear,
+    /// Clears the calculation and memory (AC)
+    ClearAll,
+    MemoryAdd,
+    MemorySubtract,
+    MemoryRecall,
+    MemoryClear,
+    /// The registered binary operation at this position in the
+    /// `OperationRegistry` the keypad was built from
+    Binary(usize),
+    /// The registered unary operation at this position, like √
+    Unary(usize),
+    /// The registered constant at this position, like π
+    Constant(usize),
+    /// Rounds the operand to this many decimals, half away from zero
+    Round(u32),
+}
+
+/// The keypad event for a keyboard event, if any.
+///
+/// egui reports numpad keys as their main-keyboard twins (NumpadEnter is
+/// Enter, Numpad5 is Num5), so digits, operators and the decimal point are
+/// taken from the text they type instead. That covers both key variants the
+/// same way and follows the user's layout, including numpads whose decimal
+/// key types a comma. Enter, Backspace, Delete and Escape type nothing and
+/// are matched as keys. Escape is CE and Shift+Escape is AC; a quick double Escape is
+/// timed by the app.
+pub fn from_keyboard(event: &egui::Event) -> Option<InputEvent> {
+    match event {
+        egui::Event::Text(text) => {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => from_char(c),
+                _ => None,
+            }
+        }
+        egui::Event::Key {
+            key,
+            pressed: true,
+            modifiers,
+            ..
+        } => match (key, *modifiers) {
+            (egui::Key::Enter, egui::Modifiers::NONE) => Some(InputEvent::Equals),
+            (egui::Key::Backspace, egui::Modifiers::NONE) => Some(InputEvent::Backspace),
+            (egui::Key::Delete | egui::Key::Escape, egui::Modifiers::NONE) => Some(InputEvent::ClearEntry),
+            (egui::Key::Escape, egui::Modifiers::SHIFT) => Some(InputEvent::ClearAll),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// How far a finger has to travel across the display for a swipe to count.
+pub const SWIPE_DISTANCE: f32 = 40.0;
+
+/// The keypad event for a swipe across the display that moved by `delta`:
+/// leftwards, far enough and more sideways than up or down, it's ⌫.
+pub fn from_swipe(delta: egui::Vec2) -> Option<InputEvent> {
+    (delta.x <= -SWIPE_DISTANCE && delta.x.abs() > 2.0 * delta.y.abs()).then_some(InputEvent::Backspace)
+}
+
+fn from_char(c: char) -> Option<InputEvent> {
+    let event = match c {
+        '0'..='9' => InputEvent::Digit(c as u8 - b'0'),
+        '.' | ',' => InputEvent::DecimalPoint,
+        '=' => InputEvent::Equals,
+        _ => return Operation::from_key(c).map(InputEvent::Operation),
+    };
+    Some(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Option<InputEvent> {
+        from_keyboard(&egui::Event::Text(text.to_string()))
+    }
+
+    fn key(key: egui::Key, modifiers: egui::Modifiers) -> Option<InputEvent> {
+        from_keyboard(&egui::Event::Key {
+            key,
+            pressed: true,
+            repeat: false,
+            modifiers,
+        })
+    }
+
+    #[test]
+    fn test_typed_keys() {
+        for digit in 0..=9u8 {
+            assert_eq!(text(&digit.to_string()), Some(InputEvent::Digit(digit)));
+        }
+        assert_eq!(text("."), Some(InputEvent::DecimalPoint));
+        // Numpad decimal key on layouts with a decimal comma
+        assert_eq!(text(","), Some(InputEvent::DecimalPoint));
+        assert_eq!(text("+"), Some(InputEvent::Operation(Operation::Add)));
+        assert_eq!(text("-"), Some(InputEvent::Operation(Operation::Subtract)));
+        assert_eq!(text("*"), Some(InputEvent::Operation(Operation::Multiply)));
+        assert_eq!(text("/"), Some(InputEvent::Operation(Operation::Divide)));
+        assert_eq!(text("="), Some(InputEvent::Equals));
+        assert_eq!(text("a"), None);
+        assert_eq!(text("12"), None);
+    }
+
+    #[test]
+    fn test_special_keys() {
+        let none = egui::Modifiers::NONE;
+        // Enter and the numpad's Enter arrive as the same key
+        assert_eq!(key(egui::Key::Enter, none), Some(InputEvent::Equals));
+        assert_eq!(key(egui::Key::Delete, none), Some(InputEvent::ClearEntry));
+        assert_eq!(key(egui::Key::Escape, none), Some(InputEvent::ClearEntry));
+        assert_eq!(key(egui::Key::Escape, egui::Modifiers::SHIFT), Some(InputEvent::ClearAll));
+        assert_eq!(key(egui::Key::Backspace, none), Some(InputEvent::Backspace));
+        assert_eq!(key(egui::Key::Enter, egui::Modifiers::CTRL), None);
+        // Digit keys are handled through their text, not twice
+        assert_eq!(key(egui::Key::Num5, none), None);
+        let released = egui::Event::Key {
+            key: egui::Key::Enter,
+            pressed: false,
+            repeat: false,
+            modifiers: none,
+        };
+        assert_eq!(from_keyboard(&released), None);
+    }
+
+    #[test]
+    fn test_swipes() {
+        assert_eq!(from_swipe(egui::vec2(-60.0, 5.0)), Some(InputEvent::Backspace));
+        assert_eq!(from_swipe(egui::vec2(-SWIPE_DISTANCE, 0.0)), Some(InputEvent::Backspace));
+        // Too short, the wrong way, or mostly a scroll
+        assert_eq!(from_swipe(egui::vec2(-20.0, 0.0)), None);
+        assert_eq!(from_swipe(egui::vec2(60.0, 0.0)), None);
+        assert_eq!(from_swipe(egui::vec2(-60.0, -45.0)), None);
+    }
+}
@@ -0,0 +1,185 @@
+// Single Instance
+//
+// With single-instance on, the running calculator listens on a loopback port.
+// A copy launched later connects there first; if a calculator answers, the
+// copy hands over its `--expr`, if any, and exits instead of opening a second
+// window. Nobody listening means start normally.
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+/// Where the running calculator listens.
+pub const PORT: u16 = 47_813;
+// Sent first, so a stray connection from some other program is ignored
+const GREETING: &str = "hello_project instance 1";
+// How long a launch waits for an answer before starting on its own
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+fn address() -> SocketAddr {
+    SocketAddr::from((Ipv4Addr::LOCALHOST, PORT))
+}
+
+/// What a new launch asks of the running calculator.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    /// Just come to the front
+    Focus,
+    /// Come to the front and evaluate this in expression mode
+    Expression(String),
+}
+
+impl Message {
+    /// One line of text.
+    pub fn encode(&self) -> String {
+        match self {
+            Message::Focus => String::from("focus"),
+            Message::Expression(source) => format!("expr {}", source.replace(['\r', '\n'], " ")),
+        }
+    }
+
+    pub fn decode(line: &str) -> Option<Message> {
+        match line {
+            "focus" => Some(Message::Focus),
+            _ => line.strip_prefix("expr ").map(|source| Message::Expression(source.to_string())),
+        }
+    }
+}
+
+/// Passes `message` to a running calculator. `true` means it took it and
+/// this launch should exit.
+pub fn hand_off(message: &Message) -> bool {
+    hand_off_to(address(), message)
+}
+
+fn hand_off_to(address: SocketAddr, message: &Message) -> bool {
+    let send = || -> std::io::Result<bool> {
+        let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        writeln!(stream, "{}\n{}", GREETING, message.encode())?;
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+        Ok(reply.trim_end() == "ok")
+    };
+    send().unwrap_or(false)
+}
+
+/// Takes messages from later launches while it's kept. Dropping it frees
+/// the port, so launches start their own window again.
+pub struct Listener {
+    receiver: mpsc::Receiver<Message>,
+    address: SocketAddr,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Listener {
+    /// Starts listening. `notify` runs on the listening thread after each
+    /// message, e.g. to wake the UI with a repaint. Fails if the port is
+    /// taken, e.g. by another calculator that's already listening.
+    pub fn start(notify: impl Fn() + Send + 'static) -> std::io::Result<Self> {
+        Self::start_on(address(), notify)
+    }
+
+    fn start_on(address: SocketAddr, notify: impl Fn() + Send + 'static) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let (sender, receiver) = mpsc::channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stop = Arc::clone(&stopped);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let Some(message) = receive(&stream) else {
+                    continue;
+                };
+                // The app has stopped listening; let the launch open its own window
+                if sender.send(message).is_err() {
+                    break;
+                }
+                let _ = writeln!(&stream, "ok");
+                notify();
+            }
+        });
+        Ok(Self {
+            receiver,
+            address,
+            stopped,
+        })
+    }
+
+    /// The messages that arrived since the last call.
+    pub fn poll(&self) -> Vec<Message> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        // Wakes the thread from waiting on a connection so it sees the flag
+        let _ = TcpStream::connect_timeout(&self.address, TIMEOUT);
+    }
+}
+
+fn receive(stream: &TcpStream) -> Option<Message> {
+    stream.set_read_timeout(Some(TIMEOUT)).ok()?;
+    let mut lines = BufReader::new(stream).lines();
+    if lines.next()?.ok()? != GREETING {
+        return None;
+    }
+    Message::decode(&lines.next()?.ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn any_port() -> SocketAddr {
+        SocketAddr::from((Ipv4Addr::LOCALHOST, 0))
+    }
+
+    #[test]
+    fn test_hand_off() {
+        let (notified, woken) = mpsc::channel();
+        let listener = Listener::start_on(any_port(), move || notified.send(()).unwrap()).unwrap();
+        let message = Message::Expression(String::from("2^10"));
+        assert!(hand_off_to(listener.address, &message));
+        woken.recv().unwrap();
+        assert_eq!(listener.poll(), [message]);
+        assert_eq!(listener.poll(), []);
+
+        // A connection that doesn't greet is dropped without an answer
+        let mut stranger = TcpStream::connect(listener.address).unwrap();
+        writeln!(stranger, "GET / HTTP/1.1").unwrap();
+        let mut reply = String::new();
+        BufReader::new(stranger).read_line(&mut reply).unwrap();
+        assert_eq!(reply, "");
+
+        let address = listener.address;
+        drop(listener);
+        assert!(!hand_off_to(address, &Message::Focus));
+    }
+
+    // Feature: gui-calculator, Property 52: Any message survives encoding as a line
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_message_round_trip(source in "[^\r\n]{0,40}") {
+            let message = Message::Expression(source);
+            let line = message.encode();
+            prop_assert!(!line.contains('\n'));
+            prop_assert_eq!(Message::decode(&line), Some(message));
+            prop_assert_eq!(Message::decode(&Message::Focus.encode()), Some(Message::Focus));
+        }
+    }
+}
@@ -0,0 +1,400 @@
+// Crash Journal
+//
+// Changes to the display, memory and history are appended to a file as they
+// happen, one JSON record per line, and the file is removed on a clean exit.
+// Each running instance has its own journal, named after its process id and
+// kept locked through a lock file beside it. A journal found at startup whose
+// lock nobody holds means that session ended abruptly, and replaying it gives
+// back the calculator as it was at that moment.
+use std::cmp::Reverse;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::calculator::{Calculator, Session};
+use crate::history::{History, HistoryEntry};
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Record {
+    /// The whole state; starts the journal, which is rewritten from one
+    /// whenever the app saves
+    Checkpoint { session: Session, history: History },
+    Session(Session),
+    /// A calculation added to the history
+    Calculated(HistoryEntry),
+    /// The history after entries were removed other than from the oldest end
+    History(History),
+}
+
+/// A session read back from the journal of one that didn't exit cleanly.
+#[derive(Debug)]
+pub struct Recovered {
+    pub session: Session,
+    pub history: History,
+    // Keeps the journal from being recovered by another instance too, and
+    // removes it once dropped; none when read with `read`
+    claim: Option<Claim>,
+}
+
+impl Recovered {
+    /// Whether restoring would change anything in `calculator`.
+    pub fn differs_from(&self, calculator: &Calculator) -> bool {
+        self.session != calculator.session() || self.history.entries() != calculator.history().entries()
+    }
+}
+
+pub struct Journal {
+    path: PathBuf,
+    // Held locked for as long as the instance runs
+    lock: Option<File>,
+    // Dropped when a write fails; the app carries on without a journal
+    file: Option<File>,
+    revision: u64,
+    session: Session,
+    // Ids of the history entries as last written
+    history: Vec<u64>,
+}
+
+impl Journal {
+    /// Starts this instance's journal in `dir` with the current state of
+    /// `calculator`.
+    pub fn start(dir: impl AsRef<Path>, calculator: &Calculator) -> Self {
+        let dir = dir.as_ref();
+        let path = dir.join(format!("calculator-{}.journal", process::id()));
+        // Locked before the journal is written, so no other instance ever
+        // sees it unlocked
+        let lock = fs::create_dir_all(dir)
+            .ok()
+            .and_then(|_| open_lock(&path))
+            .filter(|lock| lock.try_lock().is_ok());
+        let mut journal = Self {
+            path,
+            lock,
+            file: None,
+            revision: 0,
+            session: calculator.session(),
+            history: Vec::new(),
+        };
+        journal.checkpoint(calculator);
+        journal
+    }
+
+    /// Rewrites the journal as a single record of the current state, so it
+    /// doesn't grow for the whole session.
+    pub fn checkpoint(&mut self, calculator: &Calculator) {
+        self.revision = calculator.revision();
+        self.session = calculator.session();
+        self.history = ids(calculator.history());
+        if self.lock.is_none() {
+            return;
+        }
+        let record = Record::Checkpoint {
+            session: self.session.clone(),
+            history: calculator.history().clone(),
+        };
+        // Written beside the journal and renamed over it, so a crash part
+        // way through leaves the old journal rather than an empty one
+        let temporary = self.path.with_extension("tmp");
+        self.file = line(&record)
+            .and_then(|line| fs::write(&temporary, line).ok())
+            .and_then(|_| fs::rename(&temporary, &self.path).ok())
+            .and_then(|_| OpenOptions::new().append(true).open(&self.path).ok());
+    }
+
+    /// Appends whatever changed in `calculator` since the last call.
+    pub fn record(&mut self, calculator: &Calculator) {
+        if calculator.revision() == self.revision {
+            return;
+        }
+        self.revision = calculator.revision();
+
+        let session = calculator.session();
+        if session != self.session {
+            self.write(&Record::Session(session.clone()));
+            self.session = session;
+        }
+
+        let history = calculator.history();
+        let current = ids(history);
+        if current == self.history {
+            return;
+        }
+        let newest = self.history.last().copied().unwrap_or(0);
+        let added = history.entries().iter().filter(|entry| entry.id > newest);
+        let mut pushed = self.history.clone();
+        pushed.extend(added.clone().map(|entry| entry.id));
+        // Entries pushed, maybe with the oldest dropped to stay within the
+        // limit, replay as pushes and the limit is applied on restore.
+        // Anything else needs the whole history.
+        let trimmed = pushed.len() > current.len() && history.limit() == Some(current.len());
+        if pushed.ends_with(&current) && (pushed.len() == current.len() || trimmed) {
+            for entry in added {
+                self.write(&Record::Calculated(entry.clone()));
+            }
+        } else {
+            self.write(&Record::History(history.clone()));
+        }
+        self.history = current;
+    }
+
+    /// Removes the journal, for a clean exit.
+    pub fn close(self) {
+        let _ = fs::remove_file(&self.path);
+        if let Some(lock) = self.lock {
+            drop(lock);
+            let _ = fs::remove_file(self.path.with_extension("lock"));
+        }
+    }
+
+    fn write(&mut self, record: &Record) {
+        let Some(file) = &mut self.file else {
+            return;
+        };
+        let Some(line) = line(record) else {
+            return;
+        };
+        // Unbuffered, so each record reaches the OS before the next frame
+        if file.write_all(line.as_bytes()).is_err() {
+            self.file = None;
+        }
+    }
+}
+
+// A journal left by an instance that's no longer running, locked by this one
+// until its contents have been restored or turned down
+#[derive(Debug)]
+struct Claim {
+    path: PathBuf,
+    lock: Option<File>,
+}
+
+impl Claim {
+    fn take(path: PathBuf) -> Option<Self> {
+        let lock = open_lock(&path)?;
+        lock.try_lock().ok()?;
+        Some(Self { path, lock: Some(lock) })
+    }
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        drop(self.lock.take());
+        let _ = fs::remove_file(self.path.with_extension("lock"));
+    }
+}
+
+fn open_lock(journal: &Path) -> Option<File> {
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(journal.with_extension("lock"))
+        .ok()
+}
+
+fn line(record: &Record) -> Option<String> {
+    let mut line = serde_json::to_string(record).ok()?;
+    line.push('\n');
+    Some(line)
+}
+
+fn ids(history: &History) -> Vec<u64> {
+    history.entries().iter().map(|entry| entry.id).collect()
+}
+
+/// The most recent session left in `dir` by an instance that didn't exit
+/// cleanly, if there is one. Its journal is removed once the result is
+/// dropped, and ones that can't be read are removed straight away.
+pub fn recover(dir: impl AsRef<Path>) -> Option<Recovered> {
+    let mut journals: Vec<(SystemTime, PathBuf)> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "journal"))
+        .map(|path| {
+            let modified = fs::metadata(&path).and_then(|data| data.modified());
+            (modified.unwrap_or(SystemTime::UNIX_EPOCH), path)
+        })
+        .collect();
+    journals.sort_by_key(|&(modified, _)| Reverse(modified));
+    journals.into_iter().find_map(|(_, path)| {
+        // Journals of running instances stay locked and are passed over
+        let claim = Claim::take(path)?;
+        let mut recovered = read(&claim.path)?;
+        recovered.claim = Some(claim);
+        Some(recovered)
+    })
+}
+
+/// The state a journal left at `path` describes, if there is one.
+pub fn read(path: impl AsRef<Path>) -> Option<Recovered> {
+    replay(&fs::read_to_string(path).ok()?)
+}
+
+fn replay(text: &str) -> Option<Recovered> {
+    // A crash mid-write can leave the last line cut short; everything
+    // before it still counts
+    let mut records = text.lines().map_while(|line| serde_json::from_str::<Record>(line).ok());
+    let Some(Record::Checkpoint { session, history }) = records.next() else {
+        return None;
+    };
+    let mut recovered = Recovered { session, history, claim: None };
+    for record in records {
+        match record {
+            Record::Checkpoint { session, history } => {
+                recovered.session = session;
+                recovered.history = history;
+            }
+            Record::Session(session) => recovered.session = session,
+            Record::Calculated(entry) => recovered.history.push(entry),
+            Record::History(history) => recovered.history = history,
+        }
+    }
+    Some(recovered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+
+    use crate::input::InputEvent;
+    use crate::operation::Operation;
+    use crate::testing;
+    use proptest::prelude::*;
+
+    // A directory per test, as all of a test run's journals share its
+    // process id
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("calculator-{}-{}", process::id(), name))
+    }
+
+    fn journal_path(dir: &Path) -> PathBuf {
+        dir.join(format!("calculator-{}.journal", process::id()))
+    }
+
+    fn restored(recovered: Recovered) -> Calculator {
+        let mut calculator = Calculator::new();
+        calculator.restore_session(recovered.session);
+        *calculator.history_mut() = recovered.history;
+        calculator
+    }
+
+    #[test]
+    fn test_restores_display_memory_and_history() {
+        let dir = temp_dir("restore");
+        let path = journal_path(&dir);
+        let mut calc = Calculator::new();
+        let mut journal = Journal::start(&dir, &calc);
+        let events = [
+            InputEvent::Digit(1),
+            InputEvent::Digit(2),
+            InputEvent::Operation(Operation::Add),
+            InputEvent::Digit(3),
+            InputEvent::Equals,
+            InputEvent::MemoryAdd,
+            InputEvent::Digit(7),
+            InputEvent::DecimalPoint,
+        ];
+        for event in events {
+            calc.handle_event(event);
+            journal.record(&calc);
+        }
+
+        let back = restored(read(&path).unwrap());
+        assert_eq!(back.get_display_text(), "7.");
+        assert_eq!(back.memory(), Some(15.0));
+        assert_eq!(back.history().entries(), calc.history().entries());
+
+        // Removing history entries is journaled too
+        let id = calc.history().entries()[0].id;
+        calc.history_mut().remove(id);
+        journal.record(&calc);
+        assert!(read(&path).unwrap().history.entries().is_empty());
+
+        journal.close();
+        assert!(read(&path).is_none());
+    }
+
+    #[test]
+    fn test_torn_last_line_is_ignored() {
+        let dir = temp_dir("torn");
+        let path = journal_path(&dir);
+        let mut calc = Calculator::new();
+        let mut journal = Journal::start(&dir, &calc);
+        calc.handle_event(InputEvent::Digit(4));
+        journal.record(&calc);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"Session\":{\"val").unwrap();
+
+        assert_eq!(restored(read(&path).unwrap()).get_display_text(), "4");
+        assert!(replay("not a journal").is_none());
+        journal.close();
+    }
+
+    #[test]
+    fn test_only_abandoned_journals_are_recovered() {
+        let dir = temp_dir("recover");
+        let mut calc = Calculator::new();
+        let mut journal = Journal::start(&dir, &calc);
+        calc.handle_event(InputEvent::Digit(5));
+        journal.record(&calc);
+        journal.checkpoint(&calc);
+        assert!(!journal_path(&dir).with_extension("tmp").exists());
+
+        // A running instance keeps its journal locked
+        assert!(recover(&dir).is_none());
+
+        // Dropped without closing, as when the app is killed
+        drop(journal);
+        let recovered = recover(&dir).unwrap();
+        assert_eq!(restored(recovered).get_display_text(), "5");
+        // Recovering removes it, so it's offered only once
+        assert!(recover(&dir).is_none());
+        assert!(!journal_path(&dir).exists());
+
+        let first = Journal::start(&dir, &calc);
+        drop(first);
+        let recovered = recover(&dir).unwrap();
+        // Nobody else can take it while it's being restored
+        assert!(recover(&dir).is_none());
+        drop(recovered);
+        assert!(fs::read_dir(&dir).unwrap().next().is_none());
+    }
+
+    // Feature: gui-calculator, Property 41: Replaying the journal gives back the calculator as it was
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_journal_replays_to_same_state(
+            events in prop::collection::vec(testing::input_event(), 0..30),
+            checkpoint_at in 0usize..30
+        ) {
+            let dir = temp_dir("property");
+            let path = journal_path(&dir);
+            let mut calc = Calculator::new();
+            calc.history_mut().set_limit(Some(3));
+            let mut journal = Journal::start(&dir, &calc);
+            for (step, event) in events.into_iter().enumerate() {
+                calc.handle_event(event);
+                journal.record(&calc);
+                if step == checkpoint_at {
+                    journal.checkpoint(&calc);
+                }
+            }
+
+            let mut back = restored(read(&path).unwrap());
+            back.history_mut().set_limit(Some(3));
+            journal.close();
+            prop_assert_eq!(back.session(), calc.session());
+            prop_assert_eq!(back.get_display_text(), calc.get_display_text());
+            prop_assert_eq!(back.history().entries(), calc.history().entries());
+        }
+    }
+}
@@ -0,0 +1,379 @@
+// Keypad Buttons
+//
+// The keypad is drawn every frame, and going through `egui::Button` with a
+// `RichText` label builds a new string and text layout for each key each
+// time. The labels never change, so each key is laid out once and the galley
+// reused until the scale changes.
+//
+// How big the keys are, how they're drawn and where the function keys go
+// comes from a `KeypadLayout`, so the accessibility preset and the landscape
+// arrangement are the same keypad built from different numbers.
+use std::sync::Arc;
+
+use egui::{Color32, FontId, Galley, Sense, Stroke, Ui, Vec2, WidgetInfo, WidgetType};
+use serde::{Deserialize, Serialize};
+
+use crate::input::InputEvent;
+use crate::operation::{Operation, OperationInfo, OperationRegistry};
+
+/// Sizes and styling the keypad is built from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeypadLayout {
+    /// Side of a digit or operator key; the other keys are sized from it
+    pub key_size: f32,
+    pub spacing: f32,
+    /// Label size on digit and operator keys, scaled down on smaller keys
+    pub font_size: f32,
+    pub bold: bool,
+    /// Solid keys in the text color with a heavy outline, inverted on hover
+    pub high_contrast: bool,
+    /// Whether keys light up under the pointer; a finger leaves no pointer
+    /// behind, so touch layouts only show presses
+    pub hover: bool,
+    pub arrangement: Arrangement,
+}
+
+/// Where the function keys go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arrangement {
+    /// In a row above the digits, for tall windows
+    Portrait,
+    /// In columns of full-size keys beside the digits, for wide windows
+    Landscape,
+}
+
+// How much wider than tall a window has to be for the landscape keypad
+const LANDSCAPE_RATIO: f32 = 1.2;
+
+impl KeypadLayout {
+    pub const STANDARD: KeypadLayout = KeypadLayout {
+        key_size: 65.0,
+        spacing: 8.0,
+        font_size: 24.0,
+        bold: false,
+        high_contrast: false,
+        hover: true,
+        arrangement: Arrangement::Portrait,
+    };
+
+    pub const LARGE: KeypadLayout = KeypadLayout {
+        key_size: 96.0,
+        spacing: 12.0,
+        font_size: 40.0,
+        bold: true,
+        high_contrast: true,
+        hover: true,
+        arrangement: Arrangement::Portrait,
+    };
+
+    /// The same layout with keys at least a fingertip wide.
+    pub fn for_touch(self) -> Self {
+        Self {
+            key_size: self.key_size.max(80.0),
+            spacing: self.spacing.max(10.0),
+            font_size: self.font_size.max(30.0),
+            hover: false,
+            ..self
+        }
+    }
+
+    /// The same layout arranged for a window of `size`.
+    pub fn arranged_for(self, size: Vec2) -> Self {
+        let arrangement = if size.x >= size.y * LANDSCAPE_RATIO {
+            Arrangement::Landscape
+        } else {
+            Arrangement::Portrait
+        };
+        Self { arrangement, ..self }
+    }
+}
+
+/// The layouts offered in settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum KeypadPreset {
+    #[default]
+    Standard,
+    /// Extra-large keys, high contrast and bold labels
+    Large,
+}
+
+impl KeypadPreset {
+    pub const ALL: [KeypadPreset; 2] = [KeypadPreset::Standard, KeypadPreset::Large];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            KeypadPreset::Standard => "Standard",
+            KeypadPreset::Large => "Large, high contrast",
+        }
+    }
+
+    pub fn layout(self) -> KeypadLayout {
+        match self {
+            KeypadPreset::Standard => KeypadLayout::STANDARD,
+            KeypadPreset::Large => KeypadLayout::LARGE,
+        }
+    }
+}
+
+struct Key {
+    label: &'static str,
+    hover: Option<&'static str>,
+    size: Vec2,
+    font_size: f32,
+    event: InputEvent,
+    // MC and MR do nothing while memory is empty
+    needs_memory: bool,
+    galley: Option<Arc<Galley>>,
+}
+
+impl Key {
+    fn new(label: &'static str, size: [f32; 2], font_size: f32, event: InputEvent) -> Self {
+        Self {
+            label,
+            hover: None,
+            size: size.into(),
+            font_size,
+            event,
+            needs_memory: false,
+            galley: None,
+        }
+    }
+
+    fn digit(digit: u8, layout: &KeypadLayout) -> Self {
+        const DIGITS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+        Self::grid(DIGITS[usize::from(digit)], layout, InputEvent::Digit(digit))
+    }
+
+    fn operation(operation: Operation, layout: &KeypadLayout) -> Self {
+        Self::grid(operation.symbol(), layout, InputEvent::Operation(operation))
+    }
+
+    // A key of the 4x4 grid
+    fn grid(label: &'static str, layout: &KeypadLayout, event: InputEvent) -> Self {
+        Self::new(label, [layout.key_size; 2], layout.font_size, event)
+    }
+
+    fn hover(self, text: &'static str) -> Self {
+        Self {
+            hover: Some(text),
+            ..self
+        }
+    }
+
+    fn ui(&mut self, ui: &mut Ui, has_memory: bool, layout: &KeypadLayout) -> bool {
+        let enabled = has_memory || !self.needs_memory;
+        let sense = if enabled { Sense::click() } else { Sense::hover() };
+        let (rect, response) = ui.allocate_exact_size(self.size, sense);
+        let label = self.label;
+        response.widget_info(|| WidgetInfo::labeled(WidgetType::Button, label));
+
+        if ui.is_rect_visible(rect) {
+            let galley = self.galley.get_or_insert_with(|| {
+                let font = FontId::proportional(self.font_size);
+                // The color is replaced when painting, to follow hover and theme
+                ui.fonts(|fonts| fonts.layout_no_wrap(label.to_owned(), font, Color32::WHITE))
+            });
+            let pressed = response.is_pointer_button_down_on();
+            let visuals = if !enabled {
+                &ui.visuals().widgets.noninteractive
+            } else if layout.hover || pressed {
+                ui.style().interact(&response)
+            } else {
+                &ui.visuals().widgets.inactive
+            };
+            let (fill, stroke, text_color) = if layout.high_contrast {
+                let ink = if enabled {
+                    ui.visuals().strong_text_color()
+                } else {
+                    ui.visuals().weak_text_color()
+                };
+                let paper = ui.visuals().extreme_bg_color;
+                if enabled && (pressed || layout.hover && response.hovered()) {
+                    (ink, Stroke::new(3.0, ink), paper)
+                } else {
+                    (paper, Stroke::new(3.0, ink), ink)
+                }
+            } else if enabled {
+                (visuals.weak_bg_fill, visuals.bg_stroke, visuals.text_color())
+            } else {
+                (visuals.weak_bg_fill, visuals.bg_stroke, ui.visuals().weak_text_color())
+            };
+            let painter = ui.painter();
+            painter.rect(rect.expand(visuals.expansion), visuals.rounding, fill, stroke);
+            let text_pos = rect.center() - galley.size() / 2.0;
+            painter.galley_with_color(text_pos, Arc::clone(galley), text_color);
+            // The default fonts have no bold face; a second pass a hair to the
+            // right thickens the strokes
+            if layout.bold {
+                let offset = Vec2::new((self.font_size / 24.0).max(1.0), 0.0);
+                painter.galley_with_color(text_pos + offset, Arc::clone(galley), text_color);
+            }
+        }
+
+        let response = match self.hover {
+            Some(text) => response.on_hover_text(text),
+            None => response,
+        };
+        response.clicked()
+    }
+}
+
+/// The Basic mode keys, built once with their labels laid out on first draw.
+pub struct Keypad {
+    memory: Vec<Key>,
+    functions: Vec<Key>,
+    grid: [[Key; 4]; 4],
+    clear: [Key; 2],
+    layout: KeypadLayout,
+    // Galleys are only good for the scale they were laid out at
+    pixels_per_point: f32,
+}
+
+impl Keypad {
+    pub fn new(operations: &OperationRegistry, layout: KeypadLayout) -> Self {
+        let KeypadLayout { key_size, spacing, font_size, .. } = layout;
+        let memory_key = |label, event, needs_memory| Key {
+            needs_memory,
+            ..Key::new(label, [key_size, key_size * 0.5], font_size * 2.0 / 3.0, event)
+        };
+        let small_font = font_size * 5.0 / 6.0;
+        // Beside the digits the function keys line up with them
+        let (function_size, function_font) = match layout.arrangement {
+            Arrangement::Portrait => ([key_size, key_size * 0.6], small_font),
+            Arrangement::Landscape => ([key_size; 2], font_size),
+        };
+        let binary = operations.binary().enumerate().map(|(index, op)| {
+            Key::new(op.symbol(), function_size, function_font, InputEvent::Binary(index)).hover(op.name())
+        });
+        let unary = operations.unary().enumerate().map(|(index, op)| {
+            Key::new(op.symbol(), function_size, function_font, InputEvent::Unary(index)).hover(op.name())
+        });
+        let constants = operations.constants().enumerate().map(|(index, op)| {
+            Key::new(op.symbol(), function_size, function_font, InputEvent::Constant(index)).hover(op.name())
+        });
+        // C and AC together are as wide as the grid
+        let clear_size = [key_size * 2.0 + spacing, key_size * 0.75];
+        Self {
+            memory: vec![
+                memory_key("MC", InputEvent::MemoryClear, true),
+                memory_key("MR", InputEvent::MemoryRecall, true),
+                memory_key("M+", InputEvent::MemoryAdd, false),
+                memory_key("M−", InputEvent::MemorySubtract, false),
+            ],
+            functions: binary.chain(unary).chain(constants).collect(),
+            grid: [
+                [
+                    Key::digit(7, &layout),
+                    Key::digit(8, &layout),
+                    Key::digit(9, &layout),
+                    Key::operation(Operation::Divide, &layout),
+                ],
+                [
+                    Key::digit(4, &layout),
+                    Key::digit(5, &layout),
+                    Key::digit(6, &layout),
+                    Key::operation(Operation::Multiply, &layout),
+                ],
+                [
+                    Key::digit(1, &layout),
+                    Key::digit(2, &layout),
+                    Key::digit(3, &layout),
+                    Key::operation(Operation::Subtract, &layout),
+                ],
+                [
+                    Key::digit(0, &layout),
+                    Key::grid(".", &layout, InputEvent::DecimalPoint),
+                    Key::grid("=", &layout, InputEvent::Equals),
+                    Key::operation(Operation::Add, &layout),
+                ],
+            ],
+            clear: [
+                Key::new("C", clear_size, small_font, InputEvent::Clear).hover("Clear the calculation, keep memory"),
+                Key::new("AC", clear_size, small_font, InputEvent::ClearAll)
+                    .hover("Clear everything, including memory"),
+            ],
+            layout,
+            pixels_per_point: 0.0,
+        }
+    }
+
+    /// Rebuilds the keys if `layout` differs from the one they were built for.
+    pub fn set_layout(&mut self, layout: KeypadLayout, operations: &OperationRegistry) {
+        if layout != self.layout {
+            *self = Self::new(operations, layout);
+        }
+    }
+
+    fn keys_mut(&mut self) -> impl Iterator<Item = &mut Key> {
+        self.memory
+            .iter_mut()
+            .chain(self.functions.iter_mut())
+            .chain(self.grid.iter_mut().flatten())
+            .chain(self.clear.iter_mut())
+    }
+
+    /// Draws the keypad and returns the key pressed this frame, if any.
+    pub fn ui(&mut self, ui: &mut Ui, has_memory: bool) -> Option<InputEvent> {
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        if pixels_per_point != self.pixels_per_point {
+            self.pixels_per_point = pixels_per_point;
+            self.keys_mut().for_each(|key| key.galley = None);
+        }
+
+        let layout = self.layout;
+        let row = |ui: &mut Ui, keys: &mut [Key]| {
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = layout.spacing;
+                keys_ui(ui, keys, has_memory, &layout)
+            })
+            .inner
+        };
+        let mut pressed = row(ui, &mut self.memory);
+        if layout.arrangement == Arrangement::Portrait {
+            pressed = row(ui, &mut self.functions).or(pressed);
+        }
+        ui.add_space(8.0);
+
+        // 4x4 grid of digits and operators
+        let grid = |ui: &mut Ui, grid: &mut [[Key; 4]; 4]| {
+            ui.spacing_mut().item_spacing.y = layout.spacing;
+            let mut pressed = None;
+            for keys in grid {
+                pressed = row(ui, keys).or(pressed);
+            }
+            pressed
+        };
+        match layout.arrangement {
+            Arrangement::Portrait => pressed = ui.scope(|ui| grid(ui, &mut self.grid)).inner.or(pressed),
+            Arrangement::Landscape => {
+                let (functions, digits) = (&mut self.functions, &mut self.grid);
+                let beside = ui.horizontal_top(|ui| {
+                    ui.spacing_mut().item_spacing = Vec2::splat(layout.spacing);
+                    let mut pressed = None;
+                    // As many columns as it takes, each as tall as the grid
+                    for column in functions.chunks_mut(digits.len()) {
+                        pressed = ui.vertical(|ui| keys_ui(ui, column, has_memory, &layout)).inner.or(pressed);
+                    }
+                    ui.vertical(|ui| grid(ui, digits)).inner.or(pressed)
+                });
+                pressed = beside.inner.or(pressed);
+            }
+        }
+        ui.add_space(15.0);
+
+        // C and AC share the full width
+        row(ui, &mut self.clear).or(pressed)
+    }
+}
+
+// Draws `keys` in the current layout direction and returns the event of the
+// one pressed, if any
+fn keys_ui(ui: &mut Ui, keys: &mut [Key], has_memory: bool, layout: &KeypadLayout) -> Option<InputEvent> {
+    let mut pressed = None;
+    for key in keys {
+        if key.ui(ui, has_memory, layout) {
+            pressed = Some(key.event);
+        }
+    }
+    pressed
+}
@@ -0,0 +1,71 @@
+// Calculator Library
+//
+// The app and its engine, shared by the binary and the benchmarks.
+pub mod operation;
+pub mod state;
+pub mod calculator;
+pub mod cashflow;
+pub mod cli;
+pub mod color;
+pub mod constants;
+pub mod convert;
+pub mod cooking;
+pub mod datasize;
+pub mod announce;
+pub mod app;
+pub mod batch;
+pub mod business;
+pub mod decimal;
+pub mod diagnostics;
+pub mod distributions;
+#[cfg(feature = "digest")]
+pub mod digest;
+pub mod dms;
+pub mod error;
+pub mod expression;
+pub mod format;
+pub mod grid;
+pub mod highlight;
+pub mod history;
+pub mod ieee754;
+pub mod input;
+pub mod instance;
+pub mod journal;
+pub mod keypad;
+pub mod link;
+pub mod macros;
+pub mod matrix;
+pub mod montecarlo;
+pub mod number;
+pub mod percent;
+pub mod polynomial;
+pub mod precision;
+pub mod print;
+pub mod programmer;
+pub mod ratio;
+pub mod rational;
+pub mod report;
+pub mod selfcheck;
+pub mod settings;
+pub mod solver;
+pub mod stats;
+pub mod status;
+pub mod symbols;
+pub mod task;
+pub mod template;
+pub mod timestamp;
+// Shared strategies and a reference model for property tests, also for
+// other crates' tests with the `testing` feature
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod tip;
+pub mod total;
+pub mod travel;
+pub mod tray;
+pub mod typeset;
+pub mod variables;
+pub mod window;
+pub mod words;
+pub mod worksheet;
+#[cfg(feature = "scripting")]
+pub mod scripting;
@@ -0,0 +1,102 @@
+// Share Links
+//
+// `calc://` links that open the calculator with an expression filled in and
+// evaluated, e.g. calc://eval?expr=2%5E10. A desktop that has the scheme
+// registered passes the link as the only argument, which `cli::parse` takes
+// like `--expr`.
+
+pub const SCHEME: &str = "calc://";
+
+// The link's "host", naming what it does
+const EVAL: &str = "eval";
+
+/// The share link for `source`.
+pub fn encode(source: &str) -> String {
+    format!("{}{}?expr={}", SCHEME, EVAL, percent_encode(source))
+}
+
+/// The expression in a share link, or what's wrong with the link.
+pub fn decode(link: &str) -> Result<String, String> {
+    let invalid = || format!("Not a calculator link: {}", link);
+    let rest = link
+        .get(..SCHEME.len())
+        .filter(|scheme| scheme.eq_ignore_ascii_case(SCHEME))
+        .map(|_| &link[SCHEME.len()..])
+        .ok_or_else(invalid)?;
+    let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+    if !action.trim_end_matches('/').eq_ignore_ascii_case(EVAL) {
+        return Err(invalid());
+    }
+    let encoded = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("expr="))
+        .ok_or_else(|| format!("The link has no expression: {}", link))?;
+    percent_decode(encoded).ok_or_else(invalid)
+}
+
+// Everything but letters, digits and -._~ as %XX of its UTF-8 bytes
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+// Also reads + as a space, as links typed or built by hand often have it
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::new();
+    let mut rest = text.bytes();
+    while let Some(byte) = rest.next() {
+        match byte {
+            b'%' => {
+                let high = (rest.next()? as char).to_digit(16)?;
+                let low = (rest.next()? as char).to_digit(16)?;
+                bytes.push((high * 16 + low) as u8);
+            }
+            b'+' => bytes.push(b' '),
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_links() {
+        assert_eq!(encode("2^10"), "calc://eval?expr=2%5E10");
+        assert_eq!(encode("sqrt(2) * π"), "calc://eval?expr=sqrt%282%29%20%2A%20%CF%80");
+        assert_eq!(decode("calc://eval?expr=2%5E10").as_deref(), Ok("2^10"));
+        assert_eq!(decode("CALC://eval/?expr=1+%2B+1").as_deref(), Ok("1 + 1"));
+        assert_eq!(decode("calc://eval?from=mail&expr=3*4").as_deref(), Ok("3*4"));
+        assert_eq!(decode("calc://eval?expr=").as_deref(), Ok(""));
+    }
+
+    #[test]
+    fn test_bad_links() {
+        assert_eq!(decode("calc://eval"), Err(String::from("The link has no expression: calc://eval")));
+        assert_eq!(decode("calc://plot?expr=x"), Err(String::from("Not a calculator link: calc://plot?expr=x")));
+        assert!(decode("http://eval?expr=1").is_err());
+        assert!(decode("calc:").is_err());
+        assert!(decode("calc://eval?expr=%4").is_err());
+        assert!(decode("calc://eval?expr=%FF").is_err());
+    }
+
+    // Feature: gui-calculator, Property 62: Any expression comes back from its share link unchanged
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_links_round_trip(source in "\\PC*") {
+            prop_assert_eq!(decode(&encode(&source)), Ok(source));
+        }
+    }
+}
@@ -0,0 +1,103 @@
+// Macro Recording
+use serde::{Deserialize, Serialize};
+
+use crate::calculator::Calculator;
+use crate::input::InputEvent;
+use crate::operation::OperationRegistry;
+
+/// A named, saved sequence of input events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Macro {
+    pub name: String,
+    pub events: Vec<InputEvent>,
+}
+
+impl Macro {
+    pub fn new(name: impl Into<String>, events: Vec<InputEvent>) -> Self {
+        Self {
+            name: name.into(),
+            events,
+        }
+    }
+
+    /// Replays the events on top of the calculator's current state, so a macro
+    /// that starts with an operation acts on the value currently displayed.
+    /// Registered keys are looked up in `operations`, as when recorded.
+    pub fn replay(&self, calculator: &mut Calculator, operations: &OperationRegistry) {
+        for &event in &self.events {
+            calculator.handle_event_with(event, operations);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MacroRecorder {
+    recording: Option<Vec<InputEvent>>,
+}
+
+impl MacroRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn start(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    /// Captures the event if a recording is in progress.
+    pub fn record(&mut self, event: InputEvent) {
+        if let Some(events) = self.recording.as_mut() {
+            events.push(event);
+        }
+    }
+
+    /// Ends the recording and hands back everything captured since `start`.
+    pub fn stop(&mut self) -> Vec<InputEvent> {
+        self.recording.take().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::keypad_event;
+    use proptest::prelude::*;
+
+    // Feature: gui-calculator, Property 10: Macro replay equivalence
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_macro_replay_equivalence(
+            prefix in prop::collection::vec(0u8..=9, 1..=4),
+            events in prop::collection::vec(keypad_event(), 0..=12)
+        ) {
+            let operations = OperationRegistry::basic();
+            let mut live = Calculator::new();
+            for &digit in &prefix {
+                live.input_digit(digit);
+            }
+            let mut replayed = live.clone();
+
+            // Record while driving the live calculator
+            let mut recorder = MacroRecorder::new();
+            recorder.start();
+            for &event in &events {
+                recorder.record(event);
+                live.handle_event_with(event, &operations);
+            }
+            let recorded = Macro::new("test", recorder.stop());
+
+            prop_assert!(!recorder.is_recording());
+            prop_assert_eq!(&recorded.events, &events);
+
+            // Replaying on the same starting value must reach the same display
+            recorded.replay(&mut replayed, &operations);
+            prop_assert_eq!(replayed.get_display_text(), live.get_display_text());
+        }
+    }
+}
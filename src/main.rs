@@ -1,21 +1,45 @@
-mod operation;
-mod state;
-mod calculator;
-mod app;
-
-use app::CalculatorApp;
+use hello_project::app::{CalculatorApp, TITLE};
+use hello_project::cli;
+use hello_project::instance::{self, Message};
+use hello_project::selfcheck;
 
 fn main() -> Result<(), eframe::Error> {
+    let launch = match cli::parse(std::env::args().skip(1)) {
+        Ok(launch) => launch,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if launch.help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+    if launch.selfcheck {
+        let outcomes = selfcheck::run();
+        println!("{}", selfcheck::report(&outcomes));
+        std::process::exit(if outcomes.iter().all(selfcheck::Outcome::passed) { 0 } else { 1 });
+    }
+
+    // A calculator already running with single-instance on takes over
+    let message = match &launch.expr {
+        Some(source) => Message::Expression(source.clone()),
+        None => Message::Focus,
+    };
+    if instance::hand_off(&message) {
+        return Ok(());
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([320.0, 480.0]),
+            .with_inner_size([320.0, 600.0])
+            .with_drag_and_drop(true),
         ..Default::default()
     };
 
     eframe::run_native(
-        "GUI Calculator",
+        TITLE,
         options,
-        Box::new(|cc| Box::new(CalculatorApp::new(cc))),
+        Box::new(|cc| Box::new(CalculatorApp::new(cc, launch))),
     )
 }
-
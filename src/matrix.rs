@@ -0,0 +1,560 @@
+// Matrices and Vectors
+//
+// Values for expressions with matrix literals such as `[1, 2; 3, 4]`: `*` is
+// the matrix product, `.*`, `./` and `.^` work element by element, and a
+// number combines with every element. Results that come out as a single
+// number, like `det(...)` or `dot(...)`, carry on as ordinary numbers.
+use std::fmt;
+
+use crate::error::CalculatorError;
+use crate::expression::{BinaryOp, Environment, Expr};
+
+/// Functions on matrices, with their argument counts and a short description.
+pub const MATRIX_FUNCTIONS: &[(&str, usize, &str)] = &[
+    ("det", 1, "Determinant of a square matrix"),
+    ("inv", 1, "Inverse of a square matrix"),
+    ("transpose", 1, "Rows and columns swapped"),
+    ("dot", 2, "Dot product of two vectors"),
+    ("norm", 1, "Length of a vector, Frobenius norm of a matrix"),
+];
+
+// Up to this size determinants and inverses go by cofactors, which keeps
+// results of whole-number matrices whole
+const COFACTOR_LIMIT: usize = 3;
+
+/// Numbers in rows and columns, stored row by row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<f64>,
+}
+
+impl Matrix {
+    /// `None` unless every row has the same, nonzero length.
+    pub fn from_rows(rows: Vec<Vec<f64>>) -> Option<Self> {
+        let cols = rows.first()?.len();
+        if cols == 0 || rows.iter().any(|row| row.len() != cols) {
+            return None;
+        }
+        Some(Self {
+            rows: rows.len(),
+            cols,
+            data: rows.concat(),
+        })
+    }
+
+    pub fn identity(size: usize) -> Self {
+        let mut data = vec![0.0; size * size];
+        for index in 0..size {
+            data[index * size + index] = 1.0;
+        }
+        Self {
+            rows: size,
+            cols: size,
+            data,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> f64 {
+        self.data[row * self.cols + col]
+    }
+
+    /// E.g. "2×3".
+    pub fn size(&self) -> String {
+        format!("{}×{}", self.rows, self.cols)
+    }
+
+    fn is_vector(&self) -> bool {
+        self.rows == 1 || self.cols == 1
+    }
+
+    fn map(&self, f: impl Fn(f64) -> Result<f64, CalculatorError>) -> Result<Matrix, CalculatorError> {
+        let data = self.data.iter().map(|&value| f(value)).collect::<Result<_, _>>()?;
+        Ok(Matrix { data, ..*self })
+    }
+
+    fn zip_with(
+        &self,
+        other: &Matrix,
+        f: impl Fn(f64, f64) -> Result<f64, CalculatorError>,
+    ) -> Result<Matrix, CalculatorError> {
+        if (self.rows, self.cols) != (other.rows, other.cols) {
+            return Err(CalculatorError::Dimension(format!(
+                "Matrix sizes {} and {} don't match",
+                self.size(),
+                other.size()
+            )));
+        }
+        let data = self.data.iter().zip(&other.data).map(|(&a, &b)| f(a, b)).collect::<Result<_, _>>()?;
+        Ok(Matrix { data, ..*self })
+    }
+
+    pub fn transpose(&self) -> Matrix {
+        let mut data = Vec::with_capacity(self.data.len());
+        for col in 0..self.cols {
+            for row in 0..self.rows {
+                data.push(self.get(row, col));
+            }
+        }
+        Matrix {
+            rows: self.cols,
+            cols: self.rows,
+            data,
+        }
+    }
+
+    /// The matrix product.
+    pub fn multiply(&self, other: &Matrix) -> Result<Matrix, CalculatorError> {
+        if self.cols != other.rows {
+            return Err(CalculatorError::Dimension(format!(
+                "Can't multiply {} by {}: the columns of the first must match the rows of the second",
+                self.size(),
+                other.size()
+            )));
+        }
+        let mut data = Vec::with_capacity(self.rows * other.cols);
+        for row in 0..self.rows {
+            for col in 0..other.cols {
+                data.push((0..self.cols).map(|k| self.get(row, k) * other.get(k, col)).sum());
+            }
+        }
+        Ok(Matrix {
+            rows: self.rows,
+            cols: other.cols,
+            data,
+        })
+    }
+
+    fn require_square(&self, operation: &str) -> Result<(), CalculatorError> {
+        if self.rows == self.cols {
+            Ok(())
+        } else {
+            Err(CalculatorError::Dimension(format!("{} needs a square matrix, not {}", operation, self.size())))
+        }
+    }
+
+    pub fn determinant(&self) -> Result<f64, CalculatorError> {
+        self.require_square("det")?;
+        if self.rows <= COFACTOR_LIMIT {
+            return Ok(self.cofactor_determinant());
+        }
+        // Elimination with partial pivoting; the determinant is the product
+        // of the pivots, negated for every row swap
+        let n = self.rows;
+        let mut data = self.data.clone();
+        let mut determinant = 1.0;
+        for col in 0..n {
+            let pivot = (col..n)
+                .max_by(|&a, &b| data[a * n + col].abs().total_cmp(&data[b * n + col].abs()))
+                .unwrap_or(col);
+            if data[pivot * n + col] == 0.0 {
+                return Ok(0.0);
+            }
+            if pivot != col {
+                for k in 0..n {
+                    data.swap(pivot * n + k, col * n + k);
+                }
+                determinant = -determinant;
+            }
+            determinant *= data[col * n + col];
+            for row in col + 1..n {
+                let factor = data[row * n + col] / data[col * n + col];
+                for k in col..n {
+                    data[row * n + k] -= factor * data[col * n + k];
+                }
+            }
+        }
+        Ok(determinant)
+    }
+
+    // Expansion along the first row
+    fn cofactor_determinant(&self) -> f64 {
+        if self.rows == 1 {
+            return self.data[0];
+        }
+        (0..self.cols)
+            .map(|col| {
+                let sign = if col % 2 == 0 { 1.0 } else { -1.0 };
+                sign * self.get(0, col) * self.minor(0, col).cofactor_determinant()
+            })
+            .sum()
+    }
+
+    // The matrix without `row` and `col`
+    fn minor(&self, row: usize, col: usize) -> Matrix {
+        let data = (0..self.rows)
+            .filter(|&r| r != row)
+            .flat_map(|r| (0..self.cols).filter(move |&c| c != col).map(move |c| (r, c)))
+            .map(|(r, c)| self.get(r, c))
+            .collect();
+        Matrix {
+            rows: self.rows - 1,
+            cols: self.cols - 1,
+            data,
+        }
+    }
+
+    /// Fails for a singular matrix.
+    pub fn inverse(&self) -> Result<Matrix, CalculatorError> {
+        self.require_square("inv")?;
+        let singular = || CalculatorError::Domain(String::from("inv (the matrix is singular)"));
+        let n = self.rows;
+        if n == 1 {
+            return match self.data[0] {
+                0.0 => Err(singular()),
+                value => Ok(Matrix {
+                    data: vec![1.0 / value],
+                    ..*self
+                }),
+            };
+        }
+        if n <= COFACTOR_LIMIT {
+            let determinant = self.cofactor_determinant();
+            if determinant == 0.0 {
+                return Err(singular());
+            }
+            // The adjugate, the transposed cofactors, over the determinant
+            let mut data = Vec::with_capacity(n * n);
+            for row in 0..n {
+                for col in 0..n {
+                    let sign = if (row + col) % 2 == 0 { 1.0 } else { -1.0 };
+                    data.push(sign * self.minor(col, row).cofactor_determinant() / determinant);
+                }
+            }
+            return Ok(Matrix { data, ..*self });
+        }
+
+        // Gauss-Jordan elimination alongside the identity
+        let scale = self.data.iter().fold(0.0f64, |max, value| max.max(value.abs()));
+        let mut left = self.data.clone();
+        let mut right = Matrix::identity(n).data;
+        for col in 0..n {
+            let pivot = (col..n)
+                .max_by(|&a, &b| left[a * n + col].abs().total_cmp(&left[b * n + col].abs()))
+                .unwrap_or(col);
+            if left[pivot * n + col].abs() <= f64::EPSILON * scale * n as f64 {
+                return Err(singular());
+            }
+            for k in 0..n {
+                left.swap(pivot * n + k, col * n + k);
+                right.swap(pivot * n + k, col * n + k);
+            }
+            let divisor = left[col * n + col];
+            for k in 0..n {
+                left[col * n + k] /= divisor;
+                right[col * n + k] /= divisor;
+            }
+            for row in (0..n).filter(|&row| row != col) {
+                let factor = left[row * n + col];
+                for k in 0..n {
+                    left[row * n + k] -= factor * left[col * n + k];
+                    right[row * n + k] -= factor * right[col * n + k];
+                }
+            }
+        }
+        Ok(Matrix { data: right, ..*self })
+    }
+
+    /// A whole-number power of a square matrix; negative powers invert it.
+    pub fn power(&self, exponent: f64) -> Result<Matrix, CalculatorError> {
+        self.require_square("A matrix power")?;
+        if exponent.fract() != 0.0 || exponent.abs() > u32::MAX as f64 {
+            return Err(CalculatorError::Dimension(String::from(
+                "Matrix powers need a whole-number exponent",
+            )));
+        }
+        let mut base = if exponent < 0.0 { self.inverse()? } else { self.clone() };
+        let mut remaining = exponent.abs() as u32;
+        let mut result = Matrix::identity(self.rows);
+        // Squaring, for large exponents
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result.multiply(&base)?;
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                base = base.multiply(&base)?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// The square root of the sum of the squared elements: a vector's
+    /// length, or a matrix's Frobenius norm.
+    pub fn norm(&self) -> f64 {
+        self.data.iter().map(|value| value * value).sum::<f64>().sqrt()
+    }
+
+    pub fn dot(&self, other: &Matrix) -> Result<f64, CalculatorError> {
+        if !self.is_vector() || !other.is_vector() || self.data.len() != other.data.len() {
+            return Err(CalculatorError::Dimension(format!(
+                "dot needs two vectors of the same length, not {} and {}",
+                self.size(),
+                other.size()
+            )));
+        }
+        Ok(self.data.iter().zip(&other.data).map(|(a, b)| a * b).sum())
+    }
+}
+
+/// `[1, 2; 3, 4]`, which parses back to the same matrix.
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for row in 0..self.rows {
+            if row > 0 {
+                write!(f, "; ")?;
+            }
+            for col in 0..self.cols {
+                if col > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", self.get(row, col))?;
+            }
+        }
+        write!(f, "]")
+    }
+}
+
+/// What an expression with matrices evaluates to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Matrix(Matrix),
+}
+
+impl Value {
+    /// The number this is; a 1×1 matrix counts, as `[1, 2] * [3; 4]` is one.
+    pub fn number(self) -> Result<f64, CalculatorError> {
+        match self {
+            Value::Number(value) => Ok(value),
+            Value::Matrix(matrix) if matrix.data.len() == 1 => Ok(matrix.data[0]),
+            Value::Matrix(matrix) => Err(CalculatorError::Dimension(format!(
+                "The result is a {} matrix, not a number",
+                matrix.size()
+            ))),
+        }
+    }
+
+    fn map(self, f: impl Fn(f64) -> Result<f64, CalculatorError>) -> Result<Value, CalculatorError> {
+        match self {
+            Value::Number(value) => f(value).map(Value::Number),
+            Value::Matrix(matrix) => matrix.map(f).map(Value::Matrix),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(value) => write!(f, "{}", value),
+            Value::Matrix(matrix) => write!(f, "{}", matrix),
+        }
+    }
+}
+
+/// Whether `name` is one of `MATRIX_FUNCTIONS`.
+pub fn is_matrix_function(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    MATRIX_FUNCTIONS.iter().any(|&(function, _, _)| function == lower)
+}
+
+/// Evaluates an expression that may hold matrices.
+pub fn eval(expr: &Expr, env: &Environment) -> Result<Value, CalculatorError> {
+    match expr {
+        Expr::Number(value) => Ok(Value::Number(*value)),
+        Expr::Variable(name) => env.lookup(name).map(Value::Number),
+        Expr::Negate(operand) => eval(operand, env)?.map(|value| Ok(-value)),
+        Expr::Binary { op, left, right } => binary(*op, eval(left, env)?, eval(right, env)?),
+        Expr::Call { name, args } => {
+            let args = args.iter().map(|arg| eval(arg, env)).collect::<Result<Vec<Value>, _>>()?;
+            call(name, args, env)
+        }
+        Expr::Matrix(rows) => {
+            let not_number = || CalculatorError::Dimension(String::from("Matrix elements must be numbers"));
+            let rows = rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|element| eval(element, env)?.number().map_err(|_| not_number()))
+                        .collect::<Result<Vec<f64>, _>>()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            // The parser only builds rectangular literals
+            Matrix::from_rows(rows).map(Value::Matrix).ok_or_else(not_number)
+        }
+    }
+}
+
+fn binary(op: BinaryOp, left: Value, right: Value) -> Result<Value, CalculatorError> {
+    let element = |a: f64, b: f64| op.scalar().apply(a, b);
+    match (op, left, right) {
+        (_, Value::Number(a), Value::Number(b)) => op.apply(a, b).map(Value::Number),
+        (BinaryOp::Divide, _, Value::Matrix(_)) => Err(CalculatorError::Dimension(String::from(
+            "Can't divide by a matrix; multiply by its inv(...) instead",
+        ))),
+        (BinaryOp::Power, Value::Matrix(matrix), Value::Number(exponent)) => matrix.power(exponent).map(Value::Matrix),
+        (BinaryOp::Power, _, _) => Err(CalculatorError::Dimension(String::from(
+            "Only a square matrix can be raised to a power, and only to a number; use .^ for elements",
+        ))),
+        (BinaryOp::Multiply, Value::Matrix(a), Value::Matrix(b)) => a.multiply(&b).map(Value::Matrix),
+        // Everything else goes element by element, a number with every element
+        (_, Value::Matrix(a), Value::Matrix(b)) => a.zip_with(&b, element).map(Value::Matrix),
+        (_, Value::Matrix(a), Value::Number(b)) => a.map(|a| element(a, b)).map(Value::Matrix),
+        (_, Value::Number(a), Value::Matrix(b)) => b.map(|b| element(a, b)).map(Value::Matrix),
+    }
+}
+
+fn call(name: &str, args: Vec<Value>, env: &Environment) -> Result<Value, CalculatorError> {
+    let lower = name.to_ascii_lowercase();
+    if let Some(&(function, arity, _)) = MATRIX_FUNCTIONS.iter().find(|(function, _, _)| *function == lower) {
+        if args.len() != arity {
+            return Err(CalculatorError::WrongArgumentCount {
+                name: function.to_string(),
+                expected: arity,
+                found: args.len(),
+            });
+        }
+        // A number stands for a 1×1 matrix
+        let as_matrix = |value: &Value| match value {
+            Value::Number(number) => Matrix {
+                rows: 1,
+                cols: 1,
+                data: vec![*number],
+            },
+            Value::Matrix(matrix) => matrix.clone(),
+        };
+        let matrix = as_matrix(&args[0]);
+        return match function {
+            "det" => matrix.determinant().map(Value::Number),
+            "inv" => matrix.inverse().map(Value::Matrix),
+            "transpose" => Ok(Value::Matrix(matrix.transpose())),
+            "dot" => matrix.dot(&as_matrix(&args[1])).map(Value::Number),
+            _ => Ok(Value::Number(matrix.norm())),
+        };
+    }
+
+    match args.as_slice() {
+        // Other one-argument functions apply to every element
+        [Value::Matrix(matrix)] => matrix.map(|value| env.call(name, &[value])).map(Value::Matrix),
+        _ => {
+            let numbers = args
+                .into_iter()
+                .map(|arg| {
+                    arg.number().map_err(|_| {
+                        CalculatorError::Dimension(format!("{} takes numbers, not matrices", name))
+                    })
+                })
+                .collect::<Result<Vec<f64>, _>>()?;
+            env.call(name, &numbers).map(Value::Number)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::parse;
+    use proptest::prelude::*;
+
+    fn value(source: &str) -> Result<Value, CalculatorError> {
+        eval(&parse(source).unwrap(), &Environment::new())
+    }
+
+    fn matrix(source: &str) -> Matrix {
+        match value(source) {
+            Ok(Value::Matrix(matrix)) => matrix,
+            other => panic!("{} gave {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(matrix("[1, 2; 3, 4] * [5; 6]").to_string(), "[17; 39]");
+        assert_eq!(matrix("[1, 2; 3, 4] .* [5, 6; 7, 8]").to_string(), "[5, 12; 21, 32]");
+        assert_eq!(matrix("[1, 2; 3, 4] + 10").to_string(), "[11, 12; 13, 14]");
+        assert_eq!(matrix("2 * [1, 2] - [1, 1]").to_string(), "[1, 3]");
+        assert_eq!(matrix("[2, 4] / 2").to_string(), "[1, 2]");
+        assert_eq!(matrix("1 ./ [2, 4]").to_string(), "[0.5, 0.25]");
+        assert_eq!(matrix("[1, 2] .^ 2").to_string(), "[1, 4]");
+        assert_eq!(matrix("-[1, -2]").to_string(), "[-1, 2]");
+        assert_eq!(matrix("[1, 1; 0, 1] ^ 5").to_string(), "[1, 5; 0, 1]");
+        assert_eq!(matrix("[2, 0; 0, 4] ^ -1").to_string(), "[0.5, 0; 0, 0.25]");
+        assert_eq!(matrix("sqrt([4, 9])").to_string(), "[2, 3]");
+        assert_eq!(matrix("transpose([1, 2, 3])").to_string(), "[1; 2; 3]");
+        assert_eq!(matrix("[pi / pi, 1 + 1]").to_string(), "[1, 2]");
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(value("det([1, 2; 3, 4])"), Ok(Value::Number(-2.0)));
+        assert_eq!(value("det([2, 0, 1; 1, 3, 2; 1, 1, 2])"), Ok(Value::Number(6.0)));
+        assert_eq!(matrix("inv([1, 2; 3, 4])").to_string(), "[-2, 1; 1.5, -0.5]");
+        assert_eq!(value("dot([1, 2, 3], [4; 5; 6])"), Ok(Value::Number(32.0)));
+        assert_eq!(value("norm([3, 4])"), Ok(Value::Number(5.0)));
+        assert_eq!(value("[1, 2] * [3; 4]").and_then(Value::number), Ok(11.0));
+        assert_eq!(value("max(det([2]), 1)"), Ok(Value::Number(2.0)));
+
+        // Elimination for larger matrices
+        let large = "[2, 1, 0, 0; 1, 2, 1, 0; 0, 1, 2, 1; 0, 0, 1, 2]";
+        let determinant = value(&format!("det({})", large)).and_then(Value::number).unwrap();
+        assert!((determinant - 5.0).abs() < 1e-12);
+        let product = matrix(&format!("{0} * inv({0})", large));
+        for row in 0..4 {
+            for col in 0..4 {
+                let expected = if row == col { 1.0 } else { 0.0 };
+                assert!((product.get(row, col) - expected).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let dimension = |source| matches!(value(source), Err(CalculatorError::Dimension(_)));
+        assert!(dimension("[1, 2] + [1, 2, 3]"));
+        assert!(dimension("[1, 2] * [3, 4]"));
+        assert!(dimension("det([1, 2])"));
+        assert!(dimension("2 / [1, 2]"));
+        assert!(dimension("2 ^ [1, 2]"));
+        assert!(dimension("[1, 1; 0, 1] ^ 0.5"));
+        assert!(dimension("dot([1, 2], [1; 2; 3])"));
+        assert!(dimension("[[1, 2], 3]"));
+        assert!(dimension("max([1, 2], 3)"));
+        assert_eq!(
+            value("inv([1, 2; 2, 4])"),
+            Err(CalculatorError::Domain(String::from("inv (the matrix is singular)")))
+        );
+        assert_eq!(value("[1, 0] ./ [1, 0]"), Err(CalculatorError::DivisionByZero));
+        assert_eq!(value("[1, 2; 3, 4]").and_then(Value::number).unwrap_err().to_string(), "Error: The result is a 2×2 matrix, not a number");
+    }
+
+    // Feature: gui-calculator, Property 59: A matrix times its inverse is the identity
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_inverse(size in 1usize..6, elements in prop::collection::vec(-10i32..10, 25)) {
+            let rows: Vec<Vec<f64>> = (0..size)
+                .map(|row| (0..size).map(|col| {
+                    // A heavy diagonal keeps it well away from singular
+                    let diagonal = if row == col { 50.0 } else { 0.0 };
+                    diagonal + elements[row * 5 + col] as f64
+                }).collect())
+                .collect();
+            let matrix = Matrix::from_rows(rows).unwrap();
+            let product = matrix.multiply(&matrix.inverse().unwrap()).unwrap();
+            let identity = Matrix::identity(size);
+            prop_assert!(product.data.iter().zip(&identity.data).all(|(a, b)| (a - b).abs() < 1e-12), "{}", product);
+            prop_assert!(matrix.determinant().unwrap().abs() > 0.0);
+        }
+    }
+}
@@ -0,0 +1,341 @@
+// Monte Carlo "What If"
+//
+// Variables given as distributions, e.g. `x ~ normal(10, 2)`, are sampled
+// over and over and the expression evaluated with each draw, so the spread of
+// the result shows alongside its typical value.
+use crate::error::CalculatorError;
+use crate::expression::{self, Environment, Expr, ParseOptions};
+use crate::task::Progress;
+
+/// Percentiles reported in a summary.
+pub const PERCENTILES: [u8; 5] = [5, 25, 50, 75, 95];
+const HISTOGRAM_BINS: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    Normal { mean: f64, std_dev: f64 },
+    Uniform { low: f64, high: f64 },
+    Triangular { low: f64, mode: f64, high: f64 },
+}
+
+impl Distribution {
+    /// `normal(mean, sd)`, `uniform(low, high)` or `triangular(low, mode, high)`.
+    fn from_call(name: &str, args: &[f64]) -> Result<Self, CalculatorError> {
+        let lower = name.to_ascii_lowercase();
+        let expected = match lower.as_str() {
+            "normal" | "uniform" => 2,
+            "triangular" => 3,
+            _ => return Err(CalculatorError::UnknownFunction(name.to_string())),
+        };
+        if args.len() != expected {
+            return Err(CalculatorError::WrongArgumentCount {
+                name: lower,
+                expected,
+                found: args.len(),
+            });
+        }
+        let distribution = match *args {
+            [mean, std_dev] if lower == "normal" => Distribution::Normal { mean, std_dev },
+            [low, high] => Distribution::Uniform { low, high },
+            [low, mode, high] => Distribution::Triangular { low, mode, high },
+            _ => unreachable!("argument count checked above"),
+        };
+        let valid = match distribution {
+            Distribution::Normal { std_dev, .. } => std_dev >= 0.0,
+            Distribution::Uniform { low, high } => low <= high,
+            Distribution::Triangular { low, mode, high } => low <= mode && mode <= high,
+        };
+        if !valid {
+            return Err(CalculatorError::Domain(lower));
+        }
+        Ok(distribution)
+    }
+
+    pub fn sample(&self, rng: &mut Rng) -> f64 {
+        match *self {
+            Distribution::Normal { mean, std_dev } => {
+                // Box-Muller; 1 - u keeps the logarithm's argument above zero
+                let (u, v) = (1.0 - rng.next_f64(), rng.next_f64());
+                mean + std_dev * (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+            }
+            Distribution::Uniform { low, high } => low + (high - low) * rng.next_f64(),
+            Distribution::Triangular { low, mode, high } => {
+                let u = rng.next_f64();
+                let split = if high > low { (mode - low) / (high - low) } else { 0.5 };
+                let value = if u < split {
+                    low + ((high - low) * (mode - low) * u).sqrt()
+                } else {
+                    high - ((high - low) * (high - mode) * (1.0 - u)).sqrt()
+                };
+                // Rounding can land a hair outside
+                value.clamp(low, high)
+            }
+        }
+    }
+}
+
+/// A variable drawn from a distribution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub name: String,
+    pub distribution: Distribution,
+}
+
+/// Reads one line of `name ~ distribution(...)`. `None` for blank and `#`
+/// comment lines. Arguments may be expressions, e.g. `normal(0, 1/3)`.
+pub fn parse_definition(line: &str, env: &Environment) -> Result<Option<Definition>, CalculatorError> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let Some((name, source)) = line.split_once('~') else {
+        return Err(CalculatorError::Syntax {
+            message: String::from("Expected 'name ~ distribution(...)'"),
+            column: 1,
+        });
+    };
+    let name = name.trim();
+    let is_identifier = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if !is_identifier {
+        return Err(CalculatorError::Syntax {
+            message: format!("'{}' isn't a variable name", name),
+            column: 1,
+        });
+    }
+    let Expr::Call { name: function, args } = expression::parse_with(source, ParseOptions::default())? else {
+        return Err(CalculatorError::Syntax {
+            message: String::from("Expected a distribution such as normal(10, 2)"),
+            column: line.find('~').unwrap_or(0) + 2,
+        });
+    };
+    let args = args.iter().map(|arg| arg.eval(env)).collect::<Result<Vec<f64>, _>>()?;
+    Ok(Some(Definition {
+        name: name.to_string(),
+        distribution: Distribution::from_call(&function, &args)?,
+    }))
+}
+
+/// SplitMix64: small, fast and plenty for sampling, and the same seed gives
+/// the same run.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Counts of results in equal-width bins from `low` to `high`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub low: f64,
+    pub high: f64,
+    pub counts: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    /// Draws with a finite result
+    pub count: usize,
+    /// Draws where evaluating failed, e.g. dividing by a zero that was drawn
+    pub failed: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    /// Alongside `PERCENTILES`
+    pub percentiles: Vec<f64>,
+    pub histogram: Histogram,
+}
+
+/// Evaluates `expr` `samples` times with fresh draws for every definition.
+/// Errs with the first failure if no draw gave a result.
+pub fn simulate(
+    expr: &Expr,
+    definitions: &[Definition],
+    samples: usize,
+    mut env: Environment,
+    seed: u64,
+    progress: &Progress,
+) -> Option<Result<Summary, CalculatorError>> {
+    let mut rng = Rng::new(seed);
+    let mut results = Vec::with_capacity(samples);
+    let mut first_error = None;
+    progress.set_total(samples);
+    for _ in 0..samples {
+        if progress.is_cancelled() {
+            return None;
+        }
+        progress.advance();
+        for definition in definitions {
+            env.variables.insert(definition.name.clone(), definition.distribution.sample(&mut rng));
+        }
+        match expr.eval(&env) {
+            Ok(value) if value.is_finite() => results.push(value),
+            Ok(_) => {
+                first_error.get_or_insert(CalculatorError::TooLarge);
+            }
+            Err(err) => {
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+    if results.is_empty() {
+        return Some(Err(first_error.unwrap_or(CalculatorError::Domain(String::from("samples")))));
+    }
+    Some(Ok(summarize(results, samples)))
+}
+
+fn summarize(mut results: Vec<f64>, samples: usize) -> Summary {
+    results.sort_by(f64::total_cmp);
+    let count = results.len();
+    let mean = results.iter().sum::<f64>() / count as f64;
+    let variance = if count > 1 {
+        results.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / (count - 1) as f64
+    } else {
+        0.0
+    };
+    let percentiles = PERCENTILES.iter().map(|&p| percentile(&results, p as f64 / 100.0)).collect();
+
+    let (low, high) = (results[0], results[count - 1]);
+    let mut counts = vec![0; HISTOGRAM_BINS];
+    for value in &results {
+        let bin = if high > low {
+            ((value - low) / (high - low) * HISTOGRAM_BINS as f64) as usize
+        } else {
+            0
+        };
+        // The maximum belongs in the last bin rather than one past it
+        counts[bin.min(HISTOGRAM_BINS - 1)] += 1;
+    }
+
+    Summary {
+        count,
+        failed: samples - count,
+        mean,
+        std_dev: variance.sqrt(),
+        percentiles,
+        histogram: Histogram { low, high, counts },
+    }
+}
+
+// Interpolates between the two nearest of the sorted values
+fn percentile(sorted: &[f64], fraction: f64) -> f64 {
+    let position = fraction * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::parse;
+    use proptest::prelude::*;
+
+    fn definitions(text: &str) -> Vec<Definition> {
+        text.lines()
+            .filter_map(|line| parse_definition(line, &Environment::new()).unwrap())
+            .collect()
+    }
+
+    fn run(source: &str, text: &str, samples: usize) -> Result<Summary, CalculatorError> {
+        let progress = Progress::default();
+        simulate(&parse(source).unwrap(), &definitions(text), samples, Environment::new(), 7, &progress).unwrap()
+    }
+
+    #[test]
+    fn test_parse_definitions() {
+        assert_eq!(
+            definitions("x ~ normal(10, 2)\n\n# costs\nrate ~ Uniform(0.5, 1 + 0.5)"),
+            [
+                Definition {
+                    name: String::from("x"),
+                    distribution: Distribution::Normal { mean: 10.0, std_dev: 2.0 },
+                },
+                Definition {
+                    name: String::from("rate"),
+                    distribution: Distribution::Uniform { low: 0.5, high: 1.5 },
+                },
+            ]
+        );
+        let parse_line = |line| parse_definition(line, &Environment::new());
+        assert!(matches!(parse_line("x = 3"), Err(CalculatorError::Syntax { .. })));
+        assert!(matches!(parse_line("2x ~ normal(0, 1)"), Err(CalculatorError::Syntax { .. })));
+        assert!(matches!(parse_line("x ~ 3"), Err(CalculatorError::Syntax { .. })));
+        assert_eq!(parse_line("x ~ cauchy(0, 1)"), Err(CalculatorError::UnknownFunction(String::from("cauchy"))));
+        assert!(matches!(parse_line("x ~ normal(1)"), Err(CalculatorError::WrongArgumentCount { expected: 2, .. })));
+        assert_eq!(parse_line("x ~ normal(0, -1)"), Err(CalculatorError::Domain(String::from("normal"))));
+        assert_eq!(parse_line("x ~ triangular(0, 5, 2)"), Err(CalculatorError::Domain(String::from("triangular"))));
+    }
+
+    #[test]
+    fn test_normal_summary() {
+        let summary = run("x * 3 + 1", "x ~ normal(10, 2)", 20_000).unwrap();
+        assert_eq!((summary.count, summary.failed), (20_000, 0));
+        assert!((summary.mean - 31.0).abs() < 0.2, "mean {}", summary.mean);
+        assert!((summary.std_dev - 6.0).abs() < 0.2, "std {}", summary.std_dev);
+        // About 1.645 standard deviations either side of the mean
+        assert!((summary.percentiles[0] - 21.13).abs() < 0.5);
+        assert!((summary.percentiles[4] - 40.87).abs() < 0.5);
+        assert_eq!(summary.histogram.counts.iter().sum::<usize>(), 20_000);
+
+        // The same seed gives the same run
+        assert_eq!(run("x * 3 + 1", "x ~ normal(10, 2)", 20_000), Ok(summary));
+    }
+
+    #[test]
+    fn test_failures() {
+        let summary = run("1 / x", "x ~ uniform(0, 0)", 10);
+        assert_eq!(summary, Err(CalculatorError::DivisionByZero));
+        let summary = run("price * (1 + tax)", "tax ~ triangular(0.1, 0.19, 0.2)", 10);
+        assert_eq!(summary, Err(CalculatorError::UnknownVariable(String::from("price"))));
+        assert_eq!(percentile(&[1.0, 2.0, 3.0, 4.0], 0.5), 2.5);
+    }
+
+    #[test]
+    fn test_progress_and_cancel() {
+        let progress = Progress::default();
+        let definitions = definitions("x ~ uniform(0, 1)");
+        assert!(simulate(&parse("x").unwrap(), &definitions, 50, Environment::new(), 7, &progress).is_some());
+        assert_eq!(progress.counts(), (50, 50));
+
+        let progress = Progress::default();
+        progress.cancel();
+        assert_eq!(simulate(&parse("x").unwrap(), &definitions, 50, Environment::new(), 7, &progress), None);
+        assert_eq!(progress.counts(), (0, 50));
+    }
+
+    // Feature: gui-calculator, Property 56: Sampled percentiles are ordered and within the distribution's range
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_percentiles_within_range(low in -1e3..1e3f64, width in 0.0..1e3f64, mode in 0.0..1.0f64, seed in any::<u64>()) {
+            let high = low + width;
+            let distribution = Distribution::Triangular { low, mode: low + width * mode, high };
+            let definitions = [Definition { name: String::from("x"), distribution }];
+            let progress = Progress::default();
+            let summary = simulate(&parse("x").unwrap(), &definitions, 200, Environment::new(), seed, &progress)
+                .unwrap()
+                .unwrap();
+            prop_assert!(summary.histogram.low >= low && summary.histogram.high <= high);
+            prop_assert!(summary.percentiles.windows(2).all(|pair| pair[0] <= pair[1]));
+            prop_assert!(summary.percentiles.iter().all(|&p| p >= summary.histogram.low && p <= summary.histogram.high));
+            prop_assert_eq!(summary.histogram.counts.iter().sum::<usize>(), 200);
+        }
+    }
+}
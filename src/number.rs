@@ -0,0 +1,120 @@
+// Keypad Numbers
+//
+// Negative zero policy: a result of -0, as from 0 × -1 or round(-0.4), is
+// kept as plain 0, so it shows as 0 and chains like 0. Inside a calculation
+// the sign still counts where it decides something, and there dividing by
+// either zero is an error rather than ±∞.
+use rust_decimal::Decimal;
+
+use crate::decimal;
+use crate::error::{CalculatorError, Warning};
+use crate::operation::{BinaryOperation, Operation};
+
+/// A value on the keypad: the float it displays and computes as, plus the
+/// exact decimal it stands for when known. Chained operations carry the
+/// decimal along, so `0.1 + 0.2 + 0.3` doesn't pile up binary error from
+/// step to step; rounding to a float happens once per result.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Number {
+    float: f64,
+    exact: Option<Decimal>,
+}
+
+impl Number {
+    /// A value as the user sees it, e.g. typed or recalled: 0.1 stands for
+    /// exactly 0.1, not its binary approximation.
+    pub fn new(value: f64) -> Self {
+        let value = unsigned_zero(value);
+        Self {
+            float: value,
+            exact: decimal::to_decimal(value),
+        }
+    }
+
+    /// A computed result. In decimal mode the exact result, rounded once,
+    /// is what's shown; otherwise the binary `float` is.
+    pub fn computed(float: f64, exact: Option<Decimal>, decimal_mode: bool) -> Self {
+        let float = match exact.and_then(decimal::to_float) {
+            Some(rounded) if decimal_mode => rounded,
+            _ => float,
+        };
+        Self {
+            float: unsigned_zero(float),
+            exact,
+        }
+    }
+
+    pub fn float(self) -> f64 {
+        self.float
+    }
+
+    /// The exact value as the closest f64, when binary arithmetic made the
+    /// float differ from it.
+    pub fn exact_difference(self) -> Option<f64> {
+        decimal::to_float(self.exact?).filter(|&exact| exact != self.float)
+    }
+
+    /// The same value shown as its exact decimal, as decimal mode would have.
+    pub fn to_decimal_mode(self) -> Self {
+        Self::computed(self.float, self.exact, true)
+    }
+
+    /// `self op other`, in binary and, when both sides are known exactly,
+    /// in decimal too.
+    pub fn apply(self, op: Operation, other: Number, decimal_mode: bool) -> Result<Number, CalculatorError> {
+        let float = op.apply(self.float, other.float)?;
+        let exact = match (self.exact, other.exact) {
+            (Some(left), Some(right)) => decimal::apply(op.into(), left, right),
+            _ => None,
+        };
+        Ok(Self::computed(float, exact, decimal_mode))
+    }
+}
+
+/// `value` as a result: ±∞ means some step went past the largest double,
+/// and NaN that a step had no defined result.
+pub fn in_range(value: f64) -> Result<f64, CalculatorError> {
+    if value.is_nan() {
+        Err(CalculatorError::Domain(String::from("this calculation")))
+    } else if value.is_infinite() {
+        Err(CalculatorError::TooLarge)
+    } else {
+        Ok(value)
+    }
+}
+
+/// What's off about a result that can still be shown, if anything.
+pub fn warning(value: f64) -> Option<Warning> {
+    value.is_subnormal().then_some(Warning::Subnormal)
+}
+
+/// `value` with negative zero made plain zero; everything else unchanged.
+pub fn unsigned_zero(value: f64) -> f64 {
+    if value == 0.0 {
+        0.0
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_keeps_exact_intermediates() {
+        let sum = Number::new(0.1)
+            .apply(Operation::Add, Number::new(0.2), false)
+            .and_then(|sum| sum.apply(Operation::Add, Number::new(0.3), false))
+            .unwrap();
+        assert_eq!(sum.float(), 0.1 + 0.2 + 0.3);
+        assert_eq!(sum.exact_difference(), Some(0.6));
+        assert_eq!(sum.to_decimal_mode().float(), 0.6);
+
+        // A third carried as 28 digits rather than its shortest float string
+        let third = Number::new(1.0).apply(Operation::Divide, Number::new(3.0), true).unwrap();
+        let one = third.apply(Operation::Multiply, Number::new(3.0), true).unwrap();
+        assert_eq!(one.float(), 1.0);
+        assert_eq!(Number::new(third.float()).apply(Operation::Multiply, Number::new(3.0), true).unwrap().float(), 0.9999999999999999);
+    }
+}
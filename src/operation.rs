@@ -1,6 +1,48 @@
-// Operation Enum
+// Operations
+//
+// Every operation describes itself through `OperationInfo`; what it computes
+// depends on its arity, through `BinaryOperation`, `UnaryOperation` or
+// `ConstOperation`. A mode offers more operations by registering them in an
+// `OperationRegistry` rather than by growing a match.
+use std::fmt;
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+use crate::error::CalculatorError;
+use crate::expression::Expr;
+use crate::number::Number;
+
+/// What keypads, key bindings and displays need to know about an operation.
+pub trait OperationInfo {
+    fn name(&self) -> &'static str;
+    /// How it's written on keys and in readouts, e.g. "×"
+    fn symbol(&self) -> &'static str;
+    /// The typed character that triggers it, if any
+    fn key(&self) -> Option<char> {
+        None
+    }
+    /// 2 for binary, 1 for unary and 0 for constants
+    fn arity(&self) -> usize;
+}
+
+pub trait BinaryOperation: OperationInfo {
+    /// How tightly it binds in infix notation; higher binds tighter
+    fn precedence(&self) -> u8;
+    fn apply(&self, left: f64, right: f64) -> Result<f64, CalculatorError>;
+}
+
+pub trait UnaryOperation: OperationInfo {
+    fn apply(&self, value: f64) -> Result<f64, CalculatorError>;
+}
+
+pub trait ConstOperation: OperationInfo {
+    fn value(&self) -> f64;
+}
+
+/// The four keypad operations. A closed, serializable set, as macros and the
+/// calculator state store them by value.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Operation {
     Add,
     Subtract,
@@ -9,14 +51,68 @@ pub enum Operation {
 }
 
 impl Operation {
-    pub fn apply(&self, left: f64, right: f64) -> Result<f64, String> {
+    pub const ALL: [Operation; 4] = [Operation::Add, Operation::Subtract, Operation::Multiply, Operation::Divide];
+
+    /// The operation a typed character stands for.
+    pub fn from_key(key: char) -> Option<Operation> {
+        Self::ALL.into_iter().find(|op| op.key() == Some(key))
+    }
+
+    /// + or −, the operations an adding machine counts items for.
+    pub fn is_additive(self) -> bool {
+        matches!(self, Operation::Add | Operation::Subtract)
+    }
+}
+
+impl OperationInfo for Operation {
+    fn name(&self) -> &'static str {
+        match self {
+            Operation::Add => "add",
+            Operation::Subtract => "subtract",
+            Operation::Multiply => "multiply",
+            Operation::Divide => "divide",
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Operation::Add => "+",
+            Operation::Subtract => "−",
+            Operation::Multiply => "×",
+            Operation::Divide => "÷",
+        }
+    }
+
+    fn key(&self) -> Option<char> {
+        Some(match self {
+            Operation::Add => '+',
+            Operation::Subtract => '-',
+            Operation::Multiply => '*',
+            Operation::Divide => '/',
+        })
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+}
+
+impl BinaryOperation for Operation {
+    fn precedence(&self) -> u8 {
+        match self {
+            Operation::Add | Operation::Subtract => 1,
+            Operation::Multiply | Operation::Divide => 2,
+        }
+    }
+
+    fn apply(&self, left: f64, right: f64) -> Result<f64, CalculatorError> {
         match self {
             Operation::Add => Ok(left + right),
             Operation::Subtract => Ok(left - right),
             Operation::Multiply => Ok(left * right),
             Operation::Divide => {
                 if right == 0.0 {
-                    Err(String::from("Error: Division by zero"))
+                    Err(CalculatorError::DivisionByZero)
                 } else {
                     Ok(left / right)
                 }
@@ -25,11 +121,279 @@ impl Operation {
     }
 }
 
+/// A registered binary operation, shared with the calculators that hold it
+/// pending.
+pub type SharedBinary = Arc<dyn BinaryOperation + Send + Sync>;
+
+/// An operation waiting for its second operand: one of the keypad's four,
+/// or a binary operation a mode registered.
+#[derive(Clone)]
+pub enum PendingOperation {
+    Keypad(Operation),
+    Registered(SharedBinary),
+}
+
+impl PendingOperation {
+    /// The keypad operation, if it's one of the four.
+    pub fn keypad(&self) -> Option<Operation> {
+        match self {
+            PendingOperation::Keypad(op) => Some(*op),
+            PendingOperation::Registered(_) => None,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            PendingOperation::Keypad(op) => op.symbol(),
+            PendingOperation::Registered(op) => op.symbol(),
+        }
+    }
+
+    /// `left op right`. Only the keypad's four are also worked out in
+    /// decimal; a registered operation computes in binary.
+    pub fn apply(&self, left: Number, right: Number, decimal_mode: bool) -> Result<Number, CalculatorError> {
+        match self {
+            PendingOperation::Keypad(op) => left.apply(*op, right, decimal_mode),
+            PendingOperation::Registered(op) => op.apply(left.float(), right.float()).map(Number::new),
+        }
+    }
+
+    /// The calculation as an expression: infix for the keypad's four and a
+    /// call of the registered name otherwise, e.g. `mod(7, 3)`.
+    pub fn expression(&self, left: f64, right: f64) -> Expr {
+        match self {
+            PendingOperation::Keypad(op) => Expr::binary((*op).into(), Expr::Number(left), Expr::Number(right)),
+            PendingOperation::Registered(op) => Expr::Call {
+                name: op.name().to_string(),
+                args: vec![Expr::Number(left), Expr::Number(right)],
+            },
+        }
+    }
+}
+
+impl From<Operation> for PendingOperation {
+    fn from(op: Operation) -> Self {
+        PendingOperation::Keypad(op)
+    }
+}
+
+// Registered operations are told apart by name, as the registry never holds
+// two of the same name
+impl PartialEq for PendingOperation {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (PendingOperation::Keypad(a), PendingOperation::Keypad(b)) => a == b,
+            (PendingOperation::Registered(a), PendingOperation::Registered(b)) => a.name() == b.name(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for PendingOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PendingOperation::Keypad(op) => f.debug_tuple("Keypad").field(op).finish(),
+            PendingOperation::Registered(op) => f.debug_tuple("Registered").field(&op.name()).finish(),
+        }
+    }
+}
+
+/// A binary operation made from a plain function, for registering without a
+/// type of its own.
+pub struct FnBinary {
+    pub name: &'static str,
+    pub symbol: &'static str,
+    pub precedence: u8,
+    pub function: fn(f64, f64) -> Result<f64, CalculatorError>,
+}
+
+impl OperationInfo for FnBinary {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn symbol(&self) -> &'static str {
+        self.symbol
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+}
+
+impl BinaryOperation for FnBinary {
+    fn precedence(&self) -> u8 {
+        self.precedence
+    }
+
+    fn apply(&self, left: f64, right: f64) -> Result<f64, CalculatorError> {
+        (self.function)(left, right)
+    }
+}
+
+/// A unary operation made from a plain function, for registering without a
+/// type of its own.
+pub struct FnUnary {
+    pub name: &'static str,
+    pub symbol: &'static str,
+    pub function: fn(f64) -> Result<f64, CalculatorError>,
+}
+
+impl OperationInfo for FnUnary {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn symbol(&self) -> &'static str {
+        self.symbol
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+}
+
+impl UnaryOperation for FnUnary {
+    fn apply(&self, value: f64) -> Result<f64, CalculatorError> {
+        (self.function)(value)
+    }
+}
+
+/// A named constant.
+pub struct Constant {
+    pub name: &'static str,
+    pub symbol: &'static str,
+    pub value: f64,
+}
+
+impl OperationInfo for Constant {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn symbol(&self) -> &'static str {
+        self.symbol
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+}
+
+impl ConstOperation for Constant {
+    fn value(&self) -> f64 {
+        self.value
+    }
+}
+
+/// The operations a keypad offers besides + − × ÷, in key order: binary
+/// operations, then unary ones, then constants.
+#[derive(Default)]
+pub struct OperationRegistry {
+    binary: Vec<SharedBinary>,
+    unary: Vec<Box<dyn UnaryOperation>>,
+    constants: Vec<Box<dyn ConstOperation>>,
+}
+
+impl OperationRegistry {
+    /// √, x², ± and π, the keys of the basic keypad.
+    pub fn basic() -> Self {
+        let mut registry = Self::default();
+        registry.register_unary(FnUnary {
+            name: "sqrt",
+            symbol: "√",
+            function: |value| {
+                if value < 0.0 {
+                    Err(CalculatorError::Domain(String::from("sqrt")))
+                } else {
+                    Ok(value.sqrt())
+                }
+            },
+        });
+        registry.register_unary(FnUnary {
+            name: "square",
+            symbol: "x²",
+            function: |value| Ok(value * value),
+        });
+        registry.register_unary(FnUnary {
+            name: "negate",
+            symbol: "±",
+            function: |value| Ok(-value),
+        });
+        registry.register_constant(Constant {
+            name: "pi",
+            symbol: "π",
+            value: std::f64::consts::PI,
+        });
+        registry
+    }
+
+    pub fn register_binary(&mut self, operation: impl BinaryOperation + Send + Sync + 'static) {
+        debug_assert_eq!(operation.arity(), 2, "{} is not binary", operation.name());
+        self.binary.push(Arc::new(operation));
+    }
+
+    pub fn register_unary(&mut self, operation: impl UnaryOperation + 'static) {
+        debug_assert_eq!(operation.arity(), 1, "{} is not unary", operation.name());
+        self.unary.push(Box::new(operation));
+    }
+
+    pub fn register_constant(&mut self, operation: impl ConstOperation + 'static) {
+        debug_assert_eq!(operation.arity(), 0, "{} is not a constant", operation.name());
+        self.constants.push(Box::new(operation));
+    }
+
+    pub fn binary(&self) -> impl Iterator<Item = &SharedBinary> {
+        self.binary.iter()
+    }
+
+    pub fn unary(&self) -> impl Iterator<Item = &dyn UnaryOperation> {
+        self.unary.iter().map(|operation| operation.as_ref())
+    }
+
+    pub fn constants(&self) -> impl Iterator<Item = &dyn ConstOperation> {
+        self.constants.iter().map(|operation| operation.as_ref())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use proptest::prelude::*;
 
+    #[test]
+    fn test_metadata_and_registry() {
+        for op in Operation::ALL {
+            assert_eq!(Operation::from_key(op.key().unwrap()), Some(op));
+            assert_eq!(op.arity(), 2);
+        }
+        assert!(Operation::Multiply.precedence() > Operation::Subtract.precedence());
+        assert_eq!(Operation::from_key('x'), None);
+
+        let registry = OperationRegistry::basic();
+        let sqrt = registry.unary().find(|op| op.name() == "sqrt").unwrap();
+        assert_eq!(sqrt.apply(9.0), Ok(3.0));
+        assert_eq!(sqrt.apply(-1.0), Err(CalculatorError::Domain(String::from("sqrt"))));
+        let pi = registry.constants().next().unwrap();
+        assert_eq!((pi.symbol(), pi.arity()), ("π", 0));
+        assert_eq!(pi.value(), std::f64::consts::PI);
+
+        let mut registry = OperationRegistry::basic();
+        assert_eq!(registry.binary().count(), 0);
+        registry.register_binary(FnBinary {
+            name: "hypot",
+            symbol: "hyp",
+            precedence: 2,
+            function: |left, right| Ok(left.hypot(right)),
+        });
+        let hypot = registry.binary().next().unwrap();
+        assert_eq!((hypot.arity(), hypot.apply(3.0, 4.0)), (2, Ok(5.0)));
+        let pending = PendingOperation::Registered(Arc::clone(hypot));
+        assert_eq!(pending.apply(Number::new(5.0), Number::new(12.0), true), Ok(Number::new(13.0)));
+        assert_eq!(pending.expression(3.0, 4.0).to_string(), "hypot(3, 4)");
+        assert_eq!(PendingOperation::from(Operation::Add).expression(3.0, 4.0).to_string(), "3 + 4");
+    }
+
     // Feature: gui-calculator, Property 4: Arithmetic correctness
     // Validates: Requirements 2.2, 2.3, 2.4, 2.5, 2.6
     proptest! {
@@ -69,7 +433,7 @@ mod tests {
         ) {
             let result = Operation::Divide.apply(left, 0.0);
             prop_assert!(result.is_err());
-            prop_assert_eq!(result.unwrap_err(), "Error: Division by zero");
+            prop_assert_eq!(result.unwrap_err().to_string(), "Error: Division by zero");
         }
     }
 }
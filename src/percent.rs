@@ -0,0 +1,89 @@
+// Percentage Calculations
+use crate::error::CalculatorError;
+
+/// What percentage `part` is of `whole`, e.g. 30 is 25% of 120.
+pub fn percent_of(part: f64, whole: f64) -> Result<f64, CalculatorError> {
+    if whole == 0.0 {
+        return Err(CalculatorError::DivisionByZero);
+    }
+    Ok(part / whole * 100.0)
+}
+
+/// The change from `from` to `to` in percent; negative for a decrease.
+pub fn percent_change(from: f64, to: f64) -> Result<f64, CalculatorError> {
+    if from == 0.0 {
+        return Err(CalculatorError::DivisionByZero);
+    }
+    // Relative to the size of the start value, so -50 to -25 is an increase
+    Ok((to - from) / from.abs() * 100.0)
+}
+
+/// How far `b` is from `a`: `b − a`, and that as a percent change from `a`
+/// unless `a` is zero. A gap within the rounding error of the two values,
+/// as with 0.1 + 0.2 against 0.3, counts as none.
+pub fn difference(a: f64, b: f64) -> (f64, Option<f64>) {
+    let mut absolute = b - a;
+    if absolute.abs() <= 4.0 * f64::EPSILON * a.abs().max(b.abs()) {
+        absolute = 0.0;
+    }
+    (absolute, percent_change(a, a + absolute).ok())
+}
+
+/// `value` increased by `percent` percent; a negative percent decreases it.
+pub fn increase_by(value: f64, percent: f64) -> f64 {
+    value * (1.0 + percent / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_percent_functions() {
+        assert_eq!(percent_of(30.0, 120.0), Ok(25.0));
+        assert_eq!(percent_of(1.0, 0.0), Err(CalculatorError::DivisionByZero));
+        assert_eq!(percent_change(80.0, 100.0), Ok(25.0));
+        assert_eq!(percent_change(100.0, 80.0), Ok(-20.0));
+        assert_eq!(percent_change(-50.0, -25.0), Ok(50.0));
+        assert_eq!(percent_change(0.0, 5.0), Err(CalculatorError::DivisionByZero));
+        assert_eq!(increase_by(200.0, 50.0), 300.0);
+        assert_eq!(increase_by(200.0, -50.0), 100.0);
+    }
+
+    #[test]
+    fn test_difference() {
+        assert_eq!(difference(80.0, 100.0), (20.0, Some(25.0)));
+        assert_eq!(difference(100.0, 80.0), (-20.0, Some(-20.0)));
+        assert_eq!(difference(0.1 + 0.2, 0.3), (0.0, Some(0.0)));
+        assert_eq!(difference(0.0, 5.0), (5.0, None));
+    }
+
+    // Feature: gui-calculator, Property 20: Percent change undoes an increase
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_change_after_increase(
+            value in 0.01..1000000.0f64,
+            percent in -99.0..1000.0f64
+        ) {
+            let increased = increase_by(value, percent);
+            let change = percent_change(value, increased).unwrap();
+            prop_assert!((change - percent).abs() < 1e-6,
+                "{} increased by {}% is {}, but the change was {}%", value, percent, increased, change);
+        }
+    }
+
+    // Feature: gui-calculator, Property 42: Comparing the other way round flips the difference
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_difference_is_antisymmetric(a in -1e12..1e12f64, b in -1e12..1e12f64) {
+            let (forward, _) = difference(a, b);
+            let (backward, _) = difference(b, a);
+            prop_assert_eq!(forward, -backward);
+        }
+    }
+}
@@ -0,0 +1,243 @@
+// Polynomial Roots
+use std::fmt;
+use std::ops::{Add, Div, Mul, Sub};
+
+use crate::error::CalculatorError;
+use crate::ieee754;
+
+const MAX_ITERATIONS: usize = 500;
+// Imaginary parts this small relative to the root are rounding noise
+const REAL_TOLERANCE: f64 = 1e-7;
+const SIGNIFICANT_DIGITS: usize = 12;
+
+/// Just enough complex arithmetic for root finding.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub fn norm(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    pub fn is_real(self) -> bool {
+        self.im == 0.0
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+    fn div(self, other: Complex) -> Complex {
+        let scale = other.re * other.re + other.im * other.im;
+        Complex::new(
+            (self.re * other.re + self.im * other.im) / scale,
+            (self.im * other.re - self.re * other.im) / scale,
+        )
+    }
+}
+
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_real() {
+            write!(f, "{}", self.re)
+        } else if self.im < 0.0 {
+            write!(f, "{} − {}i", self.re, -self.im)
+        } else {
+            write!(f, "{} + {}i", self.re, self.im)
+        }
+    }
+}
+
+/// The value at `x` of the polynomial with `coefficients`, highest power first.
+pub fn eval(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().fold(0.0, |sum, &coefficient| sum * x + coefficient)
+}
+
+// The value and the derivative at `z`, by Horner's scheme
+fn eval_complex(coefficients: &[f64], z: Complex) -> (Complex, Complex) {
+    let zero = Complex::new(0.0, 0.0);
+    coefficients.iter().fold((zero, zero), |(value, derivative), &coefficient| {
+        (value * z + Complex::new(coefficient, 0.0), derivative * z + value)
+    })
+}
+
+/// All roots of the polynomial with `coefficients`, highest power first, so
+/// `[1, -3, 2]` is x² − 3x + 2. Repeated roots appear once per multiplicity;
+/// real roots come first in increasing order.
+///
+/// Uses the Aberth–Ehrlich method, refining every root at once. Roots whose
+/// imaginary part is noise are made exactly real.
+pub fn roots(coefficients: &[f64]) -> Result<Vec<Complex>, CalculatorError> {
+    let domain = || CalculatorError::Domain(String::from("polynomial"));
+    if coefficients.iter().any(|coefficient| !coefficient.is_finite()) {
+        return Err(domain());
+    }
+    // Leading zeros don't change the polynomial
+    let start = coefficients.iter().position(|&coefficient| coefficient != 0.0).ok_or_else(domain)?;
+    let coefficients = &coefficients[start..];
+    if coefficients.len() < 2 {
+        return Err(domain());
+    }
+
+    // Trailing zeros are exact roots at 0; dividing them out keeps the rest well-conditioned
+    let end = coefficients.iter().rposition(|&coefficient| coefficient != 0.0).unwrap_or(0) + 1;
+    let mut found = vec![Complex::new(0.0, 0.0); coefficients.len() - end];
+    let monic: Vec<f64> = coefficients[..end].iter().map(|coefficient| coefficient / coefficients[0]).collect();
+    found.extend(aberth(&monic));
+
+    for root in &mut found {
+        if root.im.abs() <= REAL_TOLERANCE * root.norm().max(1.0) {
+            root.im = 0.0;
+            // 1.0000000000000002 becomes 1 when 1 is at least as good a root
+            let rounded = ieee754::round_to_significant(root.re, SIGNIFICANT_DIGITS);
+            if eval(coefficients, rounded).abs() <= eval(coefficients, root.re).abs() {
+                root.re = rounded;
+            }
+        }
+    }
+    found.sort_by(|a, b| {
+        (!a.is_real(), a.re, a.im)
+            .partial_cmp(&(!b.is_real(), b.re, b.im))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(found)
+}
+
+fn aberth(monic: &[f64]) -> Vec<Complex> {
+    let degree = monic.len() - 1;
+    if degree == 0 {
+        return Vec::new();
+    }
+    // Every root lies within the Cauchy bound; start spread on a circle inside
+    // it, off the real axis so conjugate pairs can separate
+    let bound = 1.0 + monic[1..].iter().fold(0.0f64, |max, coefficient| max.max(coefficient.abs()));
+    let mut roots: Vec<Complex> = (0..degree)
+        .map(|k| {
+            let angle = std::f64::consts::TAU * k as f64 / degree as f64 + 0.4;
+            Complex::new(bound * 0.5 * angle.cos(), bound * 0.5 * angle.sin())
+        })
+        .collect();
+
+    for _ in 0..MAX_ITERATIONS {
+        let mut largest_step = 0.0f64;
+        for k in 0..degree {
+            let (value, derivative) = eval_complex(monic, roots[k]);
+            if value.norm() == 0.0 {
+                continue;
+            }
+            let ratio = value / derivative;
+            let repulsion = (0..degree)
+                .filter(|&j| j != k)
+                .fold(Complex::new(0.0, 0.0), |sum, j| sum + Complex::new(1.0, 0.0) / (roots[k] - roots[j]));
+            let step = ratio / (Complex::new(1.0, 0.0) - ratio * repulsion);
+            if step.re.is_finite() && step.im.is_finite() {
+                roots[k] = roots[k] - step;
+                largest_step = largest_step.max(step.norm());
+            }
+        }
+        if largest_step <= f64::EPSILON * bound {
+            break;
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn close(a: f64, b: f64, tolerance: f64) -> bool {
+        (a - b).abs() <= tolerance
+    }
+
+    #[test]
+    fn test_quadratic_and_cubic() {
+        assert_eq!(roots(&[1.0, -3.0, 2.0]).unwrap(), vec![Complex::new(1.0, 0.0), Complex::new(2.0, 0.0)]);
+        assert_eq!(roots(&[0.0, 2.0, -6.0]).unwrap(), vec![Complex::new(3.0, 0.0)]);
+
+        // x³ − 1: one real root and a conjugate pair
+        let found = roots(&[1.0, 0.0, 0.0, -1.0]).unwrap();
+        assert_eq!(found.len(), 3);
+        assert!(found[0].is_real() && close(found[0].re, 1.0, 1e-12));
+        let half_root_three = 3f64.sqrt() / 2.0;
+        assert!(close(found[1].re, -0.5, 1e-12) && close(found[1].im, -half_root_three, 1e-12));
+        assert!(close(found[2].re, -0.5, 1e-12) && close(found[2].im, half_root_three, 1e-12));
+
+        // x² − 4x: the root at zero is exact
+        assert_eq!(roots(&[1.0, -4.0, 0.0]).unwrap(), vec![Complex::new(0.0, 0.0), Complex::new(4.0, 0.0)]);
+        // (x − 1)², a double root
+        let found = roots(&[1.0, -2.0, 1.0]).unwrap();
+        assert!(found.iter().all(|root| root.is_real() && close(root.re, 1.0, 1e-7)));
+
+        assert_eq!(Complex::new(1.5, -2.0).to_string(), "1.5 − 2i");
+        assert_eq!(eval(&[1.0, -3.0, 2.0], 5.0), 12.0);
+    }
+
+    #[test]
+    fn test_not_a_polynomial() {
+        let domain = Err(CalculatorError::Domain(String::from("polynomial")));
+        assert_eq!(roots(&[]), domain);
+        assert_eq!(roots(&[0.0, 0.0]), domain);
+        assert_eq!(roots(&[5.0]), domain);
+        assert_eq!(roots(&[1.0, f64::NAN]), domain);
+    }
+
+    // Feature: gui-calculator, Property 34: Roots of a product of factors are its factors
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_roots_of_factors(mut expected in prop::collection::btree_set(-20i32..=20, 1..=5)
+            .prop_map(|set| set.into_iter().map(f64::from).collect::<Vec<f64>>())) {
+            // Multiply out (x − r₁)(x − r₂)..., highest power first
+            let mut coefficients = vec![1.0];
+            for &root in &expected {
+                let mut next = coefficients.clone();
+                next.push(0.0);
+                for (index, &coefficient) in coefficients.iter().enumerate() {
+                    next[index + 1] -= root * coefficient;
+                }
+                coefficients = next;
+            }
+
+            let found = roots(&coefficients).unwrap();
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            prop_assert_eq!(found.len(), expected.len());
+            for (root, expected) in found.iter().zip(&expected) {
+                prop_assert!(root.is_real() && close(root.re, *expected, 1e-6),
+                    "{:?} gave {:?}", coefficients, found);
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+// Precision Comparison
+//
+// The same expression worked out in binary floating point, in decimal and as
+// an exact fraction, so it's visible where rounding creeps in: 0.1 + 0.2 is
+// 0.30000000000000004 as a float but exactly 0.3 in the other two.
+use crate::expression::{Environment, Expr};
+use crate::{decimal, rational};
+
+// Digits after the point written out for the exact fraction
+const RATIONAL_DIGITS: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Float,
+    Decimal,
+    Rational,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 3] = [Backend::Float, Backend::Decimal, Backend::Rational];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Backend::Float => "f64",
+            Backend::Decimal => "Decimal",
+            Backend::Rational => "Rational",
+        }
+    }
+}
+
+/// One backend's result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reading {
+    pub backend: Backend,
+    /// The digits, or why there are none
+    pub text: Result<String, String>,
+    /// The exact fraction, when it isn't a whole number
+    pub fraction: Option<String>,
+}
+
+/// `expr` evaluated by every backend, in `Backend::ALL` order.
+pub fn compare(expr: &Expr, env: &Environment) -> Vec<Reading> {
+    let float = match expr.eval(env) {
+        Ok(value) => Ok(value.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+    let decimal = decimal::eval(expr, env)
+        .map(|value| value.normalize().to_string())
+        .ok_or_else(|| String::from("Not available for powers, functions or very large values"));
+    let exact = rational::eval(expr, env);
+    let fraction = exact.map(|value| value.to_string()).filter(|text| text.contains('/'));
+    let exact = exact
+        .map(|value| value.to_decimal_string(RATIONAL_DIGITS))
+        .ok_or_else(|| String::from("Not exact for functions, fractional powers or very large values"));
+    vec![
+        Reading {
+            backend: Backend::Float,
+            text: float,
+            fraction: None,
+        },
+        Reading {
+            backend: Backend::Decimal,
+            text: decimal,
+            fraction: None,
+        },
+        Reading {
+            backend: Backend::Rational,
+            text: exact,
+            fraction,
+        },
+    ]
+}
+
+/// The reading the others are held against: the most exact one available.
+pub fn reference(readings: &[Reading]) -> Option<&str> {
+    [Backend::Rational, Backend::Decimal, Backend::Float]
+        .iter()
+        .find_map(|&backend| readings.iter().find(|reading| reading.backend == backend)?.text.as_deref().ok())
+}
+
+/// How many bytes `text` shares with `reference` before they part. Stopping
+/// short isn't parting: "0.333" agrees with "0.33333…" throughout, while
+/// "0.30000000000000004" parts from "0.3" after three.
+pub fn divergence(text: &str, reference: &str) -> usize {
+    let mut theirs = reference.chars();
+    text.char_indices()
+        .find(|&(_, ours)| theirs.next() != Some(ours))
+        .map_or(text.len(), |(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::parse;
+    use proptest::prelude::*;
+
+    fn readings(source: &str) -> Vec<Reading> {
+        compare(&parse(source).unwrap(), &Environment::new())
+    }
+
+    fn texts(readings: &[Reading]) -> Vec<Result<&str, ()>> {
+        readings.iter().map(|reading| reading.text.as_deref().map_err(|_| ())).collect()
+    }
+
+    #[test]
+    fn test_backends() {
+        let sum = readings("0.1 + 0.2");
+        assert_eq!(texts(&sum), [Ok("0.30000000000000004"), Ok("0.3"), Ok("0.3")]);
+        assert_eq!(sum[2].fraction.as_deref(), Some("3/10"));
+        assert_eq!(reference(&sum), Some("0.3"));
+        assert_eq!(divergence(sum[0].text.as_ref().unwrap(), "0.3"), 3);
+
+        let third = readings("1 / 3");
+        assert_eq!(third[2].text.as_ref().unwrap(), &format!("0.{}…", "3".repeat(RATIONAL_DIGITS)));
+        assert_eq!(divergence("0.3333333333333333", reference(&third).unwrap()), 18);
+
+        let root = readings("sqrt(2)");
+        assert_eq!(texts(&root), [Ok("1.4142135623730951"), Err(()), Err(())]);
+        assert_eq!(reference(&root), Some("1.4142135623730951"));
+
+        assert_eq!(texts(&readings("2 ^ 10")), [Ok("1024"), Err(()), Ok("1024")]);
+        assert!(readings("1 / 0").iter().all(|reading| reading.text.is_err()));
+    }
+
+    // Feature: gui-calculator, Property 55: Readings agree up to the divergence and differ right after it
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_divergence(text in "[0-9.…-]{0,12}", reference in "[0-9.…-]{0,12}") {
+            let at = divergence(&text, &reference);
+            prop_assert!(reference.starts_with(&text[..at]));
+            if let (Some(ours), Some(theirs)) = (text[at..].chars().next(), reference[at..].chars().next()) {
+                prop_assert_ne!(ours, theirs);
+            }
+        }
+    }
+}
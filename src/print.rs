@@ -0,0 +1,191 @@
+// Printing the Tape
+//
+// The history laid out like an adding machine's paper tape, each calculation
+// with its result flush right, split into A4 pages and written as a PDF that
+// any viewer can print. Text is set in Courier, one of the fonts every PDF
+// reader has, so nothing needs embedding and the columns line up.
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+
+use crate::history::History;
+
+// A4 in points, and what fits on it in 10pt Courier with 50pt margins
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const FONT_SIZE: f32 = 10.0;
+const LINE_HEIGHT: f32 = 14.0;
+/// Characters per line; every Courier glyph is 0.6em wide
+pub const COLUMNS: usize = ((PAGE_WIDTH - 2.0 * MARGIN) / (FONT_SIZE * 0.6)) as usize;
+/// Lines per page below the two header lines
+pub const ROWS: usize = ((PAGE_HEIGHT - 2.0 * MARGIN) / LINE_HEIGHT) as usize - 2;
+
+/// The lines each history entry takes, oldest first: the expression, wrapped
+/// to `columns`, and `= result` at the right edge of its last line or of a
+/// line of its own when there's no room.
+pub fn tape_lines(history: &History, columns: usize) -> Vec<Vec<String>> {
+    history
+        .entries()
+        .iter()
+        .map(|entry| {
+            let result = format!("= {}", entry.result);
+            let chars: Vec<char> = entry.source.chars().collect();
+            let mut lines: Vec<String> = chars.chunks(columns.max(1)).map(|chunk| chunk.iter().collect()).collect();
+            let last = lines.last().map_or(0, |line| line.chars().count());
+            if !lines.is_empty() && last + 1 + result.chars().count() <= columns {
+                let line = lines.last_mut().unwrap();
+                let padding = columns - last - result.chars().count();
+                line.push_str(&" ".repeat(padding));
+                line.push_str(&result);
+            } else {
+                lines.push(format!("{:>width$}", result, width = columns));
+            }
+            lines
+        })
+        .collect()
+}
+
+/// Splits the entries into pages of at most `rows` lines, starting a new
+/// page rather than splitting an entry unless it's longer than a page.
+pub fn paginate(entries: Vec<Vec<String>>, rows: usize) -> Vec<Vec<String>> {
+    let mut pages: Vec<Vec<String>> = Vec::new();
+    let mut page: Vec<String> = Vec::new();
+    for entry in entries {
+        if !page.is_empty() && page.len() + entry.len() > rows {
+            pages.push(std::mem::take(&mut page));
+        }
+        for line in entry {
+            if page.len() == rows {
+                pages.push(std::mem::take(&mut page));
+            }
+            page.push(line);
+        }
+    }
+    if !page.is_empty() || pages.is_empty() {
+        pages.push(page);
+    }
+    pages
+}
+
+/// The tape as a PDF with `title` and page numbers at the top of each page.
+pub fn tape_pdf(history: &History, title: &str) -> Vec<u8> {
+    let pages = paginate(tape_lines(history, COLUMNS), ROWS);
+    let catalog_id = Ref::new(1);
+    let tree_id = Ref::new(2);
+    let font_id = Ref::new(3);
+    let info_id = Ref::new(4);
+    // Then a page and its content stream for each page
+    let page_ids: Vec<Ref> = (0..pages.len()).map(|index| Ref::new(5 + 2 * index as i32)).collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(tree_id);
+    pdf.pages(tree_id).kids(page_ids.iter().copied()).count(pages.len() as i32);
+    pdf.type1_font(font_id)
+        .base_font(Name(b"Courier"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.document_info(info_id).title(TextStr(title));
+
+    for (index, lines) in pages.iter().enumerate() {
+        let page_id = page_ids[index];
+        let content_id = Ref::new(page_id.get() + 1);
+        let mut page = pdf.page(page_id);
+        page.parent(tree_id)
+            .media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+            .contents(content_id);
+        page.resources().fonts().pair(Name(b"F1"), font_id);
+        page.finish();
+
+        let number = format!("Page {} of {}", index + 1, pages.len());
+        let header = format!("{}{:>width$}", title, number, width = COLUMNS.saturating_sub(title.chars().count()));
+        let mut content = Content::new();
+        content.begin_text();
+        content.set_font(Name(b"F1"), FONT_SIZE);
+        content.set_leading(LINE_HEIGHT);
+        content.next_line(MARGIN, PAGE_HEIGHT - MARGIN);
+        content.show(Str(&win_ansi(&header)));
+        content.next_line_using_leading();
+        for line in lines {
+            content.next_line_using_leading();
+            content.show(Str(&win_ansi(line)));
+        }
+        content.end_text();
+        pdf.stream(content_id, &content.finish());
+    }
+    pdf.finish()
+}
+
+// Latin-1 bytes, which WinAnsi agrees with for letters; anything else is
+// shown as ?
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars().map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?')).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::Expr;
+    use crate::history::HistoryEntry;
+    use proptest::prelude::*;
+
+    fn history(entries: &[(&str, f64)]) -> History {
+        let mut history = History::new();
+        for &(source, result) in entries {
+            history.push(HistoryEntry::new(source, Expr::Number(result), result));
+        }
+        history
+    }
+
+    #[test]
+    fn test_tape_lines() {
+        let tape = tape_lines(&history(&[("12 + 3", 15.0), ("123456789 * 2", 246913578.0)]), 20);
+        assert_eq!(tape[0], ["12 + 3          = 15"]);
+        assert_eq!(tape[1], ["123456789 * 2", "         = 246913578"]);
+        let wrapped = tape_lines(&history(&[("1 + 2 + 3 + 4 + 5 + 6 + 7", 28.0)]), 10);
+        assert_eq!(wrapped[0], ["1 + 2 + 3 ", "+ 4 + 5 + ", "6 + 7 = 28"]);
+    }
+
+    #[test]
+    fn test_pdf_has_a_page_per_page() {
+        let entries: Vec<(String, f64)> = (0..120).map(|n| (format!("{} + 1", n), f64::from(n + 1))).collect();
+        let entries: Vec<(&str, f64)> = entries.iter().map(|(source, result)| (source.as_str(), *result)).collect();
+        let pdf = tape_pdf(&history(&entries), "Tape");
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-"));
+        let pages = 120usize.div_ceil(ROWS);
+        assert!(text.contains(&format!("/Count {}", pages)));
+        assert!(text.contains(&format!("Page {} of {}", pages, pages)));
+        assert!(text.contains("(119 + 1"));
+
+        // An empty history still prints a page with the header
+        assert!(String::from_utf8_lossy(&tape_pdf(&History::new(), "Tape")).contains("/Count 1"));
+    }
+
+    // Feature: gui-calculator, Property 48: Pagination keeps every line, in order, within the page size
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_pagination_keeps_lines(
+            sizes in prop::collection::vec(1usize..8, 0..40),
+            rows in 1usize..12
+        ) {
+            let entries: Vec<Vec<String>> = sizes
+                .iter()
+                .enumerate()
+                .map(|(entry, &size)| (0..size).map(|line| format!("{}.{}", entry, line)).collect())
+                .collect();
+            let all: Vec<String> = entries.concat();
+            let pages = paginate(entries, rows);
+            prop_assert!(pages.iter().all(|page| page.len() <= rows));
+            prop_assert_eq!(pages.concat(), all);
+            // An entry that fits on a page is never split
+            for (entry, &size) in sizes.iter().enumerate() {
+                if size <= rows {
+                    let first = format!("{}.0", entry);
+                    let page = pages.iter().find(|page| page.contains(&first)).unwrap();
+                    let last = format!("{}.{}", entry, size - 1);
+                    prop_assert!(page.contains(&last), "entry {} split across pages", entry);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,479 @@
+// Programmer Mode: Fixed-Width Integer Arithmetic
+use serde::{Deserialize, Serialize};
+
+use crate::error::CalculatorError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WordSize {
+    Bits8,
+    Bits16,
+    Bits32,
+    #[default]
+    Bits64,
+}
+
+impl WordSize {
+    pub const ALL: [WordSize; 4] = [WordSize::Bits8, WordSize::Bits16, WordSize::Bits32, WordSize::Bits64];
+
+    pub fn bits(self) -> u32 {
+        match self {
+            WordSize::Bits8 => 8,
+            WordSize::Bits16 => 16,
+            WordSize::Bits32 => 32,
+            WordSize::Bits64 => 64,
+        }
+    }
+
+    pub fn mask(self) -> u64 {
+        u64::MAX >> (64 - self.bits())
+    }
+
+    /// `value` with bit `bit` (0 is the lowest) flipped, kept to the word.
+    pub fn toggle_bit(self, value: u64, bit: u32) -> u64 {
+        debug_assert!(bit < self.bits(), "bit {} is outside a {}-bit word", bit, self.bits());
+        (value ^ (1 << bit)) & self.mask()
+    }
+
+    /// Reads the low bits of `value` as a two's complement number.
+    pub fn to_signed(self, value: u64) -> i64 {
+        let shift = 64 - self.bits();
+        ((value << shift) as i64) >> shift
+    }
+
+    fn fits_signed(self, value: i128) -> bool {
+        let half = 1i128 << (self.bits() - 1);
+        -half <= value && value < half
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Base {
+    Bin,
+    Oct,
+    #[default]
+    Dec,
+    Hex,
+}
+
+impl Base {
+    pub const ALL: [Base; 4] = [Base::Hex, Base::Dec, Base::Oct, Base::Bin];
+
+    pub fn radix(self) -> u32 {
+        match self {
+            Base::Bin => 2,
+            Base::Oct => 8,
+            Base::Dec => 10,
+            Base::Hex => 16,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Base::Bin => "BIN",
+            Base::Oct => "OCT",
+            Base::Dec => "DEC",
+            Base::Hex => "HEX",
+        }
+    }
+
+    /// Formats a word; only decimal shows a sign, the others show the bits.
+    pub fn format(self, value: u64, size: WordSize, signed: bool) -> String {
+        let value = value & size.mask();
+        match self {
+            Base::Bin => format!("{:b}", value),
+            Base::Oct => format!("{:o}", value),
+            Base::Dec if signed => size.to_signed(value).to_string(),
+            Base::Dec => value.to_string(),
+            Base::Hex => format!("{:X}", value),
+        }
+    }
+
+    /// Parses a word, rejecting values that don't fit in `size`.
+    pub fn parse(self, text: &str, size: WordSize) -> Result<u64, CalculatorError> {
+        let text = text.trim().replace('_', "");
+        let invalid = || CalculatorError::Syntax {
+            message: format!("Invalid {} number", self.name()),
+            column: 1,
+        };
+
+        if let Some(digits) = text.strip_prefix('-') {
+            if self != Base::Dec {
+                return Err(invalid());
+            }
+            let magnitude = digits.parse::<u64>().map_err(|_| invalid())?;
+            let value = -(magnitude as i128);
+            if !size.fits_signed(value) {
+                return Err(CalculatorError::Overflow);
+            }
+            return Ok(value as u64 & size.mask());
+        }
+
+        let value = u64::from_str_radix(&text, self.radix()).map_err(|err| match err.kind() {
+            std::num::IntErrorKind::PosOverflow => CalculatorError::Overflow,
+            _ => invalid(),
+        })?;
+        if value > size.mask() {
+            return Err(CalculatorError::Overflow);
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    And,
+    Or,
+    Xor,
+    ShiftLeft,
+    ShiftRight,
+}
+
+impl IntOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            IntOp::Add => "+",
+            IntOp::Subtract => "−",
+            IntOp::Multiply => "×",
+            IntOp::Divide => "÷",
+            IntOp::And => "AND",
+            IntOp::Or => "OR",
+            IntOp::Xor => "XOR",
+            IntOp::ShiftLeft => "<<",
+            IntOp::ShiftRight => ">>",
+        }
+    }
+}
+
+/// What to do when a result doesn't fit the word size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OverflowBehavior {
+    /// Keep the low bits, like the hardware does
+    #[default]
+    Wrap,
+    /// Report an overflow error instead of a result
+    Error,
+}
+
+/// A result truncated to the word size, with the flags a CPU would set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub value: u64,
+    /// Unsigned carry out of the top bit, or a borrow for subtraction
+    pub carry: bool,
+    /// Signed (two's complement) overflow
+    pub overflow: bool,
+}
+
+impl Outcome {
+    /// Whether the true result was lost, for the chosen interpretation.
+    pub fn wrapped(&self, signed: bool) -> bool {
+        if signed {
+            self.overflow
+        } else {
+            self.carry
+        }
+    }
+}
+
+/// Applies `op` to two words. `signed` selects signed division and
+/// arithmetic right shifts; the flags are reported either way.
+pub fn apply(op: IntOp, a: u64, b: u64, size: WordSize, signed: bool) -> Result<Outcome, CalculatorError> {
+    let mask = size.mask();
+    let (a, b) = (a & mask, b & mask);
+    let (signed_a, signed_b) = (size.to_signed(a) as i128, size.to_signed(b) as i128);
+    let (unsigned_a, unsigned_b) = (a as u128, b as u128);
+
+    // The exact result both ways; anything outside the word sets a flag
+    let (unsigned, signed_value) = match op {
+        IntOp::Add => (Some(unsigned_a + unsigned_b), signed_a + signed_b),
+        IntOp::Subtract => (unsigned_a.checked_sub(unsigned_b), signed_a - signed_b),
+        IntOp::Multiply => (Some(unsigned_a * unsigned_b), signed_a * signed_b),
+        IntOp::Divide => {
+            if b == 0 {
+                return Err(CalculatorError::DivisionByZero);
+            }
+            (Some(unsigned_a / unsigned_b), signed_a / signed_b)
+        }
+        IntOp::And | IntOp::Or | IntOp::Xor => {
+            let value = match op {
+                IntOp::And => a & b,
+                IntOp::Or => a | b,
+                _ => a ^ b,
+            };
+            return Ok(Outcome { value, carry: false, overflow: false });
+        }
+        IntOp::ShiftLeft | IntOp::ShiftRight => {
+            if b >= size.bits() as u64 {
+                return Err(CalculatorError::Domain(String::from("shift")));
+            }
+            if op == IntOp::ShiftLeft {
+                (Some(unsigned_a << b), signed_a << b)
+            } else {
+                (Some(unsigned_a >> b), signed_a >> b)
+            }
+        }
+    };
+
+    let carry = !matches!(unsigned, Some(value) if value <= mask as u128);
+    let overflow = !size.fits_signed(signed_value);
+    let value = match op {
+        IntOp::Divide | IntOp::ShiftRight if signed => signed_value as u64,
+        _ => unsigned.unwrap_or(unsigned_a.wrapping_sub(unsigned_b)) as u64,
+    } & mask;
+    Ok(Outcome { value, carry, overflow })
+}
+
+/// The word's bytes in memory order: least significant first when
+/// `little_endian`, most significant first otherwise.
+pub fn bytes(value: u64, size: WordSize, little_endian: bool) -> Vec<u8> {
+    let count = (size.bits() / 8) as usize;
+    let little = &value.to_le_bytes()[..count];
+    if little_endian {
+        little.to_vec()
+    } else {
+        little.iter().rev().copied().collect()
+    }
+}
+
+/// The word with its byte order reversed, as read by a machine of the other
+/// endianness.
+pub fn swap_bytes(value: u64, size: WordSize) -> u64 {
+    (value & size.mask()).swap_bytes() >> (64 - size.bits())
+}
+
+/// Bytes as text, with `.` for anything that isn't printable ASCII.
+pub fn ascii(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| if (0x20..=0x7E).contains(&byte) { byte as char } else { '.' })
+        .collect()
+}
+
+/// Writes `value` in any base from 2 to 36, using letters for digits above 9.
+pub fn to_radix(value: i128, radix: u32, uppercase: bool) -> String {
+    assert!((2..=36).contains(&radix), "radix must be between 2 and 36");
+    let mut magnitude = value.unsigned_abs();
+    let mut digits = Vec::new();
+    loop {
+        let digit = std::char::from_digit((magnitude % radix as u128) as u32, radix).unwrap_or('?');
+        digits.push(if uppercase { digit.to_ascii_uppercase() } else { digit });
+        magnitude /= radix as u128;
+        if magnitude == 0 {
+            break;
+        }
+    }
+    if value < 0 {
+        digits.push('-');
+    }
+    digits.iter().rev().collect()
+}
+
+/// `value` as a literal the expression parser reads back, e.g. `0xFF` or
+/// `-0b101`, or `None` if it isn't a whole number that fits in 64 bits.
+pub fn format_literal(value: f64, base: Base) -> Option<String> {
+    if value.fract() != 0.0 || value.abs() >= 2f64.powi(64) {
+        return None;
+    }
+    let prefix = match base {
+        Base::Hex => "0x",
+        Base::Oct => "0o",
+        Base::Bin => "0b",
+        Base::Dec => "",
+    };
+    let sign = if value < 0.0 { "-" } else { "" };
+    Some(format!("{}{}{}", sign, prefix, to_radix(value.abs() as i128, base.radix(), true)))
+}
+
+/// Reads an integer written in base `radix` (2 to 36), pointing at the first
+/// digit that isn't valid in that base. Magnitudes are limited to 64 bits.
+pub fn from_radix(text: &str, radix: u32) -> Result<i128, CalculatorError> {
+    let trimmed = text.trim_start();
+    let offset = text.len() - trimmed.len();
+    let (negative, digits) = match trimmed.trim_end().strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed.trim_end()),
+    };
+    if digits.is_empty() {
+        return Err(CalculatorError::Syntax {
+            message: String::from("Expected a number"),
+            column: offset + negative as usize + 1,
+        });
+    }
+
+    let mut magnitude: u128 = 0;
+    for (index, ch) in digits.char_indices() {
+        if ch == '_' {
+            continue;
+        }
+        let Some(digit) = ch.to_digit(radix) else {
+            return Err(CalculatorError::Syntax {
+                message: format!("'{}' is not a base {} digit", ch, radix),
+                column: offset + negative as usize + digits[..index].chars().count() + 1,
+            });
+        };
+        magnitude = magnitude * radix as u128 + digit as u128;
+        if magnitude > u64::MAX as u128 {
+            return Err(CalculatorError::Overflow);
+        }
+    }
+    let value = magnitude as i128;
+    Ok(if negative { -value } else { value })
+}
+
+/// Applies the overflow behavior to an outcome.
+pub fn check(outcome: Outcome, signed: bool, behavior: OverflowBehavior) -> Result<Outcome, CalculatorError> {
+    if behavior == OverflowBehavior::Error && outcome.wrapped(signed) {
+        return Err(CalculatorError::Overflow);
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_flags() {
+        let add = apply(IntOp::Add, 200, 100, WordSize::Bits8, false).unwrap();
+        assert_eq!(add, Outcome { value: 44, carry: true, overflow: false });
+        assert!(add.wrapped(false));
+        assert!(!add.wrapped(true));
+
+        // 127 + 1 fits unsigned but overflows signed
+        let add = apply(IntOp::Add, 127, 1, WordSize::Bits8, true).unwrap();
+        assert_eq!(add, Outcome { value: 0x80, carry: false, overflow: true });
+
+        let borrow = apply(IntOp::Subtract, 1, 2, WordSize::Bits16, false).unwrap();
+        assert_eq!(borrow, Outcome { value: 0xFFFF, carry: true, overflow: false });
+
+        let divide = apply(IntOp::Divide, 0xF0, 2, WordSize::Bits8, true).unwrap();
+        assert_eq!(WordSize::Bits8.to_signed(divide.value), -8);
+        assert_eq!(apply(IntOp::Divide, 0xF0, 2, WordSize::Bits8, false).unwrap().value, 0x78);
+
+        assert_eq!(apply(IntOp::Divide, 1, 0, WordSize::Bits8, false), Err(CalculatorError::DivisionByZero));
+        assert!(apply(IntOp::ShiftLeft, 1, 8, WordSize::Bits8, false).is_err());
+        assert!(apply(IntOp::ShiftLeft, 0x81, 1, WordSize::Bits8, false).unwrap().carry);
+    }
+
+    #[test]
+    fn test_overflow_behavior() {
+        let outcome = apply(IntOp::Multiply, u64::MAX, 2, WordSize::Bits64, false).unwrap();
+        assert_eq!(outcome.value, u64::MAX - 1);
+        assert_eq!(check(outcome, false, OverflowBehavior::Wrap), Ok(outcome));
+        assert_eq!(check(outcome, false, OverflowBehavior::Error), Err(CalculatorError::Overflow));
+        // -1 * 2 is fine when the words are signed
+        assert_eq!(check(outcome, true, OverflowBehavior::Error), Ok(outcome));
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        assert_eq!(Base::Hex.parse("ff", WordSize::Bits8), Ok(255));
+        assert_eq!(Base::Hex.parse("100", WordSize::Bits8), Err(CalculatorError::Overflow));
+        assert_eq!(Base::Dec.parse("-128", WordSize::Bits8), Ok(0x80));
+        assert_eq!(Base::Dec.parse("-129", WordSize::Bits8), Err(CalculatorError::Overflow));
+        assert!(Base::Bin.parse("102", WordSize::Bits8).is_err());
+        assert!(Base::Hex.parse("-1", WordSize::Bits8).is_err());
+        assert_eq!(Base::Bin.parse("1010_1010", WordSize::Bits8), Ok(0xAA));
+
+        assert_eq!(Base::Dec.format(0x80, WordSize::Bits8, true), "-128");
+        assert_eq!(Base::Dec.format(0x80, WordSize::Bits8, false), "128");
+        assert_eq!(Base::Hex.format(0xBEEF, WordSize::Bits16, true), "BEEF");
+        assert_eq!(Base::Oct.format(8, WordSize::Bits32, false), "10");
+    }
+
+    #[test]
+    fn test_toggle_bit() {
+        assert_eq!(WordSize::Bits8.toggle_bit(0, 7), 0x80);
+        assert_eq!(WordSize::Bits8.toggle_bit(0xFF, 0), 0xFE);
+        // Bits above the word are dropped, not carried along
+        assert_eq!(WordSize::Bits8.toggle_bit(0x1FF, 1), 0xFD);
+        assert_eq!(WordSize::Bits64.toggle_bit(0, 63), 1 << 63);
+    }
+
+    #[test]
+    fn test_byte_views() {
+        assert_eq!(bytes(0xDEADBEEF, WordSize::Bits32, true), [0xEF, 0xBE, 0xAD, 0xDE]);
+        assert_eq!(bytes(0xDEADBEEF, WordSize::Bits32, false), [0xDE, 0xAD, 0xBE, 0xEF]);
+        assert_eq!(bytes(0x1234, WordSize::Bits8, true), [0x34]);
+        assert_eq!(swap_bytes(0x1234, WordSize::Bits16), 0x3412);
+        assert_eq!(swap_bytes(0x0102_0304, WordSize::Bits64), 0x0403_0201_0000_0000);
+        assert_eq!(ascii(&bytes(0x0048_690A, WordSize::Bits32, false)), ".Hi.");
+    }
+
+    #[test]
+    fn test_radix_conversion() {
+        assert_eq!(to_radix(255, 16, true), "FF");
+        assert_eq!(to_radix(255, 16, false), "ff");
+        assert_eq!(to_radix(35, 36, false), "z");
+        assert_eq!(to_radix(-10, 2, false), "-1010");
+        assert_eq!(to_radix(0, 7, false), "0");
+        assert_eq!(format_literal(255.0, Base::Hex).as_deref(), Some("0xFF"));
+        assert_eq!(format_literal(-5.0, Base::Bin).as_deref(), Some("-0b101"));
+        assert_eq!(format_literal(8.0, Base::Oct).as_deref(), Some("0o10"));
+        assert_eq!(format_literal(2.5, Base::Hex), None);
+
+        assert_eq!(from_radix("zz", 36), Ok(36 * 36 - 1));
+        assert_eq!(from_radix(" -1_000 ", 3), Ok(-27));
+        assert_eq!(
+            from_radix("12a4", 10),
+            Err(CalculatorError::Syntax { message: String::from("'a' is not a base 10 digit"), column: 3 })
+        );
+        assert_eq!(
+            from_radix("-", 8),
+            Err(CalculatorError::Syntax { message: String::from("Expected a number"), column: 2 })
+        );
+        assert_eq!(from_radix("1".repeat(65).as_str(), 2), Err(CalculatorError::Overflow));
+    }
+
+    // Feature: gui-calculator, Property 27: Base conversions round-trip for every base
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_radix_round_trip(
+            value in -(u64::MAX as i128)..=(u64::MAX as i128),
+            radix in 2u32..=36,
+            uppercase in prop::bool::ANY
+        ) {
+            let text = to_radix(value, radix, uppercase);
+            prop_assert_eq!(from_radix(&text, radix), Ok(value));
+            prop_assert_eq!(from_radix(&text.to_lowercase(), radix), Ok(value));
+        }
+    }
+
+    // Feature: gui-calculator, Property 26: Wrapped results agree with wrapping arithmetic and flags with checked arithmetic
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_matches_native_u8_i8(a in any::<u8>(), b in any::<u8>()) {
+            let (a64, b64) = (a as u64, b as u64);
+
+            let add = apply(IntOp::Add, a64, b64, WordSize::Bits8, false).unwrap();
+            prop_assert_eq!(add.value, a.wrapping_add(b) as u64);
+            prop_assert_eq!(add.carry, a.checked_add(b).is_none());
+            prop_assert_eq!(add.overflow, (a as i8).checked_add(b as i8).is_none());
+
+            let sub = apply(IntOp::Subtract, a64, b64, WordSize::Bits8, false).unwrap();
+            prop_assert_eq!(sub.value, a.wrapping_sub(b) as u64);
+            prop_assert_eq!(sub.carry, a.checked_sub(b).is_none());
+            prop_assert_eq!(sub.overflow, (a as i8).checked_sub(b as i8).is_none());
+
+            let mul = apply(IntOp::Multiply, a64, b64, WordSize::Bits8, true).unwrap();
+            prop_assert_eq!(mul.value, a.wrapping_mul(b) as u64);
+            prop_assert_eq!(mul.carry, a.checked_mul(b).is_none());
+            prop_assert_eq!(mul.overflow, (a as i8).checked_mul(b as i8).is_none());
+
+            if b != 0 {
+                let div = apply(IntOp::Divide, a64, b64, WordSize::Bits8, true).unwrap();
+                prop_assert_eq!(div.value, (a as i8).wrapping_div(b as i8) as u8 as u64);
+                prop_assert_eq!(div.overflow, (a as i8).checked_div(b as i8).is_none());
+            }
+        }
+    }
+}
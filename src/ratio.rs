@@ -0,0 +1,68 @@
+// Ratio and Proportion
+use crate::error::CalculatorError;
+
+/// Solves the proportion `a : b = c : d` for its one unknown term.
+///
+/// `terms` are `[a, b, c, d]` with exactly one of them `None`; the result
+/// has it filled in. Uses `a * d = b * c`, so any term may be the unknown.
+pub fn solve(terms: [Option<f64>; 4]) -> Result<[f64; 4], CalculatorError> {
+    let mut unknowns = terms.iter().enumerate().filter(|(_, term)| term.is_none());
+    let (Some((missing, _)), None) = (unknowns.next(), unknowns.next()) else {
+        return Err(CalculatorError::Domain(String::from("proportion")));
+    };
+
+    let value = |index: usize| terms[index].unwrap_or_default();
+    // The unknown times its diagonal partner equals the other diagonal
+    let (partner, product) = match missing {
+        0 => (value(3), value(1) * value(2)),
+        1 => (value(2), value(0) * value(3)),
+        2 => (value(1), value(0) * value(3)),
+        _ => (value(0), value(1) * value(2)),
+    };
+    if partner == 0.0 {
+        return Err(CalculatorError::DivisionByZero);
+    }
+
+    let mut solved = terms.map(Option::unwrap_or_default);
+    solved[missing] = product / partner;
+    Ok(solved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_solve_each_position() {
+        assert_eq!(solve([Some(2.0), Some(3.0), Some(4.0), None]), Ok([2.0, 3.0, 4.0, 6.0]));
+        assert_eq!(solve([None, Some(3.0), Some(4.0), Some(6.0)]), Ok([2.0, 3.0, 4.0, 6.0]));
+        assert_eq!(solve([Some(2.0), None, Some(4.0), Some(6.0)]), Ok([2.0, 3.0, 4.0, 6.0]));
+        assert_eq!(solve([Some(2.0), Some(3.0), None, Some(6.0)]), Ok([2.0, 3.0, 4.0, 6.0]));
+    }
+
+    #[test]
+    fn test_invalid_proportions() {
+        let proportion = Err(CalculatorError::Domain(String::from("proportion")));
+        assert_eq!(solve([Some(1.0), Some(2.0), Some(3.0), Some(4.0)]), proportion);
+        assert_eq!(solve([None, None, Some(3.0), Some(4.0)]), proportion);
+        assert_eq!(solve([Some(0.0), Some(2.0), Some(3.0), None]), Err(CalculatorError::DivisionByZero));
+    }
+
+    // Feature: gui-calculator, Property 23: A solved proportion keeps its cross products equal
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_cross_products(
+            values in prop::array::uniform4(0.01..1000.0f64),
+            missing in 0usize..4
+        ) {
+            let mut terms = values.map(Some);
+            terms[missing] = None;
+            let [a, b, c, d] = solve(terms).unwrap();
+
+            prop_assert!((a * d - b * c).abs() <= 1e-9 * (a * d).abs().max((b * c).abs()));
+        }
+    }
+}
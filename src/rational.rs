@@ -0,0 +1,191 @@
+// Exact Fractions
+//
+// Arithmetic on fractions of whole numbers, so + − × ÷ and whole powers come
+// out exact with no rounding at all. The reference the other numeric
+// backends are checked against, in the precision view and in tests.
+use std::fmt;
+
+use crate::expression::{BinaryOp, Environment, Expr};
+
+/// An exact fraction in lowest terms with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rational {
+    pub fn new(numerator: i128, denominator: i128) -> Option<Self> {
+        if denominator == 0 {
+            return None;
+        }
+        let divisor = gcd(numerator, denominator);
+        let sign = denominator.signum();
+        Some(Self {
+            numerator: sign * numerator / divisor,
+            denominator: sign * denominator / divisor,
+        })
+    }
+
+    /// "12.5" as exactly 125/10. Plain decimal notation only.
+    pub fn parse_decimal(text: &str) -> Option<Self> {
+        let (negative, unsigned) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if !format!("{}{}", integer, fraction).bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        let digits: i128 = format!("{}{}", integer, fraction).parse().ok()?;
+        let numerator = if negative { -digits } else { digits };
+        Rational::new(numerator, 10i128.checked_pow(fraction.len() as u32)?)
+    }
+
+    /// The decimal `value` is printed as, e.g. 0.1 as exactly 1/10 rather
+    /// than the binary number stored.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        value.is_finite().then(|| Self::parse_decimal(&value.to_string()))?
+    }
+
+    /// The operation applied exactly; `None` for division by zero, powers
+    /// that aren't whole, or when the exact value outgrows `i128`.
+    pub fn apply(self, op: BinaryOp, other: Rational) -> Option<Rational> {
+        let (a, b, c, d) = (self.numerator, self.denominator, other.numerator, other.denominator);
+        match op.scalar() {
+            BinaryOp::Add => Rational::new(a.checked_mul(d)?.checked_add(c.checked_mul(b)?)?, b.checked_mul(d)?),
+            BinaryOp::Subtract => Rational::new(a.checked_mul(d)?.checked_sub(c.checked_mul(b)?)?, b.checked_mul(d)?),
+            BinaryOp::Multiply => Rational::new(a.checked_mul(c)?, b.checked_mul(d)?),
+            BinaryOp::Divide => Rational::new(a.checked_mul(d)?, b.checked_mul(c)?),
+            BinaryOp::Power => {
+                if d != 1 {
+                    return None;
+                }
+                let exponent = u32::try_from(c.unsigned_abs()).ok()?;
+                let (numerator, denominator) = (a.checked_pow(exponent)?, b.checked_pow(exponent)?);
+                if c < 0 {
+                    Rational::new(denominator, numerator)
+                } else {
+                    Rational::new(numerator, denominator)
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    /// Written out in decimal with at most `digits` after the point, ending
+    /// in "…" when there are more.
+    pub fn to_decimal_string(self, digits: usize) -> String {
+        let mut text = String::new();
+        if self.numerator < 0 {
+            text.push('-');
+        }
+        let numerator = self.numerator.unsigned_abs();
+        let denominator = self.denominator.unsigned_abs();
+        text.push_str(&(numerator / denominator).to_string());
+        let mut remainder = numerator % denominator;
+        if remainder != 0 {
+            text.push('.');
+        }
+        for _ in 0..digits {
+            if remainder == 0 {
+                break;
+            }
+            // Below the denominator, so ten times it fits in u128 whenever
+            // the denominator fits in i128
+            remainder *= 10;
+            text.push(char::from(b'0' + (remainder / denominator) as u8));
+            remainder %= denominator;
+        }
+        if remainder != 0 {
+            text.push('…');
+        }
+        text
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1)
+}
+
+/// Evaluates `expr` exactly. `None` for function calls, powers that aren't
+/// whole, and values too large to track.
+pub fn eval(expr: &Expr, env: &Environment) -> Option<Rational> {
+    match expr {
+        Expr::Number(value) => Rational::from_f64(*value),
+        Expr::Variable(name) => Rational::from_f64(env.lookup(name).ok()?),
+        Expr::Negate(operand) => {
+            let value = eval(operand, env)?;
+            Rational::new(value.numerator.checked_neg()?, value.denominator)
+        }
+        Expr::Binary { op, left, right } => eval(left, env)?.apply(*op, eval(right, env)?),
+        Expr::Call { .. } | Expr::Matrix(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::parse;
+    use proptest::prelude::*;
+
+    fn exact(source: &str) -> Option<Rational> {
+        eval(&parse(source).unwrap(), &Environment::new())
+    }
+
+    #[test]
+    fn test_exact_results() {
+        assert_eq!(exact("0.1 + 0.2"), Rational::new(3, 10));
+        assert_eq!(exact("1 / 3 * 3"), Rational::new(1, 1));
+        assert_eq!(exact("(2 / 3) ^ 2"), Rational::new(4, 9));
+        assert_eq!(exact("2 ^ -2"), Rational::new(1, 4));
+        assert_eq!(exact("-(1 - 1.5)"), Rational::new(1, 2));
+        assert_eq!(exact("1 / 0"), None);
+        assert_eq!(exact("2 ^ 0.5"), None);
+        assert_eq!(exact("sqrt(4)"), None);
+        assert_eq!(exact("10 ^ 40"), None);
+    }
+
+    #[test]
+    fn test_decimal_strings() {
+        let third = Rational::new(1, 3).unwrap();
+        assert_eq!(third.to_decimal_string(5), "0.33333…");
+        assert_eq!(third.to_string(), "1/3");
+        assert_eq!(Rational::new(-7, 4).unwrap().to_decimal_string(5), "-1.75");
+        assert_eq!(Rational::new(12, 1).unwrap().to_decimal_string(5), "12");
+        assert_eq!(Rational::parse_decimal("-0.05"), Rational::new(-1, 20));
+        assert_eq!(Rational::parse_decimal("1e5"), None);
+    }
+
+    // Feature: gui-calculator, Property 54: Exact fractions of typed decimals print back as typed
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_decimal_round_trip(whole in -1_000_000i64..1_000_000, fraction in 0u32..10_000) {
+            let text = format!("{}.{:04}", whole, fraction);
+            let value = Rational::parse_decimal(&text).unwrap();
+            let printed = value.to_decimal_string(10);
+            prop_assert!(!printed.contains('…'));
+            prop_assert_eq!(Rational::parse_decimal(&printed), Some(value));
+        }
+    }
+}
@@ -0,0 +1,320 @@
+// Session Reports
+//
+// Calculations and their results written out as a Markdown table or a LaTeX
+// `align*` block, ready to paste into a report or a homework write-up.
+use crate::error::CalculatorError;
+use crate::expression::{self, BinaryOp, Expr, ParseOptions};
+use crate::history::History;
+use crate::worksheet::WorksheetLine;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Latex,
+}
+
+impl ReportFormat {
+    pub const ALL: [ReportFormat; 2] = [ReportFormat::Markdown, ReportFormat::Latex];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "Markdown",
+            ReportFormat::Latex => "LaTeX",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Latex => "tex",
+        }
+    }
+}
+
+/// One calculation in a report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    /// A worksheet line's label
+    pub label: Option<String>,
+    /// The expression as typed
+    pub source: String,
+    pub result: Result<f64, CalculatorError>,
+}
+
+/// The history's calculations, oldest first.
+pub fn history_rows(history: &History) -> Vec<Row> {
+    history
+        .entries()
+        .iter()
+        .map(|entry| Row {
+            label: None,
+            source: entry.source.clone(),
+            result: Ok(entry.result),
+        })
+        .collect()
+}
+
+/// The worksheet's lines that have a result, given its text and what
+/// `worksheet::evaluate` made of it.
+pub fn worksheet_rows(text: &str, lines: &[WorksheetLine]) -> Vec<Row> {
+    text.split('\n')
+        .zip(lines)
+        .filter_map(|(line, evaluated)| {
+            let result = evaluated.result.clone()?;
+            let source = match &evaluated.label {
+                Some(_) => line.split_once(':').map_or(line, |(_, rest)| rest),
+                None => line,
+            };
+            Some(Row {
+                label: evaluated.label.clone(),
+                source: source.trim().to_string(),
+                result,
+            })
+        })
+        .collect()
+}
+
+pub fn render(rows: &[Row], format: ReportFormat, options: ParseOptions) -> String {
+    match format {
+        ReportFormat::Markdown => markdown(rows),
+        ReportFormat::Latex => latex(rows, options),
+    }
+}
+
+fn result_text(result: &Result<f64, CalculatorError>) -> String {
+    match result {
+        Ok(value) => value.to_string(),
+        Err(err) => err.to_string(),
+    }
+}
+
+/// A two-column table of expressions, as code, and results.
+pub fn markdown(rows: &[Row]) -> String {
+    let mut table = String::from("| Expression | Result |\n|---|---:|\n");
+    for row in rows {
+        let expression = match &row.label {
+            Some(label) => format!("{}: {}", label, row.source),
+            None => row.source.clone(),
+        };
+        // A | would end the cell, even inside code
+        table.push_str(&format!(
+            "| `{}` | {} |\n",
+            expression.replace('|', "\\|"),
+            result_text(&row.result).replace('|', "\\|")
+        ));
+    }
+    table
+}
+
+/// An `align*` block with one line per calculation, aligned on the `=`.
+pub fn latex(rows: &[Row], options: ParseOptions) -> String {
+    let lines: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            let label = row
+                .label
+                .as_ref()
+                .map_or_else(String::new, |label| format!("{} = ", latex_name(label)));
+            let result = match &row.result {
+                Ok(value) => value.to_string(),
+                Err(err) => format!("\\text{{{}}}", latex_text(&err.to_string())),
+            };
+            match expression::parse_equation(&row.source, options) {
+                Ok(Some((left, right))) => format!(
+                    "{}{} = {} &\\Rightarrow {}",
+                    label,
+                    latex_expr(&left),
+                    latex_expr(&right),
+                    result
+                ),
+                _ => {
+                    let expression = match expression::parse_with(&row.source, options) {
+                        Ok(expr) => latex_expr(&expr),
+                        Err(_) => format!("\\text{{{}}}", latex_text(&row.source)),
+                    };
+                    format!("{}{} &= {}", label, expression, result)
+                }
+            }
+        })
+        .collect();
+    let mut block = String::from("\\begin{align*}\n");
+    if !lines.is_empty() {
+        block.push_str(&lines.join(" \\\\\n"));
+        block.push('\n');
+    }
+    block.push_str("\\end{align*}\n");
+    block
+}
+
+/// The expression in LaTeX math mode: `\cdot` for products, fractions for
+/// division, `\sqrt` and the standard function names.
+pub fn latex_expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(value) => value.to_string(),
+        Expr::Variable(name) => latex_name(name),
+        Expr::Negate(operand) => {
+            let parenthesize = operand.precedence() < BinaryOp::Power.precedence();
+            format!("-{}", latex_child(operand, parenthesize))
+        }
+        Expr::Binary { op: BinaryOp::Divide, left, right } => {
+            format!("\\frac{{{}}}{{{}}}", latex_expr(left), latex_expr(right))
+        }
+        Expr::Binary { op: BinaryOp::Power, left, right } => {
+            // A negative base needs its parentheses: (-2)^{2}, not -2^{2}
+            let parenthesize = left.precedence() <= BinaryOp::Power.precedence();
+            format!("{}^{{{}}}", latex_child(left, parenthesize), latex_expr(right))
+        }
+        // The Hadamard power, A^{\circ 2}
+        Expr::Binary { op: BinaryOp::ElementPower, left, right } => {
+            let parenthesize = left.precedence() <= BinaryOp::Power.precedence();
+            format!("{}^{{\\circ {}}}", latex_child(left, parenthesize), latex_expr(right))
+        }
+        Expr::Binary { op, left, right } => {
+            let precedence = op.precedence();
+            let symbol = match op {
+                BinaryOp::Multiply => "\\cdot",
+                BinaryOp::ElementMultiply => "\\odot",
+                BinaryOp::ElementDivide => "\\oslash",
+                BinaryOp::Subtract => "-",
+                _ => "+",
+            };
+            format!(
+                "{} {} {}",
+                latex_child(left, left.precedence() < precedence),
+                symbol,
+                latex_child(right, right.precedence() <= precedence)
+            )
+        }
+        Expr::Call { name, args } => {
+            let args: Vec<String> = args.iter().map(latex_expr).collect();
+            let args = args.join(", ");
+            match name.to_ascii_lowercase().as_str() {
+                "sqrt" => format!("\\sqrt{{{}}}", args),
+                "abs" => format!("\\left|{}\\right|", args),
+                "exp" => format!("e^{{{}}}", args),
+                "asin" => format!("\\arcsin\\left({}\\right)", args),
+                "acos" => format!("\\arccos\\left({}\\right)", args),
+                "atan" => format!("\\arctan\\left({}\\right)", args),
+                known @ ("sin" | "cos" | "tan" | "ln" | "log" | "min" | "max") => {
+                    format!("\\{}\\left({}\\right)", known, args)
+                }
+                _ => format!("\\operatorname{{{}}}\\left({}\\right)", latex_text(name), args),
+            }
+        }
+        Expr::Matrix(rows) => {
+            let rows: Vec<String> = rows
+                .iter()
+                .map(|row| row.iter().map(latex_expr).collect::<Vec<_>>().join(" & "))
+                .collect();
+            format!("\\begin{{pmatrix}} {} \\end{{pmatrix}}", rows.join(" \\\\ "))
+        }
+    }
+}
+
+fn latex_child(expr: &Expr, parenthesize: bool) -> String {
+    if parenthesize {
+        format!("\\left({}\\right)", latex_expr(expr))
+    } else {
+        latex_expr(expr)
+    }
+}
+
+// Single letters in italics as usual, longer names upright
+fn latex_name(name: &str) -> String {
+    match name {
+        "pi" | "π" => String::from("\\pi"),
+        _ if name.chars().count() == 1 => latex_text(name),
+        _ => format!("\\mathrm{{{}}}", latex_text(name)),
+    }
+}
+
+// Escapes the characters LaTeX treats specially
+fn latex_text(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worksheet;
+    use proptest::prelude::*;
+
+    fn row(source: &str, result: Result<f64, CalculatorError>) -> Row {
+        Row {
+            label: None,
+            source: source.to_string(),
+            result,
+        }
+    }
+
+    fn latex_of(source: &str) -> String {
+        latex_expr(&expression::parse_with(source, ParseOptions::default()).unwrap())
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let rows = [row("12 + 3", Ok(15.0)), row("1 / 0", Err(CalculatorError::DivisionByZero))];
+        assert_eq!(
+            markdown(&rows),
+            "| Expression | Result |\n|---|---:|\n| `12 + 3` | 15 |\n| `1 / 0` | Error: Division by zero |\n"
+        );
+    }
+
+    #[test]
+    fn test_latex_expressions() {
+        assert_eq!(latex_of("1 + 2 * 3"), "1 + 2 \\cdot 3");
+        assert_eq!(latex_of("(1 + 2) * 3"), "\\left(1 + 2\\right) \\cdot 3");
+        assert_eq!(latex_of("(1 + 2) / 3"), "\\frac{1 + 2}{3}");
+        assert_eq!(latex_of("(-2)^2 - 2^(1 + 1)"), "\\left(-2\\right)^{2} - 2^{1 + 1}");
+        assert_eq!(latex_of("sqrt(2) * sin(pi / 4)"), "\\sqrt{2} \\cdot \\sin\\left(\\frac{\\pi}{4}\\right)");
+        assert_eq!(latex_of("abs(x) + my_fn(price)"), "\\left|x\\right| + \\operatorname{my\\_fn}\\left(\\mathrm{price}\\right)");
+        assert_eq!(latex_of("5 - (3 - 1)"), "5 - \\left(3 - 1\\right)");
+    }
+
+    #[test]
+    fn test_latex_block() {
+        let options = ParseOptions::default();
+        let text = "subtotal: 120 * 3\n# comment\n3x + 5 = 20\n1 / 0";
+        let lines = worksheet::evaluate(text, None, options);
+        let mut rows = worksheet_rows(text, &lines);
+        rows[1].result = Ok(5.0);
+        assert_eq!(
+            latex(&rows, options),
+            "\\begin{align*}\n\
+             \\mathrm{subtotal} = 120 \\cdot 3 &= 360 \\\\\n\
+             3 \\cdot x + 5 = 20 &\\Rightarrow 5 \\\\\n\
+             \\frac{1}{0} &= \\text{Error: Division by zero}\n\
+             \\end{align*}\n"
+        );
+    }
+
+    // Feature: gui-calculator, Property 46: Every calculation becomes exactly one row of the report
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_one_row_per_calculation(
+            rows in prop::collection::vec(("[0-9a-z+*/()| ^_&%-]{0,20}", -1e9..1e9f64), 0..10)
+        ) {
+            let rows: Vec<Row> = rows.into_iter().map(|(source, value)| row(&source, Ok(value))).collect();
+            let table = markdown(&rows);
+            prop_assert_eq!(table.lines().count(), rows.len() + 2);
+            let block = latex(&rows, ParseOptions::default());
+            prop_assert_eq!(block.matches(" &").count(), rows.len());
+            prop_assert_eq!(block.lines().count(), rows.len() + 2);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+// User Script Functions
+use std::path::Path;
+
+use rhai::{Dynamic, Engine, Scope, AST, FLOAT};
+
+use crate::error::CalculatorError;
+use crate::expression::Functions;
+
+/// Script file loaded at startup when present in the working directory.
+pub const DEFAULT_SCRIPT_PATH: &str = "functions.rhai";
+
+/// A function defined in the user's script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptFunction {
+    pub name: String,
+    pub arity: usize,
+}
+
+/// Sandboxed rhai engine holding the user's compiled function definitions.
+/// Scripts have no file or network access and are limited in how much work
+/// a single call may do, so a runaway loop cannot freeze the UI.
+pub struct ScriptEngine {
+    engine: Engine,
+    ast: AST,
+    functions: Vec<ScriptFunction>,
+    // Kept to compile the same functions again on a worker thread, since
+    // the engine itself can't move between threads
+    source: String,
+}
+
+impl Default for ScriptEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScriptEngine {
+    pub fn new() -> Self {
+        let mut engine = Engine::new();
+        engine.set_max_operations(100_000);
+        engine.set_max_call_levels(32);
+        engine.set_max_expr_depths(64, 32);
+        engine.set_max_string_size(1_000);
+        engine.set_max_array_size(1_000);
+        engine.set_max_map_size(1_000);
+
+        Self {
+            engine,
+            ast: AST::empty(),
+            functions: Vec::new(),
+            source: String::new(),
+        }
+    }
+
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<(), CalculatorError> {
+        let source = std::fs::read_to_string(path.as_ref()).map_err(|err| {
+            CalculatorError::Script(format!("{}: {}", path.as_ref().display(), err))
+        })?;
+        self.load_source(&source)
+    }
+
+    /// Compiles `source`, replacing any previously loaded functions.
+    /// On failure the previous definitions stay active.
+    pub fn load_source(&mut self, source: &str) -> Result<(), CalculatorError> {
+        let ast = self
+            .engine
+            .compile(source)
+            .map_err(|err| CalculatorError::Script(err.to_string()))?;
+
+        let mut functions: Vec<ScriptFunction> = ast
+            .iter_functions()
+            .map(|f| ScriptFunction {
+                name: f.name.to_string(),
+                arity: f.params.len(),
+            })
+            .collect();
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+
+        self.functions = functions;
+        self.ast = ast;
+        self.source = source.to_string();
+        Ok(())
+    }
+
+    pub fn functions(&self) -> &[ScriptFunction] {
+        &self.functions
+    }
+
+    /// The script the current functions were compiled from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn call(&self, name: &str, args: &[f64]) -> Result<f64, CalculatorError> {
+        let args: Vec<Dynamic> = args.iter().map(|&arg| Dynamic::from_float(arg as FLOAT)).collect();
+        let result: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, name, args)
+            .map_err(|err| CalculatorError::Script(format!("{}: {}", name, err)))?;
+
+        // Scripts doing pure integer math return INT
+        if let Ok(value) = result.as_float() {
+            Ok(value)
+        } else if let Ok(value) = result.as_int() {
+            Ok(value as f64)
+        } else {
+            Err(CalculatorError::Script(format!(
+                "{} returned {}, expected a number",
+                name,
+                result.type_name()
+            )))
+        }
+    }
+}
+
+/// Makes script functions callable from expressions.
+impl Functions for ScriptEngine {
+    fn call(&self, name: &str, args: &[f64]) -> Option<Result<f64, CalculatorError>> {
+        self.functions
+            .iter()
+            .any(|function| function.name == name && function.arity == args.len())
+            .then(|| ScriptEngine::call(self, name, args))
+    }
+
+    fn signatures(&self) -> Vec<(String, usize)> {
+        self.functions
+            .iter()
+            .map(|function| (function.name.clone(), function.arity))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::{parse, Environment};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_script_functions_are_listed() {
+        let mut scripts = ScriptEngine::new();
+        scripts
+            .load_source("fn vat(x) { x * 1.19 } fn hyp(a, b) { sqrt(a * a + b * b) }")
+            .unwrap();
+
+        assert_eq!(
+            scripts.functions(),
+            &[
+                ScriptFunction { name: String::from("hyp"), arity: 2 },
+                ScriptFunction { name: String::from("vat"), arity: 1 },
+            ]
+        );
+        assert_eq!(scripts.call("hyp", &[3.0, 4.0]), Ok(5.0));
+
+        // Script functions are available to expressions
+        let mut env = Environment::new();
+        env.functions = Some(&scripts);
+        assert_eq!(parse("hyp(3, 4) + vat(100)").unwrap().eval(&env), Ok(5.0 + 100.0 * 1.19));
+    }
+
+    #[test]
+    fn test_script_errors_are_calculator_errors() {
+        let mut scripts = ScriptEngine::new();
+
+        // Syntax errors keep the previous (empty) definitions
+        assert!(matches!(
+            scripts.load_source("fn broken(x) { x + }"),
+            Err(CalculatorError::Script(_))
+        ));
+        assert!(scripts.functions().is_empty());
+
+        // Unknown functions, wrong return types and runaway loops all fail cleanly
+        scripts
+            .load_source(r#"fn text(x) { "abc" } fn spin(x) { loop { x += 1; } }"#)
+            .unwrap();
+        assert!(matches!(scripts.call("missing", &[1.0]), Err(CalculatorError::Script(_))));
+        assert!(matches!(scripts.call("text", &[1.0]), Err(CalculatorError::Script(_))));
+        assert!(matches!(scripts.call("spin", &[1.0]), Err(CalculatorError::Script(_))));
+    }
+
+    // Feature: gui-calculator, Property 11: Script results match native arithmetic
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_script_arithmetic(
+            x in -1000000.0..1000000.0f64,
+        ) {
+            let mut scripts = ScriptEngine::new();
+            scripts.load_source("fn vat(x) { x * 1.19 } fn int_only(x) { 42 }").unwrap();
+
+            prop_assert_eq!(scripts.call("vat", &[x]), Ok(x * 1.19));
+            prop_assert_eq!(scripts.call("int_only", &[x]), Ok(42.0));
+        }
+    }
+}
@@ -0,0 +1,140 @@
+// Self-Check
+//
+// A battery of arithmetic edge cases run through the same calculator the
+// window uses, for `--selfcheck`: overflow, binary rounding, signed zero,
+// subnormals and rounding halves. The expected results are what IEEE 754
+// doubles give, so a failure points at the platform's float or math library.
+use crate::calculator::Calculator;
+
+/// One expression and the display text it must give.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub source: &'static str,
+    pub expected: &'static str,
+    /// Evaluate in decimal mode instead of binary floating point
+    pub decimal: bool,
+}
+
+const fn check(name: &'static str, source: &'static str, expected: &'static str) -> Check {
+    Check {
+        name,
+        source,
+        expected,
+        decimal: false,
+    }
+}
+
+pub const CHECKS: &[Check] = &[
+    check("Overflow is an error, not infinity", "1e308 * 10", "Error: Result too large to represent"),
+    check("Division by zero is an error", "1 / 0", "Error: Division by zero"),
+    check("Division by negative zero too", "1 / -0", "Error: Division by zero"),
+    check("Negative zero plus zero is zero", "-0 + 0", "0"),
+    check("Negative zero shows as 0", "0 * -1", "0"),
+    check("Zero to a negative power is division by zero", "0 ^ -1", "Error: Division by zero"),
+    check("Square root of a negative", "sqrt(-1)", "Error: Invalid argument for sqrt"),
+    check("Binary rounding shows", "0.1 + 0.2", "0.30000000000000004"),
+    Check {
+        decimal: true,
+        ..check("Decimal mode is exact", "0.1 + 0.2", "0.3")
+    },
+    check("Rounding back to a whole number", "1 / 3 * 3", "1"),
+    check("Square root rounding", "sqrt(2) ^ 2", "2.0000000000000004"),
+    check("Whole numbers are exact up to 2^53", "2 ^ 53 + 1", "9007199254740992"),
+    check("Halves round away from zero", "round(2.5)", "3"),
+    check("Negative halves too", "round(-2.5)", "-3"),
+    check("Just under a half rounds down", "round(0.49999999999999994)", "0"),
+    check("Subnormals aren't flushed to zero", "log(5e-324)", "-323.3062153431158"),
+    check("sin(π) is as close to 0 as a double allows", "sin(pi)", "0.00000000000000012246467991473532"),
+    check("exp(1) is e", "exp(1) - e", "0"),
+    check("Powers of ten are exact", "10 ^ 15", "1000000000000000"),
+];
+
+/// A check and what the calculator actually showed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub check: Check,
+    pub actual: String,
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        self.actual == self.check.expected
+    }
+}
+
+/// Runs every check on a fresh calculator.
+pub fn run() -> Vec<Outcome> {
+    CHECKS
+        .iter()
+        .map(|&check| {
+            let mut calculator = Calculator::new();
+            calculator.set_decimal_mode(check.decimal);
+            let actual = match calculator.evaluate_expression(check.source, None) {
+                Ok(_) => calculator.get_display_text().to_string(),
+                Err(err) => err.to_string(),
+            };
+            Outcome { check, actual }
+        })
+        .collect()
+}
+
+/// The outcomes one per line, with a count at the end.
+pub fn report(outcomes: &[Outcome]) -> String {
+    let mut report = format!(
+        "Calculator self-check {} ({} {})\n\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    for outcome in outcomes {
+        let mode = if outcome.check.decimal { " [decimal]" } else { "" };
+        report.push_str(&format!(
+            "{:<6}{}: {}{} = {}",
+            if outcome.passed() { "ok" } else { "FAIL" },
+            outcome.check.name,
+            outcome.check.source,
+            mode,
+            outcome.actual
+        ));
+        if !outcome.passed() {
+            report.push_str(&format!(" (expected {})", outcome.check.expected));
+        }
+        report.push('\n');
+    }
+    let passed = outcomes.iter().filter(|outcome| outcome.passed()).count();
+    report.push_str(&format!("\n{} of {} checks passed", passed, outcomes.len()));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks_pass() {
+        let outcomes = run();
+        assert!(outcomes.iter().all(Outcome::passed), "{}", report(&outcomes));
+    }
+
+    #[test]
+    fn test_report() {
+        let failed = Outcome {
+            check: check("Binary rounding shows", "0.1 + 0.2", "0.30000000000000004"),
+            actual: String::from("0.3"),
+        };
+        let passed = Outcome {
+            actual: String::from("0.3"),
+            check: Check {
+                decimal: true,
+                ..check("Decimal mode is exact", "0.1 + 0.2", "0.3")
+            },
+        };
+        let report = report(&[failed, passed]);
+        let lines: Vec<&str> = report.lines().collect();
+        assert!(lines[0].starts_with("Calculator self-check "));
+        assert_eq!(lines[2], "FAIL  Binary rounding shows: 0.1 + 0.2 = 0.3 (expected 0.30000000000000004)");
+        assert_eq!(lines[3], "ok    Decimal mode is exact: 0.1 + 0.2 [decimal] = 0.3");
+        assert_eq!(lines.last(), Some(&"1 of 2 checks passed"));
+    }
+}
@@ -0,0 +1,150 @@
+// User Settings
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+use crate::calculator::Calculator;
+use crate::expression::ParseOptions;
+use crate::format::{self, DecimalPlaces, MoneyStyle, NumberFormat, RoundingSwitch};
+use crate::keypad::{KeypadLayout, KeypadPreset};
+use crate::programmer::OverflowBehavior;
+use crate::template::Template;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// Preferences persisted between sessions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Maximum number of history entries kept (and saved)
+    pub max_history: usize,
+    /// Copy every result to the clipboard when = is pressed
+    pub auto_copy_result: bool,
+    /// Do + - * / in decimal so results like 0.1 + 0.2 come out exact
+    pub decimal_mode: bool,
+    /// Read `2(3+4)` and `3π` as products; off for strict syntax
+    pub implicit_multiplication: bool,
+    /// Whether programmer mode wraps results that don't fit the word size
+    pub integer_overflow: OverflowBehavior,
+    /// How results are written on the display
+    pub number_format: NumberFormat,
+    /// Unit shown after SI-prefixed and engineering results, e.g. Ω
+    pub unit_suffix: String,
+    /// Symbol, rounding and negatives for the money format
+    pub money: MoneyStyle,
+    /// Show results within `cleanup_epsilon` of a short decimal as that
+    /// decimal, e.g. 2.9999999999999996 as 3; only the display changes
+    pub smart_cleanup: bool,
+    /// How close counts for smart cleanup, relative to the result
+    pub cleanup_epsilon: f64,
+    /// Keep a grand total of every result under the display, like an
+    /// adding machine
+    pub running_total: bool,
+    /// The desk calculator's F / CUT / 5/4 switch, for every result shown
+    pub rounding: RoundingSwitch,
+    /// Places the rounding switch cuts or rounds to; ADD2 also has numbers
+    /// typed in cents
+    pub decimal_places: DecimalPlaces,
+    pub theme: Theme,
+    /// Key sizes and styling of the keypad
+    pub keypad: KeypadPreset,
+    /// Larger tap targets, nothing that needs hovering, and swipe left on
+    /// the display for backspace
+    pub touch_mode: bool,
+    /// Hide to the tray icon instead of closing or minimizing, where there is one
+    pub minimize_to_tray: bool,
+    /// Draw our own title bar instead of the system one
+    pub frameless_window: bool,
+    /// Launching the calculator again brings this window forward instead
+    pub single_instance: bool,
+    /// Saved expressions to run with new inputs
+    pub templates: Vec<Template>,
+}
+
+impl Settings {
+    /// ADD2 on the decimal-place selector also means numbers are typed in cents.
+    pub fn add_mode(&self) -> bool {
+        self.decimal_places == DecimalPlaces::Add2
+    }
+
+    /// Sets up `calculator` as these settings ask; every calculator the app
+    /// shows, in whatever window, goes through here.
+    pub fn configure(&self, calculator: &mut Calculator) {
+        calculator.set_decimal_mode(self.decimal_mode);
+        calculator.set_add_mode(self.add_mode());
+        calculator.set_parse_options(ParseOptions {
+            implicit_multiplication: self.implicit_multiplication,
+        });
+    }
+
+    /// The result on `calculator`'s display as smart cleanup shows it, when
+    /// cleanup changes it.
+    pub fn cleaned_value(&self, calculator: &Calculator) -> Option<f64> {
+        if !self.smart_cleanup || calculator.is_entering() {
+            return None;
+        }
+        format::clean_up(calculator.display_value()?, self.cleanup_epsilon)
+    }
+
+    /// `calculator`'s display as these settings show it: cleaned up, in the
+    /// number format, or cut or rounded by the desk switch. A number being
+    /// typed stays as typed, and `raw` leaves out the cleanup.
+    pub fn display_text<'c>(&self, calculator: &'c Calculator, raw: bool) -> Cow<'c, str> {
+        let cleaned = if raw { None } else { self.cleaned_value(calculator) };
+        let value = match cleaned.or(calculator.display_value()) {
+            Some(value) if !calculator.is_entering() => value,
+            _ => return Cow::Borrowed(calculator.get_display_text()),
+        };
+        match self.number_format {
+            NumberFormat::Money => Cow::Owned(format::format_money(value, &self.money)),
+            NumberFormat::Plain if self.rounding != RoundingSwitch::Float => {
+                Cow::Owned(format::format_desk(value, self.rounding, self.decimal_places))
+            }
+            NumberFormat::Plain if cleaned.is_some() => Cow::Owned(value.to_string()),
+            NumberFormat::Plain => Cow::Borrowed(calculator.get_display_text()),
+            format => Cow::Owned(format::format_number(value, format, &self.unit_suffix)),
+        }
+    }
+
+    /// The keypad preset, enlarged in touch mode.
+    pub fn keypad_layout(&self) -> KeypadLayout {
+        let layout = self.keypad.layout();
+        if self.touch_mode {
+            layout.for_touch()
+        } else {
+            layout
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            max_history: 100,
+            auto_copy_result: false,
+            decimal_mode: false,
+            implicit_multiplication: true,
+            integer_overflow: OverflowBehavior::Wrap,
+            number_format: NumberFormat::Plain,
+            unit_suffix: String::new(),
+            money: MoneyStyle::default(),
+            smart_cleanup: false,
+            cleanup_epsilon: format::DEFAULT_CLEANUP_EPSILON,
+            running_total: false,
+            rounding: RoundingSwitch::Float,
+            decimal_places: DecimalPlaces::Two,
+            theme: Theme::Dark,
+            keypad: KeypadPreset::Standard,
+            touch_mode: false,
+            minimize_to_tray: false,
+            frameless_window: false,
+            single_instance: false,
+            templates: Vec::new(),
+        }
+    }
+}
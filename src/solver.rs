@@ -0,0 +1,205 @@
+// Linear Equation Solver
+use crate::error::CalculatorError;
+use crate::expression::{BinaryOp, Environment, Expr};
+use crate::number;
+
+/// An equation solved for its unknown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub unknown: String,
+    pub value: f64,
+}
+
+// `slope * unknown + offset`
+#[derive(Debug, Clone, Copy)]
+struct Linear {
+    slope: f64,
+    offset: f64,
+}
+
+impl Linear {
+    fn constant(offset: f64) -> Self {
+        Self { slope: 0.0, offset }
+    }
+
+    fn scaled(self, factor: f64) -> Self {
+        Self {
+            slope: self.slope * factor,
+            offset: self.offset * factor,
+        }
+    }
+}
+
+fn unsolvable(reason: impl Into<String>) -> CalculatorError {
+    CalculatorError::Unsolvable(reason.into())
+}
+
+/// Solves `left = right` for the one name `env` doesn't know, as long as
+/// both sides are linear in it, e.g. `3x + 5 = 20` gives x = 5.
+pub fn solve(left: &Expr, right: &Expr, env: &Environment) -> Result<Solution, CalculatorError> {
+    let mut unknowns = Vec::new();
+    collect_unknowns(left, env, &mut unknowns);
+    collect_unknowns(right, env, &mut unknowns);
+    let unknown = match unknowns.as_slice() {
+        [unknown] => unknown.clone(),
+        [] => return Err(unsolvable("no unknown")),
+        _ => return Err(unsolvable(format!("more than one unknown ({})", unknowns.join(", ")))),
+    };
+
+    let left = linear(left, &unknown, env)?;
+    let right = linear(right, &unknown, env)?;
+    // slope * unknown = offset, with everything moved to one side
+    let slope = left.slope - right.slope;
+    let offset = right.offset - left.offset;
+    if slope == 0.0 {
+        return Err(unsolvable(if offset == 0.0 {
+            "every value is a solution"
+        } else {
+            "no solution"
+        }));
+    }
+    let value = number::in_range(offset / slope)?;
+    Ok(Solution { unknown, value })
+}
+
+// Names without a value, in order of appearance
+fn collect_unknowns(expr: &Expr, env: &Environment, unknowns: &mut Vec<String>) {
+    match expr {
+        Expr::Number(_) => {}
+        Expr::Variable(name) => {
+            if env.lookup(name).is_err() && !unknowns.contains(name) {
+                unknowns.push(name.clone());
+            }
+        }
+        Expr::Negate(operand) => collect_unknowns(operand, env, unknowns),
+        Expr::Binary { left, right, .. } => {
+            collect_unknowns(left, env, unknowns);
+            collect_unknowns(right, env, unknowns);
+        }
+        Expr::Call { args, .. } => {
+            for arg in args {
+                collect_unknowns(arg, env, unknowns);
+            }
+        }
+        Expr::Matrix(rows) => {
+            for element in rows.iter().flatten() {
+                collect_unknowns(element, env, unknowns);
+            }
+        }
+    }
+}
+
+// `expr` as a line in `unknown`, or an error where it stops being one, like x * x
+fn linear(expr: &Expr, unknown: &str, env: &Environment) -> Result<Linear, CalculatorError> {
+    let not_linear = || unsolvable(format!("not linear in {}", unknown));
+    match expr {
+        Expr::Number(value) => Ok(Linear::constant(*value)),
+        Expr::Variable(name) if name == unknown => Ok(Linear { slope: 1.0, offset: 0.0 }),
+        Expr::Variable(name) => Ok(Linear::constant(env.lookup(name)?)),
+        Expr::Negate(operand) => Ok(linear(operand, unknown, env)?.scaled(-1.0)),
+        Expr::Binary { op, left, right } => {
+            let left = linear(left, unknown, env)?;
+            let right = linear(right, unknown, env)?;
+            match op {
+                BinaryOp::Add => Ok(Linear {
+                    slope: left.slope + right.slope,
+                    offset: left.offset + right.offset,
+                }),
+                BinaryOp::Subtract => Ok(Linear {
+                    slope: left.slope - right.slope,
+                    offset: left.offset - right.offset,
+                }),
+                BinaryOp::Multiply if left.slope == 0.0 => Ok(right.scaled(left.offset)),
+                BinaryOp::Multiply if right.slope == 0.0 => Ok(left.scaled(right.offset)),
+                BinaryOp::Divide if right.slope == 0.0 => {
+                    if right.offset == 0.0 {
+                        return Err(CalculatorError::DivisionByZero);
+                    }
+                    Ok(left.scaled(1.0 / right.offset))
+                }
+                _ if left.slope == 0.0 && right.slope == 0.0 => {
+                    Ok(Linear::constant(op.apply(left.offset, right.offset)?))
+                }
+                _ => Err(not_linear()),
+            }
+        }
+        // Matrix functions of known values too, like det([1, 2; 3, 4])
+        Expr::Call { .. } if expr.uses_matrices() && !expr.variables().contains(&unknown) => {
+            Ok(Linear::constant(expr.eval(env)?))
+        }
+        // Functions only of known values are just numbers
+        Expr::Call { name, args } => {
+            let mut values = Vec::with_capacity(args.len());
+            for arg in args {
+                let arg = linear(arg, unknown, env)?;
+                if arg.slope != 0.0 {
+                    return Err(not_linear());
+                }
+                values.push(arg.offset);
+            }
+            Ok(Linear::constant(env.call(name, &values)?))
+        }
+        Expr::Matrix(_) => Err(not_linear()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::{parse_equation, ParseOptions};
+    use proptest::prelude::*;
+
+    fn solve_text(source: &str) -> Result<Solution, CalculatorError> {
+        let (left, right) = parse_equation(source, ParseOptions::default())?.expect("an equation");
+        solve(&left, &right, &Environment::new())
+    }
+
+    fn solution(unknown: &str, value: f64) -> Result<Solution, CalculatorError> {
+        Ok(Solution {
+            unknown: unknown.to_string(),
+            value,
+        })
+    }
+
+    #[test]
+    fn test_linear_equations() {
+        assert_eq!(solve_text("3x + 5 = 20"), solution("x", 5.0));
+        assert_eq!(solve_text("20 = 5 + 3x"), solution("x", 5.0));
+        assert_eq!(solve_text("2(y - 1) = y / 2 + 1"), solution("y", 2.0));
+        assert_eq!(solve_text("-rate * 4 = sqrt(16)"), solution("rate", -1.0));
+        assert_eq!(solve_text("x / (1 + 1) = pi"), solution("x", 2.0 * std::f64::consts::PI));
+    }
+
+    #[test]
+    fn test_cannot_solve() {
+        let unsolvable = |reason: &str| Err(CalculatorError::Unsolvable(reason.to_string()));
+        assert_eq!(solve_text("x * x = 4"), unsolvable("not linear in x"));
+        assert_eq!(solve_text("sqrt(x) = 4"), unsolvable("not linear in x"));
+        assert_eq!(solve_text("2 ^ x = 8"), unsolvable("not linear in x"));
+        assert_eq!(solve_text("x + y = 1"), unsolvable("more than one unknown (x, y)"));
+        assert_eq!(solve_text("1 + 2 = 3"), unsolvable("no unknown"));
+        assert_eq!(solve_text("x + 1 = x"), unsolvable("no solution"));
+        assert_eq!(solve_text("2x = x + x"), unsolvable("every value is a solution"));
+        assert_eq!(solve_text("x / 0 = 1"), Err(CalculatorError::DivisionByZero));
+        assert!(matches!(parse_equation("x = 1 = 2", ParseOptions::default()), Err(CalculatorError::Syntax { .. })));
+        assert_eq!(parse_equation("1 + 2", ParseOptions::default()), Ok(None));
+    }
+
+    // Feature: gui-calculator, Property 33: A solved linear equation balances
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_solution_balances(
+            a in (-100i32..100).prop_filter("a nonzero slope", |a| *a != 0),
+            b in -1000i32..1000,
+            c in -1000i32..1000
+        ) {
+            let source = format!("{} * x + {} = {}", a, b, c);
+            let solved = solve_text(&source).unwrap();
+
+            let balanced = a as f64 * solved.value + b as f64;
+            prop_assert!((balanced - c as f64).abs() <= 1e-9, "{} gave x = {}", source, solved.value);
+        }
+    }
+}
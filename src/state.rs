@@ -1,25 +1,82 @@
 // State Model
-use crate::operation::Operation;
+use serde::{Deserialize, Serialize};
+
+use crate::error::CalculatorError;
+use crate::number::Number;
+use crate::operation::PendingOperation;
+
+/// Where the user is in entering a number. A single value rather than a set
+/// of flags, so combinations like "typing while in error" can't arise.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EntryState {
+    /// Nothing typed since a clear, or the operand was replaced (CE, √,
+    /// recall). The next digit replaces the display, and a following
+    /// operation consumes the shown value.
+    Fresh,
+    TypingInteger,
+    /// A decimal point has been typed
+    TypingFraction,
+    /// Digits typed in ADD2 mode, read as cents: 1995 is 19.95. A decimal
+    /// point makes them whole units again, as typed.
+    TypingCents,
+    /// A result, or the operand stored for a pending operation, is shown. The
+    /// next digit starts a new number and another operation only replaces
+    /// the pending one.
+    ResultShown,
+    /// Blocks all input until cleared
+    Error { error: CalculatorError },
+}
+
+impl EntryState {
+    pub fn error(&self) -> Option<&CalculatorError> {
+        match self {
+            EntryState::Error { error } => Some(error),
+            _ => None,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.error().is_some()
+    }
+
+    pub fn is_typing(&self) -> bool {
+        matches!(self, EntryState::TypingInteger | EntryState::TypingFraction | EntryState::TypingCents)
+    }
+}
 
 #[derive(Clone)]
 pub struct CalculatorState {
-    pub display: String,
-    pub stored_value: Option<f64>,
-    pub current_operation: Option<Operation>,
-    pub waiting_for_operand: bool,
-    pub error: Option<String>,
-    pub fresh_start: bool,  // True when in initial state or after clear
+    /// The current operand or result. Computation only ever reads this, never
+    /// the formatted display.
+    pub value: Number,
+    /// The characters typed so far while `entry` is typing, e.g. "12." — kept
+    /// so the display shows what was typed, including a trailing point
+    pub input: String,
+    /// The first operand for `current_operation`; a chain's running result,
+    /// exact decimal included
+    pub stored_value: Option<Number>,
+    pub current_operation: Option<PendingOperation>,
+    pub entry: EntryState,
+    /// How many numbers the current sum has added or subtracted, like an
+    /// adding machine's item counter; 0 outside a sum
+    pub item_count: usize,
+}
+
+impl Default for CalculatorState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CalculatorState {
     pub fn new() -> Self {
         Self {
-            display: String::from("0"),
+            value: Number::new(0.0),
+            input: String::new(),
             stored_value: None,
             current_operation: None,
-            waiting_for_operand: false,
-            error: None,
-            fresh_start: true,
+            entry: EntryState::Fresh,
+            item_count: 0,
         }
     }
 }
@@ -0,0 +1,153 @@
+// Hypothesis Tests
+//
+// One-sample z and t tests from summary statistics, as in a statistics
+// homework: the sample's mean, standard deviation and size against the mean
+// claimed by the null hypothesis.
+use crate::distributions::Distribution;
+use crate::error::CalculatorError;
+
+/// Which side of the null hypothesis the alternative lies on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tail {
+    /// μ ≠ μ₀
+    TwoSided,
+    /// μ < μ₀
+    Less,
+    /// μ > μ₀
+    Greater,
+}
+
+impl Tail {
+    pub const ALL: [Tail; 3] = [Tail::TwoSided, Tail::Less, Tail::Greater];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Tail::TwoSided => "μ ≠ μ₀",
+            Tail::Less => "μ < μ₀",
+            Tail::Greater => "μ > μ₀",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TestResult {
+    /// z or t
+    pub statistic: f64,
+    /// Degrees of freedom, for a t test
+    pub df: Option<f64>,
+    pub p_value: f64,
+}
+
+/// The z test, for a known population standard deviation.
+pub fn z_test(
+    sample_mean: f64,
+    null_mean: f64,
+    population_sd: f64,
+    n: u64,
+    tail: Tail,
+) -> Result<TestResult, CalculatorError> {
+    if n == 0 || !(population_sd > 0.0 && population_sd.is_finite()) {
+        return Err(CalculatorError::Domain(String::from("z test")));
+    }
+    let statistic = (sample_mean - null_mean) / (population_sd / (n as f64).sqrt());
+    let normal = Distribution::Normal { mean: 0.0, std_dev: 1.0 };
+    Ok(TestResult {
+        statistic,
+        df: None,
+        p_value: p_value(&normal, statistic, tail),
+    })
+}
+
+/// The t test, for a standard deviation estimated from the sample itself.
+/// Needs at least two observations.
+pub fn t_test(
+    sample_mean: f64,
+    null_mean: f64,
+    sample_sd: f64,
+    n: u64,
+    tail: Tail,
+) -> Result<TestResult, CalculatorError> {
+    if n < 2 || !(sample_sd > 0.0 && sample_sd.is_finite()) {
+        return Err(CalculatorError::Domain(String::from("t test")));
+    }
+    let statistic = (sample_mean - null_mean) / (sample_sd / (n as f64).sqrt());
+    let df = (n - 1) as f64;
+    Ok(TestResult {
+        statistic,
+        df: Some(df),
+        p_value: p_value(&Distribution::StudentT { df }, statistic, tail),
+    })
+}
+
+// How likely a statistic at least this extreme is under the null hypothesis
+fn p_value(distribution: &Distribution, statistic: f64, tail: Tail) -> f64 {
+    let below = distribution.cdf(statistic);
+    match tail {
+        Tail::Less => below,
+        Tail::Greater => 1.0 - below,
+        // The distributions are symmetric, so twice the smaller tail
+        Tail::TwoSided => (2.0 * below.min(1.0 - below)).min(1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-12, "{} is not close to {}", actual, expected);
+    }
+
+    #[test]
+    fn test_z_test() {
+        // IQ scores: 36 people averaging 105 against a mean of 100, σ = 15
+        let result = z_test(105.0, 100.0, 15.0, 36, Tail::TwoSided).unwrap();
+        assert_close(result.statistic, 2.0);
+        assert_eq!(result.df, None);
+        assert_close(result.p_value, 0.045_500_263_896_358_42);
+        assert_close(z_test(105.0, 100.0, 15.0, 36, Tail::Greater).unwrap().p_value, 0.022_750_131_948_179_21);
+        assert_close(z_test(105.0, 100.0, 15.0, 36, Tail::Less).unwrap().p_value, 0.977_249_868_051_820_8);
+        assert!(z_test(105.0, 100.0, 0.0, 36, Tail::TwoSided).is_err());
+        assert!(z_test(105.0, 100.0, 15.0, 0, Tail::TwoSided).is_err());
+    }
+
+    #[test]
+    fn test_t_test() {
+        // With 2 degrees of freedom, P(T ≤ t) = 1/2 + t / (2√(2 + t²))
+        let result = t_test(12.0, 10.0, 3f64.sqrt(), 3, Tail::TwoSided).unwrap();
+        assert_close(result.statistic, 2.0);
+        assert_eq!(result.df, Some(2.0));
+        assert_close(result.p_value, 1.0 - 2.0 / 6f64.sqrt());
+
+        // With 1, it's the Cauchy distribution: P(T ≤ 1) = 3/4
+        let result = t_test(11.0, 10.0, 2f64.sqrt(), 2, Tail::Greater).unwrap();
+        assert_close(result.statistic, 1.0);
+        assert_close(result.p_value, 0.25);
+        assert_close(t_test(9.0, 10.0, 2f64.sqrt(), 2, Tail::TwoSided).unwrap().p_value, 0.5);
+
+        assert!(t_test(12.0, 10.0, 1.0, 1, Tail::TwoSided).is_err());
+        assert!(t_test(12.0, 10.0, -1.0, 10, Tail::TwoSided).is_err());
+    }
+
+    // Feature: gui-calculator, Property 58: One-sided p-values add up to 1 and the two-sided one is twice the smaller
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_p_values_consistent(
+            sample_mean in -100.0..100.0f64,
+            null_mean in -100.0..100.0f64,
+            sd in 0.1..50.0f64,
+            n in 2u64..500
+        ) {
+            for test in [z_test, t_test] {
+                let p = |tail| test(sample_mean, null_mean, sd, n, tail).unwrap().p_value;
+                let (two_sided, less, greater) = (p(Tail::TwoSided), p(Tail::Less), p(Tail::Greater));
+                prop_assert!((0.0..=1.0).contains(&two_sided));
+                prop_assert!((less + greater - 1.0).abs() < 1e-12);
+                prop_assert!((two_sided - (2.0 * less.min(greater)).min(1.0)).abs() < 1e-12);
+            }
+        }
+    }
+}
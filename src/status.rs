@@ -0,0 +1,113 @@
+// Status Bar Model
+use crate::format::{DecimalPlaces, RoundingSwitch};
+use crate::state::CalculatorState;
+
+/// Everything the status strip shows, derived in one place so the strip
+/// never disagrees with the calculator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Status {
+    pub mode: &'static str,
+    /// Trigonometric functions work in radians
+    pub angle_unit: &'static str,
+    pub memory: Option<f64>,
+    /// How + - * / round: "DEC" in decimal mode, "BIN" in binary floating point
+    pub rounding: &'static str,
+    /// The desk calculator's rounding switch, F, CUT or 5/4
+    pub rounding_switch: RoundingSwitch,
+    /// The decimal-place selector beside it
+    pub decimal_places: DecimalPlaces,
+    /// The stored operand and operator awaiting a second operand, e.g. "12 ×"
+    pub pending: Option<String>,
+    pub error: bool,
+}
+
+impl Status {
+    pub fn new(
+        mode: &'static str,
+        state: &CalculatorState,
+        memory: Option<f64>,
+        decimal_mode: bool,
+        rounding_switch: RoundingSwitch,
+        decimal_places: DecimalPlaces,
+    ) -> Self {
+        let pending = match (state.stored_value, &state.current_operation) {
+            (Some(stored), Some(op)) if !state.entry.is_error() => Some(format!("{} {}", stored.float(), op.symbol())),
+            _ => None,
+        };
+        Self {
+            mode,
+            angle_unit: "RAD",
+            memory,
+            rounding: if decimal_mode { "DEC" } else { "BIN" },
+            rounding_switch,
+            decimal_places,
+            pending,
+            error: state.entry.is_error(),
+        }
+    }
+
+    /// The switch and places as printed beside a desk calculator's display,
+    /// e.g. "5/4 2". At F only ADD2 shows, as the places don't matter there.
+    pub fn desk_rounding(&self) -> Option<String> {
+        match (self.rounding_switch, self.decimal_places) {
+            (RoundingSwitch::Float, DecimalPlaces::Add2) => Some(String::from("ADD2")),
+            (RoundingSwitch::Float, _) => None,
+            (switch, places) => Some(format!("{} {}", switch.label(), places.label())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculator::Calculator;
+    use crate::input::InputEvent;
+    use crate::operation::Operation;
+
+    #[test]
+    fn test_status_follows_calculator() {
+        let mut calc = Calculator::new();
+        let status = calc.status("Basic", RoundingSwitch::Float, DecimalPlaces::Two);
+        assert_eq!(status.pending, None);
+        assert_eq!(status.memory, None);
+        assert_eq!(status.rounding, "BIN");
+        assert_eq!(status.desk_rounding(), None);
+
+        for event in [
+            InputEvent::Digit(1),
+            InputEvent::Digit(2),
+            InputEvent::MemoryAdd,
+            InputEvent::Operation(Operation::Multiply),
+            InputEvent::Digit(3),
+        ] {
+            calc.handle_event(event);
+        }
+        let status = calc.status("Basic", RoundingSwitch::Float, DecimalPlaces::Two);
+        assert_eq!(status.pending.as_deref(), Some("12 ×"));
+        assert_eq!(status.memory, Some(12.0));
+
+        calc.handle_event(InputEvent::Equals);
+        assert_eq!(calc.status("Basic", RoundingSwitch::Float, DecimalPlaces::Two).pending, None);
+
+        calc.handle_event(InputEvent::Operation(Operation::Divide));
+        calc.handle_event(InputEvent::Digit(0));
+        calc.handle_event(InputEvent::Equals);
+        let status = calc.status("Basic", RoundingSwitch::Float, DecimalPlaces::Two);
+        assert!(status.error);
+        assert_eq!(status.pending, None);
+
+        calc.set_decimal_mode(true);
+        assert_eq!(calc.status("Expression", RoundingSwitch::Float, DecimalPlaces::Two).rounding, "DEC");
+    }
+
+    #[test]
+    fn test_desk_rounding() {
+        let calc = Calculator::new();
+        let desk = |switch, places| calc.status("Basic", switch, places).desk_rounding();
+        assert_eq!(desk(RoundingSwitch::Round, DecimalPlaces::Two).as_deref(), Some("5/4 2"));
+        assert_eq!(desk(RoundingSwitch::Cut, DecimalPlaces::Zero).as_deref(), Some("CUT 0"));
+        assert_eq!(desk(RoundingSwitch::Cut, DecimalPlaces::Add2).as_deref(), Some("CUT ADD2"));
+        assert_eq!(desk(RoundingSwitch::Float, DecimalPlaces::Add2).as_deref(), Some("ADD2"));
+        assert_eq!(desk(RoundingSwitch::Float, DecimalPlaces::Three), None);
+    }
+}
@@ -0,0 +1,177 @@
+// Symbol Catalog for Autocomplete
+use crate::constants::CONSTANTS;
+use crate::expression::{Functions, BUILTIN_CONSTANTS, BUILTIN_FUNCTIONS};
+use crate::matrix::MATRIX_FUNCTIONS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Constant,
+    Variable,
+}
+
+/// A name the evaluator understands, with what to show about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// How it's written, e.g. `min(x, y)`
+    pub signature: String,
+    pub doc: String,
+}
+
+fn signature(name: &str, arity: usize) -> String {
+    let params = ["x", "y", "z"];
+    let params: Vec<String> = (0..arity)
+        .map(|index| params.get(index).map_or_else(|| format!("x{}", index + 1), |param| param.to_string()))
+        .collect();
+    format!("{}({})", name, params.join(", "))
+}
+
+/// Everything an expression can refer to: the built-in functions and
+/// constants the evaluator uses, the physical constants, the user's script
+/// functions and the given variables.
+pub fn catalog(functions: Option<&dyn Functions>, variables: &[(&str, f64)]) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    for &(name, arity, doc) in BUILTIN_FUNCTIONS.iter().chain(MATRIX_FUNCTIONS) {
+        symbols.push(Symbol {
+            name: name.to_string(),
+            kind: SymbolKind::Function,
+            signature: signature(name, arity),
+            doc: doc.to_string(),
+        });
+    }
+    for (name, arity) in functions.map(|functions| functions.signatures()).unwrap_or_default() {
+        symbols.push(Symbol {
+            signature: signature(&name, arity),
+            name,
+            kind: SymbolKind::Function,
+            doc: String::from("User script function"),
+        });
+    }
+    for &(name, value, doc) in BUILTIN_CONSTANTS {
+        symbols.push(Symbol {
+            name: name.to_string(),
+            kind: SymbolKind::Constant,
+            signature: name.to_string(),
+            doc: format!("{} = {}", doc, value),
+        });
+    }
+    for constant in CONSTANTS {
+        symbols.push(Symbol {
+            name: constant.name.to_string(),
+            kind: SymbolKind::Constant,
+            signature: constant.name.to_string(),
+            doc: format!("{} = {:e} {}", constant.description, constant.value, constant.unit),
+        });
+    }
+    for &(name, value) in variables {
+        symbols.push(Symbol {
+            name: name.to_string(),
+            kind: SymbolKind::Variable,
+            signature: name.to_string(),
+            doc: format!("= {}", value),
+        });
+    }
+    symbols
+}
+
+/// Symbols starting with `prefix`, ignoring case. Ones matching the case as
+/// typed come first, then shorter names, then alphabetical.
+pub fn completions<'a>(catalog: &'a [Symbol], prefix: &str) -> Vec<&'a Symbol> {
+    let lower = prefix.to_lowercase();
+    let mut matches: Vec<&Symbol> = catalog
+        .iter()
+        .filter(|symbol| symbol.name.to_lowercase().starts_with(&lower))
+        .collect();
+    matches.sort_by_key(|symbol| (!symbol.name.starts_with(prefix), symbol.name.len(), symbol.name.clone()));
+    matches.dedup_by(|a, b| a.name == b.name);
+    matches
+}
+
+/// The byte range of the name being typed just before the caret (a character
+/// index). A run starting with a digit is a number, maybe with an SI prefix.
+pub fn word_before(text: &str, caret: usize) -> Option<(usize, usize)> {
+    let end = text.char_indices().nth(caret).map_or(text.len(), |(index, _)| index);
+    let start = text[..end]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, ch)| ch.is_alphanumeric() || ch == '_')
+        .last()
+        .map(|(index, _)| index)?;
+
+    let first = text[start..].chars().next()?;
+    (first.is_alphabetic() || first == '_').then_some((start, end))
+}
+
+/// Replaces the name before the caret with `symbol`, opening the argument
+/// list of a function. Returns the new text and caret (a character index).
+pub fn complete(text: &str, caret: usize, symbol: &Symbol) -> (String, usize) {
+    let (start, end) = word_before(text, caret).unwrap_or_else(|| {
+        let at = text.char_indices().nth(caret).map_or(text.len(), |(index, _)| index);
+        (at, at)
+    });
+    let mut inserted = symbol.name.clone();
+    if symbol.kind == SymbolKind::Function && !text[end..].starts_with('(') {
+        inserted.push('(');
+    }
+
+    let completed = format!("{}{}{}", &text[..start], inserted, &text[end..]);
+    let caret = text[..start].chars().count() + inserted.chars().count();
+    (completed, caret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::{parse, Environment};
+
+    fn names(symbols: Vec<&Symbol>) -> Vec<&str> {
+        symbols.iter().map(|symbol| symbol.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_completions() {
+        let catalog = catalog(None, &[("ans", 42.0)]);
+        assert_eq!(names(completions(&catalog, "si")), ["sin", "sigma"]);
+        assert_eq!(names(completions(&catalog, "m_")), ["m_e", "m_p", "m_u"]);
+        assert_eq!(names(completions(&catalog, "AN")), ["ans"]);
+        assert!(completions(&catalog, "zzz").is_empty());
+
+        let min = catalog.iter().find(|symbol| symbol.name == "min").unwrap();
+        assert_eq!(min.signature, "min(x, y)");
+    }
+
+    #[test]
+    fn test_catalog_matches_evaluator() {
+        // Every catalog entry is something the evaluator resolves
+        let mut env = Environment::new();
+        env.variables.insert(String::from("ans"), 1.0);
+        for symbol in catalog(None, &[("ans", 1.0)]) {
+            let source = match symbol.kind {
+                SymbolKind::Function => {
+                    let arity = symbol.signature.matches(',').count() + 1;
+                    format!("{}({})", symbol.name, vec!["0.5"; arity].join(", "))
+                }
+                _ => symbol.name.clone(),
+            };
+            assert!(parse(&source).unwrap().eval(&env).is_ok(), "{} is not evaluable", source);
+        }
+    }
+
+    #[test]
+    fn test_word_and_complete() {
+        assert_eq!(word_before("2 * sq", 6), Some((4, 6)));
+        assert_eq!(word_before("2 * sq", 5), Some((4, 5)));
+        assert_eq!(word_before("4.7k", 4), None);
+        assert_eq!(word_before("2 + ", 4), None);
+        assert_eq!(word_before("x2", 2), Some((0, 2)));
+
+        let catalog = catalog(None, &[]);
+        let sqrt = catalog.iter().find(|symbol| symbol.name == "sqrt").unwrap();
+        assert_eq!(complete("2 * sq + 1", 6, sqrt), (String::from("2 * sqrt( + 1"), 9));
+        assert_eq!(complete("sq(2)", 2, sqrt), (String::from("sqrt(2)"), 4));
+        let pi = catalog.iter().find(|symbol| symbol.name == "pi").unwrap();
+        assert_eq!(complete("µ + P", 5, pi), (String::from("µ + pi"), 6));
+    }
+}
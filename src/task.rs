@@ -0,0 +1,128 @@
+// Background Tasks
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+/// Shared between a task and the UI: how far the work got, and whether the
+/// user asked it to stop.
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    done: Arc<AtomicUsize>,
+    total: Arc<AtomicUsize>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Progress {
+    pub fn set_total(&self, total: usize) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self) {
+        self.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Work should check this between steps and give up once it's set.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Steps done and the total, for a "123 / 5000" label.
+    pub fn counts(&self) -> (usize, usize) {
+        (self.done.load(Ordering::Relaxed), self.total.load(Ordering::Relaxed))
+    }
+}
+
+/// Work running on its own thread so the UI keeps drawing meanwhile.
+/// Dropping the task cancels it.
+pub struct Task<T> {
+    receiver: mpsc::Receiver<T>,
+    progress: Progress,
+}
+
+impl<T: Send + 'static> Task<T> {
+    /// Starts `work` on a new thread. `notify` runs there once the result is
+    /// ready, e.g. to wake the UI with a repaint.
+    pub fn spawn(
+        work: impl FnOnce(&Progress) -> T + Send + 'static,
+        notify: impl FnOnce() + Send + 'static,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let progress = Progress::default();
+        let shared = progress.clone();
+        thread::spawn(move || {
+            // The UI may have stopped waiting, in which case nobody needs the result
+            if sender.send(work(&shared)).is_ok() {
+                notify();
+            }
+        });
+        Self { receiver, progress }
+    }
+}
+
+impl<T> Task<T> {
+    /// The result, once the work has finished.
+    pub fn poll(&self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    pub fn cancel(&self) {
+        self.progress.cancel();
+    }
+}
+
+impl<T> Drop for Task<T> {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_result_and_progress() {
+        let (notified, woken) = mpsc::channel();
+        let task = Task::spawn(
+            |progress| {
+                progress.set_total(3);
+                (0..3).map(|step| {
+                    progress.advance();
+                    step * 2
+                }).sum::<i32>()
+            },
+            move || notified.send(()).unwrap(),
+        );
+        woken.recv().unwrap();
+        assert_eq!(task.poll(), Some(6));
+        assert_eq!(task.progress().counts(), (3, 3));
+        assert_eq!(task.poll(), None);
+    }
+
+    #[test]
+    fn test_cancel_stops_work() {
+        let (started, running) = mpsc::channel();
+        let task = Task::spawn(
+            move |progress| {
+                started.send(()).unwrap();
+                let mut steps = 0u64;
+                while !progress.is_cancelled() {
+                    steps += 1;
+                }
+                steps
+            },
+            || {},
+        );
+        running.recv().unwrap();
+        task.cancel();
+        assert!(task.receiver.recv().is_ok());
+    }
+}
@@ -0,0 +1,124 @@
+// Calculation Templates
+use serde::{Deserialize, Serialize};
+
+use crate::error::CalculatorError;
+use crate::expression::{self, Environment, Functions, ParseOptions};
+use crate::number;
+use crate::variables;
+
+/// A saved expression with named inputs, e.g. `price * (1 + vat)`, run later
+/// by giving each input a value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Template {
+    pub name: String,
+    pub source: String,
+    /// In order of first use in `source`
+    pub inputs: Vec<TemplateInput>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateInput {
+    /// The name used in the expression
+    pub name: String,
+    /// What the input's field is labeled with
+    pub label: String,
+}
+
+impl Template {
+    /// A template for `source`, with an input for every name in it that
+    /// isn't a constant. Labels start out as the names.
+    pub fn new(name: &str, source: &str, options: ParseOptions) -> Result<Self, CalculatorError> {
+        let expr = expression::parse_with(source, options)?;
+        let env = Environment::new();
+        let mut inputs: Vec<TemplateInput> = Vec::new();
+        for used in expr.variables() {
+            let known = env.lookup(used).is_ok() || !variables::is_name(used);
+            if !known && !inputs.iter().any(|input| input.name == used) {
+                inputs.push(TemplateInput {
+                    name: used.to_string(),
+                    label: used.to_string(),
+                });
+            }
+        }
+        Ok(Self {
+            name: name.to_string(),
+            source: source.trim().to_string(),
+            inputs,
+        })
+    }
+
+    /// Keeps the labels of inputs `previous` also had, for when the
+    /// expression of a template being written changes.
+    pub fn keep_labels(&mut self, previous: &Template) {
+        for input in &mut self.inputs {
+            if let Some(old) = previous.inputs.iter().find(|old| old.name == input.name) {
+                input.label = old.label.clone();
+            }
+        }
+    }
+
+    /// The result with `values` given to the inputs, in order.
+    pub fn run(
+        &self,
+        values: &[f64],
+        functions: Option<&dyn Functions>,
+        options: ParseOptions,
+    ) -> Result<f64, CalculatorError> {
+        let expr = expression::parse_with(&self.source, options)?;
+        let mut env = Environment::new();
+        env.functions = functions;
+        for (input, &value) in self.inputs.iter().zip(values) {
+            env.variables.insert(input.name.clone(), value);
+        }
+        number::in_range(expr.eval(&env)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn names(template: &Template) -> Vec<&str> {
+        template.inputs.iter().map(|input| input.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_inputs_and_run() {
+        let options = ParseOptions::default();
+        let template = Template::new("Gross", " price * (1 + vat) + round(price / pi) ", options).unwrap();
+        assert_eq!(template.source, "price * (1 + vat) + round(price / pi)");
+        assert_eq!(names(&template), ["price", "vat"]);
+        assert_eq!(template.run(&[100.0, 0.2], None, options), Ok(152.0));
+        assert_eq!(template.run(&[0.0, 0.0], None, options), Ok(0.0));
+        assert!(template.run(&[100.0], None, options).is_err());
+
+        assert!(Template::new("Broken", "price *", options).is_err());
+        assert_eq!(Template::new("Fixed", "2 * pi", options).unwrap().inputs, []);
+    }
+
+    #[test]
+    fn test_labels_kept_while_editing() {
+        let options = ParseOptions::default();
+        let mut before = Template::new("Volume", "width * height", options).unwrap();
+        before.inputs[0].label = String::from("Width (m)");
+        let mut after = Template::new("Volume", "width * height * depth", options).unwrap();
+        after.keep_labels(&before);
+        let labels: Vec<&str> = after.inputs.iter().map(|input| input.label.as_str()).collect();
+        assert_eq!(labels, ["Width (m)", "height", "depth"]);
+    }
+
+    // Feature: gui-calculator, Property 45: Running a template matches evaluating it with the values written in
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_run_matches_substitution(a in -1000.0..1000.0f64, b in -1000.0..1000.0f64) {
+            let options = ParseOptions::default();
+            let template = Template::new("T", "a * 3 - b / 4 + a", options).unwrap();
+            let substituted = format!("({a}) * 3 - ({b}) / 4 + ({a})");
+            let expected = expression::parse_with(&substituted, options).unwrap().eval(&Environment::new());
+            prop_assert_eq!(template.run(&[a, b], None, options), expected);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+// Property Test Utilities
+//
+// Input strategies and an exact reference model for keypad calculations,
+// shared by the property tests of every mode instead of each writing its own.
+use proptest::prelude::*;
+
+use crate::input::InputEvent;
+use crate::operation::Operation;
+use crate::rational::Rational;
+
+pub fn operation() -> impl Strategy<Value = Operation> {
+    prop::sample::select(Operation::ALL.to_vec())
+}
+
+/// Any single digit, point, operation or `=`, whether or not it makes
+/// sense where it lands.
+pub fn input_event() -> impl Strategy<Value = InputEvent> {
+    prop_oneof![
+        (0u8..=9).prop_map(InputEvent::Digit),
+        Just(InputEvent::DecimalPoint),
+        operation().prop_map(InputEvent::Operation),
+        Just(InputEvent::Equals),
+    ]
+}
+
+/// Like `input_event`, plus the keys of `OperationRegistry::basic` and
+/// rounding. Positions run one past the registry's keys.
+pub fn keypad_event() -> impl Strategy<Value = InputEvent> {
+    prop_oneof![
+        3 => input_event(),
+        1 => (0usize..=3).prop_map(InputEvent::Unary),
+        1 => (0usize..=1).prop_map(InputEvent::Constant),
+        1 => (0u32..=3).prop_map(InputEvent::Round),
+    ]
+}
+
+/// A number as typed: one to four digits, then maybe a point and up to two
+/// more digits.
+pub fn typed_number() -> impl Strategy<Value = Vec<InputEvent>> {
+    (
+        prop::collection::vec(0u8..=9, 1..=4),
+        prop::option::of(prop::collection::vec(0u8..=9, 0..=2)),
+    )
+        .prop_map(|(integer, fraction)| {
+            let mut events: Vec<InputEvent> = integer.into_iter().map(InputEvent::Digit).collect();
+            if let Some(fraction) = fraction {
+                events.push(InputEvent::DecimalPoint);
+                events.extend(fraction.into_iter().map(InputEvent::Digit));
+            }
+            events
+        })
+}
+
+/// A complete calculation `a op b op c ... =` as keypad events.
+pub fn calculation() -> impl Strategy<Value = Vec<InputEvent>> {
+    (typed_number(), prop::collection::vec((operation(), typed_number()), 1..=4)).prop_map(|(first, rest)| {
+        let mut events = first;
+        for (op, number) in rest {
+            events.push(InputEvent::Operation(op));
+            events.extend(number);
+        }
+        events.push(InputEvent::Equals);
+        events
+    })
+}
+
+/// What the keypad should show after `events`, worked out exactly: operations
+/// apply left to right as they are entered, with no precedence. `None` if a
+/// division by zero (or an exact value too large to track) is reached.
+///
+/// Covers digits, the point, operations and `=`, as generated by
+/// `calculation`, starting from a cleared calculator.
+pub fn reference_result(events: &[InputEvent]) -> Option<Rational> {
+    let mut shown = Rational::new(0, 1)?;
+    // The characters of the number being typed, if one is
+    let mut typed: Option<String> = None;
+    let mut stored: Option<Rational> = None;
+    let mut pending: Option<Operation> = None;
+
+    for &event in events {
+        match event {
+            InputEvent::Digit(digit) => {
+                let text = typed.get_or_insert_with(String::new);
+                text.push(char::from(b'0' + digit));
+                shown = Rational::parse_decimal(text)?;
+            }
+            InputEvent::DecimalPoint => {
+                let text = typed.get_or_insert_with(|| String::from("0"));
+                if !text.contains('.') {
+                    text.push('.');
+                }
+                shown = Rational::parse_decimal(text)?;
+            }
+            InputEvent::Operation(op) => {
+                // Chains only when a second operand was typed
+                if let (Some(left), Some(previous), Some(_)) = (stored, pending, &typed) {
+                    shown = left.apply(previous.into(), shown)?;
+                }
+                stored = Some(shown);
+                pending = Some(op);
+                typed = None;
+            }
+            InputEvent::Equals => {
+                if let (Some(left), Some(op)) = (stored, pending) {
+                    shown = left.apply(op.into(), shown)?;
+                    stored = Some(shown);
+                    pending = None;
+                    typed = None;
+                }
+            }
+            other => unreachable!("the reference model doesn't cover {:?}", other),
+        }
+    }
+    Some(shown)
+}
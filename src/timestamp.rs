@@ -0,0 +1,137 @@
+// Unix Timestamps
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::error::CalculatorError;
+
+// Magnitudes from here on are taken as milliseconds: 1e11 seconds is past
+// the year 5000, while 1e11 milliseconds is in 1973
+const MILLISECONDS_FROM: i64 = 100_000_000_000;
+
+/// The unit a timestamp counts in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    Seconds,
+    Milliseconds,
+}
+
+impl Precision {
+    /// Guesses the unit from the size, as a current date is about 1.7e9 in
+    /// seconds but 1.7e12 in milliseconds.
+    pub fn detect(timestamp: i64) -> Self {
+        if timestamp.unsigned_abs() >= MILLISECONDS_FROM as u64 {
+            Precision::Milliseconds
+        } else {
+            Precision::Seconds
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Precision::Seconds => "seconds",
+            Precision::Milliseconds => "milliseconds",
+        }
+    }
+}
+
+/// The instant `timestamp` stands for, counted in `precision` since
+/// 1970-01-01 00:00 UTC; `None` if it's outside the supported years.
+pub fn to_datetime(timestamp: i64, precision: Precision) -> Option<DateTime<Utc>> {
+    match precision {
+        Precision::Seconds => DateTime::from_timestamp(timestamp, 0),
+        Precision::Milliseconds => DateTime::from_timestamp_millis(timestamp),
+    }
+}
+
+/// E.g. `2023-11-14 22:13:20 UTC`, with milliseconds only when there are some.
+pub fn format_utc(datetime: DateTime<Utc>) -> String {
+    format!("{} UTC", datetime.format(time_format(datetime)))
+}
+
+/// The same instant on this computer's clock, with its UTC offset.
+pub fn format_local(datetime: DateTime<Utc>) -> String {
+    let local = datetime.with_timezone(&Local);
+    format!("{} {}", local.format(time_format(datetime)), local.format("%:z"))
+}
+
+fn time_format(datetime: DateTime<Utc>) -> &'static str {
+    if datetime.timestamp_subsec_millis() == 0 {
+        "%Y-%m-%d %H:%M:%S"
+    } else {
+        "%Y-%m-%d %H:%M:%S%.3f"
+    }
+}
+
+/// Reads a date and time: RFC 3339 with an offset (`2023-11-14T22:13:20+01:00`),
+/// or `2023-11-14 22:13[:20[.5]]` or a bare date, which are taken as UTC or
+/// local time as `local` says.
+pub fn parse(text: &str, local: bool) -> Result<DateTime<Utc>, CalculatorError> {
+    let text = text.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+
+    let naive = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(|| CalculatorError::Syntax {
+            message: String::from("Expected a date like 2023-11-14 22:13:20"),
+            column: 1,
+        })?;
+    if !local {
+        return Ok(naive.and_utc());
+    }
+    // A time skipped by a daylight saving change has no local reading
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .ok_or_else(|| CalculatorError::Domain(String::from("local time")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_detect_and_format() {
+        assert_eq!(Precision::detect(1_700_000_000), Precision::Seconds);
+        assert_eq!(Precision::detect(1_700_000_000_123), Precision::Milliseconds);
+        assert_eq!(Precision::detect(-86_400), Precision::Seconds);
+
+        let seconds = to_datetime(1_700_000_000, Precision::Seconds).unwrap();
+        assert_eq!(format_utc(seconds), "2023-11-14 22:13:20 UTC");
+        let millis = to_datetime(1_700_000_000_123, Precision::Milliseconds).unwrap();
+        assert_eq!(format_utc(millis), "2023-11-14 22:13:20.123 UTC");
+        assert_eq!(format_utc(to_datetime(-86_400, Precision::Seconds).unwrap()), "1969-12-31 00:00:00 UTC");
+    }
+
+    #[test]
+    fn test_parse() {
+        let expected = to_datetime(1_700_000_000, Precision::Seconds).unwrap();
+        assert_eq!(parse("2023-11-14 22:13:20", false), Ok(expected));
+        assert_eq!(parse("2023-11-14T22:13:20Z", true), Ok(expected));
+        assert_eq!(parse("2023-11-14T23:13:20+01:00", false), Ok(expected));
+        assert_eq!(parse("2023-11-14 22:13", false).map(|datetime| datetime.timestamp()), Ok(1_699_999_980));
+        assert_eq!(parse("1970-01-02", false).map(|datetime| datetime.timestamp()), Ok(86_400));
+        assert!(matches!(parse("yesterday", false), Err(CalculatorError::Syntax { .. })));
+    }
+
+    // Feature: gui-calculator, Property 35: Timestamps survive formatting and parsing
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_timestamp_round_trip(millis in -2_000_000_000_000i64..10_000_000_000_000) {
+            let datetime = to_datetime(millis, Precision::Milliseconds).unwrap();
+            let text = format_utc(datetime);
+            let parsed = parse(text.trim_end_matches(" UTC"), false).unwrap();
+            prop_assert_eq!(parsed.timestamp_millis(), millis, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// Tip and Bill Splitting
+use crate::error::CalculatorError;
+
+/// How a bill works out once the tip is added and it is split.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TipSplit {
+    pub tip: f64,
+    pub total: f64,
+    pub per_person: f64,
+}
+
+/// Adds `tip_percent` to `bill` and splits the total between `people`.
+/// With `round_up` every share is rounded up to a whole amount and the
+/// extra goes to the tip.
+pub fn split(bill: f64, tip_percent: f64, people: u32, round_up: bool) -> Result<TipSplit, CalculatorError> {
+    if people == 0 {
+        return Err(CalculatorError::Domain(String::from("people")));
+    }
+    if bill < 0.0 {
+        return Err(CalculatorError::Domain(String::from("bill")));
+    }
+    if tip_percent < 0.0 {
+        return Err(CalculatorError::Domain(String::from("tip")));
+    }
+
+    let mut total = bill + bill * tip_percent / 100.0;
+    let mut per_person = total / people as f64;
+    if round_up {
+        // Round to cents first so 30.000000001 doesn't become 31
+        per_person = ((per_person * 100.0).round() / 100.0).ceil();
+        total = per_person * people as f64;
+    }
+
+    Ok(TipSplit {
+        tip: total - bill,
+        total,
+        per_person,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_split() {
+        assert_eq!(
+            split(100.0, 15.0, 4, false),
+            Ok(TipSplit { tip: 15.0, total: 115.0, per_person: 28.75 })
+        );
+        assert_eq!(
+            split(100.0, 15.0, 4, true),
+            Ok(TipSplit { tip: 16.0, total: 116.0, per_person: 29.0 })
+        );
+        assert_eq!(split(90.0, 0.0, 3, true).unwrap().per_person, 30.0);
+        assert_eq!(split(10.0, 10.0, 0, false), Err(CalculatorError::Domain(String::from("people"))));
+        assert!(split(-10.0, 10.0, 2, false).is_err());
+    }
+
+    // Feature: gui-calculator, Property 21: Rounded shares cover the bill by less than one unit each
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_round_up_covers_total(
+            bill in 0.0..10000.0f64,
+            tip_percent in 0.0..50.0f64,
+            people in 1u32..=20
+        ) {
+            let exact = split(bill, tip_percent, people, false).unwrap();
+            let rounded = split(bill, tip_percent, people, true).unwrap();
+
+            prop_assert!((exact.per_person * people as f64 - exact.total).abs() < 1e-6);
+            prop_assert_eq!(rounded.per_person, rounded.per_person.trunc());
+            prop_assert!(rounded.per_person >= exact.per_person - 0.005);
+            prop_assert!(rounded.per_person < exact.per_person + 1.0);
+            prop_assert!((rounded.tip - (rounded.total - bill)).abs() < 1e-9);
+        }
+    }
+}
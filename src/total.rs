@@ -0,0 +1,126 @@
+// Running Total
+//
+// The adding machine's grand total: every result goes into a second
+// accumulator shown under the display, cleared with its own key. A result
+// can be left out of the total, and put back, without losing it.
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::decimal;
+
+/// A result that went into the total.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TotalEntry {
+    /// The history entry it came from
+    pub id: u64,
+    pub value: f64,
+    pub included: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunningTotal {
+    entries: Vec<TotalEntry>,
+}
+
+impl RunningTotal {
+    /// Adds the result of history entry `id`. Infinities and NaN can't be
+    /// totalled and are skipped.
+    pub fn add(&mut self, id: u64, value: f64) {
+        if value.is_finite() {
+            self.entries.push(TotalEntry { id, value, included: true });
+        }
+    }
+
+    /// Puts the result from history entry `id` back into the total or
+    /// leaves it out.
+    pub fn set_included(&mut self, id: u64, included: bool) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == id) {
+            entry.included = included;
+        }
+    }
+
+    pub fn entries(&self) -> &[TotalEntry] {
+        &self.entries
+    }
+
+    /// The sum of the included results, added in decimal so 0.1 + 0.2 makes
+    /// 0.3 as on a paper tape. Falls back to floating point for results too
+    /// large for a decimal.
+    pub fn total(&self) -> f64 {
+        let included = || self.entries.iter().filter(|entry| entry.included).map(|entry| entry.value);
+        included()
+            .try_fold(Decimal::ZERO, |sum, value| sum.checked_add(decimal::to_decimal(value)?))
+            .and_then(decimal::to_float)
+            .unwrap_or_else(|| included().sum())
+    }
+
+    /// How many results are in the total.
+    pub fn count(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.included).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Clears the total (CGT).
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_running_total() {
+        let mut total = RunningTotal::default();
+        assert_eq!(total.total(), 0.0);
+        total.add(1, 0.1);
+        total.add(2, 0.2);
+        total.add(3, f64::INFINITY);
+        total.add(4, 12.5);
+        assert_eq!(total.entries().len(), 3);
+        assert_eq!(total.total(), 12.8);
+        assert_eq!(total.count(), 3);
+
+        total.set_included(4, false);
+        assert_eq!(total.total(), 0.3);
+        assert_eq!(total.count(), 2);
+        total.set_included(4, true);
+        total.set_included(9, false);
+        assert_eq!(total.total(), 12.8);
+
+        // Beyond what a decimal holds, the sum is in floating point
+        total.add(5, 1e300);
+        assert_eq!(total.total(), 1e300);
+
+        total.clear();
+        assert!(total.is_empty());
+        assert_eq!(total.total(), 0.0);
+    }
+
+    // Feature: gui-calculator, Property 63: Leaving results out of the total is the same as never adding them
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_excluded_results_dont_count(
+            values in prop::collection::vec((-1e6..1e6f64, any::<bool>()), 0..20)
+        ) {
+            let mut all = RunningTotal::default();
+            let mut kept = RunningTotal::default();
+            for (id, &(value, included)) in values.iter().enumerate() {
+                all.add(id as u64, value);
+                all.set_included(id as u64, included);
+                if included {
+                    kept.add(id as u64, value);
+                }
+            }
+            prop_assert_eq!(all.total(), kept.total());
+            prop_assert_eq!(all.count(), kept.count());
+        }
+    }
+}
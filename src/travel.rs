@@ -0,0 +1,244 @@
+// Fuel Economy, Speed and Pace
+//
+// Litres per 100 km and minutes per km grow as mpg and km/h shrink, so these
+// convert through a reciprocal rather than a plain factor.
+use crate::error::CalculatorError;
+use crate::ieee754;
+
+const KILOMETERS_PER_MILE: f64 = 1.609344;
+const LITERS_PER_US_GALLON: f64 = 3.785411784;
+const LITERS_PER_IMPERIAL_GALLON: f64 = 4.54609;
+const METERS_PER_NAUTICAL_MILE: f64 = 1852.0;
+const SIGNIFICANT_DIGITS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Economy {
+    #[default]
+    MilesPerUsGallon,
+    MilesPerImperialGallon,
+    KilometersPerLiter,
+    LitersPer100Km,
+}
+
+impl Economy {
+    pub const ALL: [Economy; 4] = [
+        Economy::MilesPerUsGallon,
+        Economy::MilesPerImperialGallon,
+        Economy::KilometersPerLiter,
+        Economy::LitersPer100Km,
+    ];
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Economy::MilesPerUsGallon => "mpg (US)",
+            Economy::MilesPerImperialGallon => "mpg (UK)",
+            Economy::KilometersPerLiter => "km/L",
+            Economy::LitersPer100Km => "L/100 km",
+        }
+    }
+
+    fn to_kilometers_per_liter(self, value: f64) -> f64 {
+        match self {
+            Economy::MilesPerUsGallon => value * KILOMETERS_PER_MILE / LITERS_PER_US_GALLON,
+            Economy::MilesPerImperialGallon => value * KILOMETERS_PER_MILE / LITERS_PER_IMPERIAL_GALLON,
+            Economy::KilometersPerLiter => value,
+            Economy::LitersPer100Km => 100.0 / value,
+        }
+    }
+
+    fn of_kilometers_per_liter(self, value: f64) -> f64 {
+        match self {
+            Economy::MilesPerUsGallon => value * LITERS_PER_US_GALLON / KILOMETERS_PER_MILE,
+            Economy::MilesPerImperialGallon => value * LITERS_PER_IMPERIAL_GALLON / KILOMETERS_PER_MILE,
+            Economy::KilometersPerLiter => value,
+            Economy::LitersPer100Km => 100.0 / value,
+        }
+    }
+}
+
+/// A speed, or a pace: the time a distance takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Speed {
+    #[default]
+    KilometersPerHour,
+    MilesPerHour,
+    MetersPerSecond,
+    Knots,
+    /// Minutes per kilometre
+    PacePerKilometer,
+    /// Minutes per mile
+    PacePerMile,
+}
+
+impl Speed {
+    pub const ALL: [Speed; 6] = [
+        Speed::KilometersPerHour,
+        Speed::MilesPerHour,
+        Speed::MetersPerSecond,
+        Speed::Knots,
+        Speed::PacePerKilometer,
+        Speed::PacePerMile,
+    ];
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Speed::KilometersPerHour => "km/h",
+            Speed::MilesPerHour => "mph",
+            Speed::MetersPerSecond => "m/s",
+            Speed::Knots => "kn",
+            Speed::PacePerKilometer => "min/km",
+            Speed::PacePerMile => "min/mi",
+        }
+    }
+
+    /// Paces are written as minutes and seconds, e.g. 5:30.
+    pub fn is_pace(self) -> bool {
+        matches!(self, Speed::PacePerKilometer | Speed::PacePerMile)
+    }
+
+    fn to_meters_per_second(self, value: f64) -> f64 {
+        match self {
+            Speed::KilometersPerHour => value / 3.6,
+            Speed::MilesPerHour => value * KILOMETERS_PER_MILE / 3.6,
+            Speed::MetersPerSecond => value,
+            Speed::Knots => value * METERS_PER_NAUTICAL_MILE / 3600.0,
+            Speed::PacePerKilometer => 1000.0 / (value * 60.0),
+            Speed::PacePerMile => KILOMETERS_PER_MILE * 1000.0 / (value * 60.0),
+        }
+    }
+
+    fn of_meters_per_second(self, value: f64) -> f64 {
+        match self {
+            Speed::KilometersPerHour => value * 3.6,
+            Speed::MilesPerHour => value * 3.6 / KILOMETERS_PER_MILE,
+            Speed::MetersPerSecond => value,
+            Speed::Knots => value * 3600.0 / METERS_PER_NAUTICAL_MILE,
+            Speed::PacePerKilometer => 1000.0 / (value * 60.0),
+            Speed::PacePerMile => KILOMETERS_PER_MILE * 1000.0 / (value * 60.0),
+        }
+    }
+}
+
+// Zero has no reciprocal, and a negative speed or consumption means nothing
+fn positive(value: f64, name: &str) -> Result<f64, CalculatorError> {
+    if value > 0.0 && value.is_finite() {
+        Ok(value)
+    } else {
+        Err(CalculatorError::Domain(name.to_string()))
+    }
+}
+
+pub fn convert_economy(value: f64, from: Economy, to: Economy) -> Result<f64, CalculatorError> {
+    let value = positive(value, "fuel economy")?;
+    let converted = to.of_kilometers_per_liter(from.to_kilometers_per_liter(value));
+    Ok(ieee754::round_to_significant(converted, SIGNIFICANT_DIGITS))
+}
+
+pub fn convert_speed(value: f64, from: Speed, to: Speed) -> Result<f64, CalculatorError> {
+    let value = positive(value, "speed")?;
+    let converted = to.of_meters_per_second(from.to_meters_per_second(value));
+    Ok(ieee754::round_to_significant(converted, SIGNIFICANT_DIGITS))
+}
+
+/// Reads a value in `unit`: a number, or for a pace also `5:30` for five
+/// and a half minutes.
+pub fn parse_speed(text: &str, unit: Speed) -> Result<f64, CalculatorError> {
+    let invalid = || CalculatorError::Syntax {
+        message: String::from("Expected a number or minutes:seconds"),
+        column: 1,
+    };
+    let text = text.trim();
+    match text.split_once(':') {
+        Some((minutes, seconds)) if unit.is_pace() => {
+            let minutes: u32 = minutes.trim().parse().map_err(|_| invalid())?;
+            let seconds: f64 = seconds.trim().parse().map_err(|_| invalid())?;
+            if !(0.0..60.0).contains(&seconds) {
+                return Err(invalid());
+            }
+            Ok(f64::from(minutes) + seconds / 60.0)
+        }
+        _ => text.parse().map_err(|_| invalid()),
+    }
+}
+
+/// A value in `unit`, paces as minutes and whole seconds.
+pub fn format_speed(value: f64, unit: Speed) -> String {
+    if !unit.is_pace() {
+        return ieee754::round_to_significant(value, 6).to_string();
+    }
+    // Rounds to the second before splitting, so 4.9999 min is 5:00 rather than 4:60
+    let seconds = (value * 60.0).round() as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn test_fuel_economy_is_inverse() {
+        let per_100 = |mpg| convert_economy(mpg, Economy::MilesPerUsGallon, Economy::LitersPer100Km).unwrap();
+        assert!(close(per_100(30.0), 7.84048611111));
+        // Doubling the mpg halves the consumption rather than doubling it
+        assert!(close(per_100(60.0), per_100(30.0) / 2.0));
+        assert!(close(
+            convert_economy(5.0, Economy::LitersPer100Km, Economy::KilometersPerLiter).unwrap(),
+            20.0
+        ));
+        assert!(close(
+            convert_economy(40.0, Economy::MilesPerImperialGallon, Economy::MilesPerUsGallon).unwrap(),
+            33.3069673852
+        ));
+        assert_eq!(
+            convert_economy(0.0, Economy::LitersPer100Km, Economy::MilesPerUsGallon),
+            Err(CalculatorError::Domain(String::from("fuel economy")))
+        );
+    }
+
+    #[test]
+    fn test_speed_and_pace() {
+        assert_eq!(convert_speed(5.0, Speed::PacePerKilometer, Speed::KilometersPerHour), Ok(12.0));
+        assert_eq!(convert_speed(10.0, Speed::KilometersPerHour, Speed::PacePerKilometer), Ok(6.0));
+        assert_eq!(convert_speed(36.0, Speed::KilometersPerHour, Speed::MetersPerSecond), Ok(10.0));
+        assert!(close(convert_speed(8.0, Speed::PacePerMile, Speed::PacePerKilometer).unwrap(), 4.9709695379));
+        assert_eq!(
+            convert_speed(-1.0, Speed::MilesPerHour, Speed::Knots),
+            Err(CalculatorError::Domain(String::from("speed")))
+        );
+
+        assert_eq!(parse_speed("5:30", Speed::PacePerKilometer), Ok(5.5));
+        assert_eq!(parse_speed("5.5", Speed::PacePerKilometer), Ok(5.5));
+        assert!(parse_speed("5:75", Speed::PacePerKilometer).is_err());
+        assert!(parse_speed("5:30", Speed::KilometersPerHour).is_err());
+        assert_eq!(format_speed(4.97097, Speed::PacePerKilometer), "4:58");
+        assert_eq!(format_speed(4.9999, Speed::PacePerKilometer), "5:00");
+        assert_eq!(format_speed(10.9361329834, Speed::MilesPerHour), "10.9361");
+    }
+
+    // Feature: gui-calculator, Property 38: Economy and speed conversions come back to where they started
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_round_trips(
+            value in 0.1f64..1000.0,
+            economy in 0..Economy::ALL.len(),
+            speed in 0..Speed::ALL.len()
+        ) {
+            let economy = Economy::ALL[economy];
+            let there = convert_economy(value, economy, Economy::LitersPer100Km).unwrap();
+            let back = convert_economy(there, Economy::LitersPer100Km, economy).unwrap();
+            prop_assert!(close(back, value), "{} {:?} → {} → {}", value, economy, there, back);
+
+            let speed = Speed::ALL[speed];
+            let there = convert_speed(value, speed, Speed::PacePerKilometer).unwrap();
+            let back = convert_speed(there, Speed::PacePerKilometer, speed).unwrap();
+            prop_assert!(close(back, value), "{} {:?} → {} → {}", value, speed, there, back);
+        }
+    }
+}
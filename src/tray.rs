@@ -0,0 +1,119 @@
+// System Tray Icon
+//
+// Built with the `tray` feature on Windows and macOS. Linux trays need a GTK
+// main loop next to winit's, so there `Tray::new` always returns `None` and
+// the app runs as usual.
+
+/// What the user picked from the tray menu. Only the native tray ever
+/// produces one.
+#[cfg_attr(not(all(feature = "tray", any(windows, target_os = "macos"))), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayAction {
+    ToggleWindow,
+    CopyLastResult,
+    Quit,
+}
+
+#[cfg(all(feature = "tray", any(windows, target_os = "macos")))]
+pub use native::Tray;
+
+#[cfg(all(feature = "tray", any(windows, target_os = "macos")))]
+mod native {
+    use std::sync::mpsc::{self, Receiver};
+
+    use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+    use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+
+    use super::TrayAction;
+
+    const ICON_SIZE: u32 = 32;
+
+    /// The tray icon and its menu. Dropping it removes the icon.
+    pub struct Tray {
+        _icon: TrayIcon,
+        toggle: MenuItem,
+        copy: MenuItem,
+        quit: MenuItem,
+        events: Receiver<MenuEvent>,
+    }
+
+    impl Tray {
+        /// Adds the icon, or `None` if the platform refuses. Menu clicks
+        /// repaint `ctx` so they're handled even while the window is hidden.
+        pub fn new(ctx: &egui::Context) -> Option<Self> {
+            let toggle = MenuItem::new("Show/Hide", true, None);
+            let copy = MenuItem::new("Copy last result", true, None);
+            let quit = MenuItem::new("Quit", true, None);
+            let menu = Menu::new();
+            menu.append_items(&[&toggle, &copy, &PredefinedMenuItem::separator(), &quit])
+                .ok()?;
+
+            let icon = TrayIconBuilder::new()
+                .with_menu(Box::new(menu))
+                .with_tooltip("Calculator")
+                .with_icon(icon()?)
+                .build()
+                .ok()?;
+
+            let (sender, events) = mpsc::channel();
+            let ctx = ctx.clone();
+            MenuEvent::set_event_handler(Some(move |event| {
+                let _ = sender.send(event);
+                ctx.request_repaint();
+            }));
+
+            Some(Self {
+                _icon: icon,
+                toggle,
+                copy,
+                quit,
+                events,
+            })
+        }
+
+        /// Menu picks since the last call.
+        pub fn poll(&self) -> Vec<TrayAction> {
+            self.events
+                .try_iter()
+                .filter_map(|event| {
+                    if event.id == *self.toggle.id() {
+                        Some(TrayAction::ToggleWindow)
+                    } else if event.id == *self.copy.id() {
+                        Some(TrayAction::CopyLastResult)
+                    } else if event.id == *self.quit.id() {
+                        Some(TrayAction::Quit)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        }
+    }
+
+    // A blue square with an equals sign
+    fn icon() -> Option<Icon> {
+        let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+        for y in 0..ICON_SIZE {
+            for x in 0..ICON_SIZE {
+                let bar = (8..24).contains(&x) && ((10..14).contains(&y) || (18..22).contains(&y));
+                rgba.extend_from_slice(if bar { &[255, 255, 255, 255] } else { &[40, 110, 200, 255] });
+            }
+        }
+        Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE).ok()
+    }
+}
+
+/// Stands in where there's no tray support, so the app needs no cfgs.
+#[cfg(not(all(feature = "tray", any(windows, target_os = "macos"))))]
+pub struct Tray;
+
+#[cfg(not(all(feature = "tray", any(windows, target_os = "macos"))))]
+impl Tray {
+    pub fn new(_ctx: &egui::Context) -> Option<Self> {
+        None
+    }
+
+    pub fn poll(&self) -> Vec<TrayAction> {
+        Vec::new()
+    }
+}
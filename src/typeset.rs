@@ -0,0 +1,379 @@
+// Typeset Expressions
+//
+// Lays out a parsed expression the way it would be written by hand: powers
+// as raised superscripts, square roots under a radical sign and division as
+// a fraction bar. The layout is a small tree of boxes built from the syntax
+// tree; drawing measures each box around a shared axis, the height of the
+// fraction bar, and stacks or raises the boxes relative to it.
+use egui::{Color32, FontId, Pos2, Stroke, Ui, Vec2};
+
+use crate::expression::{BinaryOp, Expr};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Layout {
+    Text(String),
+    Row(Vec<Layout>),
+    Fraction(Box<Layout>, Box<Layout>),
+    Power { base: Box<Layout>, exponent: Box<Layout> },
+    Radical(Box<Layout>),
+    Parens(Box<Layout>),
+}
+
+/// The layout of `expr`, with parentheses only where the grouping isn't
+/// already clear from a fraction bar, radical or exponent.
+pub fn layout(expr: &Expr) -> Layout {
+    match expr {
+        Expr::Number(value) => Layout::Text(number(*value)),
+        Expr::Variable(name) => Layout::Text(match name.as_str() {
+            "pi" => String::from("π"),
+            _ => name.clone(),
+        }),
+        Expr::Negate(operand) => Layout::Row(vec![
+            Layout::Text(String::from("−")),
+            child(operand, operand.precedence() < BinaryOp::Power.precedence()),
+        ]),
+        Expr::Binary { op: BinaryOp::Divide, left, right } => {
+            Layout::Fraction(Box::new(layout(left)), Box::new(layout(right)))
+        }
+        Expr::Binary { op: BinaryOp::Power, left, right } => Layout::Power {
+            base: Box::new(child(left, left.precedence() <= BinaryOp::Power.precedence() || is_fraction(left))),
+            exponent: Box::new(layout(right)),
+        },
+        Expr::Binary { op, left, right } => {
+            let precedence = op.precedence();
+            let symbol = match op {
+                BinaryOp::Add => " + ",
+                BinaryOp::Subtract => " − ",
+                BinaryOp::ElementMultiply => " ⊙ ",
+                BinaryOp::ElementDivide => " ⊘ ",
+                BinaryOp::ElementPower => " .^ ",
+                _ => " × ",
+            };
+            // A fraction groups its parts already
+            Layout::Row(vec![
+                child(left, left.precedence() < precedence && !is_fraction(left)),
+                Layout::Text(String::from(symbol)),
+                child(right, right.precedence() <= precedence && !is_fraction(right)),
+            ])
+        }
+        Expr::Call { name, args } if args.len() == 1 && name.eq_ignore_ascii_case("sqrt") => {
+            Layout::Radical(Box::new(layout(&args[0])))
+        }
+        Expr::Call { name, args } if args.len() == 1 && name.eq_ignore_ascii_case("abs") => Layout::Row(vec![
+            Layout::Text(String::from("|")),
+            layout(&args[0]),
+            Layout::Text(String::from("|")),
+        ]),
+        Expr::Call { name, args } => {
+            let mut list = Vec::new();
+            for (index, arg) in args.iter().enumerate() {
+                if index > 0 {
+                    list.push(Layout::Text(String::from(", ")));
+                }
+                list.push(layout(arg));
+            }
+            Layout::Row(vec![Layout::Text(name.clone()), Layout::Parens(Box::new(Layout::Row(list)))])
+        }
+        Expr::Matrix(_) => Layout::Text(expr.to_string()),
+    }
+}
+
+/// `left = right`, for an equation.
+pub fn layout_equation(left: &Expr, right: &Expr) -> Layout {
+    Layout::Row(vec![layout(left), Layout::Text(String::from(" = ")), layout(right)])
+}
+
+fn child(expr: &Expr, parenthesize: bool) -> Layout {
+    if parenthesize {
+        Layout::Parens(Box::new(layout(expr)))
+    } else {
+        layout(expr)
+    }
+}
+
+fn is_fraction(expr: &Expr) -> bool {
+    matches!(expr, Expr::Binary { op: BinaryOp::Divide, .. })
+}
+
+// A true minus sign rather than a hyphen
+fn number(value: f64) -> String {
+    if value < 0.0 {
+        format!("−{}", -value)
+    } else {
+        value.to_string()
+    }
+}
+
+// Exponents shrink to this much of the surrounding size, down to a minimum
+const SCRIPT_SCALE: f32 = 0.7;
+const MIN_SIZE: f32 = 9.0;
+
+// Width, and extent above and below the axis
+#[derive(Debug, Clone, Copy)]
+struct Extent {
+    width: f32,
+    above: f32,
+    below: f32,
+}
+
+impl Extent {
+    fn height(self) -> f32 {
+        self.above + self.below
+    }
+}
+
+impl Layout {
+    fn extent(&self, ui: &Ui, size: f32) -> Extent {
+        match self {
+            Layout::Text(text) => {
+                let galley = ui.painter().layout_no_wrap(text.clone(), FontId::proportional(size), Color32::WHITE);
+                let half = galley.size().y / 2.0;
+                Extent {
+                    width: galley.size().x,
+                    above: half,
+                    below: half,
+                }
+            }
+            Layout::Row(items) => items.iter().map(|item| item.extent(ui, size)).fold(
+                Extent {
+                    width: 0.0,
+                    above: 0.0,
+                    below: 0.0,
+                },
+                |row, item| Extent {
+                    width: row.width + item.width,
+                    above: row.above.max(item.above),
+                    below: row.below.max(item.below),
+                },
+            ),
+            Layout::Fraction(numerator, denominator) => {
+                let (top, bottom) = (numerator.extent(ui, size), denominator.extent(ui, size));
+                let gap = size * 0.15;
+                Extent {
+                    width: top.width.max(bottom.width) + size * 0.3,
+                    above: top.height() + gap,
+                    below: bottom.height() + gap,
+                }
+            }
+            Layout::Power { base, exponent } => {
+                let base = base.extent(ui, size);
+                let script = exponent.extent(ui, script_size(size));
+                // The exponent's bottom sits a little below the base's top
+                let raise = base.above * 0.5 + script.below;
+                Extent {
+                    width: base.width + script.width,
+                    above: base.above.max(raise + script.above),
+                    below: base.below,
+                }
+            }
+            Layout::Radical(radicand) => {
+                let inner = radicand.extent(ui, size);
+                Extent {
+                    width: inner.width + size * 0.7,
+                    above: inner.above + size * 0.2,
+                    below: inner.below,
+                }
+            }
+            Layout::Parens(inner) => {
+                let inner = inner.extent(ui, size);
+                Extent {
+                    width: inner.width + size * 0.7,
+                    above: inner.above,
+                    below: inner.below,
+                }
+            }
+        }
+    }
+
+    // Draws with the left end of the axis at `origin`
+    fn paint(&self, ui: &Ui, origin: Pos2, size: f32, color: Color32) {
+        let painter = ui.painter();
+        let stroke = Stroke::new((size / 14.0).max(1.0), color);
+        match self {
+            Layout::Text(text) => {
+                let galley = painter.layout_no_wrap(text.clone(), FontId::proportional(size), color);
+                let top = origin.y - galley.size().y / 2.0;
+                painter.galley(Pos2::new(origin.x, top), galley);
+            }
+            Layout::Row(items) => {
+                let mut x = origin.x;
+                for item in items {
+                    item.paint(ui, Pos2::new(x, origin.y), size, color);
+                    x += item.extent(ui, size).width;
+                }
+            }
+            Layout::Fraction(numerator, denominator) => {
+                let extent = self.extent(ui, size);
+                let (top, bottom) = (numerator.extent(ui, size), denominator.extent(ui, size));
+                let gap = size * 0.15;
+                let bar_margin = size * 0.1;
+                painter.line_segment(
+                    [
+                        Pos2::new(origin.x + bar_margin, origin.y),
+                        Pos2::new(origin.x + extent.width - bar_margin, origin.y),
+                    ],
+                    stroke,
+                );
+                let centered = |width: f32| origin.x + (extent.width - width) / 2.0;
+                numerator.paint(ui, Pos2::new(centered(top.width), origin.y - gap - top.below), size, color);
+                denominator.paint(ui, Pos2::new(centered(bottom.width), origin.y + gap + bottom.above), size, color);
+            }
+            Layout::Power { base, exponent } => {
+                let base_extent = base.extent(ui, size);
+                let script = exponent.extent(ui, script_size(size));
+                base.paint(ui, origin, size, color);
+                let raise = base_extent.above * 0.5 + script.below;
+                exponent.paint(
+                    ui,
+                    Pos2::new(origin.x + base_extent.width, origin.y - raise),
+                    script_size(size),
+                    color,
+                );
+            }
+            Layout::Radical(radicand) => {
+                let inner = radicand.extent(ui, size);
+                let sign = size * 0.6;
+                let top = origin.y - inner.above - size * 0.1;
+                let bottom = origin.y + inner.below;
+                // The tick, the long stroke up, then the bar over the radicand
+                let points = vec![
+                    Pos2::new(origin.x + size * 0.05, origin.y),
+                    Pos2::new(origin.x + sign * 0.35, bottom),
+                    Pos2::new(origin.x + sign, top),
+                    Pos2::new(origin.x + sign + inner.width + size * 0.1, top),
+                ];
+                painter.add(egui::Shape::line(points, stroke));
+                radicand.paint(ui, Pos2::new(origin.x + sign + size * 0.05, origin.y), size, color);
+            }
+            Layout::Parens(inner) => {
+                let extent = inner.extent(ui, size);
+                let width = size * 0.35;
+                let (top, bottom) = (origin.y - extent.above, origin.y + extent.below);
+                // Each parenthesis as a shallow curve the height of its contents
+                for (x, bulge) in [(origin.x + width * 0.75, -width * 0.5), (origin.x + width * 1.25 + extent.width, width * 0.5)] {
+                    let points: Vec<Pos2> = (0..=8)
+                        .map(|step| {
+                            let t = step as f32 / 8.0;
+                            let curve = 1.0 - (2.0 * t - 1.0).powi(2);
+                            Pos2::new(x + bulge * curve, top + (bottom - top) * t)
+                        })
+                        .collect();
+                    painter.add(egui::Shape::line(points, stroke));
+                }
+                inner.paint(ui, Pos2::new(origin.x + width, origin.y), size, color);
+            }
+        }
+    }
+
+    /// Draws the layout at `size` points, taking up only the space it needs.
+    pub fn show(&self, ui: &mut Ui, size: f32) -> egui::Response {
+        let extent = self.extent(ui, size);
+        let (rect, response) = ui.allocate_exact_size(Vec2::new(extent.width, extent.height()), egui::Sense::hover());
+        if ui.is_rect_visible(rect) {
+            let color = ui.visuals().text_color();
+            self.paint(ui, Pos2::new(rect.left(), rect.top() + extent.above), size, color);
+        }
+        response
+    }
+}
+
+fn script_size(size: f32) -> f32 {
+    (size * SCRIPT_SCALE).max(MIN_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::{self, ParseOptions};
+    use proptest::prelude::*;
+
+    fn text(text: &str) -> Layout {
+        Layout::Text(text.to_string())
+    }
+
+    fn parsed(source: &str) -> Layout {
+        layout(&expression::parse_with(source, ParseOptions::default()).unwrap())
+    }
+
+    #[test]
+    fn test_layouts() {
+        assert_eq!(
+            parsed("(1 + 2) / 3"),
+            Layout::Fraction(Box::new(Layout::Row(vec![text("1"), text(" + "), text("2")])), Box::new(text("3")))
+        );
+        assert_eq!(
+            parsed("sqrt(x^2)"),
+            Layout::Radical(Box::new(Layout::Power {
+                base: Box::new(text("x")),
+                exponent: Box::new(text("2")),
+            }))
+        );
+        // The exponent's parentheses aren't needed once it's raised
+        assert_eq!(
+            parsed("2^(n - 1)"),
+            Layout::Power {
+                base: Box::new(text("2")),
+                exponent: Box::new(Layout::Row(vec![text("n"), text(" − "), text("1")])),
+            }
+        );
+        assert_eq!(
+            parsed("(-2)^2"),
+            Layout::Power {
+                base: Box::new(Layout::Parens(Box::new(Layout::Row(vec![text("−"), text("2")])))),
+                exponent: Box::new(text("2")),
+            }
+        );
+        assert_eq!(
+            parsed("a / b * c"),
+            Layout::Row(vec![
+                Layout::Fraction(Box::new(text("a")), Box::new(text("b"))),
+                text(" × "),
+                text("c"),
+            ])
+        );
+        assert_eq!(
+            parsed("max(pi, 3)"),
+            Layout::Row(vec![
+                text("max"),
+                Layout::Parens(Box::new(Layout::Row(vec![text("π"), text(", "), text("3")]))),
+            ])
+        );
+    }
+
+    fn texts(layout: &Layout, out: &mut String) {
+        match layout {
+            Layout::Text(text) => out.push_str(text),
+            Layout::Row(items) => items.iter().for_each(|item| texts(item, out)),
+            Layout::Fraction(top, bottom) => {
+                texts(top, out);
+                texts(bottom, out);
+            }
+            Layout::Power { base, exponent } => {
+                texts(base, out);
+                texts(exponent, out);
+            }
+            Layout::Radical(inner) | Layout::Parens(inner) => texts(inner, out),
+        }
+    }
+
+    // Feature: gui-calculator, Property 47: Typesetting keeps every number and name of the expression
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_layout_keeps_operands(
+            operands in prop::collection::vec(prop_oneof![(0u32..1000).prop_map(|n| n.to_string()), "[a-d]"], 1..6),
+            operators in prop::collection::vec(prop::sample::select(vec!["+", "-", "*", "/", "^"]), 5)
+        ) {
+            let mut source = operands[0].clone();
+            for (operand, operator) in operands[1..].iter().zip(&operators) {
+                source.push_str(&format!(" {} {}", operator, operand));
+            }
+            let mut shown = String::new();
+            texts(&parsed(&source), &mut shown);
+            let expected: String = operands.concat();
+            let kept: String = shown.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+            prop_assert_eq!(kept, expected);
+        }
+    }
+}
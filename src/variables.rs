@@ -0,0 +1,257 @@
+// User Variables
+//
+// Values the user names in the variables panel for expressions to use, and a
+// record of which of them a result was computed from, so the result can be
+// recomputed as soon as one of those changes and not otherwise. The watch
+// list builds on that to keep expressions like `subtotal * 1.19` up to date.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::CalculatorError;
+use crate::expression::Expr;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Variables {
+    values: BTreeMap<String, f64>,
+}
+
+impl Variables {
+    /// The value of `name`, found as written or in lowercase like any other
+    /// name in an expression.
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.values
+            .get(name)
+            .or_else(|| self.values.get(&name.to_ascii_lowercase()))
+            .copied()
+    }
+
+    pub fn set(&mut self, name: &str, value: f64) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.values.remove(name);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.values.iter().map(|(name, &value)| (name.as_str(), value))
+    }
+
+    /// What `expressions` read from these variables right now.
+    pub fn dependencies<'e>(&self, expressions: impl IntoIterator<Item = &'e Expr>) -> Dependencies {
+        let mut read: Vec<(String, Option<f64>)> = Vec::new();
+        for expr in expressions {
+            for name in expr.variables() {
+                if !read.iter().any(|(seen, _)| seen == name) {
+                    read.push((name.to_string(), self.get(name)));
+                }
+            }
+        }
+        Dependencies { read }
+    }
+}
+
+/// Whether `name` can be given a value: letters, digits and `_`, not
+/// starting with a digit, and not `ans`.
+pub fn is_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !name.eq_ignore_ascii_case("ans")
+}
+
+/// The names an expression uses and the variable values it saw for them,
+/// `None` where there was no variable of that name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dependencies {
+    read: Vec<(String, Option<f64>)>,
+}
+
+impl Dependencies {
+    /// Every name used, in order of first appearance.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.read.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Whether a variable read has since changed, been defined or been
+    /// removed.
+    pub fn changed(&self, variables: &Variables) -> bool {
+        self.read.iter().any(|(name, value)| variables.get(name) != *value)
+    }
+}
+
+/// A watched expression with its result as of the last refresh.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Watch {
+    pub source: String,
+    // Only the expression is kept between runs
+    #[serde(skip)]
+    result: Option<Result<f64, CalculatorError>>,
+    #[serde(skip)]
+    dependencies: Dependencies,
+}
+
+impl Watch {
+    /// `None` until the first refresh.
+    pub fn result(&self) -> Option<&Result<f64, CalculatorError>> {
+        self.result.as_ref()
+    }
+}
+
+/// Expressions kept in view while variables are edited, each recomputed
+/// only when a variable it reads changes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WatchList {
+    watches: Vec<Watch>,
+}
+
+impl WatchList {
+    /// Watches `source` unless it's blank or already watched.
+    pub fn add(&mut self, source: &str) {
+        let source = source.trim();
+        if source.is_empty() || self.watches.iter().any(|watch| watch.source == source) {
+            return;
+        }
+        self.watches.push(Watch {
+            source: source.to_string(),
+            ..Watch::default()
+        });
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.watches.len() {
+            self.watches.remove(index);
+        }
+    }
+
+    pub fn watches(&self) -> &[Watch] {
+        &self.watches
+    }
+
+    /// Has every watch recomputed on the next refresh, for changes the
+    /// variables don't show, like reloaded functions.
+    pub fn invalidate(&mut self) {
+        for watch in &mut self.watches {
+            watch.result = None;
+        }
+    }
+
+    /// Recomputes the watches that are out of date, with `evaluate` giving a
+    /// result and what it read. Returns how many were recomputed.
+    pub fn refresh(
+        &mut self,
+        variables: &Variables,
+        mut evaluate: impl FnMut(&str) -> (Result<f64, CalculatorError>, Dependencies),
+    ) -> usize {
+        let mut recomputed = 0;
+        for watch in &mut self.watches {
+            if watch.result.is_some() && !watch.dependencies.changed(variables) {
+                continue;
+            }
+            let (result, dependencies) = evaluate(&watch.source);
+            watch.result = Some(result);
+            watch.dependencies = dependencies;
+            recomputed += 1;
+        }
+        recomputed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculator::Calculator;
+    use crate::expression::{self, ParseOptions};
+    use proptest::prelude::*;
+
+    fn parse(source: &str) -> Expr {
+        expression::parse_with(source, ParseOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let mut variables = Variables::default();
+        variables.set("price", 20.0);
+        let expr = parse("price * (1 + vat) + Price / pi");
+        let dependencies = variables.dependencies([&expr]);
+        assert_eq!(dependencies.names().collect::<Vec<_>>(), ["price", "vat", "Price", "pi"]);
+        assert!(!dependencies.changed(&variables));
+
+        variables.set("other", 1.0);
+        assert!(!dependencies.changed(&variables));
+        variables.set("vat", 0.2);
+        assert!(dependencies.changed(&variables));
+        let dependencies = variables.dependencies([&expr]);
+        variables.remove("price");
+        assert!(dependencies.changed(&variables));
+    }
+
+    #[test]
+    fn test_watch_list() {
+        let mut calculator = Calculator::new();
+        calculator.variables_mut().set("subtotal", 100.0);
+        let mut watches = WatchList::default();
+        watches.add(" subtotal * 1.19 ");
+        watches.add("subtotal * 1.19");
+        watches.add("sqrt(16)");
+        watches.add("  ");
+        watches.add("rate");
+        assert_eq!(watches.watches().len(), 3);
+        assert_eq!(watches.watches()[0].result(), None);
+
+        let refresh = |watches: &mut WatchList, calculator: &Calculator| {
+            watches.refresh(calculator.variables(), |source| {
+                (calculator.preview_expression(source, None), calculator.dependencies(source))
+            })
+        };
+        assert_eq!(refresh(&mut watches, &calculator), 3);
+        assert_eq!(watches.watches()[0].result(), Some(&Ok(119.0)));
+        assert!(matches!(watches.watches()[2].result(), Some(Err(_))));
+        assert_eq!(refresh(&mut watches, &calculator), 0);
+
+        // Only the watches reading a changed variable are recomputed
+        calculator.variables_mut().set("subtotal", 200.0);
+        assert_eq!(refresh(&mut watches, &calculator), 1);
+        assert_eq!(watches.watches()[0].result(), Some(&Ok(238.0)));
+        calculator.variables_mut().set("rate", 0.5);
+        assert_eq!(refresh(&mut watches, &calculator), 1);
+        assert_eq!(watches.watches()[2].result(), Some(&Ok(0.5)));
+
+        watches.invalidate();
+        assert_eq!(refresh(&mut watches, &calculator), 3);
+        watches.remove(1);
+        watches.remove(5);
+        assert_eq!(watches.watches().len(), 2);
+    }
+
+    #[test]
+    fn test_names() {
+        assert!(is_name("vat"));
+        assert!(is_name("_rate2"));
+        assert!(!is_name("2x"));
+        assert!(!is_name("a b"));
+        assert!(!is_name(""));
+        assert!(!is_name("Ans"));
+    }
+
+    // Feature: gui-calculator, Property 44: A result is out of date exactly when a variable it used changed
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_changed_only_for_used_variables(
+            used in prop::sample::subsequence(vec!["a", "b", "c", "d", "e"], 1..=5),
+            changed in prop::sample::select(vec!["a", "b", "c", "d", "e"]),
+            value in -1e6..1e6f64
+        ) {
+            let mut variables = Variables::default();
+            for name in ["a", "b", "c", "d", "e"] {
+                variables.set(name, 1.5);
+            }
+            let expr = parse(&used.join(" + "));
+            let dependencies = variables.dependencies([&expr]);
+            variables.set(changed, value);
+            prop_assert_eq!(dependencies.changed(&variables), used.contains(&changed) && value != 1.5);
+        }
+    }
+}
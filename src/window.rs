@@ -0,0 +1,84 @@
+// Extra Calculator Windows
+//
+// Each window opened with File → New Window has its own calculator, memory
+// and history, and follows the main window's settings and theme. Windows
+// are egui viewports; where the backend can only draw one, they show as
+// windows inside the main one instead.
+use crate::calculator::Calculator;
+use crate::input;
+use crate::keypad::Keypad;
+use crate::operation::OperationRegistry;
+use crate::settings::Settings;
+
+pub struct CalculatorWindow {
+    id: egui::ViewportId,
+    title: String,
+    calculator: Calculator,
+    keypad: Keypad,
+}
+
+impl CalculatorWindow {
+    /// The `number`th window opened this session, counting the main one as 1.
+    pub fn new(number: usize, operations: &OperationRegistry, settings: &Settings) -> Self {
+        Self {
+            id: egui::ViewportId::from_hash_of(("calculator_window", number)),
+            title: format!("Calculator {}", number),
+            calculator: Calculator::new(),
+            keypad: Keypad::new(operations, settings.keypad_layout()),
+        }
+    }
+
+    /// Draws the window for this frame. Returns `false` once it was closed.
+    pub fn show(&mut self, ctx: &egui::Context, operations: &OperationRegistry, settings: &Settings) -> bool {
+        settings.configure(&mut self.calculator);
+
+        let builder = egui::ViewportBuilder::default()
+            .with_title(&self.title)
+            .with_inner_size([320.0, 520.0]);
+        ctx.show_viewport_immediate(self.id, builder, |ctx, class| {
+            if class == egui::ViewportClass::Embedded {
+                let mut open = true;
+                egui::Window::new(&self.title)
+                    .id(egui::Id::new(self.id))
+                    .open(&mut open)
+                    .resizable(false)
+                    .show(ctx, |ui| self.ui(ui, operations, settings));
+                return open;
+            }
+            egui::CentralPanel::default().show(ctx, |ui| self.ui(ui, operations, settings));
+            !ctx.input(|i| i.viewport().close_requested())
+        })
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, operations: &OperationRegistry, settings: &Settings) {
+        let layout = settings.keypad_layout().arranged_for(ui.ctx().screen_rect().size());
+        self.keypad.set_layout(layout, operations);
+
+        // Keys typed while this window has focus
+        if !ui.ctx().wants_keyboard_input() {
+            let events: Vec<_> = ui.input(|i| i.events.iter().filter_map(input::from_keyboard).collect());
+            for event in events {
+                self.calculator.handle_event(event);
+            }
+        }
+
+        ui.vertical_centered(|ui| {
+            ui.group(|ui| {
+                ui.set_min_width(280.0);
+                egui::ScrollArea::horizontal()
+                    .max_width(280.0)
+                    .stick_to_right(true)
+                    .show(ui, |ui| {
+                        let shown = settings.display_text(&self.calculator, false);
+                        let text = egui::RichText::new(shown).size(28.0).monospace();
+                        ui.add(egui::Label::new(text).wrap(false));
+                    });
+            });
+            ui.add_space(12.0);
+            let has_memory = self.calculator.memory().is_some();
+            if let Some(event) = self.keypad.ui(ui, has_memory) {
+                self.calculator.handle_event_with(event, operations);
+            }
+        });
+    }
+}
@@ -0,0 +1,170 @@
+// Numbers in Words
+use crate::error::CalculatorError;
+
+/// The words for one language. Implement this to spell numbers in another locale.
+pub trait Wording {
+    /// A whole number in words, e.g. "one thousand two hundred thirty-four"
+    fn integer(&self, value: u64) -> String;
+    /// A single digit, used one by one after the decimal point
+    fn digit(&self, digit: u8) -> &'static str;
+    fn point(&self) -> &'static str;
+    fn minus(&self) -> &'static str;
+}
+
+pub struct English;
+
+const ONES: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven",
+    "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+];
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+const SCALES: [&str; 7] = [
+    "", "thousand", "million", "billion", "trillion", "quadrillion", "quintillion",
+];
+
+impl English {
+    // 1 to 999
+    fn below_thousand(value: u64, words: &mut Vec<String>) {
+        let (hundreds, rest) = (value / 100, value % 100);
+        if hundreds > 0 {
+            words.push(format!("{} hundred", ONES[hundreds as usize]));
+        }
+        if rest >= 20 {
+            let (tens, ones) = (rest / 10, rest % 10);
+            if ones > 0 {
+                words.push(format!("{}-{}", TENS[tens as usize], ONES[ones as usize]));
+            } else {
+                words.push(TENS[tens as usize].to_string());
+            }
+        } else if rest > 0 {
+            words.push(ONES[rest as usize].to_string());
+        }
+    }
+}
+
+impl Wording for English {
+    fn integer(&self, value: u64) -> String {
+        if value == 0 {
+            return ONES[0].to_string();
+        }
+
+        // Groups of three digits, most significant first
+        let mut groups = Vec::new();
+        let mut rest = value;
+        while rest > 0 {
+            groups.push(rest % 1000);
+            rest /= 1000;
+        }
+
+        let mut words = Vec::new();
+        for (scale, &group) in groups.iter().enumerate().rev() {
+            if group == 0 {
+                continue;
+            }
+            Self::below_thousand(group, &mut words);
+            if scale > 0 {
+                words.push(SCALES[scale].to_string());
+            }
+        }
+        words.join(" ")
+    }
+
+    fn digit(&self, digit: u8) -> &'static str {
+        ONES[digit as usize % 10]
+    }
+
+    fn point(&self) -> &'static str {
+        "point"
+    }
+
+    fn minus(&self) -> &'static str {
+        "minus"
+    }
+}
+
+/// Spells out a number written in plain decimal notation, as on the display.
+/// Taking the text rather than an `f64` keeps exactly the digits the user sees.
+pub fn spell(number: &str, wording: &dyn Wording) -> Result<String, CalculatorError> {
+    let number = number.trim();
+    let (negative, unsigned) = match number.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, number),
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+
+    let all_digits = |part: &str| part.chars().all(|ch| ch.is_ascii_digit());
+    if (integer.is_empty() && fraction.is_empty()) || !all_digits(integer) || !all_digits(fraction) {
+        return Err(CalculatorError::Domain(String::from("spell out")));
+    }
+    let whole = if integer.is_empty() {
+        0
+    } else {
+        integer.parse::<u64>().map_err(|_| CalculatorError::Overflow)?
+    };
+
+    let mut words = Vec::new();
+    if negative {
+        words.push(wording.minus().to_string());
+    }
+    words.push(wording.integer(whole));
+    if !fraction.is_empty() {
+        words.push(wording.point().to_string());
+        for digit in fraction.bytes() {
+            words.push(wording.digit(digit - b'0').to_string());
+        }
+    }
+    Ok(words.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_spell_english() {
+        assert_eq!(
+            spell("1234.56", &English),
+            Ok(String::from("one thousand two hundred thirty-four point five six"))
+        );
+        assert_eq!(spell("0", &English), Ok(String::from("zero")));
+        assert_eq!(spell("-0.05", &English), Ok(String::from("minus zero point zero five")));
+        assert_eq!(spell("1000000", &English), Ok(String::from("one million")));
+        assert_eq!(spell("2000017", &English), Ok(String::from("two million seventeen")));
+        assert_eq!(spell("90", &English), Ok(String::from("ninety")));
+        assert_eq!(
+            spell("18446744073709551615", &English).unwrap(),
+            "eighteen quintillion four hundred forty-six quadrillion seven hundred forty-four trillion \
+             seventy-three billion seven hundred nine million five hundred fifty-one thousand \
+             six hundred fifteen"
+        );
+        assert_eq!(spell("18446744073709551616", &English), Err(CalculatorError::Overflow));
+        assert!(spell("Error: Overflow", &English).is_err());
+        assert!(spell(".", &English).is_err());
+    }
+
+    // Feature: gui-calculator, Property 28: Spelled numbers have one word group per nonzero digit group
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_spell_scales(value in 1u64..u64::MAX) {
+            let words = English.integer(value);
+            let mut rest = value;
+            let mut groups = 0;
+            while rest > 0 {
+                if rest % 1000 != 0 {
+                    groups += 1;
+                }
+                rest /= 1000;
+            }
+
+            let scales = words.split(' ').filter(|word| SCALES[1..].contains(word)).count();
+            let lowest_group = value % 1000 != 0;
+            prop_assert_eq!(scales, groups - lowest_group as usize);
+            prop_assert!(!words.contains("zero"));
+        }
+    }
+}
@@ -0,0 +1,115 @@
+// Worksheet Evaluation
+use crate::error::CalculatorError;
+use crate::expression::{self, Environment, Functions, ParseOptions};
+use crate::number;
+
+/// The outcome of one worksheet line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorksheetLine {
+    pub label: Option<String>,
+    /// `None` for blank and comment lines
+    pub result: Option<Result<f64, CalculatorError>>,
+}
+
+/// Evaluates a worksheet, producing exactly one entry per input line.
+///
+/// A line may start with a label (`subtotal: 120 * 3`) that later lines can
+/// use as a variable, and `ans` always refers to the latest successful result
+/// above the current line. Lines starting with `#` are comments.
+pub fn evaluate(text: &str, functions: Option<&dyn Functions>, options: ParseOptions) -> Vec<WorksheetLine> {
+    let mut env = Environment::new();
+    env.functions = functions;
+
+    text.split('\n')
+        .map(|line| {
+            let (label, source) = split_label(line);
+            let source = source.trim();
+            if source.is_empty() || source.starts_with('#') {
+                return WorksheetLine {
+                    label: None,
+                    result: None,
+                };
+            }
+
+            let result = expression::parse_with(source, options)
+                .and_then(|expr| expr.eval(&env))
+                .and_then(number::in_range)
+                .map(number::unsigned_zero);
+
+            if let Ok(value) = result {
+                env.variables.insert(String::from("ans"), value);
+                if let Some(label) = label {
+                    env.variables.insert(label.to_string(), value);
+                }
+            }
+
+            WorksheetLine {
+                label: label.map(str::to_string),
+                result: Some(result),
+            }
+        })
+        .collect()
+}
+
+// Splits "name: expression" into its label and expression
+fn split_label(line: &str) -> (Option<&str>, &str) {
+    if let Some((label, rest)) = line.split_once(':') {
+        let label = label.trim();
+        let is_identifier = label.starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && label.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if is_identifier {
+            return (Some(label), rest);
+        }
+    }
+    (None, line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_labels_and_ans() {
+        let sheet = "subtotal: 120 * 3\nans * 0.19\n\n# comment\ntotal: subtotal + ans\nmissing + 1\ntotal / 2";
+        let results: Vec<Option<Result<f64, CalculatorError>>> =
+            evaluate(sheet, None, ParseOptions::default()).into_iter().map(|line| line.result).collect();
+
+        assert_eq!(
+            results,
+            vec![
+                Some(Ok(360.0)),
+                Some(Ok(360.0 * 0.19)),
+                None,
+                None,
+                Some(Ok(360.0 + 360.0 * 0.19)),
+                Some(Err(CalculatorError::UnknownVariable(String::from("missing")))),
+                // Errors don't change what ans and labels refer to
+                Some(Ok((360.0 + 360.0 * 0.19) / 2.0)),
+            ]
+        );
+        assert_eq!(evaluate(sheet, None, ParseOptions::default())[0].label.as_deref(), Some("subtotal"));
+    }
+
+    // Feature: gui-calculator, Property 19: Worksheet ans chains like repeated operations
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+
+        #[test]
+        fn test_ans_chain(
+            start in -1000.0..1000.0f64,
+            steps in prop::collection::vec(-1000.0..1000.0f64, 0..=10)
+        ) {
+            let mut sheet = format!("{}", start);
+            let mut expected = start;
+            for step in &steps {
+                sheet.push_str(&format!("\nans + {}", step));
+                expected += step;
+            }
+
+            let lines = evaluate(&sheet, None, ParseOptions::default());
+            prop_assert_eq!(lines.len(), steps.len() + 1);
+            prop_assert_eq!(lines.last().unwrap().result.clone(), Some(Ok(expected)));
+        }
+    }
+}